@@ -0,0 +1,87 @@
+use anyhow::Result;
+
+use crate::api::DeezerApi;
+use crate::color;
+use crate::download;
+use crate::models::{CurrentUser, GwTrack, TrackFormat};
+use crate::tr;
+
+/// A secondary account's session
+#[derive(Clone)]
+struct BackupAccount {
+    api: DeezerApi,
+    user: CurrentUser,
+}
+
+/// Secondary accounts (`backup_arls` in the config), tried when the main account streams a
+/// track only in a lower format than asked for, or not at all (region locks)
+#[derive(Clone, Default)]
+pub struct BackupAccounts {
+    accounts: Vec<BackupAccount>,
+}
+
+impl std::fmt::Debug for BackupAccounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.accounts.iter().map(|a| &a.user.name)).finish()
+    }
+}
+
+impl BackupAccounts {
+    /// Log each ARL in on its own session from `new_session`, in order. Accounts that cannot
+    /// log in are left out with a warning.
+    pub async fn login(arls: &[String], new_session: impl Fn() -> Result<DeezerApi>) -> Result<Self> {
+        let mut accounts = Vec::new();
+        for (i, arl) in arls.iter().enumerate() {
+            let api = new_session()?;
+            let user = match api.login_via_arl(arl).await {
+                Ok(true) => api.current_user.lock().await.clone(),
+                Ok(false) => None,
+                Err(e) => {
+                    eprintln!("{} {}", color::warn(), tr!("accounts.login_error", number = i + 1, error = e));
+                    continue;
+                }
+            };
+            let Some(user) = user else {
+                eprintln!("{} {}", color::warn(), tr!("accounts.login_failed", number = i + 1));
+                continue;
+            };
+            println!("{}", tr!("accounts.logged_in", name = user.name, format = user.best_format()));
+            accounts.push(BackupAccount { api, user });
+        }
+        Ok(Self { accounts })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Whether one of the accounts streams `format`
+    pub fn can_stream(&self, format: TrackFormat) -> bool {
+        self.accounts.iter().any(|a| a.user.best_format().is_at_least(format))
+    }
+
+    /// The first account that streams `track` in a better format than `delivered` (in any format
+    /// when `None`), trying `format` and then lower ones down to `min_format`.
+    /// Returns the stream URL, its format and the account's name.
+    pub async fn find_url(
+        &self,
+        track: &GwTrack,
+        format: TrackFormat,
+        min_format: Option<TrackFormat>,
+        delivered: Option<TrackFormat>,
+    ) -> Option<(String, TrackFormat, String)> {
+        for account in &self.accounts {
+            if delivered.is_some_and(|d| d.is_at_least(account.user.best_format())) {
+                continue;
+            }
+            // Track tokens belong to an account and its country: get this account's own
+            let Ok(theirs) = account.api.get_track(&track.id_str()).await else {
+                continue;
+            };
+            if let Some((url, found)) = download::media_url(&account.api, &theirs, format, min_format, delivered).await {
+                return Some((url, found, account.user.name.clone()));
+            }
+        }
+        None
+    }
+}
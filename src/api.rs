@@ -1,45 +1,236 @@
-use anyhow::{bail, Context, Result};
-use reqwest::Client;
+use futures_util::StreamExt;
+use reqwest::{Client, StatusCode};
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::client::{Download, PostBody};
+use crate::error::{DeezerError, Result};
 use crate::models::*;
+use crate::retry::RetryPolicy;
+use crate::throttle::{self, RateLimiter};
+use crate::tr;
 
 const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/79.0.3945.130 Safari/537.36";
-const GW_API_URL: &str = "http://www.deezer.com/ajax/gw-light.php";
-const MEDIA_URL: &str = "https://media.deezer.com/v1/get_url";
-const PUBLIC_API_URL: &str = "https://api.deezer.com";
+/// MusicBrainz asks clients to identify themselves and stay at one request per second
+const MUSICBRAINZ_USER_AGENT: &str = concat!("deezer-dl/", env!("CARGO_PKG_VERSION"), " ( https://github.com/jojo8356/Deezer-dl )");
+
+/// Pause after the first quota error; multiplied by the number of pauses so far
+const QUOTA_PAUSE_SECS: u64 = 30;
+const MAX_QUOTA_PAUSES: u32 = 5;
+
+/// Extract the first source URL from a media API `data` item
+fn media_url(item: &Value) -> Option<String> {
+    if item.get("errors").is_some() {
+        return None;
+    }
+    item["media"]
+        .as_array()?
+        .first()?["sources"]
+        .as_array()?
+        .first()?["url"]
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+/// Pipe API query for a track's lyrics, synced lines and their translations
+const LYRICS_QUERY: &str = "query TrackLyrics($trackId: String!) {
+  track(trackId: $trackId) {
+    lyrics {
+      text
+      copyright
+      writers
+      synchronizedLines { lrcTimestamp line lineTranslated }
+    }
+  }
+}";
+
+/// Whether a GW error object signals a quota / rate-limit condition
+fn is_quota_error(error: &Value) -> bool {
+    let text = error.to_string().to_lowercase();
+    text.contains("quota") || text.contains("too many requests") || text.contains("rate limit")
+}
+
+/// Connection settings shared by the API and download clients
+#[derive(Debug, Clone, Default)]
+pub struct NetworkOptions {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` proxy URL; credentials go in the URL
+    pub proxy: Option<String>,
+    /// Skip TLS certificate validation (only for broken proxies / debugging)
+    pub insecure: bool,
+}
+
+impl NetworkOptions {
+    /// A client builder with the user agent and proxy applied
+    fn client_builder(&self) -> Result<reqwest::ClientBuilder> {
+        let mut builder = Client::builder()
+            .user_agent(USER_AGENT)
+            .danger_accept_invalid_certs(self.insecure);
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| DeezerError::Config(format!("Invalid proxy URL {}: {}", proxy, e)))?;
+            builder = builder.proxy(proxy);
+        }
+        Ok(builder)
+    }
+}
+
+/// Base URLs of the services the client talks to; overridable for tests and mirrors
+#[derive(Debug, Clone)]
+pub struct Endpoints {
+    /// Deezer website, visited once to set the ARL cookie
+    pub site: String,
+    /// GW (internal) API endpoint
+    pub gw: String,
+    /// Media API endpoint returning stream URLs
+    pub media: String,
+    /// Public API base URL
+    pub public_api: String,
+    /// MusicBrainz web service base URL
+    pub musicbrainz: String,
+    /// Exchanges the ARL for a JWT accepted by the pipe API
+    pub auth: String,
+    /// Pipe (GraphQL) API endpoint, used for lyrics
+    pub pipe: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            site: "https://www.deezer.com/".to_string(),
+            gw: "http://www.deezer.com/ajax/gw-light.php".to_string(),
+            media: "https://media.deezer.com/v1/get_url".to_string(),
+            public_api: "https://api.deezer.com".to_string(),
+            musicbrainz: "https://musicbrainz.org/ws/2".to_string(),
+            auth: "https://auth.deezer.com/login/arl".to_string(),
+            pipe: "https://pipe.deezer.com/api".to_string(),
+        }
+    }
+}
+
+impl Endpoints {
+    /// Every endpoint under one server, e.g. a mock server in tests:
+    /// `{base}/`, `{base}/ajax/gw-light.php`, `{base}/media/get_url`, `{base}/api`, `{base}/musicbrainz`,
+    /// `{base}/auth` and `{base}/pipe`
+    pub fn local(base: &str) -> Self {
+        let base = base.trim_end_matches('/');
+        Self {
+            site: format!("{}/", base),
+            gw: format!("{}/ajax/gw-light.php", base),
+            media: format!("{}/media/get_url", base),
+            public_api: format!("{}/api", base),
+            musicbrainz: format!("{}/musicbrainz", base),
+            auth: format!("{}/auth", base),
+            pipe: format!("{}/pipe", base),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct DeezerApi {
     client: Client,
+    /// Cookie-less client shared by all CDN downloads (keeps connections pooled)
+    download_client: Client,
     api_token: Arc<Mutex<Option<String>>>,
+    /// ARL of the logged-in account, exchanged for a pipe API token when one is needed
+    arl: Arc<Mutex<Option<String>>>,
+    /// Pipe API JWT, fetched on first use
+    pipe_token: Arc<Mutex<Option<String>>>,
     pub current_user: Arc<Mutex<Option<CurrentUser>>>,
+    retry: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    /// Prefetched media URLs keyed by (track token, format)
+    url_cache: Arc<Mutex<HashMap<(String, String), String>>>,
+    /// `album.getData` results keyed by album ID, so each album is fetched once
+    album_cache: Arc<Mutex<HashMap<String, AlbumData>>>,
+    /// Public API `/album/{id}` responses keyed by album ID
+    public_album_cache: Arc<Mutex<HashMap<String, PublicAlbum>>>,
+    musicbrainz_limiter: RateLimiter,
+    endpoints: Endpoints,
 }
 
 impl DeezerApi {
-    pub fn new() -> Result<Self> {
-        let client = Client::builder()
-            .cookie_store(true)
-            .user_agent(USER_AGENT)
-            .danger_accept_invalid_certs(true)
-            .build()?;
+    pub fn new(network: &NetworkOptions) -> Result<Self> {
+        let client = network.client_builder()?.cookie_store(true).build()?;
+        let download_client = network.client_builder()?.build()?;
 
         Ok(Self {
             client,
+            download_client,
             api_token: Arc::new(Mutex::new(None)),
+            arl: Arc::new(Mutex::new(None)),
+            pipe_token: Arc::new(Mutex::new(None)),
             current_user: Arc::new(Mutex::new(None)),
+            retry: RetryPolicy::default(),
+            rate_limiter: None,
+            url_cache: Arc::new(Mutex::new(HashMap::new())),
+            album_cache: Arc::new(Mutex::new(HashMap::new())),
+            public_album_cache: Arc::new(Mutex::new(HashMap::new())),
+            musicbrainz_limiter: RateLimiter::new(1.0),
+            endpoints: Endpoints::default(),
         })
     }
 
+    /// Use a custom retry policy for API calls and downloads
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Limit GW API calls to `rps` requests per second (0 disables the limit)
+    pub fn with_rate_limit(mut self, rps: f64) -> Self {
+        self.rate_limiter = (rps > 0.0).then(|| RateLimiter::new(rps));
+        self
+    }
+
+    /// Talk to these endpoints instead of Deezer's
+    pub fn with_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Start a CDN download; a non-success status is a `DeezerError::Cdn`
+    pub async fn fetch_stream(&self, url: &str) -> Result<Download> {
+        let resp = self.download_client.get(url).send().await?;
+        if !resp.status().is_success() {
+            return Err(DeezerError::Cdn(resp.status()));
+        }
+        Ok(Download {
+            len: resp.content_length(),
+            chunks: resp
+                .bytes_stream()
+                .map(|chunk| chunk.map(|c| c.to_vec()).map_err(DeezerError::from))
+                .boxed(),
+        })
+    }
+
+    /// POST to a notification service with the download client's network settings;
+    /// returns the HTTP status. The URL is kept out of errors, it may hold a token.
+    pub async fn post(&self, url: &str, headers: &[(&str, String)], body: PostBody) -> Result<u16> {
+        let mut request = self.download_client.post(url);
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        request = match body {
+            PostBody::Json(value) => request.json(&value),
+            PostBody::Text(text) => request.body(text),
+        };
+        let resp = request.send().await.map_err(|e| e.without_url())?;
+        Ok(resp.status().as_u16())
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry
+    }
+
     /// Login using ARL cookie
     pub async fn login_via_arl(&self, arl: &str) -> Result<bool> {
         // Set the ARL cookie by making a request with it
         let cookie_val = format!("arl={}", arl.trim());
         let response = self
             .client
-            .get("https://www.deezer.com/")
+            .get(&self.endpoints.site)
             .header("Cookie", &cookie_val)
             .send()
             .await?;
@@ -108,6 +299,8 @@ impl DeezerApi {
             country,
             loved_tracks_id,
         });
+        *self.arl.lock().await = Some(arl.trim().to_string());
+        *self.pipe_token.lock().await = None;
 
         Ok(true)
     }
@@ -123,7 +316,7 @@ impl DeezerApi {
 
         let response = self
             .client
-            .post(GW_API_URL)
+            .post(&self.endpoints.gw)
             .header("Cookie", format!("arl={}", arl.trim()))
             .query(&[
                 ("api_version", "1.0"),
@@ -133,10 +326,9 @@ impl DeezerApi {
             ])
             .json(&args)
             .send()
-            .await
-            .context("GW API request failed")?;
+            .await?;
 
-        let body: Value = response.json().await.context("Failed to parse GW response")?;
+        let body: Value = response.json().await?;
 
         if let Some(results) = body.get("results") {
             // Store checkForm token if this is getUserData
@@ -152,13 +344,14 @@ impl DeezerApi {
             }
             Ok(results.clone())
         } else {
-            bail!("No results in GW response for {}: {:?}", method, body)
+            Err(DeezerError::InvalidResponse(format!("No results in GW response for {}: {:?}", method, body)))
         }
     }
 
     /// GW API call using cookie jar (after login)
     pub async fn gw_call(&self, method: &str, args: Value) -> Result<Value> {
         let mut retried = false;
+        let mut quota_pauses = 0;
 
         loop {
             let api_token = if method == "deezer.getUserData" {
@@ -176,35 +369,64 @@ impl DeezerApi {
                 }
             };
 
-            let response = self
-                .client
-                .post(GW_API_URL)
-                .query(&[
-                    ("api_version", "1.0"),
-                    ("api_token", &api_token),
-                    ("input", "3"),
-                    ("method", method),
-                ])
-                .json(&args)
-                .send()
-                .await
-                .context(format!("GW API call failed: {}", method))?;
+            // `None` means the server answered 429 Too Many Requests
+            let body: Option<GwResponse> = self
+                .retry
+                .run::<_, DeezerError, _, _>(method, || async {
+                    // Retries are requests too
+                    if let Some(limiter) = &self.rate_limiter {
+                        limiter.acquire().await;
+                    }
+                    let response = self
+                        .client
+                        .post(&self.endpoints.gw)
+                        .query(&[
+                            ("api_version", "1.0"),
+                            ("api_token", &api_token),
+                            ("input", "3"),
+                            ("method", method),
+                        ])
+                        .json(&args)
+                        .send()
+                        .await?;
+
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                        return Ok(None);
+                    }
+                    Ok(Some(response.json().await?))
+                })
+                .await?;
 
-            let body: GwResponse = response
-                .json()
-                .await
-                .context(format!("Failed to parse GW response for {}", method))?;
+            // Pause on quota errors instead of failing every following call
+            let body = match body {
+                Some(body) if !is_quota_error(&body.error) => body,
+                _ => {
+                    quota_pauses += 1;
+                    if quota_pauses > MAX_QUOTA_PAUSES {
+                        return Err(DeezerError::Quota(format!(
+                            "still exceeded after {} pauses ({})",
+                            MAX_QUOTA_PAUSES, method
+                        )));
+                    }
+                    throttle::countdown(&tr!("throttle.rate_limit"), QUOTA_PAUSE_SECS * quota_pauses as u64).await;
+                    continue;
+                }
+            };
 
             // Check for token errors - retry once
             let err_str = body.error.to_string();
-            if !retried && (err_str.contains("invalid api token") || err_str.contains("Invalid CSRF token")) {
+            let token_error = err_str.contains("invalid api token") || err_str.contains("Invalid CSRF token");
+            if token_error && !retried {
                 self.refresh_token().await?;
                 retried = true;
                 continue;
             }
 
             if body.error.is_object() && !body.error.as_object().unwrap().is_empty() {
-                bail!("GW API error for {}: {}", method, body.error);
+                if token_error {
+                    return Err(DeezerError::TokenExpired(format!("{}: {}", method, body.error)));
+                }
+                return Err(DeezerError::Api(format!("GW API error for {}: {}", method, body.error)));
             }
 
             return Ok(body.results);
@@ -214,7 +436,7 @@ impl DeezerApi {
     async fn refresh_token(&self) -> Result<()> {
         let response = self
             .client
-            .post(GW_API_URL)
+            .post(&self.endpoints.gw)
             .query(&[
                 ("api_version", "1.0"),
                 ("api_token", "null"),
@@ -231,7 +453,7 @@ impl DeezerApi {
             *token = Some(match check_form {
                 Value::String(s) => s.clone(),
                 Value::Number(n) => n.to_string(),
-                _ => bail!("Unexpected checkForm type"),
+                _ => return Err(DeezerError::InvalidResponse("Unexpected checkForm type".to_string())),
             });
         }
         Ok(())
@@ -245,8 +467,63 @@ impl DeezerApi {
         Ok(track)
     }
 
-    pub async fn get_track_page(&self, sng_id: &str) -> Result<Value> {
-        self.gw_call("deezer.pageTrack", json!({ "SNG_ID": sng_id })).await
+    pub async fn get_track_page(&self, sng_id: &str) -> Result<TrackPage> {
+        let result = self.gw_call("deezer.pageTrack", json!({ "SNG_ID": sng_id })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Pipe API: the track's lyrics (`text` and `synchronizedLines`), with each line's translation
+    /// into `lang` (`fr`, `es`...) in `lineTranslated` when Deezer has one; `None` without lyrics
+    pub async fn get_lyrics(&self, sng_id: &str, lang: Option<&str>) -> Result<Option<LyricsData>> {
+        let jwt = self.pipe_token().await?;
+        let body = json!({
+            "operationName": "TrackLyrics",
+            "variables": { "trackId": sng_id },
+            "query": LYRICS_QUERY,
+        });
+        let result: Value = self
+            .retry
+            .run("lyrics", || async {
+                let mut request = self.client.post(&self.endpoints.pipe).bearer_auth(&jwt).json(&body);
+                if let Some(lang) = lang {
+                    request = request.header(reqwest::header::ACCEPT_LANGUAGE, lang);
+                }
+                Ok::<Value, DeezerError>(request.send().await?.error_for_status()?.json().await?)
+            })
+            .await?;
+        if let Some(errors) = result.get("errors").filter(|e| !e.is_null()) {
+            return Err(DeezerError::Api(format!("Pipe API error for lyrics: {}", errors)));
+        }
+        Ok(serde_json::from_value(result["data"]["track"]["lyrics"].clone())?)
+    }
+
+    /// JWT for the pipe API, exchanged once for the ARL
+    async fn pipe_token(&self) -> Result<String> {
+        let mut token = self.pipe_token.lock().await;
+        if let Some(jwt) = token.as_ref() {
+            return Ok(jwt.clone());
+        }
+        let arl = self
+            .arl
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(|| DeezerError::Auth("Lyrics need a logged-in account".to_string()))?;
+        let result: Value = self
+            .client
+            .post(&self.endpoints.auth)
+            .header("Cookie", format!("arl={}", arl))
+            .query(&[("jo", "p"), ("rto", "c"), ("i", "c")])
+            .send()
+            .await?
+            .json()
+            .await?;
+        let jwt = result["jwt"]
+            .as_str()
+            .filter(|jwt| !jwt.is_empty())
+            .ok_or_else(|| DeezerError::InvalidResponse("No JWT in pipe login response".to_string()))?;
+        *token = Some(jwt.to_string());
+        Ok(jwt.to_string())
     }
 
     // ========== Playlist operations ==========
@@ -258,7 +535,7 @@ impl DeezerApi {
 
         let data = result["data"]
             .as_array()
-            .context("No data array in playlist response")?;
+            .ok_or_else(|| DeezerError::InvalidResponse("No data array in playlist response".to_string()))?;
 
         let mut tracks = Vec::new();
         for item in data {
@@ -269,8 +546,9 @@ impl DeezerApi {
         Ok(tracks)
     }
 
-    pub async fn get_playlist_info(&self, playlist_id: &str) -> Result<Value> {
-        self.gw_call(
+    pub async fn get_playlist_info(&self, playlist_id: &str) -> Result<PlaylistPage> {
+        let result = self
+            .gw_call(
             "deezer.pagePlaylist",
             json!({
                 "PLAYLIST_ID": playlist_id,
@@ -279,7 +557,8 @@ impl DeezerApi {
                 "tab": 0,
             }),
         )
-        .await
+        .await?;
+        Ok(serde_json::from_value(result)?)
     }
 
     // ========== User playlists ==========
@@ -307,6 +586,38 @@ impl DeezerApi {
         Ok(playlists)
     }
 
+    /// (ID, name) of the artists a user follows
+    pub async fn get_followed_artists(&self, user_id: u64) -> Result<Vec<(String, String)>> {
+        let result = self
+            .gw_call(
+                "deezer.pageProfile",
+                json!({
+                    "USER_ID": user_id,
+                    "tab": "artists",
+                    "nb": -1,
+                }),
+            )
+            .await?;
+
+        let artists = result["TAB"]["artists"]["data"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|a| {
+                        let id = match &a["ART_ID"] {
+                            Value::Number(n) => n.to_string(),
+                            Value::String(s) => s.clone(),
+                            _ => return None,
+                        };
+                        let name = a["ART_NAME"].as_str().unwrap_or("Unknown Artist");
+                        Some((id, name.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(artists)
+    }
+
     // ========== Favorites ==========
 
     pub async fn get_favorite_track_ids(&self) -> Result<Vec<String>> {
@@ -316,7 +627,7 @@ impl DeezerApi {
 
         let data = result["data"]
             .as_array()
-            .context("No data in favorites response")?;
+            .ok_or_else(|| DeezerError::InvalidResponse("No data in favorites response".to_string()))?;
 
         let ids: Vec<String> = data
             .iter()
@@ -348,7 +659,7 @@ impl DeezerApi {
 
         let data = result["data"]
             .as_array()
-            .context("No data in getListData response")?;
+            .ok_or_else(|| DeezerError::InvalidResponse("No data in getListData response".to_string()))?;
 
         let tracks: Vec<GwTrack> = data
             .iter()
@@ -358,9 +669,56 @@ impl DeezerApi {
         Ok(tracks)
     }
 
+    // ========== Library edits ==========
+
+    /// Add a track to the user's favorites
+    pub async fn add_favorite_track(&self, sng_id: &str) -> Result<()> {
+        self.gw_call("favorite_song.add", json!({ "SNG_ID": sng_id })).await?;
+        Ok(())
+    }
+
+    /// Create a playlist owned by the user with these tracks; returns its ID
+    pub async fn create_playlist(&self, title: &str, description: &str, sng_ids: &[String]) -> Result<String> {
+        let result = self
+            .gw_call(
+                "playlist.create",
+                json!({
+                    "title": title,
+                    "description": description,
+                    "status": 0,
+                    "songs": playlist_songs(sng_ids),
+                }),
+            )
+            .await?;
+        match result {
+            Value::Number(n) => Ok(n.to_string()),
+            Value::String(s) => Ok(s),
+            _ => Err(DeezerError::InvalidResponse("No playlist ID in playlist.create response".to_string())),
+        }
+    }
+
+    /// Append tracks to one of the user's playlists
+    pub async fn add_playlist_tracks(&self, playlist_id: &str, sng_ids: &[String]) -> Result<()> {
+        self.gw_call(
+            "playlist.addSongs",
+            json!({ "playlist_id": playlist_id, "songs": playlist_songs(sng_ids), "offset": -1 }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Add someone else's playlist to the user's favorite playlists
+    pub async fn add_favorite_playlist(&self, playlist_id: &str) -> Result<()> {
+        self.gw_call("playlist.addFavorite", json!({ "PARENT_PLAYLIST_ID": playlist_id }))
+            .await?;
+        Ok(())
+    }
+
     // ========== Artist operations ==========
 
-    pub async fn get_artist_discography(&self, art_id: &str) -> Result<Vec<AlbumInfo>> {
+    /// Releases of an artist; `mode` is the GW discography mode ("all" for the artist's own
+    /// releases, "featured" for releases the artist only appears on)
+    pub async fn get_artist_discography(&self, art_id: &str, mode: &str) -> Result<Vec<AlbumInfo>> {
         let mut all_albums = Vec::new();
         let mut start = 0u64;
         let limit = 100u64;
@@ -371,7 +729,7 @@ impl DeezerApi {
                     "album.getDiscography",
                     json!({
                         "ART_ID": art_id,
-                        "discography_mode": "all",
+                        "discography_mode": mode,
                         "nb": limit,
                         "nb_songs": 0,
                         "start": start,
@@ -381,7 +739,7 @@ impl DeezerApi {
 
             let data = result["data"]
                 .as_array()
-                .context("No data in discography response")?;
+                .ok_or_else(|| DeezerError::InvalidResponse("No data in discography response".to_string()))?;
 
             let albums: Vec<AlbumInfo> = data
                 .iter()
@@ -401,6 +759,20 @@ impl DeezerApi {
         Ok(all_albums)
     }
 
+    /// Album details (release dates, disc count, label), cached per album
+    pub async fn get_album_data(&self, alb_id: &str) -> Result<AlbumData> {
+        if let Some(album) = self.album_cache.lock().await.get(alb_id) {
+            return Ok(album.clone());
+        }
+        let result = self.gw_call("album.getData", json!({ "ALB_ID": alb_id })).await?;
+        let album: AlbumData = serde_json::from_value(result)?;
+        self.album_cache
+            .lock()
+            .await
+            .insert(alb_id.to_string(), album.clone());
+        Ok(album)
+    }
+
     pub async fn get_album_tracks(&self, alb_id: &str) -> Result<Vec<GwTrack>> {
         let result = self
             .gw_call("song.getListByAlbum", json!({ "ALB_ID": alb_id, "nb": -1 }))
@@ -408,7 +780,7 @@ impl DeezerApi {
 
         let data = result["data"]
             .as_array()
-            .context("No data in album tracks response")?;
+            .ok_or_else(|| DeezerError::InvalidResponse("No data in album tracks response".to_string()))?;
 
         let tracks: Vec<GwTrack> = data
             .iter()
@@ -418,76 +790,244 @@ impl DeezerApi {
         Ok(tracks)
     }
 
-    pub async fn search_artist(&self, query: &str) -> Result<Value> {
-        let result = self
+    pub async fn search_artist(&self, query: &str) -> Result<Vec<ArtistSearchResult>> {
+        let result: Value = self
             .client
-            .get(format!("{}/search/artist", PUBLIC_API_URL))
+            .get(format!("{}/search/artist", self.endpoints.public_api))
             .query(&[("q", query), ("limit", "20")])
             .send()
             .await?
             .json()
             .await?;
-        Ok(result)
+        let artists = result["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| serde_json::from_value(item.clone()).ok())
+            .collect();
+        Ok(artists)
     }
 
-    pub async fn get_artist_info(&self, art_id: &str) -> Result<Value> {
-        self.gw_call("artist.getData", json!({ "ART_ID": art_id })).await
+    /// Public API: IDs of an artist's most popular tracks
+    pub async fn get_artist_top(&self, art_id: &str, limit: usize) -> Result<Vec<String>> {
+        let result: Value = self
+            .client
+            .get(format!("{}/artist/{}/top", self.endpoints.public_api, art_id))
+            .query(&[("limit", limit.to_string())])
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(message) = result["error"]["message"].as_str() {
+            return Err(DeezerError::Api(format!("Failed to get top tracks: {}", message)));
+        }
+
+        let ids = result["data"]
+            .as_array()
+            .ok_or_else(|| DeezerError::InvalidResponse("No data in top tracks response".to_string()))?
+            .iter()
+            .filter_map(|item| item["id"].as_u64())
+            .map(|id| id.to_string())
+            .collect();
+        Ok(ids)
     }
 
-    // ========== Track URL ==========
+    /// Public API: (ID, name) of the artists Deezer lists as related, closest first
+    pub async fn get_related_artists(&self, art_id: &str, limit: usize) -> Result<Vec<(String, String)>> {
+        let result: Value = self
+            .client
+            .get(format!("{}/artist/{}/related", self.endpoints.public_api, art_id))
+            .query(&[("limit", limit.to_string())])
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(message) = result["error"]["message"].as_str() {
+            return Err(DeezerError::Api(format!("Failed to get related artists: {}", message)));
+        }
 
-    pub async fn get_track_url(&self, track_token: &str, format: &str) -> Result<Option<String>> {
-        let user = self.current_user.lock().await;
-        let user = user.as_ref().context("Not logged in")?;
+        let artists = result["data"]
+            .as_array()
+            .ok_or_else(|| DeezerError::InvalidResponse("No data in related artists response".to_string()))?
+            .iter()
+            .filter_map(|item| {
+                let id = item["id"].as_u64()?;
+                let name = item["name"].as_str().unwrap_or("Unknown Artist");
+                Some((id.to_string(), name.to_string()))
+            })
+            .take(limit)
+            .collect();
+        Ok(artists)
+    }
 
-        let response = self
+    /// Public API: look up a track by ISRC
+    pub async fn get_track_by_isrc(&self, isrc: &str) -> Result<Option<SearchTrackResult>> {
+        let result: Value = self
             .client
-            .post(MEDIA_URL)
-            .json(&json!({
-                "license_token": user.license_token,
-                "media": [{
-                    "type": "FULL",
-                    "formats": [{ "cipher": "BF_CBC_STRIPE", "format": format }]
-                }],
-                "track_tokens": [track_token],
-            }))
+            .get(format!("{}/track/isrc:{}", self.endpoints.public_api, isrc))
             .send()
+            .await?
+            .json()
             .await?;
+        if result.get("error").is_some() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_value(result)?))
+    }
 
-        let body: Value = response.json().await?;
+    /// Public API: album details (release date, label, genres, track list), cached per album
+    pub async fn get_album_public(&self, alb_id: &str) -> Result<PublicAlbum> {
+        if let Some(album) = self.public_album_cache.lock().await.get(alb_id) {
+            return Ok(album.clone());
+        }
+        let result: Value = self
+            .client
+            .get(format!("{}/album/{}", self.endpoints.public_api, alb_id))
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(error) = result.get("error") {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Err(DeezerError::Api(format!("Public API error for album {}: {}", alb_id, message)));
+        }
+        let album: PublicAlbum = serde_json::from_value(result)?;
+        self.public_album_cache
+            .lock()
+            .await
+            .insert(alb_id.to_string(), album.clone());
+        Ok(album)
+    }
 
-        if let Some(data) = body["data"].as_array() {
-            for item in data {
-                if item.get("errors").is_some() {
-                    continue;
+    /// MusicBrainz: recordings (with releases and artists) for an ISRC; `Null` when unknown
+    pub async fn musicbrainz_isrc(&self, isrc: &str) -> Result<Value> {
+        let url = format!("{}/isrc/{}", self.endpoints.musicbrainz, isrc);
+        self.retry
+            .run("MusicBrainz lookup", || async {
+                self.musicbrainz_limiter.acquire().await;
+                let resp = self
+                    .download_client
+                    .get(&url)
+                    .header(reqwest::header::USER_AGENT, MUSICBRAINZ_USER_AGENT)
+                    .query(&[("inc", "releases+artists"), ("fmt", "json")])
+                    .send()
+                    .await?;
+                if resp.status() == StatusCode::NOT_FOUND {
+                    return Ok(Value::Null);
                 }
-                if let Some(media) = item["media"].as_array() {
-                    if let Some(first) = media.first() {
-                        if let Some(sources) = first["sources"].as_array() {
-                            if let Some(source) = sources.first() {
-                                if let Some(url) = source["url"].as_str() {
-                                    return Ok(Some(url.to_string()));
-                                }
-                            }
-                        }
-                    }
+                Ok(resp.error_for_status()?.json().await?)
+            })
+            .await
+    }
+
+    pub async fn get_artist_info(&self, art_id: &str) -> Result<ArtistData> {
+        let result = self.gw_call("artist.getData", json!({ "ART_ID": art_id })).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    // ========== Track URL ==========
+
+    pub async fn get_track_url(&self, track_token: &str, format: &str) -> Result<Option<String>> {
+        let cached = self
+            .url_cache
+            .lock()
+            .await
+            .remove(&(track_token.to_string(), format.to_string()));
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        let urls = self.get_track_urls(&[(track_token, format)]).await?;
+        Ok(urls.into_iter().next().flatten())
+    }
+
+    /// Request stream URLs for many `(track_token, format)` pairs at once.
+    /// The result is aligned with `requests`; `None` means no URL for that pair.
+    pub async fn get_track_urls(&self, requests: &[(&str, &str)]) -> Result<Vec<Option<String>>> {
+        let license_token = {
+            let user = self.current_user.lock().await;
+            user.as_ref()
+                .ok_or_else(|| DeezerError::Auth("Not logged in".to_string()))?
+                .license_token
+                .clone()
+        };
+
+        let mut urls = vec![None; requests.len()];
+
+        // One media call takes a single format list, so group the tokens by format
+        let mut formats: Vec<&str> = Vec::new();
+        for (_, format) in requests {
+            if !formats.contains(format) {
+                formats.push(format);
+            }
+        }
+
+        for format in formats {
+            let indices: Vec<usize> = (0..requests.len())
+                .filter(|&i| requests[i].1 == format)
+                .collect();
+            let tokens: Vec<&str> = indices.iter().map(|&i| requests[i].0).collect();
+
+            let response = self
+                .client
+                .post(&self.endpoints.media)
+                .json(&json!({
+                    "license_token": license_token,
+                    "media": [{
+                        "type": "FULL",
+                        "formats": [{ "cipher": "BF_CBC_STRIPE", "format": format }]
+                    }],
+                    "track_tokens": tokens,
+                }))
+                .send()
+                .await?;
+
+            let body: Value = response.json().await?;
+
+            // Items come back in the same order as the tokens
+            if let Some(data) = body["data"].as_array() {
+                for (item, &idx) in data.iter().zip(&indices) {
+                    urls[idx] = media_url(item);
                 }
             }
         }
 
-        Ok(None)
+        Ok(urls)
+    }
+
+    /// Fetch URLs for a batch ahead of time; `get_track_url` will use them
+    pub async fn prefetch_track_urls(&self, requests: &[(&str, &str)]) -> Result<()> {
+        let urls = self.get_track_urls(requests).await?;
+        let mut cache = self.url_cache.lock().await;
+        for ((token, format), url) in requests.iter().zip(urls) {
+            if let Some(url) = url {
+                cache.insert((token.to_string(), format.to_string()), url);
+            }
+        }
+        Ok(())
     }
 
     /// Public API: search for tracks
-    pub async fn search_track(&self, query: &str) -> Result<Value> {
-        let result = self
+    pub async fn search_track(&self, query: &str) -> Result<Vec<SearchTrackResult>> {
+        let result: Value = self
             .client
-            .get(format!("{}/search/track", PUBLIC_API_URL))
+            .get(format!("{}/search/track", self.endpoints.public_api))
             .query(&[("q", query), ("limit", "10")])
             .send()
             .await?
             .json()
             .await?;
-        Ok(result)
+        let tracks = result["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|item| serde_json::from_value(item.clone()).ok())
+            .collect();
+        Ok(tracks)
     }
 }
+
+/// `[[SNG_ID, 0], ...]`, the song list format of the playlist write endpoints
+fn playlist_songs(sng_ids: &[String]) -> Value {
+    sng_ids.iter().map(|id| json!([id, 0])).collect()
+}
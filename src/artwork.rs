@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::client::DeezerClient;
+use crate::color;
+use crate::download::{self, DownloadOptions};
+use crate::tr;
+
+/// Default edge length in pixels of downloaded images
+pub const COVER_SIZE: u32 = 1000;
+/// Edge lengths the image CDN serves (`--cover-size`)
+pub const COVER_SIZES: [u32; 4] = [500, 1000, 1200, 1800];
+
+/// Check a cover size against [`COVER_SIZES`]
+pub fn check_cover_size(size: u32) -> Result<u32, String> {
+    if COVER_SIZES.contains(&size) {
+        Ok(size)
+    } else {
+        let sizes: Vec<String> = COVER_SIZES.iter().map(u32::to_string).collect();
+        Err(format!("unsupported cover size {} (expected {})", size, sizes.join(", ")))
+    }
+}
+
+/// Parse `--cover-size`: `1200`, or `1200x1200` as in the CDN URLs
+pub fn parse_cover_size(input: &str) -> Result<u32, String> {
+    let input = input.trim();
+    let edge = match input.split_once(['x', 'X']) {
+        Some((width, height)) if width == height => width,
+        Some(_) => return Err("covers are square; give one size, e.g. 1200".to_string()),
+        None => input,
+    };
+    let size = edge
+        .trim_end_matches("px")
+        .parse()
+        .map_err(|_| format!("invalid cover size '{}' (expected e.g. 1200)", input))?;
+    check_cover_size(size)
+}
+
+/// Image types served by the Deezer image CDN
+#[derive(Debug, Clone, Copy)]
+pub enum ImageKind {
+    Album,
+    Playlist,
+    Artist,
+}
+
+impl ImageKind {
+    fn path_segment(&self) -> &'static str {
+        match self {
+            ImageKind::Album => "cover",
+            ImageKind::Playlist => "playlist",
+            ImageKind::Artist => "artist",
+        }
+    }
+}
+
+/// CDN URL of a square JPEG for an image hash (ALB_PICTURE, PLAYLIST_PICTURE, ART_PICTURE)
+pub fn image_url(kind: ImageKind, md5: &str, size: u32) -> String {
+    format!(
+        "https://e-cdns-images.dzcdn.net/images/{}/{}/{}x{}-000000-80-0-0.jpg",
+        kind.path_segment(),
+        md5,
+        size,
+        size
+    )
+}
+
+/// Download an image `size` pixels wide, retrying transient failures
+pub async fn fetch_image(api: &impl DeezerClient, kind: ImageKind, md5: &str, size: u32) -> Result<Vec<u8>> {
+    let url = image_url(kind, md5, size);
+    api.retry_policy()
+        .run("image download", || api.fetch_bytes(&url))
+        .await
+        .with_context(|| format!("Failed to download {}", url))
+}
+
+/// File names media servers (Plex, Jellyfin, Kodi) look for in an artist folder
+const ARTIST_IMAGE_FILES: &[&str] = &["artist.jpg", "fanart.jpg"];
+
+/// Save the artist picture into an artist folder, downloading it at most once.
+/// Existing files are left alone; failures only warn.
+pub async fn save_artist_images(api: &impl DeezerClient, dir: &Path, md5: Option<&str>, size: u32) {
+    let Some(md5) = md5.filter(|m| !m.is_empty()) else {
+        return;
+    };
+    let missing: Vec<PathBuf> = ARTIST_IMAGE_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| !path.exists())
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+
+    let result = async {
+        let data = fetch_image(api, ImageKind::Artist, md5, size).await?;
+        fs::create_dir_all(dir).await?;
+        for path in &missing {
+            download::write_file_atomic(path, &data).await?;
+        }
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    if let Err(e) = result {
+        eprintln!("  {} {}", color::warn(), tr!("artwork.artist_failed", error = e));
+    }
+}
+
+/// Writes `--save-cover` images, at most once per directory and job
+pub struct CoverWriter {
+    file_name: Option<String>,
+    size: u32,
+    done: HashSet<PathBuf>,
+}
+
+impl CoverWriter {
+    pub fn new(opts: &DownloadOptions) -> Self {
+        Self {
+            file_name: opts.cover_file.clone(),
+            size: opts.cover_size,
+            done: HashSet::new(),
+        }
+    }
+
+    /// Save the image into `dir` unless covers are disabled, the directory was
+    /// already handled, or a cover file is already there. Failures only warn.
+    pub async fn save(&mut self, api: &impl DeezerClient, dir: &Path, kind: ImageKind, md5: Option<&str>) {
+        let Some(file_name) = &self.file_name else {
+            return;
+        };
+        let Some(md5) = md5.filter(|m| !m.is_empty()) else {
+            return;
+        };
+        if !self.done.insert(dir.to_path_buf()) {
+            return;
+        }
+
+        let path = dir.join(file_name);
+        if path.exists() {
+            return;
+        }
+        let result = async {
+            let data = fetch_image(api, kind, md5, self.size).await?;
+            fs::create_dir_all(dir).await?;
+            download::write_file_atomic(&path, &data).await
+        }
+        .await;
+        if let Err(e) = result {
+            eprintln!("  {} {}", color::warn(), tr!("artwork.save_failed", path = path.display(), error = e));
+        }
+    }
+}
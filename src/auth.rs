@@ -1,8 +1,14 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::fs;
 
 use crate::api::DeezerApi;
+use crate::tr;
+
+/// How long a Deezer `arl` cookie usually stays valid. Deezer does not say; `whoami` only uses
+/// it for an estimate.
+pub const ARL_LIFETIME_DAYS: u64 = 90;
 
 /// Get the config directory for storing ARL
 pub fn config_dir() -> PathBuf {
@@ -13,49 +19,68 @@ pub fn config_dir() -> PathBuf {
 
 /// Read the stored ARL from disk
 pub async fn read_stored_arl() -> Option<String> {
-    let path = config_dir().join(".arl");
+    let path = arl_path();
     fs::read_to_string(&path).await.ok().map(|s| s.trim().to_string())
 }
 
+/// Where the ARL is stored
+pub fn arl_path() -> PathBuf {
+    config_dir().join(".arl")
+}
+
+/// Days since the stored ARL was saved, from the file's modification time
+pub async fn stored_arl_age_days() -> Option<u64> {
+    let modified = fs::metadata(arl_path()).await.ok()?.modified().ok()?;
+    let age = modified.elapsed().unwrap_or(Duration::ZERO);
+    Some(age.as_secs() / 86_400)
+}
+
 /// Save the ARL to disk
 pub async fn save_arl(arl: &str) -> Result<()> {
     let dir = config_dir();
     fs::create_dir_all(&dir).await.context("Failed to create config dir")?;
-    let path = dir.join(".arl");
+    let path = arl_path();
     fs::write(&path, arl.trim()).await.context("Failed to save ARL")?;
     Ok(())
 }
 
 /// Remove stored ARL
 pub async fn remove_arl() -> Result<()> {
-    let path = config_dir().join(".arl");
+    let path = arl_path();
     if Path::new(&path).exists() {
         fs::remove_file(&path).await.context("Failed to remove ARL")?;
     }
     Ok(())
 }
 
+/// Attempt login with the stored ARL, removing it if Deezer rejects it
+pub async fn login_stored(api: &DeezerApi) -> bool {
+    let Some(arl) = read_stored_arl().await.filter(|arl| !arl.is_empty()) else {
+        return false;
+    };
+    match api.login_via_arl(&arl).await {
+        Ok(true) => true,
+        _ => {
+            eprintln!("{}", tr!("auth.stored_invalid"));
+            let _ = remove_arl().await;
+            false
+        }
+    }
+}
+
 /// Attempt login with stored ARL, or prompt the user
 pub async fn login(api: &DeezerApi) -> Result<bool> {
     // Try stored ARL first
-    if let Some(arl) = read_stored_arl().await {
-        if !arl.is_empty() {
-            match api.login_via_arl(&arl).await {
-                Ok(true) => return Ok(true),
-                _ => {
-                    eprintln!("Stored ARL is invalid, removing...");
-                    let _ = remove_arl().await;
-                }
-            }
-        }
+    if login_stored(api).await {
+        return Ok(true);
     }
 
     // Prompt for ARL
-    println!("You need a Deezer ARL cookie to use this tool.");
-    println!("Get it from your browser: open deezer.com, press F12, go to Application > Cookies > arl\n");
+    println!("{}", tr!("auth.need_arl"));
+    println!("{}\n", tr!("auth.arl_howto"));
 
     let arl: String = dialoguer::Input::new()
-        .with_prompt("Enter your ARL")
+        .with_prompt(tr!("auth.prompt"))
         .interact_text()?;
 
     let logged_in = api.login_via_arl(&arl).await?;
@@ -63,7 +88,31 @@ pub async fn login(api: &DeezerApi) -> Result<bool> {
         save_arl(&arl).await?;
         Ok(true)
     } else {
-        eprintln!("Login failed. Invalid ARL.");
+        eprintln!("{}", tr!("auth.failed"));
         Ok(false)
     }
 }
+
+/// Run `whoami`: the logged-in account and what it can stream, to tell why downloads come out in
+/// a low quality or fail
+pub async fn whoami(api: &DeezerApi) -> Result<()> {
+    let Some(user) = api.current_user.lock().await.clone() else {
+        bail!("Not logged in");
+    };
+    let yes_no = |allowed: bool| if allowed { tr!("probe.yes") } else { tr!("probe.no") };
+    println!("{}", tr!("whoami.user", name = user.name, id = user.id));
+    println!("{}", tr!("whoami.country", country = user.country));
+    println!("{}", tr!("whoami.hq", value = yes_no(user.can_stream_hq)));
+    println!("{}", tr!("whoami.lossless", value = yes_no(user.can_stream_lossless)));
+    println!("{}", tr!("whoami.best", format = user.best_format()));
+    println!("{}", tr!("whoami.loved", id = user.loved_tracks_id));
+    match stored_arl_age_days().await {
+        Some(age) if age < ARL_LIFETIME_DAYS => {
+            let left = ARL_LIFETIME_DAYS - age;
+            println!("{}", tr!("whoami.arl", age = age, lifetime = ARL_LIFETIME_DAYS, left = left));
+        }
+        Some(age) => println!("{}", tr!("whoami.arl_old", age = age, lifetime = ARL_LIFETIME_DAYS)),
+        None => println!("{}", tr!("whoami.arl_unknown", path = arl_path().display())),
+    }
+    Ok(())
+}
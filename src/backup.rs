@@ -0,0 +1,193 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::client::DeezerClient;
+use crate::color;
+use crate::download;
+use crate::export::{ExportedPlaylist, ExportedTrack};
+use crate::tr;
+
+/// Version of the backup file layout
+const BACKUP_VERSION: u32 = 1;
+
+/// Tracks per `song.getListData` call and per playlist write
+const BATCH_SIZE: usize = 50;
+
+/// Favorites and playlists of an account, written by `backup` and read by `restore`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    pub created_at: u64,
+    /// Account the backup was taken from
+    pub user_id: u64,
+    pub user_name: String,
+    /// Favorite tracks, most recently added first (as Deezer lists them)
+    pub favorites: Vec<ExportedTrack>,
+    pub playlists: Vec<BackedUpPlaylist>,
+}
+
+/// A playlist in a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackedUpPlaylist {
+    #[serde(flatten)]
+    pub playlist: ExportedPlaylist,
+    /// Created by the account; other playlists were only added to its favorites
+    pub owned: bool,
+}
+
+/// Write the logged-in account's favorites and playlists (not audio) to `path`
+pub async fn backup(api: &impl DeezerClient, path: &Path) -> Result<()> {
+    let Some(user) = api.user().await else {
+        bail!("Not logged in");
+    };
+
+    println!("{}", tr!("backup.favorites"));
+    let ids = api.get_favorite_track_ids().await?;
+    let mut favorites = Vec::with_capacity(ids.len());
+    for batch in ids.chunks(BATCH_SIZE) {
+        favorites.extend(api.get_tracks_by_ids(batch).await?);
+    }
+
+    let mut playlists = Vec::new();
+    // "Loved tracks" is the favorites list again
+    let listed = api.get_user_playlists(user.id).await?;
+    let listed: Vec<_> = listed.iter().filter(|p| p.id_str() != user.loved_tracks_id.to_string()).collect();
+    for (i, info) in listed.iter().enumerate() {
+        let name = info.display_name();
+        println!("{}", tr!("backup.playlist", index = i + 1, total = listed.len(), name = name));
+        let tracks = api.get_playlist_tracks(&info.id_str()).await?;
+        playlists.push(BackedUpPlaylist {
+            playlist: ExportedPlaylist {
+                id: info.id_str(),
+                name,
+                tracks: tracks.iter().enumerate().map(|(i, t)| ExportedTrack::new(i + 1, t, None)).collect(),
+            },
+            owned: info.is_owned_by(&user),
+        });
+    }
+
+    let backup = Backup {
+        version: BACKUP_VERSION,
+        created_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        user_id: user.id,
+        user_name: user.name.clone(),
+        favorites: favorites.iter().enumerate().map(|(i, t)| ExportedTrack::new(i + 1, t, None)).collect(),
+        playlists,
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let json = serde_json::to_string_pretty(&backup)?;
+    download::write_file_atomic(path, json.as_bytes()).await?;
+    println!(
+        "\n{}",
+        tr!(
+            "backup.written",
+            favorites = backup.favorites.len(),
+            playlists = backup.playlists.len(),
+            path = path.display(),
+        )
+    );
+    Ok(())
+}
+
+/// Re-add the favorites and playlists of a backup to the logged-in account, which may be
+/// another one. Tracks already favorited and playlists with the same name are left alone.
+pub async fn restore(api: &impl DeezerClient, path: &Path, dry_run: bool) -> Result<()> {
+    let Some(user) = api.user().await else {
+        bail!("Not logged in");
+    };
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let backup: Backup = serde_json::from_str(&content).with_context(|| format!("Invalid backup {}", path.display()))?;
+    if backup.version > BACKUP_VERSION {
+        bail!("{} was written by a newer deezer-dl (backup version {})", path.display(), backup.version);
+    }
+    println!("{}\n", tr!("restore.start", name = backup.user_name, user = user.name));
+
+    let mut failed = 0;
+    let favorited: HashSet<String> = api.get_favorite_track_ids().await?.into_iter().collect();
+    // Oldest first, so the favorites keep their order
+    let missing: Vec<&ExportedTrack> =
+        backup.favorites.iter().rev().filter(|t| !favorited.contains(&t.track_id)).collect();
+    println!("{}", tr!("restore.favorites", count = missing.len(), present = backup.favorites.len() - missing.len()));
+    let mut favorites = 0;
+    if !dry_run {
+        for track in &missing {
+            match api.add_favorite_track(&track.track_id).await {
+                Ok(()) => favorites += 1,
+                Err(e) => {
+                    failed += 1;
+                    let name = format!("{} - {}", track.artists.join(", "), track.title);
+                    eprintln!("  {} {}", color::err(), tr!("restore.track_failed", name = name, error = e));
+                }
+            }
+        }
+    }
+
+    let existing = api.get_user_playlists(user.id).await?;
+    let existing_ids: HashSet<String> = existing.iter().map(|p| p.id_str()).collect();
+    let existing_names: HashSet<String> =
+        existing.iter().filter(|p| p.is_owned_by(&user)).map(|p| p.display_name()).collect();
+    let (mut created, mut followed) = (0, 0);
+    for backed_up in &backup.playlists {
+        let playlist = &backed_up.playlist;
+        let present = if backed_up.owned {
+            existing_names.contains(&playlist.name)
+        } else {
+            existing_ids.contains(&playlist.id)
+        };
+        if present {
+            println!("  {} {}", color::skip(), tr!("restore.playlist_exists", name = playlist.name));
+            continue;
+        }
+        let message = if backed_up.owned {
+            tr!("restore.playlist_create", name = playlist.name, count = playlist.tracks.len())
+        } else {
+            tr!("restore.playlist_follow", name = playlist.name)
+        };
+        println!("  {} {}", color::ok(), message);
+        if dry_run {
+            continue;
+        }
+        let result = if backed_up.owned {
+            create_playlist(api, playlist).await
+        } else {
+            api.add_favorite_playlist(&playlist.id).await.map_err(Into::into)
+        };
+        match result {
+            Ok(()) if backed_up.owned => created += 1,
+            Ok(()) => followed += 1,
+            Err(e) => {
+                failed += 1;
+                eprintln!("  {} {}", color::err(), tr!("restore.playlist_failed", name = playlist.name, error = e));
+            }
+        }
+    }
+
+    if dry_run {
+        println!("\n{}", tr!("restore.dry_run_done"));
+    } else {
+        println!(
+            "\n{}",
+            tr!("restore.done", favorites = favorites, created = created, followed = followed, failed = failed)
+        );
+    }
+    Ok(())
+}
+
+/// Create an owned playlist with the backed-up tracks, in order
+async fn create_playlist(api: &impl DeezerClient, playlist: &ExportedPlaylist) -> Result<()> {
+    let ids: Vec<String> = playlist.tracks.iter().map(|t| t.track_id.clone()).collect();
+    let mut batches = ids.chunks(BATCH_SIZE);
+    let first = batches.next().unwrap_or_default();
+    let playlist_id = api.create_playlist(&playlist.name, "", first).await?;
+    for batch in batches {
+        api.add_playlist_tracks(&playlist_id, batch).await?;
+    }
+    Ok(())
+}
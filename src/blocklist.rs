@@ -0,0 +1,115 @@
+use anyhow::{Context, Result, bail};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::auth::config_dir;
+use crate::links::{self, LinkKind};
+use crate::models::GwTrack;
+
+/// Blocklist used when it exists and neither `--blocklist` nor the config names another
+pub fn blocklist_path() -> PathBuf {
+    config_dir().join("blocklist.txt")
+}
+
+/// Returned for tracks the blocklist excludes
+#[derive(Debug)]
+pub struct Blocked(pub String);
+
+impl std::fmt::Display for Blocked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Blocked by the blocklist ({})", self.0)
+    }
+}
+
+impl std::error::Error for Blocked {}
+
+/// Tracks, albums and artists never to download, whatever job they turn up in
+#[derive(Debug, Clone, Default)]
+pub struct Blocklist {
+    tracks: HashSet<String>,
+    albums: HashSet<String>,
+    artists: HashSet<String>,
+    /// Lower-cased
+    artist_names: HashSet<String>,
+}
+
+impl Blocklist {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read blocklist {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid blocklist {}", path.display()))
+    }
+
+    /// One entry per line, `#` starting a comment: a track ID, a track, album or artist URL,
+    /// `track:ID`, `album:ID`, `artist:ID`, or an artist name (`artist:Name` or just `Name`)
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut list = Self::default();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.chars().all(|c| c.is_ascii_digit()) {
+                list.tracks.insert(line.to_string());
+                continue;
+            }
+            if let Some((kind, id)) = links::parse(line) {
+                list.insert(kind, id).with_context(|| format!("Line {}", number + 1))?;
+                continue;
+            }
+            let (kind, value) = match line.split_once(':') {
+                Some((prefix, value)) => match prefix.trim().to_ascii_lowercase().as_str() {
+                    "track" => (Some(LinkKind::Track), value.trim()),
+                    "album" => (Some(LinkKind::Album), value.trim()),
+                    "artist" => (Some(LinkKind::Artist), value.trim()),
+                    _ => (None, line),
+                },
+                None => (None, line),
+            };
+            match kind {
+                Some(kind) if value.chars().all(|c| c.is_ascii_digit()) && !value.is_empty() => {
+                    list.insert(kind, value.to_string()).with_context(|| format!("Line {}", number + 1))?;
+                }
+                Some(LinkKind::Artist) | None => {
+                    list.artist_names.insert(value.to_lowercase());
+                }
+                Some(kind) => bail!("Line {}: {}s are blocked by ID, not '{}'", number + 1, kind.name(), value),
+            }
+        }
+        Ok(list)
+    }
+
+    fn insert(&mut self, kind: LinkKind, id: String) -> Result<()> {
+        match kind {
+            LinkKind::Track => self.tracks.insert(id),
+            LinkKind::Album => self.albums.insert(id),
+            LinkKind::Artist => self.artists.insert(id),
+            LinkKind::Playlist => bail!("Playlists cannot be blocked, only their tracks, albums or artists"),
+        };
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty() && self.albums.is_empty() && self.artists.is_empty() && self.artist_names.is_empty()
+    }
+
+    /// The entry that blocks `track`, if any: its ID, its album, or one of its artists by ID or name
+    pub fn blocks(&self, track: &GwTrack) -> Option<String> {
+        let id = track.id_str();
+        if self.tracks.contains(&id) {
+            return Some(format!("track {}", id));
+        }
+        let album = track.album_id_str();
+        if self.albums.contains(&album) {
+            return Some(format!("album {}", album));
+        }
+        if let Some(artist) = self.artists.iter().find(|artist| track.credits_artist(artist)) {
+            return Some(format!("artist {}", artist));
+        }
+        track
+            .artist_names()
+            .into_iter()
+            .chain(track.featured_artists())
+            .find(|name| self.artist_names.contains(&name.to_lowercase()))
+    }
+}
@@ -0,0 +1,225 @@
+use futures_util::StreamExt;
+use futures_util::stream::BoxStream;
+use serde_json::Value;
+use std::future::Future;
+
+use crate::api::DeezerApi;
+use crate::error::Result;
+use crate::models::*;
+use crate::retry::RetryPolicy;
+
+/// A CDN download: its announced size and its chunks as they arrive
+pub struct Download {
+    pub len: Option<u64>,
+    pub chunks: BoxStream<'static, Result<Vec<u8>>>,
+}
+
+/// Body of a [`DeezerClient::post`] request
+#[derive(Debug, Clone)]
+pub enum PostBody {
+    Json(Value),
+    Text(String),
+}
+
+/// The Deezer calls the downloader and library commands rely on.
+///
+/// [`DeezerApi`] is the real implementation. Tests can provide an in-memory
+/// fake, and other backends (a cache, recorded responses) can wrap or replace it.
+pub trait DeezerClient: Sync {
+    /// Start a CDN or other non-API download; a non-success status is a `DeezerError::Cdn`
+    fn fetch_stream(&self, url: &str) -> impl Future<Output = Result<Download>> + Send;
+    /// Download a URL whole
+    fn fetch_bytes(&self, url: &str) -> impl Future<Output = Result<Vec<u8>>> + Send {
+        async move {
+            let mut download = self.fetch_stream(url).await?;
+            let mut data = Vec::with_capacity(download.len.unwrap_or(0) as usize);
+            while let Some(chunk) = download.chunks.next().await {
+                data.extend_from_slice(&chunk?);
+            }
+            Ok(data)
+        }
+    }
+    /// POST to a webhook or chat service; returns the HTTP status
+    fn post(&self, url: &str, headers: &[(&str, String)], body: PostBody)
+    -> impl Future<Output = Result<u16>> + Send;
+    fn retry_policy(&self) -> RetryPolicy;
+    /// The logged-in user, if any
+    fn user(&self) -> impl Future<Output = Option<CurrentUser>> + Send;
+
+    fn get_track(&self, sng_id: &str) -> impl Future<Output = Result<GwTrack>> + Send;
+    fn get_tracks_by_ids(&self, ids: &[String]) -> impl Future<Output = Result<Vec<GwTrack>>> + Send;
+    /// `deezer.pageTrack`: the track with its contributors, lyrics and other page-only details
+    fn get_track_page(&self, sng_id: &str) -> impl Future<Output = Result<TrackPage>> + Send;
+    /// Pipe API lyrics, with line translations into `lang` where Deezer has them; `None` without lyrics
+    fn get_lyrics(&self, sng_id: &str, lang: Option<&str>)
+    -> impl Future<Output = Result<Option<LyricsData>>> + Send;
+    /// Stream URL for a track token in one format (`FLAC`, `MP3_320`...); `None` when not available
+    fn get_track_url(&self, track_token: &str, format: &str)
+    -> impl Future<Output = Result<Option<String>>> + Send;
+    /// Stream URLs for many `(track_token, format)` pairs at once, aligned with `requests`
+    fn get_track_urls(&self, requests: &[(&str, &str)]) -> impl Future<Output = Result<Vec<Option<String>>>> + Send;
+    /// Fetch URLs for many `(track_token, format)` pairs ahead of `get_track_url`
+    fn prefetch_track_urls(&self, requests: &[(&str, &str)]) -> impl Future<Output = Result<()>> + Send;
+
+    fn get_playlist_info(&self, playlist_id: &str) -> impl Future<Output = Result<PlaylistPage>> + Send;
+    fn get_playlist_tracks(&self, playlist_id: &str) -> impl Future<Output = Result<Vec<GwTrack>>> + Send;
+    fn get_user_playlists(&self, user_id: u64) -> impl Future<Output = Result<Vec<PlaylistInfo>>> + Send;
+    fn get_favorite_track_ids(&self) -> impl Future<Output = Result<Vec<String>>> + Send;
+
+    /// Add a track to the user's favorites
+    fn add_favorite_track(&self, sng_id: &str) -> impl Future<Output = Result<()>> + Send;
+    /// Create a playlist owned by the user; returns its ID
+    fn create_playlist(&self, title: &str, description: &str, sng_ids: &[String])
+    -> impl Future<Output = Result<String>> + Send;
+    fn add_playlist_tracks(&self, playlist_id: &str, sng_ids: &[String]) -> impl Future<Output = Result<()>> + Send;
+    /// Add someone else's playlist to the user's favorite playlists
+    fn add_favorite_playlist(&self, playlist_id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn get_album_data(&self, alb_id: &str) -> impl Future<Output = Result<AlbumData>> + Send;
+    fn get_album_tracks(&self, alb_id: &str) -> impl Future<Output = Result<Vec<GwTrack>>> + Send;
+    /// Public API album (`/album/{id}`); an error when Deezer has no such album
+    fn get_album_public(&self, alb_id: &str) -> impl Future<Output = Result<PublicAlbum>> + Send;
+
+    fn get_artist_info(&self, art_id: &str) -> impl Future<Output = Result<ArtistData>> + Send;
+    /// Releases of an artist; `mode` is `all` or `featured`
+    fn get_artist_discography(&self, art_id: &str, mode: &str)
+    -> impl Future<Output = Result<Vec<AlbumInfo>>> + Send;
+    fn get_artist_top(&self, art_id: &str, limit: usize) -> impl Future<Output = Result<Vec<String>>> + Send;
+    fn get_related_artists(&self, art_id: &str, limit: usize)
+    -> impl Future<Output = Result<Vec<(String, String)>>> + Send;
+    fn get_followed_artists(&self, user_id: u64) -> impl Future<Output = Result<Vec<(String, String)>>> + Send;
+
+    fn search_artist(&self, query: &str) -> impl Future<Output = Result<Vec<ArtistSearchResult>>> + Send;
+    /// Public API track search (`/search/track`)
+    fn search_track(&self, query: &str) -> impl Future<Output = Result<Vec<SearchTrackResult>>> + Send;
+    /// Public API track for an ISRC; `None` when Deezer has none
+    fn get_track_by_isrc(&self, isrc: &str) -> impl Future<Output = Result<Option<SearchTrackResult>>> + Send;
+    /// MusicBrainz recordings for an ISRC; `Null` when unknown
+    fn musicbrainz_isrc(&self, isrc: &str) -> impl Future<Output = Result<Value>> + Send;
+}
+
+impl DeezerClient for DeezerApi {
+    async fn fetch_stream(&self, url: &str) -> Result<Download> {
+        DeezerApi::fetch_stream(self, url).await
+    }
+
+    async fn post(&self, url: &str, headers: &[(&str, String)], body: PostBody) -> Result<u16> {
+        DeezerApi::post(self, url, headers, body).await
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        DeezerApi::retry_policy(self)
+    }
+
+    async fn user(&self) -> Option<CurrentUser> {
+        self.current_user.lock().await.clone()
+    }
+
+    async fn get_track(&self, sng_id: &str) -> Result<GwTrack> {
+        DeezerApi::get_track(self, sng_id).await
+    }
+
+    async fn get_tracks_by_ids(&self, ids: &[String]) -> Result<Vec<GwTrack>> {
+        DeezerApi::get_tracks_by_ids(self, ids).await
+    }
+
+    async fn get_track_page(&self, sng_id: &str) -> Result<TrackPage> {
+        DeezerApi::get_track_page(self, sng_id).await
+    }
+
+    async fn get_lyrics(&self, sng_id: &str, lang: Option<&str>) -> Result<Option<LyricsData>> {
+        DeezerApi::get_lyrics(self, sng_id, lang).await
+    }
+
+    async fn get_track_url(&self, track_token: &str, format: &str) -> Result<Option<String>> {
+        DeezerApi::get_track_url(self, track_token, format).await
+    }
+
+    async fn get_track_urls(&self, requests: &[(&str, &str)]) -> Result<Vec<Option<String>>> {
+        DeezerApi::get_track_urls(self, requests).await
+    }
+
+    async fn prefetch_track_urls(&self, requests: &[(&str, &str)]) -> Result<()> {
+        DeezerApi::prefetch_track_urls(self, requests).await
+    }
+
+    async fn get_playlist_info(&self, playlist_id: &str) -> Result<PlaylistPage> {
+        DeezerApi::get_playlist_info(self, playlist_id).await
+    }
+
+    async fn get_playlist_tracks(&self, playlist_id: &str) -> Result<Vec<GwTrack>> {
+        DeezerApi::get_playlist_tracks(self, playlist_id).await
+    }
+
+    async fn get_user_playlists(&self, user_id: u64) -> Result<Vec<PlaylistInfo>> {
+        DeezerApi::get_user_playlists(self, user_id).await
+    }
+
+    async fn get_favorite_track_ids(&self) -> Result<Vec<String>> {
+        DeezerApi::get_favorite_track_ids(self).await
+    }
+
+    async fn add_favorite_track(&self, sng_id: &str) -> Result<()> {
+        DeezerApi::add_favorite_track(self, sng_id).await
+    }
+
+    async fn create_playlist(&self, title: &str, description: &str, sng_ids: &[String]) -> Result<String> {
+        DeezerApi::create_playlist(self, title, description, sng_ids).await
+    }
+
+    async fn add_playlist_tracks(&self, playlist_id: &str, sng_ids: &[String]) -> Result<()> {
+        DeezerApi::add_playlist_tracks(self, playlist_id, sng_ids).await
+    }
+
+    async fn add_favorite_playlist(&self, playlist_id: &str) -> Result<()> {
+        DeezerApi::add_favorite_playlist(self, playlist_id).await
+    }
+
+    async fn get_album_data(&self, alb_id: &str) -> Result<AlbumData> {
+        DeezerApi::get_album_data(self, alb_id).await
+    }
+
+    async fn get_album_tracks(&self, alb_id: &str) -> Result<Vec<GwTrack>> {
+        DeezerApi::get_album_tracks(self, alb_id).await
+    }
+
+    async fn get_album_public(&self, alb_id: &str) -> Result<PublicAlbum> {
+        DeezerApi::get_album_public(self, alb_id).await
+    }
+
+    async fn get_artist_info(&self, art_id: &str) -> Result<ArtistData> {
+        DeezerApi::get_artist_info(self, art_id).await
+    }
+
+    async fn get_artist_discography(&self, art_id: &str, mode: &str) -> Result<Vec<AlbumInfo>> {
+        DeezerApi::get_artist_discography(self, art_id, mode).await
+    }
+
+    async fn get_artist_top(&self, art_id: &str, limit: usize) -> Result<Vec<String>> {
+        DeezerApi::get_artist_top(self, art_id, limit).await
+    }
+
+    async fn get_related_artists(&self, art_id: &str, limit: usize) -> Result<Vec<(String, String)>> {
+        DeezerApi::get_related_artists(self, art_id, limit).await
+    }
+
+    async fn get_followed_artists(&self, user_id: u64) -> Result<Vec<(String, String)>> {
+        DeezerApi::get_followed_artists(self, user_id).await
+    }
+
+    async fn search_artist(&self, query: &str) -> Result<Vec<ArtistSearchResult>> {
+        DeezerApi::search_artist(self, query).await
+    }
+
+    async fn search_track(&self, query: &str) -> Result<Vec<SearchTrackResult>> {
+        DeezerApi::search_track(self, query).await
+    }
+
+    async fn get_track_by_isrc(&self, isrc: &str) -> Result<Option<SearchTrackResult>> {
+        DeezerApi::get_track_by_isrc(self, isrc).await
+    }
+
+    async fn musicbrainz_isrc(&self, isrc: &str) -> Result<Value> {
+        DeezerApi::musicbrainz_isrc(self, isrc).await
+    }
+}
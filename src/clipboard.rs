@@ -0,0 +1,38 @@
+use anyhow::{Context, Result};
+use std::time::Duration;
+
+use crate::control;
+use crate::queue;
+use crate::tr;
+
+/// Run `watch-clipboard`: poll the clipboard every `interval` and add the Deezer track,
+/// playlist and artist URLs copied to it to the queue, until Ctrl+C.
+/// What is on the clipboard when it starts is left alone.
+pub async fn watch(interval: Duration) -> Result<()> {
+    let mut clipboard = arboard::Clipboard::new().context("Could not open the system clipboard")?;
+    let mut last = clipboard.get_text().ok();
+    println!("{}", tr!("clipboard.watching"));
+
+    loop {
+        tokio::time::sleep(interval).await;
+        // Images and other non-text content read as an error; they are not URLs either
+        let text = clipboard.get_text().ok();
+        if text.is_none() || text == last {
+            continue;
+        }
+        let urls = deezer_urls(text.as_deref().unwrap_or_default());
+        last = text;
+        if !urls.is_empty() {
+            queue::add(&urls).await?;
+        }
+    }
+}
+
+/// The Deezer URLs in a piece of copied text, in order
+pub fn deezer_urls(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_matches(|c: char| matches!(c, '<' | '>' | '(' | ')' | '"' | '\'' | ',' | ';')))
+        .filter(|word| word.contains("deezer.com/") && control::parse_url(word).is_some())
+        .map(str::to_string)
+        .collect()
+}
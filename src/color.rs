@@ -0,0 +1,101 @@
+use console::{Style, StyledObject};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// When to color console output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// `[theme]`: styles of the status tags, as dotted style names such as `green`,
+/// `red.bold` or `black.on_yellow`; an empty string leaves the tag plain
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub ok: String,
+    pub skip: String,
+    pub warn: String,
+    pub err: String,
+    /// `[alt]`, `[clean]` and `[fallback]`
+    pub note: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            ok: "green".into(),
+            skip: "cyan".into(),
+            warn: "yellow".into(),
+            err: "red.bold".into(),
+            note: "magenta".into(),
+        }
+    }
+}
+
+struct Styles {
+    ok: Style,
+    skip: Style,
+    warn: Style,
+    err: Style,
+    note: Style,
+}
+
+impl Styles {
+    fn new(theme: &Theme) -> Self {
+        Self {
+            ok: Style::from_dotted_str(&theme.ok),
+            skip: Style::from_dotted_str(&theme.skip),
+            warn: Style::from_dotted_str(&theme.warn).for_stderr(),
+            err: Style::from_dotted_str(&theme.err).for_stderr(),
+            note: Style::from_dotted_str(&theme.note),
+        }
+    }
+}
+
+static STYLES: OnceLock<Styles> = OnceLock::new();
+
+fn styles() -> &'static Styles {
+    STYLES.get_or_init(|| Styles::new(&Theme::default()))
+}
+
+/// Apply `--color` and the theme for the rest of the run; progress bars and prompts follow too
+pub fn init(choice: ColorChoice, theme: &Theme) {
+    let enabled = match choice {
+        ColorChoice::Always => Some(true),
+        ColorChoice::Never => Some(false),
+        ColorChoice::Auto if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) => Some(false),
+        ColorChoice::Auto => None,
+    };
+    if let Some(enabled) = enabled {
+        console::set_colors_enabled(enabled);
+        console::set_colors_enabled_stderr(enabled);
+    }
+    let _ = STYLES.set(Styles::new(theme));
+}
+
+pub fn ok() -> StyledObject<&'static str> {
+    styles().ok.apply_to("[ok]")
+}
+
+pub fn skip() -> StyledObject<&'static str> {
+    styles().skip.apply_to("[skip]")
+}
+
+pub fn warn() -> StyledObject<&'static str> {
+    styles().warn.apply_to("[warn]")
+}
+
+pub fn err() -> StyledObject<&'static str> {
+    styles().err.apply_to("[err]")
+}
+
+/// A secondary tag such as `[alt]`
+pub fn note(tag: &'static str) -> StyledObject<&'static str> {
+    styles().note.apply_to(tag)
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::client::DeezerClient;
+use crate::color;
+use crate::identity::{self, LocalFile};
+use crate::models::GwTrack;
+use crate::tr;
+
+/// Tracks per `song.getListData` call
+const BATCH_SIZE: usize = 50;
+
+/// What `compare` checks the local library against
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum CompareTarget {
+    /// Favorites missing from disk, and local files whose tracks are not favorited
+    Favorites {
+        /// Library directory to compare (default: the favorites output directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+}
+
+/// Run `compare`; `favorites_dir` is used when no `--dir` is given
+pub async fn run(api: &impl DeezerClient, target: &CompareTarget, favorites_dir: &Path) -> Result<()> {
+    match target {
+        CompareTarget::Favorites { dir } => favorites(api, dir.as_deref().unwrap_or(favorites_dir)).await,
+    }
+}
+
+/// Report the favorites that have no file under `dir` (by Deezer ID or ISRC) and the files
+/// under `dir` that match no favorite. Nothing is downloaded, deleted or favorited.
+pub async fn favorites(api: &impl DeezerClient, dir: &Path) -> Result<()> {
+    let dir = tokio::fs::canonicalize(dir).await.unwrap_or_else(|_| dir.to_path_buf());
+    println!("{}", tr!("compare.fetching"));
+    let ids = api.get_favorite_track_ids().await?;
+    let mut tracks: Vec<GwTrack> = Vec::with_capacity(ids.len());
+    for batch in ids.chunks(BATCH_SIZE) {
+        tracks.extend(api.get_tracks_by_ids(batch).await?);
+    }
+    let mut files = identity::local_files(&dir).await;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    println!("{}\n", tr!("compare.header", favorites = tracks.len(), files = files.len(), dir = dir.display()));
+
+    let local_ids: HashSet<&str> = files.iter().filter_map(|f| f.sng_id.as_deref()).collect();
+    let local_isrcs: HashSet<String> = files.iter().filter_map(|f| f.isrc.as_deref()).map(str::to_uppercase).collect();
+    let missing: Vec<&GwTrack> = tracks
+        .iter()
+        .filter(|t| !local_ids.contains(t.id_str().as_str()) && !isrc(t).is_some_and(|i| local_isrcs.contains(&i)))
+        .collect();
+
+    let favorite_ids: HashSet<String> = ids.iter().cloned().collect();
+    let favorite_isrcs: HashSet<String> = tracks.iter().filter_map(isrc).collect();
+    let unidentified = files.iter().filter(|f| f.sng_id.is_none() && f.isrc.is_none()).count();
+    let unfavorited: Vec<&LocalFile> = files
+        .iter()
+        .filter(|f| f.sng_id.is_some() || f.isrc.is_some())
+        .filter(|f| {
+            !f.sng_id.as_ref().is_some_and(|id| favorite_ids.contains(id))
+                && !f.isrc.as_ref().is_some_and(|i| favorite_isrcs.contains(&i.to_uppercase()))
+        })
+        .collect();
+
+    if !missing.is_empty() {
+        println!("{}", tr!("compare.missing", count = missing.len()));
+        for track in &missing {
+            let id = tr!("diff.track_id", id = track.id_str());
+            println!("  {} {} ({})", color::note("[-]"), track.display_name(), id);
+        }
+        println!();
+    }
+    if !unfavorited.is_empty() {
+        println!("{}", tr!("compare.unfavorited", count = unfavorited.len()));
+        for file in &unfavorited {
+            let path = file.path.strip_prefix(&dir).unwrap_or(&file.path);
+            println!("  {} {}", color::note("[+]"), path.display());
+        }
+        println!();
+    }
+    println!(
+        "{}",
+        tr!(
+            "compare.summary",
+            missing = missing.len(),
+            unfavorited = unfavorited.len(),
+            unidentified = unidentified,
+        )
+    );
+    Ok(())
+}
+
+/// Upper-cased ISRC of a track, if Deezer has one
+fn isrc(track: &GwTrack) -> Option<String> {
+    track.isrc.as_deref().filter(|i| !i.is_empty()).map(str::to_uppercase)
+}
@@ -0,0 +1,283 @@
+use anyhow::{Context, Result, bail};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::Notify;
+
+use crate::color;
+use crate::links::{self, LinkKind};
+use crate::pause::PauseSwitch;
+use crate::tr;
+
+/// Commands sent to a running daemon with `ctl`
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum CtlCommand {
+    /// Show what the daemon is doing
+    Status,
+    /// Download a track, playlist or artist URL as soon as the current job is done
+    Queue {
+        /// Deezer track, playlist or artist URL
+        url: String,
+    },
+    /// Hold downloads, including the track in progress, until `resume`
+    Pause,
+    /// Continue after `pause`
+    Resume,
+}
+
+impl CtlCommand {
+    fn to_line(&self) -> String {
+        match self {
+            CtlCommand::Status => "status".to_string(),
+            CtlCommand::Queue { url } => format!("queue {}", url),
+            CtlCommand::Pause => "pause".to_string(),
+            CtlCommand::Resume => "resume".to_string(),
+        }
+    }
+}
+
+/// Kind of Deezer item that can be queued
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Track,
+    Playlist,
+    Artist,
+}
+
+/// (kind, ID) of a Deezer URL such as `https://www.deezer.com/en/playlist/908622995`,
+/// in any shape [`links::parse`] understands; albums cannot be queued
+pub fn parse_url(url: &str) -> Option<(ItemKind, String)> {
+    let (kind, id) = links::parse(url)?;
+    let kind = match kind {
+        LinkKind::Track => ItemKind::Track,
+        LinkKind::Playlist => ItemKind::Playlist,
+        LinkKind::Artist => ItemKind::Artist,
+        LinkKind::Album => return None,
+    };
+    Some((kind, id))
+}
+
+/// State shared between the daemon loop and the control socket
+#[derive(Default)]
+pub struct DaemonState {
+    activity: Mutex<String>,
+    queue: Mutex<VecDeque<(ItemKind, String)>>,
+    /// `queue add` URLs attempted during the current pass; one that fails waits for the next pass
+    queue_tried: Mutex<HashSet<String>>,
+    pause: PauseSwitch,
+    wake: Notify,
+}
+
+impl DaemonState {
+    pub fn set_activity(&self, activity: impl Into<String>) {
+        *self.activity.lock().unwrap() = activity.into();
+    }
+
+    pub fn next_queued(&self) -> Option<(ItemKind, String)> {
+        self.queue.lock().unwrap().pop_front()
+    }
+
+    pub fn queue_tried(&self) -> HashSet<String> {
+        self.queue_tried.lock().unwrap().clone()
+    }
+
+    pub fn mark_queue_tried(&self, url: &str) {
+        self.queue_tried.lock().unwrap().insert(url.to_string());
+    }
+
+    /// A new pass retries `queue add` items that failed in the previous one
+    pub fn start_pass(&self) {
+        self.queue_tried.lock().unwrap().clear();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
+    /// Switch for the jobs' downloads, so `pause` also holds the track being downloaded
+    pub fn pause_switch(&self) -> PauseSwitch {
+        self.pause.clone()
+    }
+
+    /// Block while paused
+    pub async fn wait_if_paused(&self) {
+        if self.is_paused() {
+            self.set_activity("paused");
+        }
+        self.pause.wait().await;
+    }
+
+    /// Sleep for `duration`, returning early when an item is queued or the daemon is resumed
+    pub async fn sleep(&self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.wake.notified() => {}
+        }
+    }
+
+    /// Apply one command line and return the reply
+    fn handle(&self, line: &str) -> String {
+        let (command, arg) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match command {
+            "status" => {
+                let state = if self.is_paused() { "paused" } else { "running" };
+                format!(
+                    "state: {}\nactivity: {}\nqueued: {}\n",
+                    state,
+                    self.activity.lock().unwrap(),
+                    self.queue.lock().unwrap().len()
+                )
+            }
+            "queue" => match parse_url(arg.trim()) {
+                Some(item) => {
+                    let mut queue = self.queue.lock().unwrap();
+                    queue.push_back(item);
+                    let position = queue.len();
+                    drop(queue);
+                    self.wake.notify_one();
+                    format!("queued at position {}\n", position)
+                }
+                None => format!("error: not a Deezer track, playlist or artist URL: {}\n", arg.trim()),
+            },
+            "pause" => {
+                self.pause.pause();
+                "paused (the current download holds after its current chunk)\n".to_string()
+            }
+            "resume" => {
+                self.pause.resume();
+                self.wake.notify_one();
+                "resumed\n".to_string()
+            }
+            _ => format!("error: unknown command '{}' (status, queue <url>, pause, resume)\n", command),
+        }
+    }
+}
+
+/// Read one command from a connection and write the reply
+async fn handle_connection<S: AsyncRead + AsyncWrite + Unpin>(stream: S, state: &DaemonState) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    stream.read_line(&mut line).await?;
+    let reply = state.handle(&line);
+    let mut stream = stream.into_inner();
+    stream.write_all(reply.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Send a command and read the reply
+async fn request<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, command: &CtlCommand) -> Result<String> {
+    stream.write_all(format!("{}\n", command.to_line()).as_bytes()).await?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).await?;
+    Ok(reply)
+}
+
+#[cfg(unix)]
+pub fn socket_path() -> std::path::PathBuf {
+    crate::auth::config_dir().join("daemon.sock")
+}
+
+#[cfg(windows)]
+const PIPE_NAME: &str = r"\\.\pipe\deezer-dl";
+
+/// Accept control connections until the process exits
+#[cfg(unix)]
+pub async fn serve(state: Arc<DaemonState>) -> Result<()> {
+    let path = socket_path();
+    if tokio::net::UnixStream::connect(&path).await.is_ok() {
+        bail!("Another daemon is already listening on {}", path.display());
+    }
+    // Left behind by a daemon that did not shut down cleanly
+    let _ = std::fs::remove_file(&path);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let listener = tokio::net::UnixListener::bind(&path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+    println!("{}", tr!("control.socket", path = path.display()));
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connection(stream, &state).await {
+                            eprintln!("  {} {}", color::warn(), tr!("control.connection_failed", error = e));
+                        }
+                    });
+                }
+                Err(e) => eprintln!("  {} {}", color::warn(), tr!("control.accept_failed", error = e)),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Accept control connections until the process exits
+#[cfg(windows)]
+pub async fn serve(state: Arc<DaemonState>) -> Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(PIPE_NAME)
+        .with_context(|| format!("Failed to create {} (is another daemon running?)", PIPE_NAME))?;
+    println!("{}", tr!("control.pipe", name = PIPE_NAME));
+
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = server.connect().await {
+                eprintln!("  {} {}", color::warn(), tr!("control.accept_failed", error = e));
+                continue;
+            }
+            let connected = server;
+            server = match ServerOptions::new().create(PIPE_NAME) {
+                Ok(next) => next,
+                Err(e) => {
+                    eprintln!("  {} {}", color::warn(), tr!("control.pipe_stopped", error = e));
+                    return;
+                }
+            };
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(connected, &state).await {
+                    eprintln!("  {} {}", color::warn(), tr!("control.connection_failed", error = e));
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+/// `ctl`: send a command to the running daemon and print its reply
+pub async fn ctl(command: &CtlCommand) -> Result<()> {
+    if !try_ctl(command).await? {
+        #[cfg(unix)]
+        bail!("No daemon listening on {}", socket_path().display());
+        #[cfg(windows)]
+        bail!("No daemon listening on {}", PIPE_NAME);
+    }
+    Ok(())
+}
+
+/// Send a command to the daemon and print its reply; `Ok(false)` when no daemon is listening
+pub async fn try_ctl(command: &CtlCommand) -> Result<bool> {
+    #[cfg(unix)]
+    let Ok(stream) = tokio::net::UnixStream::connect(socket_path()).await else {
+        return Ok(false);
+    };
+    #[cfg(windows)]
+    let Ok(stream) = tokio::net::windows::named_pipe::ClientOptions::new().open(PIPE_NAME) else {
+        return Ok(false);
+    };
+
+    let reply = request(stream, command).await?;
+    if let Some(message) = reply.strip_prefix("error: ") {
+        bail!("{}", message.trim());
+    }
+    print!("{}", reply);
+    Ok(true)
+}
@@ -0,0 +1,106 @@
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::download;
+use crate::shutdown::PartialFile;
+
+/// Target format for `--convert`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConvertFormat {
+    Opus,
+    Ogg,
+    Aac,
+    Alac,
+}
+
+impl ConvertFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConvertFormat::Opus => "opus",
+            ConvertFormat::Ogg => "ogg",
+            ConvertFormat::Aac | ConvertFormat::Alac => "m4a",
+        }
+    }
+
+    /// ffmpeg stream mapping and codec arguments. MP4 containers keep the
+    /// embedded cover as an attached picture; Ogg cannot carry it through ffmpeg.
+    fn ffmpeg_args(&self) -> &'static [&'static str] {
+        match self {
+            ConvertFormat::Opus => &["-map", "0:a", "-c:a", "libopus", "-b:a", "128k"],
+            ConvertFormat::Ogg => &["-map", "0:a", "-c:a", "libvorbis", "-q:a", "6"],
+            ConvertFormat::Aac => &[
+                "-map",
+                "0",
+                "-c:a",
+                "aac",
+                "-b:a",
+                "256k",
+                "-c:v",
+                "copy",
+                "-disposition:v",
+                "attached_pic",
+            ],
+            ConvertFormat::Alac => &[
+                "-map",
+                "0",
+                "-c:a",
+                "alac",
+                "-c:v",
+                "copy",
+                "-disposition:v",
+                "attached_pic",
+            ],
+        }
+    }
+
+    /// ffmpeg muxer, given explicitly since the `.part` output name hides the extension
+    fn muxer(&self) -> &'static str {
+        match self {
+            ConvertFormat::Opus => "opus",
+            ConvertFormat::Ogg => "ogg",
+            ConvertFormat::Aac | ConvertFormat::Alac => "ipod",
+        }
+    }
+
+    /// Path of the converted file for a downloaded `path`
+    pub fn output_path(&self, path: &Path) -> PathBuf {
+        path.with_extension(self.extension())
+    }
+}
+
+/// Transcode `input` with ffmpeg, keeping its tags, and remove the original on success
+pub async fn convert(input: &Path, format: ConvertFormat) -> Result<PathBuf> {
+    let output = format.output_path(input);
+    let part = download::part_path(&output);
+
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(input)
+        .args(format.ffmpeg_args())
+        .args(["-map_metadata", "0", "-f", format.muxer()])
+        .arg(&part)
+        .stdin(Stdio::null());
+    // Out of the terminal's process group, so Ctrl+C lets the conversion finish
+    #[cfg(unix)]
+    command.process_group(0);
+    let partial = PartialFile::new(&part);
+    let result = command.output().await.context("Failed to run ffmpeg")?;
+
+    if !result.status.success() {
+        let _ = tokio::fs::remove_file(&part).await;
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        bail!("ffmpeg failed: {}", stderr.trim());
+    }
+    tokio::fs::rename(&part, &output)
+        .await
+        .with_context(|| format!("Failed to move {} into place", output.display()))?;
+    partial.complete();
+    tokio::fs::remove_file(input)
+        .await
+        .with_context(|| format!("Failed to remove {}", input.display()))?;
+    Ok(output)
+}
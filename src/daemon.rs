@@ -0,0 +1,175 @@
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::client::DeezerClient;
+use crate::color;
+use crate::config::{DaemonConfig, OutputDirs};
+use crate::control::{self, DaemonState};
+use crate::download::{self, DownloadOptions};
+use crate::queue;
+use crate::sync::{self, PruneArgs};
+use crate::tr;
+use crate::watch;
+
+/// What one daemon pass works through; IDs are already extracted from URLs
+pub struct DaemonJobs {
+    pub playlists: Vec<String>,
+    pub favorites: bool,
+    pub artists: Vec<String>,
+    pub followed_artists: bool,
+    pub since: Option<String>,
+    pub prune: PruneArgs,
+}
+
+impl DaemonJobs {
+    pub fn new(config: &DaemonConfig, playlists: Vec<String>, artists: Vec<String>) -> Self {
+        Self {
+            playlists,
+            favorites: config.favorites,
+            artists,
+            followed_artists: config.followed_artists,
+            since: config.since.clone(),
+            prune: PruneArgs {
+                prune: config.prune,
+                quarantine: config.quarantine.clone(),
+            },
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.playlists.is_empty() && !self.favorites && self.artists.is_empty() && !self.followed_artists
+    }
+}
+
+/// Sync everything once. A failing job is reported and the others still run, unless its
+/// error stops every job (Ctrl+C, login lost). Queued items and `pause` are handled between jobs.
+pub async fn run_once(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    jobs: &DaemonJobs,
+    outputs: &OutputDirs,
+    state: &DaemonState,
+) -> Result<()> {
+    for playlist_id in &jobs.playlists {
+        drain_queue(api, opts, outputs, state).await?;
+        state.set_activity(format!("syncing playlist {}", playlist_id));
+        println!("\n{}", tr!("daemon.playlist", id = playlist_id));
+        if let Err(e) = sync::sync_playlist(api, playlist_id, opts, &outputs.playlists, &jobs.prune).await {
+            if download::stops_job(&e) {
+                return Err(e);
+            }
+            eprintln!("{} {}", color::err(), tr!("daemon.playlist_failed", id = playlist_id, error = e));
+        }
+    }
+
+    if jobs.favorites {
+        drain_queue(api, opts, outputs, state).await?;
+        state.set_activity("syncing favorites");
+        println!("\n{}", tr!("daemon.favorites"));
+        if let Err(e) = sync::sync_favorites(api, opts, &outputs.favorites, &jobs.prune).await {
+            if download::stops_job(&e) {
+                return Err(e);
+            }
+            eprintln!("{} {}", color::err(), tr!("daemon.favorites_failed", error = e));
+        }
+    }
+
+    drain_queue(api, opts, outputs, state).await?;
+    state.set_activity("checking watched artists");
+    let artists = watch::resolve_artists(api, &jobs.artists, jobs.followed_artists).await;
+    if !artists.is_empty() {
+        println!("\n{}", tr!("daemon.artists", count = artists.len()));
+        match watch::check(api, opts, &artists, jobs.since.as_deref(), &outputs.artists).await {
+            Ok(0) => println!("{}", tr!("watch.none")),
+            Ok(found) => println!("\n{}", tr!("daemon.grabbed", count = found)),
+            Err(e) if download::stops_job(&e) => return Err(e),
+            Err(e) => eprintln!("{} {}", color::err(), tr!("daemon.artists_failed", error = e)),
+        }
+    }
+    drain_queue(api, opts, outputs, state).await
+}
+
+/// Download items queued with `ctl queue`, then those added with `queue add`,
+/// waiting first while paused
+async fn drain_queue(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    outputs: &OutputDirs,
+    state: &DaemonState,
+) -> Result<()> {
+    state.wait_if_paused().await;
+    while let Some((kind, id)) = state.next_queued() {
+        state.set_activity(format!("downloading queued {:?} {}", kind, id).to_lowercase());
+        println!("\n{}", tr!("daemon.queued", kind = format!("{:?}", kind), id = id));
+        if let Err(e) = queue::download_item(api, opts, outputs, kind, &id).await {
+            if download::stops_job(&e) {
+                return Err(e);
+            }
+            let kind = format!("{:?}", kind);
+            eprintln!("{} {}", color::err(), tr!("daemon.queued_failed", kind = kind, id = id, error = e));
+        }
+        state.wait_if_paused().await;
+    }
+
+    loop {
+        let entry = match queue::next(&state.queue_tried()).await {
+            Ok(Some(entry)) => entry,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("{} {}", color::warn(), tr!("queue.read_failed", error = e));
+                break;
+            }
+        };
+        state.mark_queue_tried(&entry.url);
+        state.set_activity(format!("downloading queued {}", entry.url));
+        println!("\n{}", tr!("queue.item", url = entry.url));
+        if let Err(e) = queue::download_entry(api, opts, outputs, &entry).await {
+            if download::stops_job(&e) {
+                return Err(e);
+            }
+            eprintln!("{} {}", color::err(), tr!("queue.item_failed", url = entry.url, error = e));
+        }
+        state.wait_if_paused().await;
+    }
+    Ok(())
+}
+
+/// How often an idle daemon looks for items added with `queue add`
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Run `daemon`: sync everything, then again every `interval`, until interrupted
+pub async fn run(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    jobs: &DaemonJobs,
+    interval: Duration,
+    outputs: &OutputDirs,
+) -> Result<()> {
+    if jobs.is_empty() {
+        anyhow::bail!(
+            "Nothing to sync: add playlists, favorites or followed_artists under [daemon] (or watch_artists) in config.toml"
+        );
+    }
+    let state = Arc::new(DaemonState::default());
+    control::serve(state.clone()).await?;
+    let opts = &DownloadOptions {
+        pause: state.pause_switch(),
+        ..opts.clone()
+    };
+
+    loop {
+        println!("{}", tr!("daemon.pass_start"));
+        state.start_pass();
+        run_once(api, opts, jobs, outputs, &state).await?;
+        println!("\n{}\n", tr!("daemon.pass_done", secs = interval.as_secs()));
+
+        // Between passes, queued items are downloaded as soon as they arrive
+        let next_pass = Instant::now() + interval;
+        while let Some(remaining) = next_pass.checked_duration_since(Instant::now()) {
+            state.set_activity(format!("idle, next pass in {}s", remaining.as_secs()));
+            state.sleep(remaining.min(QUEUE_POLL_INTERVAL)).await;
+            drain_queue(api, opts, outputs, &state).await?;
+        }
+    }
+}
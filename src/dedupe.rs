@@ -0,0 +1,234 @@
+use anyhow::{Context, Result};
+use indicatif::HumanBytes;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::color;
+use crate::download;
+use crate::history;
+use crate::library;
+use crate::tags;
+use crate::tr;
+
+/// How `--dedupe` stores a track that was already downloaded into another folder
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupeMode {
+    /// Both names share the same data on disk; falls back to a symlink across filesystems
+    Hardlink,
+    /// The new name points at the first copy
+    Symlink,
+}
+
+/// The newest downloaded copy of `sng_id` that is still on disk and has the same
+/// extension as `target` (so the same format, or the same conversion)
+pub async fn find_copy(sng_id: &str, target: &Path) -> Option<PathBuf> {
+    let entries = history::load().await.ok()?;
+    entries
+        .iter()
+        .rev()
+        .filter(|e| e.sng_id == sng_id && e.path != target && e.path.extension() == target.extension())
+        .map(|e| e.path.clone())
+        .find(|path| path.is_file())
+}
+
+/// Make `target` another name for `source`; returns how it was linked
+pub fn link(mode: DedupeMode, source: &Path, target: &Path) -> io::Result<DedupeMode> {
+    if mode == DedupeMode::Hardlink && std::fs::hard_link(source, target).is_ok() {
+        return Ok(DedupeMode::Hardlink);
+    }
+    symlink(source, target)?;
+    Ok(DedupeMode::Symlink)
+}
+
+/// One audio file found by `dedupe --scan`, with what identifies its recording
+struct ScannedFile {
+    path: PathBuf,
+    size: u64,
+    isrc: Option<String>,
+}
+
+/// `dedupe --scan`: find recordings stored more than once under `dir` (same ISRC tag,
+/// or same Deezer ID or ISRC in the history) and review each one, keeping a copy and
+/// hardlinking or deleting the others
+pub async fn scan(dir: &Path, dry_run: bool) -> Result<()> {
+    let root = tokio::fs::canonicalize(dir)
+        .await
+        .with_context(|| format!("Directory not found: {}", dir.display()))?;
+    let mut entries = history::load().await?;
+    let by_path: HashMap<PathBuf, usize> = entries.iter().enumerate().map(|(i, e)| (e.path.clone(), i)).collect();
+
+    let scan_root = root.clone();
+    let files = tokio::task::spawn_blocking(move || -> Result<Vec<ScannedFile>> {
+        // Symlinks and other names of a hardlinked file take no extra space
+        let mut inodes = HashSet::new();
+        let mut files = Vec::new();
+        for path in library::audio_files(&scan_root)? {
+            if path.is_symlink() || file_id(&path).is_some_and(|id| !inodes.insert(id)) {
+                continue;
+            }
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let isrc = tags::read_isrc(&path);
+            files.push(ScannedFile { path, size, isrc });
+        }
+        Ok(files)
+    })
+    .await??;
+    println!("{}\n", tr!("dedupe.scanning", count = files.len(), dir = root.display()));
+
+    let mut groups: BTreeMap<String, (String, Vec<ScannedFile>)> = BTreeMap::new();
+    let mut unidentified = 0;
+    for file in files {
+        let entry = by_path.get(&file.path).map(|&i| &entries[i]);
+        let isrc = file.isrc.clone().or_else(|| entry.and_then(|e| e.isrc.clone()));
+        let key = match (isrc, entry) {
+            (Some(isrc), _) => format!("isrc:{}", isrc.to_uppercase()),
+            (None, Some(entry)) => format!("id:{}", entry.sng_id),
+            (None, None) => {
+                unidentified += 1;
+                continue;
+            }
+        };
+        let label = match entry {
+            Some(e) => format!("{} - {}", e.artist, e.title),
+            None => file.path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+        };
+        groups.entry(key).or_insert_with(|| (label, Vec::new())).1.push(file);
+    }
+    let mut groups: Vec<(String, Vec<ScannedFile>)> =
+        groups.into_values().filter(|(_, files)| files.len() > 1).collect();
+    groups.sort_by_key(|(label, _)| label.to_lowercase());
+
+    let group_count = groups.len();
+    let (mut linked, mut deleted, mut freed) = (0, 0, 0);
+    let mut history_changed = false;
+    for (label, mut files) in groups {
+        // The largest copy is suggested, as it is usually the best quality
+        files.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+        println!("{}", tr!("dedupe.group", name = &label, count = files.len()));
+        let names: Vec<String> = files
+            .iter()
+            .map(|f| format!("{} ({})", f.path.strip_prefix(&root).unwrap_or(&f.path).display(), HumanBytes(f.size)))
+            .collect();
+        if dry_run {
+            for name in &names {
+                println!("  {}", name);
+            }
+            continue;
+        }
+
+        let mut choices = names.clone();
+        choices.push(tr!("dedupe.leave"));
+        choices.push(tr!("dedupe.stop"));
+        let keep = dialoguer::Select::new()
+            .with_prompt(tr!("dedupe.keep_prompt"))
+            .items(&choices)
+            .default(0)
+            .interact()?;
+        if keep == names.len() {
+            continue;
+        }
+        if keep > names.len() {
+            break;
+        }
+        let delete = dialoguer::Select::new()
+            .with_prompt(tr!("dedupe.action_prompt"))
+            .items(&[tr!("dedupe.action_hardlink"), tr!("dedupe.action_delete")])
+            .default(0)
+            .interact()?
+            == 1;
+
+        let kept = files.remove(keep);
+        for extra in files {
+            let result = if delete {
+                std::fs::remove_file(&extra.path).map(|()| None)
+            } else {
+                replace_with_link(&kept.path, &extra.path).map(Some)
+            };
+            match result {
+                Ok(target) => {
+                    freed += extra.size;
+                    history_changed = true;
+                    match target {
+                        Some(target) => {
+                            linked += 1;
+                            println!("  {} {}", color::ok(), tr!("dedupe.linked", file = target.display()));
+                            for entry in entries.iter_mut().filter(|e| e.path == extra.path) {
+                                entry.path = target.clone();
+                            }
+                        }
+                        None => {
+                            deleted += 1;
+                            println!("  {} {}", color::ok(), tr!("dedupe.deleted", file = extra.path.display()));
+                            entries.retain(|e| e.path != extra.path);
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("  {} {}", color::err(), tr!("dedupe.failed", file = extra.path.display(), error = e));
+                }
+            }
+        }
+    }
+
+    if history_changed {
+        history::save_all(&entries).await?;
+    }
+    if dry_run {
+        println!("\n{}", tr!("dedupe.dry_run_done", groups = group_count, unidentified = unidentified));
+    } else {
+        println!(
+            "\n{}",
+            tr!(
+                "dedupe.done",
+                groups = group_count,
+                linked = linked,
+                deleted = deleted,
+                freed = HumanBytes(freed),
+                unidentified = unidentified,
+            )
+        );
+    }
+    Ok(())
+}
+
+/// Replace `extra` with a hardlink to `kept`, named with the kept file's extension.
+/// The link is made next to it first, so `extra` is never lost if linking fails.
+fn replace_with_link(kept: &Path, extra: &Path) -> io::Result<PathBuf> {
+    let target = match kept.extension() {
+        Some(ext) => extra.with_extension(ext),
+        None => extra.to_path_buf(),
+    };
+    let temp = download::part_path(&target);
+    let _ = std::fs::remove_file(&temp);
+    link(DedupeMode::Hardlink, kept, &temp)?;
+    std::fs::rename(&temp, &target)?;
+    if target != extra {
+        std::fs::remove_file(extra)?;
+    }
+    Ok(target)
+}
+
+/// Device and inode, to tell names of the same file apart from copies
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(unix)]
+fn symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+#[cfg(windows)]
+fn symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(source, target)
+}
@@ -1,34 +1,737 @@
 use anyhow::{bail, Context, Result};
 use futures_util::StreamExt;
-use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 
-use crate::api::DeezerApi;
+use crate::accounts::BackupAccounts;
+use crate::client::DeezerClient;
+use crate::artwork::{self, CoverWriter, ImageKind};
+use crate::blocklist::{Blocked, Blocklist};
+use crate::color;
+use crate::convert::{self, ConvertFormat};
 use crate::crypto;
+use crate::dedupe::{self, DedupeMode};
+use crate::error::DeezerError;
+use crate::events::{Event, EventSink};
+use crate::history::{self, HistoryEntry};
+use crate::hooks;
+use crate::identity;
+use crate::library;
+use crate::lyrics;
 use crate::models::*;
+use crate::musicbrainz::{self, MusicBrainzIds};
+use crate::nfo::{self, NfoWriter};
+use crate::notify::{self, JobReport, NotifyTargets};
+use crate::pause::PauseSwitch;
+use crate::pool::{Pool, PoolView};
+use crate::progress::{JobProgress, TrackBar};
+use crate::retry::{self, Retryable};
+use crate::script::{FilterScript, ScriptDecision, ScriptSkipped};
+use crate::session::{Session, SessionPlan, SessionTrack};
+use crate::shutdown::{self, PartialFile};
+use crate::tags::{self, TrackTags};
+use crate::template::{self, TemplateVars, TrackPositions};
+use crate::throttle::BandwidthLimiter;
+use crate::tr;
 
-/// Sanitize a filename by removing/replacing invalid characters
-fn sanitize_filename(name: &str) -> String {
-    name.chars()
+/// `--quality` value choosing each track's format from what it and the account have
+pub const BEST_QUALITY: &str = "best";
+
+/// Check a `--quality` value: a format name or `best`
+pub fn parse_quality(input: &str) -> Result<String, String> {
+    if DownloadOptions::default().with_quality(input).is_none() {
+        return Err(format!(
+            "unknown quality '{}' (expected flac, 320, 128, misc, 360, 360_mid, 360_low or best)",
+            input
+        ));
+    }
+    Ok(input.to_string())
+}
+
+/// Parse a `--min-quality` format name
+pub fn parse_min_quality(input: &str) -> Result<TrackFormat, String> {
+    TrackFormat::from_quality(input)
+        .ok_or_else(|| format!("unknown quality '{}' (expected flac, 320, 128, misc, 360, 360_mid or 360_low)", input))
+}
+
+/// Tracks per batched media URL request
+pub const URL_BATCH_SIZE: usize = 25;
+
+/// Added to the name of files while they are written
+const PART_SUFFIX: &str = ".part";
+
+/// Allowed relative difference between the decrypted size and FILESIZE_*
+const SIZE_TOLERANCE: f64 = 0.05;
+
+/// Minimum time between two `TrackProgress` events for the same download
+const PROGRESS_EVENT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Options shared by every download job
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Format asked for; with `best_quality`, the best one tried
+    pub format: TrackFormat,
+    /// `--quality best`: pick each track's format from its file sizes and the account's rights
+    pub best_quality: bool,
+    /// Accounts asked for a stream when the logged-in one cannot deliver the format, or the track
+    pub backup_accounts: Option<Arc<BackupAccounts>>,
+    /// Filename template, relative to the job directory
+    pub template: String,
+    /// Transliterate file and folder names to ASCII
+    pub ascii_filenames: bool,
+    /// Longest file or folder name, in bytes
+    pub max_filename_length: usize,
+    /// Never fall back below this format
+    pub min_format: Option<TrackFormat>,
+    /// Write downgraded tracks to this JSON file at the end of a job
+    pub fallback_report: Option<PathBuf>,
+    /// Maximum CDN download speed in bytes per second
+    pub limit_rate: Option<u64>,
+    /// Search other releases (ISRC, artist + title) when a track is unavailable
+    pub alt_search: bool,
+    /// Transcode each download with ffmpeg
+    pub convert: Option<ConvertFormat>,
+    /// File name for album/playlist pictures saved next to the tracks
+    pub cover_file: Option<String>,
+    /// Edge length in pixels of the saved pictures, one of [`artwork::COVER_SIZES`]
+    pub cover_size: u32,
+    /// Write album.nfo/artist.nfo files for media centers
+    pub nfo: bool,
+    /// Put each playlist/favorites/artist/album job into its own folder.
+    /// Layout presets turn this off so the template alone decides where files go.
+    pub job_folders: bool,
+    /// Look up MusicBrainz IDs by ISRC and tag them
+    pub musicbrainz: bool,
+    /// Complete each track from its `deezer.pageTrack` page before naming and tagging it
+    pub full_metadata: bool,
+    /// Embed each track's lyrics and save them next to it as `.lrc` (or `.txt` when not synced)
+    pub lyrics: bool,
+    /// Language to translate lyrics into, when Deezer has a translation
+    pub lyrics_lang: Option<String>,
+    /// Only save lyrics files next to the tracks, without downloading any audio
+    pub lyrics_only: bool,
+    /// Skip explicit tracks, or only keep them
+    pub explicit_filter: Option<ExplicitFilter>,
+    /// Replace explicit tracks with their clean edit when Deezer has one
+    pub prefer_clean: bool,
+    /// POST a JSON summary of each finished job to this URL
+    pub webhook: Option<String>,
+    /// Show a desktop notification when a multi-track job finishes
+    pub notify: bool,
+    /// Chat services that get a summary of each finished job
+    pub notify_targets: NotifyTargets,
+    /// Shell command run after each downloaded track
+    pub exec: Option<String>,
+    /// Shell command run after each job
+    pub exec_after: Option<String>,
+    /// Rhai script deciding which tracks to download and where
+    pub script: Option<Arc<FilterScript>>,
+    /// Tracks, albums and artists skipped by every job
+    pub blocklist: Option<Arc<Blocklist>>,
+    /// Receives track and job progress (used by `rpc`)
+    pub events: Option<EventSink>,
+    /// Bar of the running job, drawn above each track's bar (set by the job functions)
+    pub job_progress: Option<JobProgress>,
+    /// Holds downloads at the next chunk (`p` key in interactive mode, `ctl pause`)
+    pub pause: PauseSwitch,
+    /// Order of the job's tracks for `{position}` (set by the playlist, favorites and top tracks jobs)
+    pub positions: TrackPositions,
+    /// Append featured artists to the title in file names and tags
+    pub feat_in_title: bool,
+    /// Append the track's version (live, remix, remaster...) to the title in file names and tags
+    pub version_in_title: bool,
+    /// Link tracks already downloaded into another folder instead of downloading them again
+    pub dedupe: Option<DedupeMode>,
+    /// Store each track once in a pool folder, named by its ID
+    pub pool: Option<Pool>,
+    /// Skip tracks whose ID or ISRC is already in the job folder under another name
+    pub identity_skip: bool,
+}
+
+impl Default for DownloadOptions {
+    /// The CLI defaults: MP3 320, the default template, one folder per job
+    fn default() -> Self {
+        Self {
+            format: TrackFormat::Mp3_320,
+            best_quality: false,
+            backup_accounts: None,
+            template: template::DEFAULT_TEMPLATE.to_string(),
+            ascii_filenames: false,
+            max_filename_length: DEFAULT_MAX_FILENAME_LENGTH,
+            min_format: None,
+            fallback_report: None,
+            limit_rate: None,
+            alt_search: false,
+            convert: None,
+            cover_file: None,
+            cover_size: artwork::COVER_SIZE,
+            nfo: false,
+            job_folders: true,
+            musicbrainz: false,
+            full_metadata: false,
+            lyrics: false,
+            lyrics_lang: None,
+            lyrics_only: false,
+            explicit_filter: None,
+            prefer_clean: false,
+            webhook: None,
+            notify: false,
+            notify_targets: NotifyTargets::default(),
+            exec: None,
+            exec_after: None,
+            script: None,
+            blocklist: None,
+            events: None,
+            job_progress: None,
+            pause: PauseSwitch::default(),
+            positions: TrackPositions::default(),
+            feat_in_title: false,
+            version_in_title: true,
+            dedupe: None,
+            pool: None,
+            identity_skip: true,
+        }
+    }
+}
+
+impl DownloadOptions {
+    /// Send an event to `events`, if set
+    pub fn emit(&self, event: impl FnOnce() -> Event) {
+        if let Some(events) = &self.events {
+            events.emit(event());
+        }
+    }
+
+    /// Title of `track` for file names and tags
+    pub fn track_title(&self, track: &GwTrack) -> String {
+        let title = if self.version_in_title {
+            track.title_with_version()
+        } else {
+            track.title()
+        };
+        if self.feat_in_title {
+            track.append_featuring(title)
+        } else {
+            title
+        }
+    }
+
+    /// Whether the template gives each album its own folder, which then gets the cover and NFO.
+    /// Never with the pool's m3u view, where files are named by ID.
+    pub fn groups_by_album(&self) -> bool {
+        template::groups_by_album(&self.template) && !self.pool.as_ref().is_some_and(|p| p.view == PoolView::M3u)
+    }
+
+    /// How to link a track to an existing copy: `--dedupe`, or symlinks into the pool
+    pub fn link_mode(&self) -> DedupeMode {
+        self.dedupe.unwrap_or(DedupeMode::Symlink)
+    }
+
+    /// These options with the format of a `--quality` value, `best` included; `None` for an
+    /// unknown value
+    pub fn with_quality(&self, quality: &str) -> Option<Self> {
+        let best = quality.eq_ignore_ascii_case(BEST_QUALITY);
+        let format = if best { TrackFormat::Flac } else { TrackFormat::from_quality(quality)? };
+        Some(Self {
+            format,
+            best_quality: best,
+            ..self.clone()
+        })
+    }
+
+    /// `name` as a file or folder name: sanitized for the current platform, transliterated
+    /// with `--ascii-filenames` and cut to [`DownloadOptions::max_name_length`]
+    pub fn file_name(&self, name: &str) -> String {
+        let name = if self.ascii_filenames {
+            sanitize_filename_for(&to_ascii(name), FilenameRules::native())
+        } else {
+            sanitize_filename_for(name, FilenameRules::native())
+        };
+        truncate_filename(&name, self.max_name_length())
+    }
+
+    /// Longest name a finished file or folder may get, leaving room for the `.part` suffix used
+    /// while writing
+    pub fn max_name_length(&self) -> usize {
+        self.max_filename_length.saturating_sub(PART_SUFFIX.len())
+    }
+
+    /// A copy for one job, reporting its overall progress to `progress`
+    pub fn with_job_progress(&self, progress: &JobProgress) -> Self {
+        Self {
+            job_progress: Some(progress.clone()),
+            ..self.clone()
+        }
+    }
+}
+
+/// `--skip-explicit` / `--explicit-only`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplicitFilter {
+    SkipExplicit,
+    ExplicitOnly,
+}
+
+impl ExplicitFilter {
+    pub fn allows(&self, track: &GwTrack) -> bool {
+        match self {
+            ExplicitFilter::SkipExplicit => !track.is_explicit(),
+            ExplicitFilter::ExplicitOnly => track.is_explicit(),
+        }
+    }
+}
+
+/// Returned for tracks excluded by the explicit content filter
+#[derive(Debug)]
+pub struct ExplicitFiltered(pub ExplicitFilter);
+
+impl std::fmt::Display for ExplicitFiltered {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            ExplicitFilter::SkipExplicit => write!(f, "Explicit track excluded by --skip-explicit"),
+            ExplicitFilter::ExplicitOnly => write!(f, "Non-explicit track excluded by --explicit-only"),
+        }
+    }
+}
+
+impl std::error::Error for ExplicitFiltered {}
+
+/// Release types selectable with `artist --only`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReleaseType {
+    Albums,
+    Singles,
+    Eps,
+    Compilations,
+}
+
+impl ReleaseType {
+    fn matches(&self, album: &AlbumInfo) -> bool {
+        let code = match self {
+            ReleaseType::Singles => 0,
+            ReleaseType::Albums => 1,
+            ReleaseType::Compilations => 2,
+            ReleaseType::Eps => 3,
+        };
+        album.type_code() == code
+    }
+}
+
+/// Options specific to `artist` downloads
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ArtistOptions {
+    /// Only these release types (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub only: Vec<ReleaseType>,
+    /// Skip releases Deezer does not mark as official (bootlegs, unofficial uploads)
+    #[arg(long)]
+    pub official_only: bool,
+    /// Pick the releases to download from a list before starting
+    #[arg(long)]
+    pub select: bool,
+    /// Only download the artist's N most popular tracks instead of the discography
+    #[arg(long, value_name = "N", conflicts_with_all = ["only", "official_only", "select", "include_features"])]
+    pub top: Option<usize>,
+    /// Also download the top tracks (`--top`, default 10) of the N most related artists
+    #[arg(long, value_name = "N")]
+    pub related: Option<usize>,
+    /// Also download releases the artist only appears on, keeping just the tracks that credit them
+    #[arg(long)]
+    pub include_features: bool,
+    /// Restrict the download to these album IDs (set by `watch`)
+    #[arg(skip)]
+    pub release_ids: Option<HashSet<String>>,
+    /// Download every copy of a recording instead of skipping ISRCs already seen on another release
+    #[arg(long)]
+    pub keep_duplicates: bool,
+}
+
+/// Returned when the only available streams are below `--min-quality`
+#[derive(Debug)]
+pub struct QualityRefused {
+    pub min: TrackFormat,
+}
+
+impl std::fmt::Display for QualityRefused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No stream available at or above minimum quality {}", self.min)
+    }
+}
+
+impl std::error::Error for QualityRefused {}
+
+/// Returned for a job that ran to its end but could not download some of its tracks
+#[derive(Debug)]
+pub struct TracksFailed(pub usize);
+
+impl std::fmt::Display for TracksFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} track(s) could not be downloaded", self.0)
+    }
+}
+
+impl std::error::Error for TracksFailed {}
+
+/// Warn when the account cannot stream the requested format, instead of letting a FLAC job on a
+/// free account quietly deliver MP3 128; with `strict` (`--strict-quality`), refuse to start.
+/// `--quality best` adapts to the account and is never refused.
+pub fn check_account_quality(user: &CurrentUser, opts: &DownloadOptions, strict: bool) -> Result<()> {
+    let allowed = match opts.format {
+        TrackFormat::Flac => user.can_stream_lossless,
+        TrackFormat::Mp3_320 => user.can_stream_hq,
+        _ => true,
+    };
+    if allowed || opts.best_quality {
+        return Ok(());
+    }
+    let best = user.best_format();
+    if strict {
+        bail!(
+            "The account {} cannot stream {} (at best {}); lower --quality or drop --strict-quality",
+            user.name,
+            opts.format,
+            best
+        );
+    }
+    eprintln!(
+        "{} {}\n",
+        color::warn(),
+        tr!("auth.quality_limited", name = user.name, format = opts.format, best = best)
+    );
+    Ok(())
+}
+
+/// A track written to (or already present on) disk
+#[derive(Debug, Clone)]
+pub struct DownloadedTrack {
+    pub path: PathBuf,
+    pub format: TrackFormat,
+    /// Format asked for; differs from `format` after a fallback
+    pub requested: TrackFormat,
+}
+
+/// A track delivered in a lower format than requested
+#[derive(Debug, Clone, Serialize)]
+pub struct FallbackRecord {
+    pub sng_id: String,
+    pub track: String,
+    pub requested: String,
+    pub delivered: String,
+    pub path: PathBuf,
+}
+
+/// A track that failed during a job, kept for the end-of-run retry pass
+#[derive(Debug, Clone)]
+pub struct FailedDownload {
+    pub track: GwTrack,
+    pub output_dir: PathBuf,
+    pub error: String,
+    /// Worth another attempt in the retry pass (not e.g. a rights restriction)
+    pub retryable: bool,
+}
+
+/// Per-job counters and notable events, printed at the end of a run
+#[derive(Debug, Default)]
+pub struct JobSummary {
+    pub downloaded: usize,
+    pub failed: usize,
+    /// Tracks refused because only formats below `--min-quality` were available
+    pub refused: Vec<String>,
+    /// Tracks left out by `--skip-explicit` / `--explicit-only` or the filter script
+    pub filtered: Vec<String>,
+    /// Tracks Deezer has no lyrics for (`--lyrics-only`)
+    pub no_lyrics: Vec<String>,
+    /// Recordings skipped because the same ISRC was already downloaded in this job
+    pub duplicates: usize,
+    pub fallbacks: Vec<FallbackRecord>,
+    pub failures: Vec<FailedDownload>,
+    /// Files written or already present
+    pub paths: Vec<PathBuf>,
+}
+
+impl JobSummary {
+    /// Count a downloaded track, noting it if it fell back to a lower format
+    pub fn record_success(&mut self, track: &GwTrack, done: &DownloadedTrack) {
+        let requested = done.requested;
+        self.downloaded += 1;
+        self.paths.push(done.path.clone());
+        if done.format != requested {
+            println!("  {} {} -> {}", color::note("[fallback]"), requested, done.format);
+            self.fallbacks.push(FallbackRecord {
+                sng_id: track.id_str(),
+                track: track.display_name(),
+                requested: requested.api_name().to_string(),
+                delivered: done.format.api_name().to_string(),
+                path: done.path.clone(),
+            });
+        }
+    }
+
+    /// Drop the failures of tracks with `isrc`, once the recording was downloaded from another release
+    pub fn forget_failures(&mut self, isrc: &str) {
+        let before = self.failures.len();
+        self.failures.retain(|f| f.track.isrc.as_deref() != Some(isrc));
+        self.failed -= before - self.failures.len();
+    }
+
+    /// Count a failed track, keeping quality refusals and filtered tracks apart
+    pub fn record_error(&mut self, track: &GwTrack, output_dir: &Path, err: &anyhow::Error) {
+        if err.downcast_ref::<QualityRefused>().is_some() {
+            self.refused.push(track.display_name());
+        } else if err.downcast_ref::<ExplicitFiltered>().is_some()
+            || err.downcast_ref::<ScriptSkipped>().is_some()
+            || err.downcast_ref::<Blocked>().is_some()
+        {
+            self.filtered.push(track.display_name());
+        } else if err.downcast_ref::<lyrics::NoLyrics>().is_some() {
+            self.no_lyrics.push(track.display_name());
+        } else {
+            self.failed += 1;
+            self.failures.push(FailedDownload {
+                track: track.clone(),
+                output_dir: output_dir.to_path_buf(),
+                error: err.to_string(),
+                retryable: err.is_retryable(),
+            });
+        }
+    }
+
+    /// Print the per-track lists collected during the run and write the fallback report
+    pub fn print_details(&self, opts: &DownloadOptions) {
+        if !self.fallbacks.is_empty() {
+            println!("\n{}", tr!("job.fallbacks", count = self.fallbacks.len()));
+            for fb in &self.fallbacks {
+                println!("  - {} ({} -> {})", fb.track, fb.requested, fb.delivered);
+            }
+        }
+        if !self.refused.is_empty() {
+            println!("\n{}", tr!("job.refused", count = self.refused.len()));
+            for name in &self.refused {
+                println!("  - {}", name);
+            }
+        }
+
+        if !self.filtered.is_empty() {
+            println!("\n{}", tr!("job.filtered", count = self.filtered.len()));
+            for name in &self.filtered {
+                println!("  - {}", name);
+            }
+        }
+
+        if !self.no_lyrics.is_empty() {
+            println!("\n{}", tr!("job.no_lyrics", count = self.no_lyrics.len()));
+            for name in &self.no_lyrics {
+                println!("  - {}", name);
+            }
+        }
+
+        if self.duplicates > 0 {
+            println!("\n{}", tr!("job.duplicates", count = self.duplicates));
+        }
+
+        if let Some(path) = &opts.fallback_report {
+            let written = serde_json::to_string_pretty(&self.fallbacks)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| std::fs::write(path, json).map_err(anyhow::Error::from));
+            match written {
+                Ok(()) => println!("\n{}", tr!("job.fallback_report", path = path.display())),
+                Err(e) => eprintln!("\n{} {}", color::warn(), tr!("job.fallback_report_failed", error = e)),
+            }
+        }
+    }
+}
+
+/// Whether `err` should stop the whole job instead of failing only this track
+pub fn stops_job(err: &anyhow::Error) -> bool {
+    DeezerError::find(err).is_some_and(DeezerError::is_fatal)
+}
+
+/// Which filesystem's naming rules [`sanitize_filename_for`] enforces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilenameRules {
+    /// Characters invalid on Windows are replaced everywhere, so a library can be copied across
+    Unix,
+    /// Also rules out reserved device names (`CON`, `NUL`, `COM1`…) and trailing dots
+    Windows,
+}
+
+impl FilenameRules {
+    /// The rules of the platform this binary runs on
+    pub fn native() -> Self {
+        if cfg!(windows) { Self::Windows } else { Self::Unix }
+    }
+}
+
+/// Names Windows reserves for devices, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "COM¹", "COM²",
+    "COM³", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9", "LPT¹", "LPT²", "LPT³",
+];
+
+/// Longest file or folder name written by default, in bytes (the limit of most filesystems)
+pub const DEFAULT_MAX_FILENAME_LENGTH: usize = 255;
+
+/// Sanitize a filename for the current platform by removing/replacing invalid characters,
+/// and cut it to the default length limit. Names of downloads use [`DownloadOptions::file_name`].
+pub fn sanitize_filename(name: &str) -> String {
+    let max = DEFAULT_MAX_FILENAME_LENGTH - PART_SUFFIX.len();
+    truncate_filename(&sanitize_filename_for(name, FilenameRules::native()), max)
+}
+
+/// Cut `name` to at most `max` bytes on a character boundary, without leaving trailing spaces or dots
+pub fn truncate_filename(name: &str, max: usize) -> String {
+    if name.len() <= max {
+        return name.to_string();
+    }
+    let mut end = max;
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    name[..end].trim_end_matches([' ', '.']).to_string()
+}
+
+/// Transliterate to ASCII (`é` → `e`, `Кино` → `Kino`, `東京` → `Dong Jing`).
+/// Characters without a transliteration are dropped.
+pub fn to_ascii(name: &str) -> String {
+    deunicode::deunicode_with_tofu(name, "").split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Sanitize a filename under the given rules. Returns an empty string when nothing usable is left.
+pub fn sanitize_filename_for(name: &str, rules: FilenameRules) -> String {
+    let replaced: String = name
+        .chars()
         .map(|c| match c {
             '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            '\t' | '\n' | '\r' => ' ',
+            c if c.is_control() => '_',
             _ => c,
         })
-        .collect::<String>()
-        .trim()
-        .to_string()
+        .collect();
+    let mut name = replaced.trim().to_string();
+    if rules == FilenameRules::Windows {
+        // Windows silently drops trailing dots and spaces, so `Album...` would not match its own path
+        name.truncate(name.trim_end_matches(['.', ' ']).len());
+        let stem = name.split('.').next().unwrap_or_default().trim_end();
+        if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+            name.insert(stem.len(), '_');
+        }
+    }
+    name
+}
+
+/// Whether `actual` is within tolerance of the expected FILESIZE_* (0 = unknown)
+pub fn size_matches(expected: u64, actual: u64) -> bool {
+    if expected == 0 {
+        return true;
+    }
+    let diff = expected.abs_diff(actual) as f64;
+    diff <= expected as f64 * SIZE_TOLERANCE
+}
+
+/// Format to ask for `track`: `opts.format`, or with `--quality best` the best one the track has
+/// and the main or a backup account streams (never below `--min-quality`)
+pub async fn requested_format(api: &impl DeezerClient, track: &GwTrack, opts: &DownloadOptions) -> TrackFormat {
+    if !opts.best_quality {
+        return opts.format;
+    }
+    // Without account details, assume everything is allowed and let the fallbacks sort it out
+    let (mut lossless, mut hq) = api
+        .user()
+        .await
+        .map_or((true, true), |user| (user.can_stream_lossless, user.can_stream_hq));
+    if let Some(backups) = &opts.backup_accounts {
+        lossless |= backups.can_stream(TrackFormat::Flac);
+        hq |= backups.can_stream(TrackFormat::Mp3_320);
+    }
+    let best = track.best_format(lossless, hq);
+    match opts.min_format {
+        Some(min) if !best.is_at_least(min) => min,
+        _ => best,
+    }
+}
+
+/// Stream URL from the media API in `format` or, failing that, a lower format down to
+/// `min_format`, stopping before `delivered` (a format already available from elsewhere)
+pub async fn media_url(
+    api: &impl DeezerClient,
+    track: &GwTrack,
+    format: TrackFormat,
+    min_format: Option<TrackFormat>,
+    delivered: Option<TrackFormat>,
+) -> Option<(String, TrackFormat)> {
+    let token = track.track_token.as_deref().filter(|token| !token.is_empty())?;
+    let mut candidate = Some(format);
+    while let Some(fmt) = candidate {
+        if delivered.is_some_and(|d| d.is_at_least(fmt)) || min_format.is_some_and(|min| !fmt.is_at_least(min)) {
+            return None;
+        }
+        if let Ok(Some(url)) = api.get_track_url(token, fmt.api_name()).await {
+            return Some((url, fmt));
+        }
+        candidate = fmt.fallback();
+    }
+    None
+}
+
+/// [`get_download_url`], turning to the backup accounts when the logged-in one only streams a
+/// lower format of the track, or none
+async fn stream_url(
+    api: &impl DeezerClient,
+    track: &GwTrack,
+    format: TrackFormat,
+    opts: &DownloadOptions,
+) -> Result<(String, TrackFormat, bool)> {
+    let Some(backups) = opts.backup_accounts.as_ref().filter(|b| !b.is_empty()) else {
+        return get_download_url(api, track, format, opts.min_format).await;
+    };
+    let refreshed;
+    let track = if track.token_expired() {
+        refreshed = api.get_track(&track.id_str()).await?;
+        &refreshed
+    } else {
+        track
+    };
+    // Only the media API tells what the account may stream; legacy URLs are guesses
+    let own = media_url(api, track, format, opts.min_format, None).await;
+    if let Some((url, found)) = &own
+        && *found == format
+    {
+        return Ok((url.clone(), format, true));
+    }
+    let delivered = own.as_ref().map(|(_, found)| *found);
+    if let Some((url, found, name)) = backups.find_url(track, format, opts.min_format, delivered).await {
+        println!("  {} {}", color::note("[account]"), tr!("accounts.used", name = name, format = found));
+        return Ok((url, found, true));
+    }
+    match own {
+        Some((url, found)) => Ok((url, found, true)),
+        None => get_download_url(api, track, format, opts.min_format).await,
+    }
 }
 
 /// Get a download URL for a track at the preferred format, with fallback
-async fn get_download_url(
-    api: &DeezerApi,
+pub async fn get_download_url(
+    api: &impl DeezerClient,
     track: &GwTrack,
     format: TrackFormat,
+    min_format: Option<TrackFormat>,
 ) -> Result<(String, TrackFormat, bool)> {
+    // Tokens expire on long runs: fetch fresh track data before asking for a URL
+    let refreshed;
+    let track = if track.token_expired() {
+        refreshed = api.get_track(&track.id_str()).await?;
+        &refreshed
+    } else {
+        track
+    };
+
     let current_format = format;
-    let is_crypted;
+    let acceptable = |fmt: TrackFormat| min_format.is_none_or(|min| fmt.is_at_least(min));
 
     // Try the new media API first
     if let Some(token) = &track.track_token {
@@ -37,342 +740,1474 @@ async fn get_download_url(
                 return Ok((url, current_format, true));
             }
             // Fallback formats with new API
-            let mut fallback = current_format.fallback();
+            let mut fallback = current_format.fallback().filter(|f| acceptable(*f));
             while let Some(fb) = fallback {
                 if let Ok(Some(url)) = api.get_track_url(token, fb.api_name()).await {
                     return Ok((url, fb, true));
                 }
-                fallback = fb.fallback();
+                fallback = fb.fallback().filter(|f| acceptable(*f));
+            }
+        }
+    }
+
+    // Fallback to legacy URL generation
+    let md5 = track.md5();
+    let media_version = track.media_ver();
+    let sng_id = track.id_str();
+
+    if md5.is_empty() {
+        return Err(DeezerError::Unavailable("Track has no MD5, cannot generate download URL".to_string()).into());
+    }
+
+    // Try preferred format first
+    let mut try_format = Some(current_format);
+    while let Some(fmt) = try_format {
+        if track.filesize_for_format(fmt) > 0 {
+            let url = crypto::generate_crypted_stream_url(&sng_id, &md5, &media_version, fmt.code());
+            return Ok((url, fmt, true));
+        }
+        try_format = fmt.fallback().filter(|f| acceptable(*f));
+    }
+
+    // Only formats below the minimum have a file: refuse instead of guessing
+    if let Some(min) = min_format {
+        let mut below = current_format.fallback();
+        while let Some(fmt) = below {
+            if !acceptable(fmt) && track.filesize_for_format(fmt) > 0 {
+                return Err(QualityRefused { min }.into());
+            }
+            below = fmt.fallback();
+        }
+    }
+
+    // Last resort: try the preferred format anyway
+    let url = crypto::generate_crypted_stream_url(&sng_id, &md5, &media_version, current_format.code());
+    Ok((url, current_format, true))
+}
+
+/// `name.ext.part`: where a file is written until it is complete, so an
+/// interrupted write never leaves a file that later counts as downloaded
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut part_name = path.as_os_str().to_os_string();
+    part_name.push(PART_SUFFIX);
+    PathBuf::from(part_name)
+}
+
+/// Write data next to `path` as `.part`, check that all of it reached the disk,
+/// then rename it into place. The `.part` file is removed if anything fails.
+pub async fn write_file_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let part_path = part_path(path);
+    let partial = PartialFile::new(&part_path);
+    let result = async {
+        let mut file = fs::File::create(&part_path).await.map_err(DeezerError::from)?;
+        file.write_all(data).await.map_err(DeezerError::from)?;
+        file.flush().await.map_err(DeezerError::from)?;
+        let written = file.metadata().await.map_err(DeezerError::from)?.len();
+        drop(file);
+        if written != data.len() as u64 {
+            bail!("Wrote {} of {} bytes to {}", written, data.len(), part_path.display());
+        }
+        fs::rename(&part_path, path)
+            .await
+            .map_err(DeezerError::from)
+            .with_context(|| format!("Failed to move {} into place", path.display()))
+    }
+    .await;
+    if result.is_err() {
+        let _ = fs::remove_file(&part_path).await;
+    }
+    partial.complete();
+    result
+}
+
+/// Shown while downloads are paused
+fn pause_message(pause: &PauseSwitch) -> String {
+    if pause.has_keys() {
+        tr!("pause.paused_keys")
+    } else {
+        tr!("pause.paused")
+    }
+}
+
+/// Download a stream URL into memory, decrypting and depadding it
+pub async fn fetch_audio(
+    api: &impl DeezerClient,
+    url: &str,
+    is_crypted: bool,
+    sng_id: &str,
+    show_progress: bool,
+    opts: &DownloadOptions,
+) -> Result<Vec<u8>> {
+    let _keys = opts.pause.listen_keys();
+    if opts.pause.is_paused() {
+        println!("  {}", pause_message(&opts.pause));
+        opts.pause.wait().await;
+    }
+    if shutdown::requested() {
+        return Err(DeezerError::Interrupted.into());
+    }
+
+    let mut response = match api.fetch_stream(url).await {
+        Ok(response) => response,
+        Err(e @ DeezerError::Cdn(_)) => return Err(e.into()),
+        Err(e) => return Err(anyhow::Error::from(e).context("Failed to download track")),
+    };
+
+    let total_size = response.len.unwrap_or(0);
+
+    let pb = (show_progress && total_size > 0).then(|| TrackBar::new(total_size, opts.job_progress.as_ref()));
+
+    // Download to memory (needed for decryption)
+    let mut data = Vec::with_capacity(total_size as usize);
+    let mut limiter = opts.limit_rate.map(BandwidthLimiter::new);
+    let mut last_event = Instant::now();
+
+    while let Some(chunk) = response.chunks.next().await {
+        let chunk = chunk.context("Error reading download stream")?;
+        if let Some(ref pb) = pb {
+            pb.inc(chunk.len() as u64);
+        }
+        data.extend_from_slice(&chunk);
+        if let Some(events) = &opts.events
+            && last_event.elapsed() >= PROGRESS_EVENT_INTERVAL
+        {
+            last_event = Instant::now();
+            events.emit(Event::TrackProgress {
+                sng_id: sng_id.to_string(),
+                received: data.len() as u64,
+                total: total_size,
+            });
+        }
+        if let Some(limiter) = limiter.as_mut() {
+            limiter.consume(chunk.len()).await;
+        }
+        if opts.pause.is_paused() {
+            if let Some(ref pb) = pb {
+                pb.set_message(pause_message(&opts.pause));
+            }
+            opts.pause.wait().await;
+            if let Some(ref pb) = pb {
+                pb.set_message(String::new());
+            }
+        }
+        // Nothing is on disk yet, so stopping here leaves no partial file
+        if shutdown::requested() {
+            if let Some(pb) = pb {
+                pb.finish();
+            }
+            return Err(DeezerError::Interrupted.into());
+        }
+    }
+
+    if let Some(pb) = pb {
+        pb.finish();
+    }
+
+    if data.is_empty() {
+        bail!("Downloaded file is empty");
+    }
+
+    // Decrypt if needed
+    let final_data = if is_crypted {
+        let blowfish_key = crypto::generate_blowfish_key(sng_id);
+        crypto::decrypt_stream(&data, &blowfish_key)
+    } else {
+        data
+    };
+
+    // Remove leading null bytes (depadding) - but not for ftyp (MP4)
+    let output_data = if !final_data.is_empty() && final_data[0] == 0 {
+        if final_data.len() > 8 && &final_data[4..8] == b"ftyp" {
+            final_data
+        } else {
+            let start = final_data.iter().position(|&b| b != 0).unwrap_or(0);
+            final_data[start..].to_vec()
+        }
+    } else {
+        final_data
+    };
+
+    Ok(output_data)
+}
+
+/// Fetch a stream with the retry policy, failing if the size doesn't match FILESIZE_*
+async fn fetch_verified(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    url: &str,
+    is_crypted: bool,
+    sng_id: &str,
+    expected_size: u64,
+    show_progress: bool,
+) -> Result<Vec<u8>> {
+    api.retry_policy()
+        .run("Download", || async {
+            let data = fetch_audio(api, url, is_crypted, sng_id, show_progress, opts).await?;
+            if !size_matches(expected_size, data.len() as u64) {
+                return Err(DeezerError::Decryption(format!(
+                    "Downloaded size {} bytes does not match expected {} bytes (truncated stream?)",
+                    data.len(),
+                    expected_size
+                ))
+                .into());
+            }
+            if !library::has_audio_header(&data) {
+                return Err(DeezerError::Decryption("Decrypted stream is not a recognised audio file".into()).into());
+            }
+            Ok(data)
+        })
+        .await
+}
+
+/// Download and decrypt a single track, switching to its FALLBACK version
+/// when the primary one is not available (e.g. geo-restricted)
+pub async fn download_track(
+    api: &impl DeezerClient,
+    track: &GwTrack,
+    opts: &DownloadOptions,
+    output_dir: &Path,
+    show_progress: bool,
+) -> Result<DownloadedTrack> {
+    if shutdown::requested() {
+        return Err(DeezerError::Interrupted.into());
+    }
+    let busy = shutdown::Busy::start();
+    // `{position}` stays the requested track's, also when another version of it is downloaded
+    let opts = &DownloadOptions {
+        positions: opts.positions.select(&track.id_str()),
+        ..opts.clone()
+    };
+    let enriched;
+    let track = if opts.full_metadata {
+        let mut full = track.clone();
+        enrich_track(api, &mut full).await;
+        enriched = full;
+        &enriched
+    } else {
+        track
+    };
+    opts.emit(|| Event::track_started(track));
+    let result = download_track_versions(api, track, opts, output_dir, show_progress).await;
+    opts.emit(|| match &result {
+        Ok(done) => Event::TrackFinished {
+            sng_id: track.id_str(),
+            track: track.display_name(),
+            path: done.path.clone(),
+            format: done.format.api_name().to_string(),
+        },
+        Err(e) => Event::TrackFailed {
+            sng_id: track.id_str(),
+            track: track.display_name(),
+            error: e.to_string(),
+        },
+    });
+    busy.finish()?;
+    result
+}
+
+/// Try the clean edit, the track itself, its FALLBACK and other releases in turn
+async fn download_track_versions(
+    api: &impl DeezerClient,
+    track: &GwTrack,
+    opts: &DownloadOptions,
+    output_dir: &Path,
+    show_progress: bool,
+) -> Result<DownloadedTrack> {
+    if let Some(reason) = opts.blocklist.as_ref().and_then(|list| list.blocks(track)) {
+        return Err(Blocked(reason).into());
+    }
+    if opts.prefer_clean
+        && track.is_explicit()
+        && let Some(clean) = find_clean_version(api, track).await
+        && substitute_allowed(opts, &clean)
+    {
+        println!("  {} {}", color::note("[clean]"), tr!("track.clean", id = clean.id_str()));
+        match download_track_once(api, &clean, opts, output_dir, show_progress).await {
+            Ok(done) => return Ok(done),
+            Err(e) => eprintln!("  {} {}", color::warn(), tr!("track.clean_failed", error = e)),
+        }
+    }
+
+    if let Some(filter) = opts.explicit_filter
+        && !filter.allows(track)
+    {
+        return Err(ExplicitFiltered(filter).into());
+    }
+
+    let err = match download_track_once(api, track, opts, output_dir, show_progress).await {
+        Err(e) if is_unavailable(&e) => e,
+        other => return other,
+    };
+
+    let mut last_err = err;
+    if let Some(alt) = track.fallback_track().filter(|alt| substitute_allowed(opts, alt)) {
+        println!(
+            "  {} {}",
+            color::note("[alt]"),
+            tr!("track.alt_fallback", id = track.id_str(), error = last_err, alt = alt.id_str())
+        );
+        match download_track_once(api, &alt, opts, output_dir, show_progress).await {
+            Err(e) if is_unavailable(&e) => last_err = e,
+            other => return other,
+        }
+    }
+
+    if opts.alt_search
+        && let Some(done) = download_alternative(api, track, opts, output_dir, show_progress).await
+    {
+        return Ok(done);
+    }
+    Err(last_err)
+}
+
+/// Whether the blocklist lets `substitute` (a clean edit or another release) stand in for the
+/// requested track. Blocked ones are reported and left out.
+fn substitute_allowed(opts: &DownloadOptions, substitute: &GwTrack) -> bool {
+    let Some(reason) = opts.blocklist.as_ref().and_then(|list| list.blocks(substitute)) else {
+        return true;
+    };
+    println!("  {} {}", color::skip(), tr!("track.substitute_blocked", id = substitute.id_str(), reason = reason));
+    false
+}
+
+/// Largest length difference (seconds) accepted between an explicit track and its clean edit
+const CLEAN_DURATION_TOLERANCE: u32 = 10;
+
+/// Search the public API for a non-explicit release with the same artist, title and length
+async fn find_clean_version(api: &impl DeezerClient, track: &GwTrack) -> Option<GwTrack> {
+    let query = format!("artist:\"{}\" track:\"{}\"", track.artist(), track.title());
+    let results = api.search_track(&query).await.ok()?;
+    let duration = track.duration_secs();
+
+    let found = results.iter().find(|item| {
+        let same_title = item.title_short.as_deref().unwrap_or(&item.title).eq_ignore_ascii_case(&track.title());
+        let close_length = duration == 0
+            || item
+                .duration
+                .is_some_and(|d| (d as u32).abs_diff(duration) <= CLEAN_DURATION_TOLERANCE);
+        let clean = item.explicit_lyrics == Some(false);
+        same_title && close_length && clean && item.readable.unwrap_or(false)
+    })?;
+    api.get_track(&found.id.to_string()).await.ok()
+}
+
+/// Maximum number of other releases tried by `--alt-search`
+const MAX_ALTERNATIVES: usize = 3;
+
+/// Find other Deezer releases of the same recording (by ISRC, then artist + title)
+async fn find_alternatives(api: &impl DeezerClient, track: &GwTrack) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    let exclude = [
+        track.id_str(),
+        track.fallback_track().map(|t| t.id_str()).unwrap_or_default(),
+    ];
+    let mut push = |id: String| {
+        if id != "0" && !exclude.contains(&id) && !ids.contains(&id) {
+            ids.push(id);
+        }
+    };
+
+    if let Some(isrc) = track.isrc.as_deref().filter(|s| !s.is_empty())
+        && let Ok(Some(found)) = api.get_track_by_isrc(isrc).await
+        && found.readable.unwrap_or(true)
+    {
+        push(found.id.to_string());
+    }
+
+    let query = format!("artist:\"{}\" track:\"{}\"", track.artist(), track.title());
+    if let Ok(results) = api.search_track(&query).await {
+        for item in results {
+            if item.title.eq_ignore_ascii_case(&track.title()) && item.readable.unwrap_or(false) {
+                push(item.id.to_string());
+            }
+        }
+    }
+
+    ids.truncate(MAX_ALTERNATIVES);
+    ids
+}
+
+/// Try other releases of the same recording; `None` if none could be downloaded
+async fn download_alternative(
+    api: &impl DeezerClient,
+    track: &GwTrack,
+    opts: &DownloadOptions,
+    output_dir: &Path,
+    show_progress: bool,
+) -> Option<DownloadedTrack> {
+    let ids = find_alternatives(api, track).await;
+    if ids.is_empty() {
+        return None;
+    }
+    let candidates = api.get_tracks_by_ids(&ids).await.ok()?;
+
+    for alt in candidates.iter().filter(|alt| substitute_allowed(opts, alt)) {
+        println!("  {} {}", color::note("[alt]"), tr!("track.alt_trying", id = alt.id_str(), album = alt.album()));
+        match download_track_once(api, alt, opts, output_dir, show_progress).await {
+            Ok(done) => return Some(done),
+            Err(e) => eprintln!("  {} {}", color::note("[alt]"), tr!("track.alt_failed", id = alt.id_str(), error = e)),
+        }
+    }
+    None
+}
+
+/// Whether an error means this SNG_ID cannot be streamed at all (rights, missing file)
+fn is_unavailable(err: &anyhow::Error) -> bool {
+    DeezerError::find(err).is_some_and(|e| matches!(e, DeezerError::Unavailable(_)) || e.is_rejection())
+}
+
+/// Download and decrypt one specific SNG_ID
+async fn download_track_once(
+    api: &impl DeezerClient,
+    track: &GwTrack,
+    opts: &DownloadOptions,
+    output_dir: &Path,
+    show_progress: bool,
+) -> Result<DownloadedTrack> {
+    let title = opts.file_name(&track.title());
+    let sng_id = track.id_str();
+
+    if sng_id == "0" || title.is_empty() {
+        bail!("Invalid track data");
+    }
+    let format = requested_format(api, track, opts).await;
+    let decision = match &opts.script {
+        Some(script) => script.decide(track, format)?,
+        None => ScriptDecision::Accept,
+    };
+    if decision == ScriptDecision::Skip {
+        return Err(ScriptSkipped.into());
+    }
+    if opts.lyrics_only {
+        let album = album_details(api, track).await;
+        let vars = naming_vars(track, album.as_ref(), opts, format);
+        let audio = template_path(opts, output_dir, &decision, &vars, format.extension());
+        return save_lyrics_only(api, track, opts, output_dir, &audio, format, show_progress).await;
+    }
+
+    // Get download URL
+    let (url, actual_format, is_crypted) = stream_url(api, track, format, opts).await?;
+    let extension = actual_format.extension();
+
+    let album = album_details(api, track).await;
+    let vars = naming_vars(track, album.as_ref(), opts, actual_format);
+    let with_conversion = |path: PathBuf| match opts.convert {
+        Some(target) => target.output_path(&path),
+        None => path,
+    };
+    let template_path = template_path(opts, output_dir, &decision, &vars, extension);
+    // With `--pool` the audio is stored under its ID in the pool, and the links view
+    // puts a link at the usual path
+    let (filepath, link_path) = match &opts.pool {
+        Some(pool) => {
+            let link = (pool.view == PoolView::Links).then(|| with_conversion(template_path));
+            (pool.path_for(&sng_id, extension), link)
+        }
+        None => (template_path, None),
+    };
+    let stored = with_conversion(filepath.clone());
+    let final_path = link_path.clone().unwrap_or_else(|| stored.clone());
+    let filename = final_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    // Create output directory
+    for dir in [filepath.parent(), final_path.parent()].into_iter().flatten() {
+        fs::create_dir_all(dir).await.map_err(DeezerError::from)?;
+    }
+
+    // Skip if already exists
+    if final_path.exists() {
+        if show_progress {
+            println!("  {} {}", color::skip(), tr!("track.exists", file = filename));
+        }
+        return Ok(DownloadedTrack {
+            path: final_path,
+            format: actual_format,
+            requested: format,
+        });
+    }
+
+    // Renamed or reorganized files are recognized by their ID or ISRC. The pool
+    // already stores tracks by ID.
+    if opts.identity_skip
+        && opts.pool.is_none()
+        && let Some(existing) = identity::find(output_dir, track, &final_path).await
+    {
+        if show_progress {
+            println!("  {} {}", color::skip(), tr!("track.exists_as", file = filename, existing = existing.display()));
+        }
+        return Ok(DownloadedTrack {
+            path: existing,
+            format: actual_format,
+            requested: format,
+        });
+    }
+
+    let copy = match &link_path {
+        Some(_) => stored.exists().then(|| stored.clone()),
+        None if opts.dedupe.is_some() => dedupe::find_copy(&sng_id, &final_path).await,
+        None => None,
+    };
+    if let Some(source) = copy {
+        match dedupe::link(opts.link_mode(), &source, &final_path) {
+            Ok(linked) => {
+                if show_progress {
+                    let source = source.display();
+                    let message = match linked {
+                        DedupeMode::Hardlink => tr!("track.hardlinked", file = filename, source = source),
+                        DedupeMode::Symlink => tr!("track.symlinked", file = filename, source = source),
+                    };
+                    println!("  {} {}", color::skip(), message);
+                }
+                // Not canonicalized: that would resolve a symlink to the first copy
+                let stored_path = std::path::absolute(&final_path).unwrap_or_else(|_| final_path.clone());
+                record_history(track, actual_format, &final_path, &stored_path, &vars).await;
+                return Ok(DownloadedTrack {
+                    path: final_path,
+                    format: actual_format,
+                    requested: format,
+                });
+            }
+            Err(e) => {
+                eprintln!("  {} {}", color::warn(), tr!("track.link_failed", source = source.display(), error = e));
             }
         }
     }
 
-    // Fallback to legacy URL generation
-    let md5 = track.md5();
-    let media_version = track.media_ver();
-    let sng_id = track.id_str();
+    // Fetch, retrying network errors and truncated streams
+    let expected_size = track.filesize_for_format(actual_format);
+    let first_try =
+        fetch_verified(api, opts, &url, is_crypted, &sng_id, expected_size, show_progress).await;
+    let output_data = match first_try {
+        // The CDN rejects stale tokens/URLs: get fresh track data and try once more
+        Err(e) if DeezerError::find(&e).is_some_and(DeezerError::is_rejection) => {
+            eprintln!("  {} {}", color::warn(), tr!("track.fresh_token", error = e));
+            let fresh = api.get_track(&sng_id).await?;
+            let (url, fresh_format, is_crypted) = stream_url(api, &fresh, format, opts).await?;
+            if fresh_format != actual_format {
+                return Err(e);
+            }
+            let expected_size = fresh.filesize_for_format(fresh_format);
+            fetch_verified(api, opts, &url, is_crypted, &sng_id, expected_size, show_progress).await?
+        }
+        other => other?,
+    };
 
-    if md5.is_empty() {
-        bail!("Track has no MD5, cannot generate download URL");
+    let mut track_tags = TrackTags::new(track, album.as_ref());
+    track_tags.title = opts.track_title(track);
+    track_tags.genres = album_genres(api, track).await;
+    if opts.musicbrainz {
+        track_tags.musicbrainz = musicbrainz_ids(api, track).await;
+    }
+    let lyrics = if opts.lyrics {
+        lyrics::fetch(api, track, opts.lyrics_lang.as_deref()).await
+    } else {
+        None
+    };
+    if let Some(lyrics) = &lyrics {
+        track_tags.lyrics = lyrics.plain();
     }
+    let output_data = match tags::apply(&output_data, actual_format, &track_tags) {
+        Ok(tagged) => tagged,
+        Err(e) => {
+            eprintln!("  {} {}", color::warn(), tr!("track.tags_failed", error = e));
+            output_data
+        }
+    };
 
-    // Try preferred format first
-    let mut try_format = Some(current_format);
-    while let Some(fmt) = try_format {
-        if track.filesize_for_format(fmt) > 0 {
-            let url = crypto::generate_crypted_stream_url(&sng_id, &md5, &media_version, fmt.code());
-            return Ok((url, fmt, true));
+    // Write to `.part` first: only complete files get the final name
+    write_file_atomic(&filepath, &output_data).await?;
+
+    let filepath = match opts.convert {
+        Some(target) => convert::convert(&filepath, target).await?,
+        None => filepath,
+    };
+    let filepath = match link_path {
+        Some(link) => {
+            dedupe::link(opts.link_mode(), &filepath, &link).map_err(DeezerError::from)?;
+            link
         }
-        try_format = fmt.fallback();
+        None => filepath,
+    };
+    if let Some(lyrics) = &lyrics
+        && let Err(e) = lyrics::save(&filepath, lyrics).await
+    {
+        eprintln!("  {} {}", color::warn(), tr!("lyrics.save_failed", error = e));
     }
 
-    // Last resort: try the preferred format anyway
-    let url = crypto::generate_crypted_stream_url(&sng_id, &md5, &media_version, current_format.code());
-    is_crypted = true;
-    Ok((url, current_format, is_crypted))
+    // Links are not canonicalized: that would resolve a symlink to the pool file
+    let stored_path = match &opts.pool {
+        Some(_) => std::path::absolute(&filepath).unwrap_or_else(|_| filepath.clone()),
+        None => fs::canonicalize(&filepath).await.unwrap_or_else(|_| filepath.clone()),
+    };
+    record_history(track, actual_format, &filepath, &stored_path, &vars).await;
+    if let Some(command) = &opts.exec {
+        hooks::exec_track(command, track, &filepath, actual_format).await;
+    }
+
+    Ok(DownloadedTrack {
+        path: filepath,
+        format: actual_format,
+        requested: format,
+    })
 }
 
-/// Download and decrypt a single track
-pub async fn download_track(
-    api: &DeezerApi,
+/// Template variables for naming `track` delivered in `format`
+fn naming_vars(
     track: &GwTrack,
+    album: Option<&AlbumData>,
+    opts: &DownloadOptions,
     format: TrackFormat,
-    output_dir: &Path,
-    show_progress: bool,
-) -> Result<PathBuf> {
-    let artist = sanitize_filename(&track.artist());
-    let title = sanitize_filename(&track.title());
-    let sng_id = track.id_str();
-
-    if sng_id == "0" || title.is_empty() {
-        bail!("Invalid track data");
+) -> TemplateVars {
+    let mut vars = TemplateVars::from(track);
+    if let Some(album) = album {
+        vars = vars.with_album(album);
     }
+    vars.title = opts.track_title(track);
+    vars.position = opts.positions.current();
+    vars.format = format.to_string();
+    vars
+}
 
-    // Get download URL
-    let (url, actual_format, is_crypted) = get_download_url(api, track, format).await?;
-    let extension = actual_format.extension();
-
-    // Create output directory
-    let track_dir = output_dir.join(sanitize_filename(&artist));
-    fs::create_dir_all(&track_dir).await?;
+/// Where the template (or the path the filter script chose) puts the track
+fn template_path(
+    opts: &DownloadOptions,
+    output_dir: &Path,
+    decision: &ScriptDecision,
+    vars: &TemplateVars,
+    extension: &str,
+) -> PathBuf {
+    match decision {
+        ScriptDecision::Path(path) => output_dir.join(format!("{}{}", path.display(), extension)),
+        _ => output_dir.join(template::render_path(&opts.template, vars, extension, opts)),
+    }
+}
 
-    let filename = format!("{} - {}{}", artist, title, extension);
-    let filepath = track_dir.join(&filename);
+/// `--lyrics-only`: save the lyrics next to the track's audio without downloading it. The audio
+/// is looked for at `audio` in any format, then by Deezer ID or ISRC under `output_dir`; the
+/// lyrics go where `audio` would be when it is not found.
+async fn save_lyrics_only(
+    api: &impl DeezerClient,
+    track: &GwTrack,
+    opts: &DownloadOptions,
+    output_dir: &Path,
+    audio: &Path,
+    format: TrackFormat,
+    show_progress: bool,
+) -> Result<DownloadedTrack> {
+    let mut found = library::AUDIO_EXTENSIONS
+        .iter()
+        .map(|ext| audio.with_extension(ext))
+        .find(|path| path.is_file());
+    for ext in library::AUDIO_EXTENSIONS {
+        if found.is_some() {
+            break;
+        }
+        found = identity::find(output_dir, track, &audio.with_extension(ext)).await;
+    }
+    let audio = found.unwrap_or_else(|| audio.to_path_buf());
 
-    // Skip if already exists
-    if filepath.exists() {
+    let existing = ["lrc", "txt"].map(|ext| audio.with_extension(ext)).into_iter().find(|path| path.is_file());
+    if let Some(path) = existing {
         if show_progress {
-            println!("  [skip] {} (already exists)", filename);
+            let file = path.file_name().unwrap_or_default().to_string_lossy();
+            println!("  {} {}", color::skip(), tr!("track.exists", file = file));
         }
-        return Ok(filepath);
+        return Ok(DownloadedTrack { path, format, requested: format });
     }
 
-    // Download
-    let client = reqwest::Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()?;
-
-    let response = client
-        .get(&url)
-        .header("User-Agent", "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/79.0.3945.130 Safari/537.36")
-        .send()
+    let lyrics = lyrics::fetch(api, track, opts.lyrics_lang.as_deref())
         .await
-        .context("Failed to download track")?;
+        .ok_or(lyrics::NoLyrics)?;
+    if let Some(dir) = audio.parent() {
+        fs::create_dir_all(dir).await.map_err(DeezerError::from)?;
+    }
+    let path = lyrics::save(&audio, &lyrics).await?;
+    Ok(DownloadedTrack { path, format, requested: format })
+}
 
-    if !response.status().is_success() {
-        bail!("Download failed with status: {}", response.status());
+/// Add the file at `path` to the download history as `stored_path`; failures only warn
+async fn record_history(track: &GwTrack, format: TrackFormat, path: &Path, stored_path: &Path, vars: &TemplateVars) {
+    let mut entry = HistoryEntry::new(track, format, stored_path);
+    entry.compilation = vars.compilation;
+    // As in the file name, so that `organize` keeps it
+    entry.title = vars.title.clone();
+    // Tags and transcoding change the size Deezer reported; verify against the file as written
+    if let Ok(meta) = fs::metadata(path).await {
+        entry.expected_size = meta.len();
+    }
+    if let Err(e) = history::record(&entry).await {
+        eprintln!("  {} {}", color::warn(), tr!("track.history_failed", error = e));
     }
+    identity::remember(track, stored_path);
+}
 
-    let total_size = response.content_length().unwrap_or(0);
+/// Album details for tags and templates (cached per album); failures only warn
+pub async fn album_details(api: &impl DeezerClient, track: &GwTrack) -> Option<AlbumData> {
+    let alb_id = track.album_id_str();
+    if alb_id == "0" {
+        return None;
+    }
+    match api.get_album_data(&alb_id).await {
+        Ok(album) => Some(album),
+        Err(e) => {
+            eprintln!("  {} {}", color::warn(), tr!("track.album_failed", error = e));
+            None
+        }
+    }
+}
 
-    let pb = if show_progress && total_size > 0 {
-        let pb = ProgressBar::new(total_size);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("  [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                .unwrap()
-                .progress_chars("##-"),
-        );
-        Some(pb)
-    } else {
-        None
+/// Genre names from the public album API (cached per album); failures only warn
+pub async fn album_genres(api: &impl DeezerClient, track: &GwTrack) -> Vec<String> {
+    let alb_id = track.album_id_str();
+    if alb_id == "0" {
+        return Vec::new();
+    }
+    match api.get_album_public(&alb_id).await {
+        Ok(album) => album.genres.data.into_iter().map(|genre| genre.name).collect(),
+        Err(e) => {
+            eprintln!("  {} {}", color::warn(), tr!("track.genres_failed", error = e));
+            Vec::new()
+        }
+    }
+}
+
+/// Merge the track's `deezer.pageTrack` page into it; failures only warn
+pub async fn enrich_track(api: &impl DeezerClient, track: &mut GwTrack) {
+    let merged = match api.get_track_page(&track.id_str()).await {
+        Ok(page) => track.merge_page(page).map_err(anyhow::Error::from),
+        Err(e) => Err(e.into()),
     };
+    if let Err(e) = merged {
+        eprintln!("  {} {}", color::warn(), tr!("track.page_failed", error = e));
+    }
+}
 
-    // Download to memory (needed for decryption)
-    let mut data = Vec::with_capacity(total_size as usize);
-    let mut stream = response.bytes_stream();
+/// MusicBrainz IDs for a track's ISRC; failures only warn
+pub async fn musicbrainz_ids(api: &impl DeezerClient, track: &GwTrack) -> Option<MusicBrainzIds> {
+    let isrc = track.isrc.as_deref().filter(|i| !i.is_empty())?;
+    match musicbrainz::lookup(api, isrc, &track.album()).await {
+        Ok(ids) => ids,
+        Err(e) => {
+            eprintln!("  {} {}", color::warn(), tr!("track.musicbrainz_failed", error = e));
+            None
+        }
+    }
+}
 
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk.context("Error reading download stream")?;
-        if let Some(ref pb) = pb {
-            pb.inc(chunk.len() as u64);
+/// Replace expired track tokens in place with fresh ones from `song.getListData`
+pub async fn refresh_expired<'a>(api: &impl DeezerClient, tracks: impl IntoIterator<Item = &'a mut GwTrack>) {
+    let mut tracks: Vec<&mut GwTrack> = tracks.into_iter().filter(|t| t.token_expired()).collect();
+    let ids: Vec<String> = tracks.iter().map(|t| t.id_str()).collect();
+    if ids.is_empty() {
+        return;
+    }
+
+    match api.get_tracks_by_ids(&ids).await {
+        Ok(fresh) => {
+            for track in tracks.iter_mut() {
+                if let Some(f) = fresh.iter().find(|f| f.id_str() == track.id_str()) {
+                    track.track_token = f.track_token.clone();
+                    track.track_token_expire = f.track_token_expire.clone();
+                }
+            }
         }
-        data.extend_from_slice(&chunk);
+        Err(e) => eprintln!("  {} {}", color::warn(), tr!("track.tokens_failed", error = e)),
     }
+}
 
-    if let Some(pb) = pb {
-        pb.finish_and_clear();
+/// Request stream URLs for a batch of tracks in a single media API call.
+/// Failures are not fatal: `download_track` falls back to per-track requests.
+pub async fn prefetch_urls<'a>(
+    api: &impl DeezerClient,
+    tracks: impl IntoIterator<Item = &'a GwTrack>,
+    format: TrackFormat,
+) {
+    let requests: Vec<(&str, &str)> = tracks
+        .into_iter()
+        .filter_map(|t| t.track_token.as_deref())
+        .filter(|t| !t.is_empty())
+        .map(|t| (t, format.api_name()))
+        .collect();
+    if requests.is_empty() {
+        return;
+    }
+    if let Err(e) = api.prefetch_track_urls(&requests).await {
+        eprintln!("  {} {}", color::warn(), tr!("track.batch_failed", error = e));
     }
+}
 
-    if data.is_empty() {
-        bail!("Downloaded file is empty");
+/// A track of a job and its index in the session plan, `None` for tracks the plan leaves out
+pub struct TrackJob {
+    pub track: GwTrack,
+    pub slot: Option<usize>,
+}
+
+impl TrackJob {
+    /// Jobs for `tracks`, in the order of the session plan
+    pub fn numbered(tracks: Vec<GwTrack>) -> Vec<Self> {
+        tracks
+            .into_iter()
+            .enumerate()
+            .map(|(i, track)| Self { track, slot: Some(i) })
+            .collect()
     }
+}
 
-    // Decrypt if needed
-    let final_data = if is_crypted {
-        let blowfish_key = crypto::generate_blowfish_key(&sng_id);
-        crypto::decrypt_stream(&data, &blowfish_key)
-    } else {
-        data
-    };
+/// What a job keeps across its calls to [`run_track_jobs`]
+pub struct JobRun {
+    pub summary: JobSummary,
+    session: Session,
+    pub covers: CoverWriter,
+    pub nfos: NfoWriter,
+    /// Album artwork goes in the folder tracks are downloaded to rather than next to each file
+    pub album_folders: bool,
+    /// Skip recordings whose ISRC was already downloaded from another release
+    pub dedupe: bool,
+    downloaded_isrcs: HashSet<String>,
+    /// Tracks shown so far and in all, for the `[n/total]` lines
+    pub shown: usize,
+    pub total: usize,
+    /// Indent track lines under a release heading
+    pub nested: bool,
+}
 
-    // Remove leading null bytes (depadding) - but not for ftyp (MP4)
-    let output_data = if !final_data.is_empty() && final_data[0] == 0 {
-        if final_data.len() > 8 && &final_data[4..8] == b"ftyp" {
-            final_data
-        } else {
-            let start = final_data.iter().position(|&b| b != 0).unwrap_or(0);
-            final_data[start..].to_vec()
+impl JobRun {
+    pub fn new(opts: &DownloadOptions, session: Session, total: usize) -> Self {
+        Self {
+            summary: JobSummary::default(),
+            session,
+            covers: CoverWriter::new(opts),
+            nfos: NfoWriter::new(opts.nfo),
+            album_folders: false,
+            dedupe: false,
+            downloaded_isrcs: HashSet::new(),
+            shown: 0,
+            total,
+            nested: false,
         }
-    } else {
-        final_data
-    };
+    }
+
+    /// Retry the failed tracks once more, then close the session
+    pub async fn finish(mut self, api: &impl DeezerClient, opts: &DownloadOptions) -> JobSummary {
+        retry::retry_pass(api, opts, &mut self.summary).await;
+        self.session.finish().await;
+        self.summary
+    }
+}
+
+/// Download `jobs` into `dir` in order, refreshing expired tokens and prefetching stream URLs a
+/// batch at a time. Returns the file of each job, `None` for tracks that failed or were skipped.
+pub async fn run_track_jobs(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    run: &mut JobRun,
+    jobs: &mut [TrackJob],
+    dir: &Path,
+) -> Result<Vec<Option<PathBuf>>> {
+    let indent = if run.nested { "  " } else { "" };
+    let by_album = opts.groups_by_album();
+    let mut paths = Vec::with_capacity(jobs.len());
 
-    // Write to file
-    let mut file = tokio::fs::File::create(&filepath).await?;
-    file.write_all(&output_data).await?;
-    file.flush().await?;
+    for batch in jobs.chunks_mut(URL_BATCH_SIZE) {
+        refresh_expired(api, batch.iter_mut().map(|job| &mut job.track)).await;
+        prefetch_urls(api, batch.iter().map(|job| &job.track), opts.format).await;
+
+        for job in batch.iter() {
+            let track = &job.track;
+            run.shown += 1;
+            println!("{}[{}/{}] {}", indent, run.shown, run.total, track.display_name());
+
+            let isrc = track.isrc.as_deref().filter(|isrc| run.dedupe && !isrc.is_empty());
+            if isrc.is_some_and(|isrc| run.downloaded_isrcs.contains(isrc)) {
+                println!("{}  {} {}", indent, color::skip(), tr!("artist.duplicate"));
+                run.summary.duplicates += 1;
+                paths.push(None);
+                finish_track(opts, track);
+                continue;
+            }
+
+            match download_track(api, track, opts, dir, true).await {
+                Ok(done) => {
+                    println!("{}  {} {}", indent, color::ok(), tr!("job.ok"));
+                    let album_dir = if run.album_folders {
+                        Some(dir)
+                    } else {
+                        done.path.parent().filter(|_| by_album)
+                    };
+                    if let Some(album_dir) = album_dir {
+                        run.covers.save(api, album_dir, ImageKind::Album, track.alb_picture.as_deref()).await;
+                        run.nfos.save_album(api, album_dir, &track.album_id_str()).await;
+                    }
+                    run.summary.record_success(track, &done);
+                    if let Some(isrc) = isrc {
+                        // Another release's copy made up for an earlier failure: do not retry that one
+                        run.summary.forget_failures(isrc);
+                        run.downloaded_isrcs.insert(isrc.to_string());
+                    }
+                    if let Some(slot) = job.slot {
+                        run.session.done(slot).await;
+                    }
+                    paths.push(Some(done.path));
+                }
+                Err(e) => {
+                    run.summary.record_error(track, dir, &e);
+                    eprintln!("{}  {} {}", indent, color::err(), tr!("job.failed", error = e));
+                    if stops_job(&e) {
+                        return Err(e);
+                    }
+                    paths.push(None);
+                }
+            }
+            finish_track(opts, track);
+        }
+    }
+    Ok(paths)
+}
 
-    Ok(filepath)
+/// Count `track` as done on the job's progress bar
+fn finish_track(opts: &DownloadOptions, track: &GwTrack) {
+    if let Some(progress) = &opts.job_progress {
+        progress.finish_track(track);
+    }
 }
 
 /// Download a playlist by ID
 pub async fn download_playlist(
-    api: &DeezerApi,
+    api: &impl DeezerClient,
     playlist_id: &str,
-    format: TrackFormat,
+    opts: &DownloadOptions,
     output_dir: &Path,
 ) -> Result<()> {
     // Get playlist info
     let info = api.get_playlist_info(playlist_id).await?;
-    let playlist_name = info["DATA"]["TITLE"]
-        .as_str()
-        .unwrap_or("Unknown Playlist");
-    let playlist_dir = output_dir.join(sanitize_filename(playlist_name));
+    let playlist_name = info.data.display_name();
+    let playlist_name = playlist_name.as_str();
+    let playlist_dir = if opts.job_folders {
+        output_dir.join(opts.file_name(playlist_name))
+    } else {
+        output_dir.to_path_buf()
+    };
 
-    println!("Downloading playlist: {}\n", playlist_name);
+    println!("{}\n", tr!("playlist.start", name = playlist_name));
 
     // Get tracks
     let tracks = api.get_playlist_tracks(playlist_id).await?;
     let total = tracks.len();
 
-    println!("Found {} tracks\n", total);
+    println!("{}\n", tr!("job.found_tracks", count = total));
 
-    let mut downloaded = 0;
-    let mut failed = 0;
-
-    for (i, track) in tracks.iter().enumerate() {
-        let display = track.display_name();
-        println!("[{}/{}] {}", i + 1, total, display);
-
-        match download_track(api, track, format, &playlist_dir, true).await {
-            Ok(_) => {
-                downloaded += 1;
-                println!("  [ok] Downloaded successfully");
-            }
-            Err(e) => {
-                failed += 1;
-                eprintln!("  [err] Failed: {}", e);
-            }
-        }
+    let progress = JobProgress::for_tracks(&tracks, opts.format);
+    let opts = &DownloadOptions {
+        positions: TrackPositions::new(tracks.iter().map(GwTrack::id_str)),
+        ..opts.with_job_progress(&progress)
+    };
+    let plan = tracks.iter().map(|t| SessionTrack::new(t.id_str(), &playlist_dir)).collect();
+    let plan = SessionPlan::new("playlist", playlist_id, playlist_name, &playlist_dir, opts, plan);
+    let mut run = JobRun::new(opts, Session::start(&plan).await, total);
+    if opts.job_folders {
+        run.covers
+            .save(api, &playlist_dir, ImageKind::Playlist, info.data.playlist_picture.as_deref())
+            .await;
     }
 
+    run_track_jobs(api, opts, &mut run, &mut TrackJob::numbered(tracks), &playlist_dir).await?;
+    let summary = run.finish(api, opts).await;
+
     println!(
-        "\nPlaylist complete: {} downloaded, {} failed out of {} tracks",
-        downloaded, failed, total
+        "\n{}",
+        tr!("playlist.done", downloaded = summary.downloaded, failed = summary.failed, total = total)
     );
+    summary.print_details(opts);
+    notify::job_finished(api, opts, &JobReport::new("playlist", playlist_name, &playlist_dir, &summary)).await;
     Ok(())
 }
 
 /// Download user's favorite (liked) tracks
 pub async fn download_favorites(
-    api: &DeezerApi,
-    format: TrackFormat,
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
     output_dir: &Path,
 ) -> Result<()> {
-    println!("Fetching favorite tracks...\n");
+    println!("{}\n", tr!("favorites.fetching"));
 
     let ids = api.get_favorite_track_ids().await?;
     if ids.is_empty() {
-        println!("No favorite tracks found.");
+        println!("{}", tr!("favorites.none"));
         return Ok(());
     }
 
-    println!("Found {} favorite tracks\n", ids.len());
+    println!("{}\n", tr!("favorites.found", count = ids.len()));
 
     // Fetch track data in batches
-    let favorites_dir = output_dir.join("Favorites");
+    let favorites_dir = if opts.job_folders {
+        output_dir.join("Favorites")
+    } else {
+        output_dir.to_path_buf()
+    };
     let total = ids.len();
-    let mut downloaded = 0;
-    let mut failed = 0;
+    let progress = JobProgress::new(total, opts.format);
+    let opts = &DownloadOptions {
+        positions: TrackPositions::new(ids.iter().cloned()),
+        ..opts.with_job_progress(&progress)
+    };
+    let plan = ids.iter().map(|id| SessionTrack::new(id.clone(), &favorites_dir)).collect();
+    let session = Session::start(&SessionPlan::new("favorites", "", "Favorites", &favorites_dir, opts, plan)).await;
+    let mut run = JobRun::new(opts, session, total);
 
     // Process in batches of 50
     for (batch_start, batch) in ids.chunks(50).enumerate() {
-        let batch_ids: Vec<String> = batch.to_vec();
-        let tracks = api.get_tracks_by_ids(&batch_ids).await?;
-
-        for (j, track) in tracks.iter().enumerate() {
-            let i = batch_start * 50 + j + 1;
-            let display = track.display_name();
-            println!("[{}/{}] {}", i, total, display);
-
-            match download_track(api, track, format, &favorites_dir, true).await {
-                Ok(_) => {
-                    downloaded += 1;
-                    println!("  [ok] Downloaded successfully");
-                }
-                Err(e) => {
-                    failed += 1;
-                    eprintln!("  [err] Failed: {}", e);
-                }
-            }
-        }
+        let tracks = api.get_tracks_by_ids(batch).await?;
+        progress.add_tracks(&tracks, batch.len());
+        // Deezer leaves out unavailable IDs, so the plan index is the ID's position
+        let mut jobs: Vec<TrackJob> = tracks
+            .into_iter()
+            .map(|track| TrackJob {
+                slot: batch.iter().position(|id| *id == track.id_str()).map(|k| batch_start * 50 + k),
+                track,
+            })
+            .collect();
+        run.shown = batch_start * 50;
+        run_track_jobs(api, opts, &mut run, &mut jobs, &favorites_dir).await?;
     }
 
+    let summary = run.finish(api, opts).await;
+
     println!(
-        "\nFavorites complete: {} downloaded, {} failed out of {} tracks",
-        downloaded, failed, total
+        "\n{}",
+        tr!("favorites.done", downloaded = summary.downloaded, failed = summary.failed, total = total)
     );
+    summary.print_details(opts);
+    notify::job_finished(api, opts, &JobReport::new("favorites", "Favorites", &favorites_dir, &summary)).await;
     Ok(())
 }
 
+/// Apply `--only` / `--official-only`, then let the user pick releases with `--select`
+fn filter_releases(albums: Vec<AlbumInfo>, artist_opts: &ArtistOptions) -> Result<Vec<AlbumInfo>> {
+    let mut albums: Vec<AlbumInfo> = albums
+        .into_iter()
+        .filter(|a| artist_opts.only.is_empty() || artist_opts.only.iter().any(|t| t.matches(a)))
+        .filter(|a| !artist_opts.official_only || a.is_official())
+        .filter(|a| artist_opts.release_ids.as_ref().is_none_or(|ids| ids.contains(&a.id_str())))
+        .collect();
+
+    if artist_opts.select && !albums.is_empty() {
+        let names: Vec<String> = albums
+            .iter()
+            .map(|a| {
+                tr!(
+                    "artist.release",
+                    title = a.alb_title.as_deref().unwrap_or("Unknown Album"),
+                    kind = a.type_name(),
+                    count = a.track_count(),
+                )
+            })
+            .collect();
+        let chosen = dialoguer::MultiSelect::new()
+            .with_prompt(tr!("artist.select_releases"))
+            .items(&names)
+            .defaults(&vec![true; names.len()])
+            .interact()?;
+        albums = chosen.into_iter().map(|i| albums[i].clone()).collect();
+    }
+    Ok(albums)
+}
+
 /// Download all tracks from an artist
 pub async fn download_artist(
-    api: &DeezerApi,
+    api: &impl DeezerClient,
     art_id: &str,
-    format: TrackFormat,
+    opts: &DownloadOptions,
+    artist_opts: &ArtistOptions,
     output_dir: &Path,
 ) -> Result<()> {
     let artist_info = api.get_artist_info(art_id).await?;
-    let artist_name = artist_info["ART_NAME"]
-        .as_str()
-        .unwrap_or("Unknown Artist");
+    let artist_name = artist_info.display_name();
+    let artist_name = artist_name.as_str();
+
+    let artist_dir = output_dir.join(opts.file_name(artist_name));
+    artwork::save_artist_images(api, &artist_dir, artist_info.art_picture.as_deref(), opts.cover_size).await;
+    if opts.nfo {
+        nfo::write_artist(&artist_dir, &artist_info).await;
+    }
+
+    if let Some(limit) = artist_opts.top {
+        let top_dir = top_tracks_dir(opts, output_dir, artist_name);
+        download_artist_top(api, art_id, artist_name, limit, opts, &top_dir).await?;
+    } else {
+        download_discography(api, art_id, artist_name, opts, artist_opts, &artist_dir, output_dir).await?;
+    }
+
+    if let Some(count) = artist_opts.related {
+        let limit = artist_opts.top.unwrap_or(RELATED_TOP_TRACKS);
+        download_related(api, art_id, count, limit, opts, output_dir).await?;
+    }
+    Ok(())
+}
+
+/// Tracks per related artist when `--related` is used without `--top`
+const RELATED_TOP_TRACKS: usize = 10;
+
+/// Where `--top` downloads go: a "Top Tracks" folder in the artist folder, or the output root
+fn top_tracks_dir(opts: &DownloadOptions, output_dir: &Path, artist_name: &str) -> PathBuf {
+    if opts.job_folders {
+        output_dir.join(opts.file_name(artist_name)).join("Top Tracks")
+    } else {
+        output_dir.to_path_buf()
+    }
+}
+
+/// Download the top tracks of the `count` artists Deezer lists as most related
+async fn download_related(
+    api: &impl DeezerClient,
+    art_id: &str,
+    count: usize,
+    limit: usize,
+    opts: &DownloadOptions,
+    output_dir: &Path,
+) -> Result<()> {
+    let related = api.get_related_artists(art_id, count).await?;
+    if related.is_empty() {
+        println!("\n{}", tr!("related.none"));
+        return Ok(());
+    }
+    println!("\n{}\n", tr!("related.start", count = related.len()));
+
+    for (i, (id, name)) in related.iter().enumerate() {
+        println!("{}", tr!("related.artist", n = i + 1, total = related.len(), name = name));
+        let top_dir = top_tracks_dir(opts, output_dir, name);
+        if let Err(e) = download_artist_top(api, id, name, limit, opts, &top_dir).await {
+            eprintln!("  {} {}", color::err(), tr!("related.failed", name = name, error = e));
+        }
+        println!();
+    }
+    Ok(())
+}
 
-    println!("Fetching discography for: {}\n", artist_name);
+/// A release of the discography with its tracks, listed before any download
+struct ListedRelease<'a> {
+    album: &'a AlbumInfo,
+    dir: PathBuf,
+    tracks: std::result::Result<Vec<GwTrack>, DeezerError>,
+    /// Index of each track in the session plan, `None` for recordings also on an earlier release,
+    /// which are only downloaded when that earlier copy fails
+    slots: Vec<Option<usize>>,
+}
+
+/// Download an artist's releases, applying the `artist` filters
+async fn download_discography(
+    api: &impl DeezerClient,
+    art_id: &str,
+    artist_name: &str,
+    opts: &DownloadOptions,
+    artist_opts: &ArtistOptions,
+    artist_dir: &Path,
+    output_dir: &Path,
+) -> Result<()> {
+    println!("{}\n", tr!("artist.fetching", name = artist_name));
 
-    let albums = api.get_artist_discography(art_id).await?;
+    let mut albums = api.get_artist_discography(art_id, "all").await?;
+    let mut featured_ids = HashSet::new();
+    if artist_opts.include_features {
+        match api.get_artist_discography(art_id, "featured").await {
+            Ok(featured) => {
+                let own: HashSet<String> = albums.iter().map(|a| a.id_str()).collect();
+                for album in featured {
+                    if !own.contains(&album.id_str()) && featured_ids.insert(album.id_str()) {
+                        albums.push(album);
+                    }
+                }
+                println!("{}", tr!("artist.featured", count = featured_ids.len()));
+            }
+            Err(e) => eprintln!("  {} {}", color::warn(), tr!("artist.featured_failed", error = e)),
+        }
+    }
+    if albums.is_empty() {
+        println!("{}", tr!("artist.no_albums"));
+        return Ok(());
+    }
+    let found = albums.len();
+    let mut albums = filter_releases(albums, artist_opts)?;
+    if albums.len() < found {
+        println!("{}", tr!("artist.selected", count = albums.len(), total = found));
+    }
     if albums.is_empty() {
-        println!("No albums found for this artist.");
         return Ok(());
     }
 
-    println!("Found {} albums/releases\n", albums.len());
+    println!("{}\n", tr!("artist.found", count = albums.len()));
 
-    let artist_dir = output_dir.join(sanitize_filename(artist_name));
-    let mut total_downloaded = 0;
-    let mut total_failed = 0;
+    // Compilations mostly repeat album tracks, so let the original releases claim each ISRC first
+    let dedupe = !artist_opts.keep_duplicates;
+    if dedupe {
+        albums.sort_by_key(|a| a.type_code() == 2);
+    }
 
+    // List every release first so the session holds the whole plan
+    println!("{}\n", tr!("artist.listing"));
+    let mut releases = Vec::with_capacity(albums.len());
+    let mut plan = Vec::new();
+    let mut seen_isrcs = HashSet::new();
     for album in &albums {
         let alb_id = album.id_str();
         let album_title = album.alb_title.as_deref().unwrap_or("Unknown Album");
-        let album_dir = artist_dir.join(sanitize_filename(album_title));
+        let dir = if opts.job_folders {
+            artist_dir.join(opts.file_name(album_title))
+        } else {
+            output_dir.to_path_buf()
+        };
+        let tracks = api.get_album_tracks(&alb_id).await.map(|mut tracks| {
+            if featured_ids.contains(&alb_id) {
+                tracks.retain(|t| t.credits_artist(art_id));
+            }
+            tracks
+        });
+        let slots = tracks
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|track| {
+                let duplicate = dedupe
+                    && track
+                        .isrc
+                        .as_deref()
+                        .is_some_and(|isrc| !isrc.is_empty() && !seen_isrcs.insert(isrc.to_string()));
+                (!duplicate).then(|| {
+                    plan.push(SessionTrack::new(track.id_str(), &dir));
+                    plan.len() - 1
+                })
+            })
+            .collect();
+        releases.push(ListedRelease { album, dir, tracks, slots });
+    }
+
+    let listed = releases.iter().flat_map(|r| r.tracks.as_deref().unwrap_or_default());
+    let progress = JobProgress::for_tracks(listed, opts.format);
+    let opts = &opts.with_job_progress(&progress);
+    let mut session_plan = SessionPlan::new("artist", art_id, artist_name, artist_dir, opts, plan);
+    session_plan.album_folders = opts.job_folders;
+    let mut run = JobRun::new(opts, Session::start(&session_plan).await, 0);
+    run.album_folders = opts.job_folders;
+    run.dedupe = dedupe;
+    run.nested = true;
+
+    for release in &releases {
+        let album = release.album;
+        let alb_id = album.id_str();
+        let album_title = album.alb_title.as_deref().unwrap_or("Unknown Album");
+        let album_dir = &release.dir;
 
-        println!("--- Album: {} ---", album_title);
+        println!("{}", tr!("artist.album", title = album_title));
 
-        let tracks = match api.get_album_tracks(&alb_id).await {
+        let tracks = match &release.tracks {
             Ok(t) => t,
             Err(e) => {
-                eprintln!("  [err] Failed to get album tracks: {}", e);
-                total_failed += 1;
+                eprintln!("  {} {}", color::err(), tr!("artist.album_failed", error = e));
+                run.summary.failed += 1;
                 continue;
             }
         };
+        if tracks.is_empty() && featured_ids.contains(&alb_id) {
+            println!("  {}", tr!("artist.not_credited", name = artist_name));
+            continue;
+        }
 
-        for (i, track) in tracks.iter().enumerate() {
-            let display = track.display_name();
-            println!("  [{}/{}] {}", i + 1, tracks.len(), display);
-
-            match download_track(api, track, format, &album_dir, true).await {
-                Ok(_) => {
-                    total_downloaded += 1;
-                    println!("    [ok] Downloaded");
-                }
-                Err(e) => {
-                    total_failed += 1;
-                    eprintln!("    [err] Failed: {}", e);
-                }
-            }
+        let picture = album
+            .alb_picture
+            .as_deref()
+            .or_else(|| tracks.first().and_then(|t| t.alb_picture.as_deref()));
+        if opts.job_folders {
+            run.covers.save(api, album_dir, ImageKind::Album, picture).await;
+            run.nfos.save_album(api, album_dir, &alb_id).await;
         }
+
+        let mut jobs: Vec<TrackJob> = tracks
+            .iter()
+            .zip(&release.slots)
+            .map(|(track, &slot)| TrackJob { track: track.clone(), slot })
+            .collect();
+        run.shown = 0;
+        run.total = jobs.len();
+        run_track_jobs(api, opts, &mut run, &mut jobs, album_dir).await?;
+    }
+
+    let summary = run.finish(api, opts).await;
+
+    println!(
+        "\n{}",
+        tr!("artist.done", downloaded = summary.downloaded, failed = summary.failed)
+    );
+    summary.print_details(opts);
+    notify::job_finished(api, opts, &JobReport::new("artist", artist_name, artist_dir, &summary)).await;
+    Ok(())
+}
+
+/// Download an artist's most popular tracks (`artist --top N`)
+async fn download_artist_top(
+    api: &impl DeezerClient,
+    art_id: &str,
+    artist_name: &str,
+    limit: usize,
+    opts: &DownloadOptions,
+    top_dir: &Path,
+) -> Result<()> {
+    println!("{}\n", tr!("top.fetching", count = limit, name = artist_name));
+
+    let ids = api.get_artist_top(art_id, limit).await?;
+    if ids.is_empty() {
+        println!("{}", tr!("top.none"));
+        return Ok(());
     }
+    let tracks = api.get_tracks_by_ids(&ids).await?;
+    let total = tracks.len();
+    println!("{}\n", tr!("job.found_tracks", count = total));
+
+    let progress = JobProgress::for_tracks(&tracks, opts.format);
+    let opts = &DownloadOptions {
+        positions: TrackPositions::new(tracks.iter().map(GwTrack::id_str)),
+        ..opts.with_job_progress(&progress)
+    };
+    let name = format!("{} (top tracks)", artist_name);
+    let plan = tracks.iter().map(|t| SessionTrack::new(t.id_str(), top_dir)).collect();
+    let top_id = format!("{}-top", art_id);
+    let session = Session::start(&SessionPlan::new("artist", &top_id, &name, top_dir, opts, plan)).await;
+    let mut run = JobRun::new(opts, session, total);
+
+    run_track_jobs(api, opts, &mut run, &mut TrackJob::numbered(tracks), top_dir).await?;
+    let summary = run.finish(api, opts).await;
 
     println!(
-        "\nArtist download complete: {} downloaded, {} failed",
-        total_downloaded, total_failed
+        "\n{}",
+        tr!("top.done", downloaded = summary.downloaded, failed = summary.failed, total = total)
     );
+    summary.print_details(opts);
+    notify::job_finished(api, opts, &JobReport::new("artist", &name, top_dir, &summary)).await;
     Ok(())
 }
 
+/// `opts` with an event sink that also keeps the report of every job run with them
+pub fn collecting_reports(opts: &DownloadOptions) -> (DownloadOptions, Arc<Mutex<Vec<JobReport>>>) {
+    let reports: Arc<Mutex<Vec<JobReport>>> = Arc::default();
+    let collected = reports.clone();
+    let forward = opts.events.clone();
+    let events = EventSink::new(move |event| {
+        if let Event::JobFinished { report } = &event {
+            collected.lock().unwrap().push(report.clone());
+        }
+        if let Some(forward) = &forward {
+            forward.emit(event);
+        }
+    });
+    let opts = DownloadOptions {
+        events: Some(events),
+        ..opts.clone()
+    };
+    (opts, reports)
+}
+
+/// Jobs of one command run one after another (`track URL1 URL2...`), with a summary of
+/// all of them at the end
+pub struct Batch {
+    opts: DownloadOptions,
+    reports: Arc<Mutex<Vec<JobReport>>>,
+    total: usize,
+    errors: usize,
+}
+
+impl Batch {
+    /// A batch of `total` items; its jobs run with [`Batch::opts`]
+    pub fn new(opts: &DownloadOptions, total: usize) -> Self {
+        let (opts, reports) = collecting_reports(opts);
+        Self {
+            opts,
+            reports,
+            total,
+            errors: 0,
+        }
+    }
+
+    pub fn opts(&self) -> &DownloadOptions {
+        &self.opts
+    }
+
+    /// Note how an item's job ended. With several items, one that could not be downloaded
+    /// is reported and the batch goes on; errors that stop every job are returned.
+    pub fn record(&mut self, item: &str, result: Result<()>) -> Result<()> {
+        match result {
+            Err(e) if self.total > 1 && !stops_job(&e) => {
+                self.errors += 1;
+                eprintln!("{} {}", color::err(), tr!("batch.item_failed", item = item, error = e));
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Print the combined summary when there was more than one item. Fails when an item, or
+    /// any track of the jobs, could not be downloaded, so the command exits with an error.
+    pub fn finish(self) -> Result<()> {
+        let reports = self.reports.lock().unwrap();
+        let downloaded: usize = reports.iter().map(|r| r.downloaded).sum();
+        let failed: usize = reports.iter().map(|r| r.failed).sum();
+        if self.total > 1 {
+            println!(
+                "\n{}",
+                tr!("batch.done", items = self.total, downloaded = downloaded, failed = failed, errors = self.errors)
+            );
+        }
+        if self.errors > 0 {
+            bail!("{} of {} items could not be downloaded", self.errors, self.total);
+        }
+        if failed > 0 {
+            return Err(TracksFailed(failed).into());
+        }
+        Ok(())
+    }
+}
+
 /// Download a single track by URL or ID
 pub async fn download_single_track(
-    api: &DeezerApi,
+    api: &impl DeezerClient,
     track_id: &str,
-    format: TrackFormat,
+    opts: &DownloadOptions,
     output_dir: &Path,
 ) -> Result<()> {
-    println!("Fetching track info...\n");
+    println!("{}\n", tr!("track.fetching"));
 
     let track = api.get_track(track_id).await?;
     let display = track.display_name();
-    println!("Downloading: {}\n", display);
+    println!("{}\n", tr!("track.downloading", name = display));
+    // Worth the extra request for a single track
+    let opts = &DownloadOptions {
+        full_metadata: true,
+        ..opts.clone()
+    };
 
-    match download_track(api, &track, format, output_dir, true).await {
-        Ok(path) => {
-            println!("\nSaved to: {}", path.display());
+    let mut summary = JobSummary::default();
+    match download_track(api, &track, opts, output_dir, true).await {
+        Ok(done) => {
+            summary.record_success(&track, &done);
+            if opts.groups_by_album()
+                && let Some(dir) = done.path.parent()
+            {
+                CoverWriter::new(opts)
+                    .save(api, dir, ImageKind::Album, track.alb_picture.as_deref())
+                    .await;
+                NfoWriter::new(opts.nfo).save_album(api, dir, &track.album_id_str()).await;
+            }
+            if done.format != done.requested {
+                println!("\n{}", tr!("track.delivered_as", delivered = done.format, requested = done.requested));
+            }
+            println!("\n{}", tr!("track.saved", path = done.path.display()));
         }
         Err(e) => {
-            eprintln!("\nFailed to download: {}", e);
+            eprintln!("\n{}", tr!("track.download_failed", error = e));
+            summary.record_error(&track, output_dir, &e);
         }
     }
 
+    notify::job_finished(api, opts, &JobReport::new("track", &display, output_dir, &summary)).await;
     Ok(())
 }
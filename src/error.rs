@@ -0,0 +1,89 @@
+use reqwest::StatusCode;
+use std::io::ErrorKind;
+
+/// Errors from the Deezer client and downloads, grouped by what the caller
+/// can do about them: retry, skip the track, or stop
+#[derive(Debug, thiserror::Error)]
+pub enum DeezerError {
+    /// The ARL was rejected or no user is logged in
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+    /// The API token stayed invalid after refreshing it
+    #[error("Token expired: {0}")]
+    TokenExpired(String),
+    /// Rights or geo restriction: the track has no stream for this account
+    #[error("{0}")]
+    Unavailable(String),
+    /// Deezer kept reporting its request quota as exceeded
+    #[error("Deezer quota exceeded: {0}")]
+    Quota(String),
+    /// Non-success HTTP status from the CDN
+    #[error("Download failed with status: {0}")]
+    Cdn(StatusCode),
+    #[error("Network error: {0}")]
+    Network(#[from] reqwest::Error),
+    /// The stream did not decrypt to the expected file (wrong size, truncated)
+    #[error("{0}")]
+    Decryption(String),
+    #[error("Filesystem error: {0}")]
+    Filesystem(#[from] std::io::Error),
+    /// Invalid settings, e.g. a malformed proxy URL
+    #[error("{0}")]
+    Config(String),
+    /// The API answered with an error
+    #[error("{0}")]
+    Api(String),
+    /// The API answered with something we could not read
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+    /// Ctrl+C was pressed during the download
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+pub type Result<T> = std::result::Result<T, DeezerError>;
+
+impl From<serde_json::Error> for DeezerError {
+    fn from(e: serde_json::Error) -> Self {
+        DeezerError::InvalidResponse(e.to_string())
+    }
+}
+
+impl DeezerError {
+    /// The first `DeezerError` in an error chain, if any
+    pub fn find(err: &anyhow::Error) -> Option<&DeezerError> {
+        err.chain().find_map(|e| e.downcast_ref::<DeezerError>())
+    }
+
+    /// Trying again later may succeed: network trouble, server errors, quota, bad downloads
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DeezerError::Network(_)
+            | DeezerError::Quota(_)
+            | DeezerError::Decryption(_)
+            | DeezerError::InvalidResponse(_) => true,
+            DeezerError::Cdn(status) => !status.is_client_error(),
+            _ => false,
+        }
+    }
+
+    /// Nothing else will work until the user steps in (new ARL, disk space, settings)
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            DeezerError::Auth(_) | DeezerError::TokenExpired(_) | DeezerError::Config(_) | DeezerError::Interrupted => {
+                true
+            }
+            // Other I/O errors (e.g. a file name the filesystem rejects) only affect one track
+            DeezerError::Filesystem(e) => matches!(
+                e.kind(),
+                ErrorKind::StorageFull | ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem
+            ),
+            _ => false,
+        }
+    }
+
+    /// 403/404 from the CDN: the URL or its track token is no longer accepted
+    pub fn is_rejection(&self) -> bool {
+        matches!(self, DeezerError::Cdn(status) if *status == StatusCode::FORBIDDEN || *status == StatusCode::NOT_FOUND)
+    }
+}
@@ -0,0 +1,66 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::models::GwTrack;
+use crate::notify::JobReport;
+
+/// Progress of a download job, for frontends that do not read the console output
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    TrackStarted {
+        sng_id: String,
+        track: String,
+    },
+    /// Bytes received from the CDN so far; `total` is 0 when the size is unknown
+    TrackProgress {
+        sng_id: String,
+        received: u64,
+        total: u64,
+    },
+    TrackFinished {
+        sng_id: String,
+        track: String,
+        path: PathBuf,
+        format: String,
+    },
+    /// Failed, refused by `--min-quality` or left out by a filter
+    TrackFailed {
+        sng_id: String,
+        track: String,
+        error: String,
+    },
+    JobFinished {
+        report: JobReport,
+    },
+}
+
+impl Event {
+    pub fn track_started(track: &GwTrack) -> Self {
+        Event::TrackStarted {
+            sng_id: track.id_str(),
+            track: track.display_name(),
+        }
+    }
+}
+
+/// Receives the events of download jobs (`DownloadOptions::events`)
+#[derive(Clone)]
+pub struct EventSink(Arc<dyn Fn(Event) + Send + Sync>);
+
+impl EventSink {
+    pub fn new(handler: impl Fn(Event) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+
+    pub fn emit(&self, event: Event) {
+        (self.0)(event)
+    }
+}
+
+impl std::fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventSink")
+    }
+}
@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::client::DeezerClient;
+use crate::download::{self, DownloadOptions};
+use crate::history;
+use crate::models::GwTrack;
+use crate::nfo::xml_escape;
+use crate::tr;
+
+/// What `export` writes
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum ExportTarget {
+    /// Track list of a playlist, without downloading audio; downloaded tracks get their file path
+    Playlist {
+        /// Deezer playlist URL or playlist ID
+        url: String,
+        #[command(flatten)]
+        args: ExportArgs,
+    },
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct ExportArgs {
+    #[arg(long, value_enum, default_value = "csv")]
+    pub format: ExportFormat,
+    /// Where to write the export (default: `<Playlist>.<format>` in the output directory)
+    #[arg(long, value_name = "FILE")]
+    pub file: Option<PathBuf>,
+}
+
+/// File formats of `export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// One row per track, artists separated by `;`
+    Csv,
+    /// The playlist's name and ID with a list of tracks
+    Json,
+    /// XSPF playlist with `file://` locations of the downloaded tracks, for players and DJ tools
+    Xspf,
+    /// iTunes library XML with the downloaded tracks and the playlist, for Apple Music, Traktor and rekordbox
+    Itunes,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Xspf => "xspf",
+            ExportFormat::Itunes => "xml",
+        }
+    }
+}
+
+/// A playlist as exported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedPlaylist {
+    pub id: String,
+    pub name: String,
+    pub tracks: Vec<ExportedTrack>,
+}
+
+/// One track as exported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedTrack {
+    pub position: usize,
+    pub title: String,
+    pub artists: Vec<String>,
+    pub album: String,
+    pub isrc: String,
+    /// Seconds
+    pub duration: u32,
+    pub track_id: String,
+    pub album_id: String,
+    pub artist_id: String,
+    /// The downloaded file, if any (from the download history)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+}
+
+impl ExportedTrack {
+    pub fn new(position: usize, track: &GwTrack, file: Option<PathBuf>) -> Self {
+        Self {
+            position,
+            title: track.title_with_version(),
+            artists: track.artist_names(),
+            album: track.album(),
+            isrc: track.isrc.clone().unwrap_or_default(),
+            duration: track.duration_secs(),
+            track_id: track.id_str(),
+            album_id: track.album_id_str(),
+            artist_id: track.artist_id_str(),
+            file,
+        }
+    }
+}
+
+/// Run `export`, writing into `output_dir` unless `--file` is given
+pub async fn run(
+    api: &impl DeezerClient,
+    target: &ExportTarget,
+    opts: &DownloadOptions,
+    output_dir: &Path,
+) -> Result<()> {
+    match target {
+        ExportTarget::Playlist { url, args } => {
+            let playlist = playlist(api, url).await?;
+            let path = match &args.file {
+                Some(file) => file.clone(),
+                None => output_dir.join(format!("{}.{}", opts.file_name(&playlist.name), args.format.extension())),
+            };
+            let content = render(&playlist, args.format)?;
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            download::write_file_atomic(&path, content.as_bytes()).await?;
+            println!("{}", tr!("export.written", count = playlist.tracks.len(), path = path.display()));
+        }
+    }
+    Ok(())
+}
+
+/// Fetch a playlist's details and tracks
+pub async fn playlist(api: &impl DeezerClient, playlist_id: &str) -> Result<ExportedPlaylist> {
+    let info = api.get_playlist_info(playlist_id).await?;
+    let tracks = api.get_playlist_tracks(playlist_id).await?;
+    let mut files = history::files_by_id().await.unwrap_or_default();
+    let tracks = tracks
+        .iter()
+        .enumerate()
+        .map(|(i, t)| ExportedTrack::new(i + 1, t, files.remove(&t.id_str())))
+        .collect();
+    Ok(ExportedPlaylist {
+        id: playlist_id.to_string(),
+        name: info.data.display_name(),
+        tracks,
+    })
+}
+
+/// The export file's content
+pub fn render(playlist: &ExportedPlaylist, format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(playlist)? + "\n"),
+        ExportFormat::Xspf => Ok(xspf(playlist)),
+        ExportFormat::Itunes => Ok(itunes(playlist)),
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            writer.write_record([
+                "position", "title", "artists", "album", "isrc", "duration", "track_id", "album_id", "artist_id",
+                "file",
+            ])?;
+            for t in &playlist.tracks {
+                writer.write_record([
+                    &t.position.to_string(),
+                    &t.title,
+                    &t.artists.join("; "),
+                    &t.album,
+                    &t.isrc,
+                    &t.duration.to_string(),
+                    &t.track_id,
+                    &t.album_id,
+                    &t.artist_id,
+                    &t.file.as_ref().map(|f| f.display().to_string()).unwrap_or_default(),
+                ])?;
+            }
+            let bytes = writer.into_inner().context("Failed to write CSV")?;
+            Ok(String::from_utf8(bytes)?)
+        }
+    }
+}
+
+/// XSPF 1 playlist; tracks that are not downloaded keep their metadata but no location
+fn xspf(playlist: &ExportedPlaylist) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+    let _ = writeln!(out, "  <title>{}</title>", xml_escape(&playlist.name));
+    let _ = writeln!(out, "  <location>https://www.deezer.com/playlist/{}</location>", xml_escape(&playlist.id));
+    out.push_str("  <trackList>\n");
+    for t in &playlist.tracks {
+        out.push_str("    <track>\n");
+        if let Some(file) = &t.file {
+            let _ = writeln!(out, "      <location>{}</location>", xml_escape(&file_uri(file)));
+        }
+        let _ = writeln!(out, "      <identifier>https://www.deezer.com/track/{}</identifier>", t.track_id);
+        let fields = [("title", t.title.clone()), ("creator", t.artists.join(", ")), ("album", t.album.clone())];
+        for (tag, value) in fields {
+            if !value.is_empty() {
+                let _ = writeln!(out, "      <{tag}>{}</{tag}>", xml_escape(&value));
+            }
+        }
+        if t.duration > 0 {
+            let _ = writeln!(out, "      <duration>{}</duration>", u64::from(t.duration) * 1000);
+        }
+        if !t.isrc.is_empty() {
+            let _ = writeln!(out, "      <meta rel=\"https://isrc.org\">{}</meta>", xml_escape(&t.isrc));
+        }
+        out.push_str("    </track>\n");
+    }
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+/// iTunes library XML: a track for each downloaded file, and the playlist referencing them.
+/// Tracks that are not downloaded are left out, as iTunes can only import files.
+fn itunes(playlist: &ExportedPlaylist) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(
+        "<!DOCTYPE plist PUBLIC \"-//Apple Computer//DTD PLIST 1.0//EN\" \
+         \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+    );
+    out.push_str("<plist version=\"1.0\">\n<dict>\n");
+    out.push_str("\t<key>Major Version</key><integer>1</integer>\n");
+    out.push_str("\t<key>Minor Version</key><integer>1</integer>\n");
+    out.push_str("\t<key>Application Version</key><string>12.0</string>\n");
+    out.push_str("\t<key>Features</key><integer>5</integer>\n");
+    out.push_str("\t<key>Tracks</key>\n\t<dict>\n");
+    let downloaded: Vec<(&ExportedTrack, &PathBuf)> =
+        playlist.tracks.iter().filter_map(|t| Some((t, t.file.as_ref()?))).collect();
+    for (t, file) in &downloaded {
+        let _ = writeln!(out, "\t\t<key>{}</key>\n\t\t<dict>", t.position);
+        plist_integer(&mut out, "Track ID", u64::try_from(t.position).unwrap_or_default());
+        for (key, value) in [("Name", t.title.clone()), ("Artist", t.artists.join(", ")), ("Album", t.album.clone())] {
+            plist_string(&mut out, key, &value);
+        }
+        if t.duration > 0 {
+            plist_integer(&mut out, "Total Time", u64::from(t.duration) * 1000);
+        }
+        plist_string(&mut out, "Persistent ID", &persistent_id(&t.track_id));
+        plist_string(&mut out, "Track Type", "File");
+        // iTunes writes local URLs with an explicit host, and rekordbox expects it
+        let location = file_uri(file).replacen("file://", "file://localhost", 1);
+        plist_string(&mut out, "Location", &location);
+        out.push_str("\t\t</dict>\n");
+    }
+    out.push_str("\t</dict>\n\t<key>Playlists</key>\n\t<array>\n\t\t<dict>\n");
+    plist_string(&mut out, "Name", &playlist.name);
+    plist_string(&mut out, "Playlist Persistent ID", &persistent_id(&playlist.id));
+    out.push_str("\t\t\t<key>All Items</key><true/>\n\t\t\t<key>Playlist Items</key>\n\t\t\t<array>\n");
+    for (t, _) in &downloaded {
+        let _ = writeln!(out, "\t\t\t\t<dict><key>Track ID</key><integer>{}</integer></dict>", t.position);
+    }
+    out.push_str("\t\t\t</array>\n\t\t</dict>\n\t</array>\n</dict>\n</plist>\n");
+    out
+}
+
+fn plist_string(out: &mut String, key: &str, value: &str) {
+    if !value.is_empty() {
+        let _ = writeln!(out, "\t\t\t<key>{}</key><string>{}</string>", key, xml_escape(value));
+    }
+}
+
+fn plist_integer(out: &mut String, key: &str, value: u64) {
+    let _ = writeln!(out, "\t\t\t<key>{}</key><integer>{}</integer>", key, value);
+}
+
+/// 16 hex digits derived from a Deezer ID, so re-imports update the same items
+fn persistent_id(deezer_id: &str) -> String {
+    format!("{:016X}", deezer_id.parse::<u64>().unwrap_or_default())
+}
+
+/// `file://` URI of an absolute path, percent-encoding everything but unreserved characters and `/`
+pub fn file_uri(path: &Path) -> String {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    // Windows drive paths (`C:/...`) need the third slash too
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => uri.push(byte as char),
+            _ => {
+                let _ = write!(uri, "%{:02X}", byte);
+            }
+        }
+    }
+    uri
+}
@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+
+use crate::client::DeezerClient;
+use crate::config::OutputDirs;
+use crate::control::{self, CtlCommand};
+use crate::download::DownloadOptions;
+use crate::queue;
+
+/// Custom schemes `handle-url` accepts besides `https://` links
+const SCHEMES: [&str; 2] = ["deezerdl:", "deezer:"];
+
+/// The `https://www.deezer.com/...` URL of a link handed over by the system: a web link, or
+/// `deezerdl://` / `deezer://` followed by a web link (with or without its scheme) or just its
+/// path, e.g. `deezerdl://track/3135556` or `deezerdl://https://www.deezer.com/en/track/3135556`.
+/// `None` when it is not a track, playlist or artist.
+pub fn deezer_url(input: &str) -> Option<String> {
+    let input = input.trim();
+    let custom = SCHEMES.iter().find_map(|scheme| {
+        let prefix = input.get(..scheme.len())?;
+        prefix.eq_ignore_ascii_case(scheme).then(|| &input[scheme.len()..])
+    });
+    let url = match custom {
+        None => input.to_string(),
+        Some(rest) => {
+            let rest = rest.trim_start_matches('/');
+            // Browsers may drop the colon of a nested `https://`
+            let rest = ["https://", "http://", "https//", "http//"]
+                .iter()
+                .find_map(|scheme| rest.strip_prefix(scheme))
+                .unwrap_or(rest);
+            if rest.starts_with("www.deezer.com/") || rest.starts_with("deezer.com/") {
+                format!("https://{}", rest)
+            } else {
+                format!("https://www.deezer.com/{}", rest)
+            }
+        }
+    };
+    control::parse_url(&url).is_some().then_some(url)
+}
+
+/// Queue the item in the running daemon; `Ok(false)` when there is none, so it has to be
+/// downloaded here
+pub async fn queue_in_daemon(input: &str) -> Result<bool> {
+    let url = parse(input)?;
+    control::try_ctl(&CtlCommand::Queue { url }).await
+}
+
+/// Download the item into its output folder, as `track`, `playlist` or `artist` would
+pub async fn download(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    outputs: &OutputDirs,
+    input: &str,
+) -> Result<()> {
+    let url = parse(input)?;
+    let (kind, id) = control::parse_url(&url).context("Not a Deezer track, playlist or artist URL")?;
+    queue::download_item(api, opts, outputs, kind, &id).await
+}
+
+fn parse(input: &str) -> Result<String> {
+    deezer_url(input).with_context(|| format!("Not a Deezer track, playlist or artist link: {}", input))
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::auth::config_dir;
+use crate::models::{GwTrack, TrackFormat};
+
+/// One successfully downloaded track, stored as a JSON line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub sng_id: String,
+    pub isrc: Option<String>,
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    /// Deezer album ID (empty in entries written by older versions)
+    #[serde(default)]
+    pub album_id: String,
+    pub format: String,
+    pub path: PathBuf,
+    pub downloaded_at: u64,
+    /// Size of the file as written, including tags (0 if unknown)
+    #[serde(default)]
+    pub expected_size: u64,
+    #[serde(default)]
+    pub track_number: u32,
+    #[serde(default)]
+    pub disc_number: u32,
+    /// Part of a various-artists compilation
+    #[serde(default)]
+    pub compilation: bool,
+}
+
+impl HistoryEntry {
+    pub fn new(track: &GwTrack, format: TrackFormat, path: &Path) -> Self {
+        let downloaded_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            sng_id: track.id_str(),
+            isrc: track.isrc.clone().filter(|s| !s.is_empty()),
+            artist: track.artist(),
+            title: track.title(),
+            album: track.album(),
+            album_id: track.album_id_str(),
+            format: format.api_name().to_string(),
+            path: path.to_path_buf(),
+            downloaded_at,
+            expected_size: track.filesize_for_format(format),
+            track_number: track.track_no(),
+            disc_number: track.disc_no(),
+            compilation: false,
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        format!("{} - {}", self.artist, self.title)
+    }
+}
+
+/// Location of the download history file
+pub fn history_path() -> PathBuf {
+    config_dir().join("history.jsonl")
+}
+
+/// Append an entry to the history file
+pub async fn record(entry: &HistoryEntry) -> Result<()> {
+    let dir = config_dir();
+    fs::create_dir_all(&dir).await.context("Failed to create config dir")?;
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path())
+        .await
+        .context("Failed to open history file")?;
+    file.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// Load all history entries, ignoring malformed lines
+pub async fn load() -> Result<Vec<HistoryEntry>> {
+    let content = match fs::read_to_string(history_path()).await {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Failed to read history file"),
+    };
+
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// The newest file still on disk for each downloaded Deezer ID
+pub async fn files_by_id() -> Result<HashMap<String, PathBuf>> {
+    let mut files = HashMap::new();
+    for entry in load().await?.into_iter().rev() {
+        if !files.contains_key(&entry.sng_id) && entry.path.is_file() {
+            files.insert(entry.sng_id, entry.path);
+        }
+    }
+    Ok(files)
+}
+
+/// Rewrite the whole history file
+pub async fn save_all(entries: &[HistoryEntry]) -> Result<()> {
+    let dir = config_dir();
+    fs::create_dir_all(&dir).await.context("Failed to create config dir")?;
+
+    let mut content = String::new();
+    for entry in entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    fs::write(history_path(), content)
+        .await
+        .context("Failed to write history file")?;
+    Ok(())
+}
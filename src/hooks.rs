@@ -0,0 +1,140 @@
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::color;
+use crate::i18n;
+use crate::models::{GwTrack, TrackFormat};
+use crate::notify::JobReport;
+use crate::tr;
+
+/// Whether an executable called `program` can be found on PATH
+pub fn program_on_path(program: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&paths).any(|dir| {
+        dir.join(program).is_file() || dir.join(format!("{}.exe", program)).is_file()
+    })
+}
+
+/// Hand a staging directory over to beets with `beet import --quiet`; nothing to do when the
+/// command left it empty (`upgrade` and `repair` replace files where they are)
+pub async fn beets_import(staging: &Path) -> Result<()> {
+    let empty = std::fs::read_dir(staging).map_or(true, |mut entries| entries.next().is_none());
+    if empty {
+        return Ok(());
+    }
+    println!("\n{}\n", tr!("hooks.beets", path = staging.display()));
+    let status = Command::new("beet")
+        .arg("import")
+        .arg("--quiet")
+        .arg(staging)
+        .status()
+        .await
+        .context("Failed to run beet")?;
+    if !status.success() {
+        bail!("beet import exited with {}", status);
+    }
+    Ok(())
+}
+
+/// `--exec`: run `command` for a freshly downloaded track. Failures only warn.
+pub async fn exec_track(command: &str, track: &GwTrack, path: &Path, format: TrackFormat) {
+    let vars = [
+        ("path", path.display().to_string()),
+        ("artist", track.artist()),
+        ("title", track.title()),
+        ("album", track.album()),
+        ("format", format.to_string()),
+    ];
+    if let Err(e) = run_shell(command, &vars).await {
+        eprintln!("  {} {}", color::warn(), tr!("hooks.exec_failed", error = e));
+    }
+}
+
+/// `--exec-after`: run `command` once a job has finished. Failures only warn.
+pub async fn exec_job(command: &str, report: &JobReport) {
+    let vars = [
+        ("path", report.output_dir.display().to_string()),
+        ("name", report.name.clone()),
+        ("entity", report.entity.clone()),
+        ("downloaded", report.downloaded.to_string()),
+        ("failed", report.failed.to_string()),
+    ];
+    if let Err(e) = run_shell(command, &vars).await {
+        eprintln!("{} {}", color::warn(), tr!("hooks.exec_after_failed", error = e));
+    }
+}
+
+/// Run `command` through the system shell with `{name}` placeholders replaced by quoted values.
+/// Each value is also exported as `DEEZER_DL_<NAME>`, which is how cmd receives it.
+async fn run_shell(command: &str, vars: &[(&str, String)]) -> Result<()> {
+    let line = fill_command(command, vars, Shell::native());
+    #[cfg(windows)]
+    let mut shell = {
+        let mut shell = Command::new("cmd");
+        // `arg` would escape quotes with backslashes, which cmd does not understand. With /S, cmd
+        // strips exactly the outer pair of quotes and runs the rest as typed.
+        shell.args(["/S", "/C"]).raw_arg(format!("\"{}\"", line));
+        shell
+    };
+    #[cfg(not(windows))]
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(&line);
+        shell
+    };
+    for (name, value) in vars {
+        // cmd scans the expanded value for quotes, so one there would end the quoted argument
+        let value = if Shell::native() == Shell::Cmd { value.replace('"', "") } else { value.clone() };
+        shell.env(env_name(name), value);
+    }
+    let status = shell
+        .status()
+        .await
+        .with_context(|| format!("Failed to run `{}`", line))?;
+    if !status.success() {
+        bail!("`{}` exited with {}", line, status);
+    }
+    Ok(())
+}
+
+/// Which shell runs hook commands, for quoting values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// `sh -c`
+    Sh,
+    /// `cmd /C` on Windows
+    Cmd,
+}
+
+impl Shell {
+    /// The shell of the platform this binary runs on
+    pub fn native() -> Self {
+        if cfg!(windows) { Self::Cmd } else { Self::Sh }
+    }
+
+    /// What a `{name}` placeholder holding `value` becomes, passed as a single argument, unexpanded
+    pub fn quote(self, name: &str, value: &str) -> String {
+        match self {
+            Self::Sh => format!("'{}'", value.replace('\'', r"'\''")),
+            // cmd has no escape for `%` or `"` on a command line, so the value stays in the
+            // environment: cmd expands the variable once and does not expand its contents again
+            Self::Cmd => format!("\"%{}%\"", env_name(name)),
+        }
+    }
+}
+
+/// The environment variable a hook placeholder is exported as, e.g. `DEEZER_DL_PATH`
+pub fn env_name(name: &str) -> String {
+    format!("DEEZER_DL_{}", name.to_uppercase())
+}
+
+/// `command` with known `{name}` placeholders replaced by values quoted for `shell`. Placeholders
+/// are replaced in one pass, so values are never expanded again; unknown ones are left as they are.
+pub fn fill_command(command: &str, vars: &[(&str, String)], shell: Shell) -> String {
+    let quoted: Vec<(&str, String)> =
+        vars.iter().map(|(name, value)| (*name, shell.quote(name, value))).collect();
+    i18n::fill(command, &quoted)
+}
@@ -0,0 +1,792 @@
+use std::sync::OnceLock;
+
+/// Language of the console messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    En,
+    Fr,
+}
+
+impl Lang {
+    /// From the first non-empty of `LC_ALL`, `LC_MESSAGES` and `LANG`; English unless it is French
+    pub fn from_env() -> Self {
+        for var in ["LC_ALL", "LC_MESSAGES", "LANG"] {
+            if let Ok(value) = std::env::var(var)
+                && !value.is_empty()
+            {
+                return if value.starts_with("fr") { Lang::Fr } else { Lang::En };
+            }
+        }
+        Lang::En
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Lang::En => EN,
+            Lang::Fr => FR,
+        }
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Pick the language for the rest of the run; only the first call counts
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+/// The language set by [`set_lang`], or the one from the environment
+pub fn lang() -> Lang {
+    *LANG.get_or_init(Lang::from_env)
+}
+
+/// The message for `key`, falling back to English, then to the key itself
+pub fn message(key: &'static str) -> &'static str {
+    let find = |lang: Lang| lang.table().iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+    find(lang()).or_else(|| find(Lang::En)).unwrap_or(key)
+}
+
+/// Replace each `{name}` in `template` with its value; unknown names are left as they are
+pub fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after
+            .find('}')
+            .and_then(|end| values.iter().find(|(name, _)| *name == &after[..end]).map(|(_, v)| (end, v)));
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A translated console message: `tr!("track.saved", path = path.display())`
+#[macro_export]
+macro_rules! tr {
+    ($key:literal) => {
+        $crate::i18n::message($key).to_string()
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::fill($crate::i18n::message($key), &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+const EN: &[(&str, &str)] = &[
+    // Login
+    ("auth.stored_invalid", "Stored ARL is invalid, removing..."),
+    ("auth.need_arl", "You need a Deezer ARL cookie to use this tool."),
+    ("auth.arl_howto", "Get it from your browser: open deezer.com, press F12, go to Application > Cookies > arl"),
+    ("auth.prompt", "Enter your ARL"),
+    ("auth.failed", "Login failed. Invalid ARL."),
+    ("auth.logged_in", "Logged in as: {name}"),
+    (
+        "auth.quality_limited",
+        "{name} cannot stream {format} on this subscription: tracks will arrive as {best} at best \
+         (--strict-quality stops instead)",
+    ),
+    ("auth.logged_out", "Logged out. Stored ARL removed."),
+    ("net.insecure", "Warning: TLS certificate validation is disabled (--insecure)"),
+    // Interactive mode
+    ("menu.output_dir", "Output directory: {dir}"),
+    ("menu.output_dirs", "Output directories:"),
+    ("menu.pause_hint", "Press p while a track downloads to pause or resume"),
+    ("menu.dir.tracks", "tracks"),
+    ("menu.dir.playlists", "playlists"),
+    ("menu.dir.favorites", "favorites"),
+    ("menu.dir.artists", "artists"),
+    ("menu.prompt", "What would you like to do?"),
+    ("menu.track", "Download a track (URL or search)"),
+    ("menu.playlist", "Download a playlist"),
+    ("menu.favorites", "Download favorites (liked songs)"),
+    ("menu.artist", "Download all songs from an artist"),
+    ("menu.quit", "Quit"),
+    ("menu.track_prompt", "Enter track URL or ID"),
+    ("menu.playlist_how", "How to find the playlist?"),
+    ("menu.playlist_prompt", "Enter playlist URL or ID"),
+    ("menu.playlist_mine", "Choose from my playlists"),
+    ("menu.no_playlists", "No playlists found."),
+    ("menu.select_playlist", "Select a playlist"),
+    ("menu.artist_prompt", "Enter artist URL, ID, or name to search"),
+    ("menu.no_artists", "No artists found for '{query}'."),
+    ("menu.select_artist", "Select an artist"),
+    ("menu.artist_fans", "{name} ({fans} fans)"),
+    ("menu.bye", "Bye!"),
+    // Jobs
+    ("job.found_tracks", "Found {count} tracks"),
+    ("job.ok", "Downloaded successfully"),
+    ("job.failed", "Failed: {error}"),
+    ("job.fallbacks", "{count} tracks delivered in a lower format:"),
+    ("job.refused", "{count} tracks refused (below minimum quality):"),
+    ("job.filtered", "{count} tracks skipped by filters:"),
+    ("job.duplicates", "{count} duplicate recordings skipped (same ISRC on another release)"),
+    ("job.no_lyrics", "{count} tracks without lyrics on Deezer:"),
+    ("job.fallback_report", "Fallback report written to {path}"),
+    ("job.fallback_report_failed", "Could not write fallback report: {error}"),
+    ("batch.item_failed", "{item} failed: {error}"),
+    (
+        "batch.done",
+        "All {items} items done: {downloaded} tracks downloaded, {failed} failed, \
+         {errors} items could not be downloaded",
+    ),
+    ("track.clean", "Using clean version {id}"),
+    ("track.clean_failed", "Clean version failed: {error}"),
+    ("track.alt_fallback", "{id} ({error}), using alternative version {alt}"),
+    ("track.alt_trying", "Trying {id} from \"{album}\" (same recording)"),
+    ("track.alt_failed", "{id} failed: {error}"),
+    ("track.substitute_blocked", "Not using {id} instead: blocked by the blocklist ({reason})"),
+    ("track.exists", "{file} (already exists)"),
+    ("track.exists_as", "{file} (already downloaded as {existing})"),
+    ("track.hardlinked", "{file} (hardlinked to {source})"),
+    ("track.symlinked", "{file} (symlinked to {source})"),
+    ("track.link_failed", "Could not link to {source}: {error}; downloading it again"),
+    ("pool.m3u_written", "Playlist of pool files written to {path}"),
+    ("pool.m3u_failed", "Could not write {path}: {error}"),
+    ("track.fresh_token", "{error}, retrying with a fresh track token"),
+    ("track.tags_failed", "Could not write tags: {error}"),
+    ("track.history_failed", "Could not update download history: {error}"),
+    ("track.album_failed", "Could not fetch album details: {error}"),
+    ("track.genres_failed", "Could not fetch album genres: {error}"),
+    ("track.musicbrainz_failed", "MusicBrainz lookup failed: {error}"),
+    ("track.page_failed", "Could not fetch the track page: {error}"),
+    ("lyrics.failed", "Could not fetch lyrics: {error}"),
+    ("lyrics.no_translation", "No {lang} translation of the lyrics, keeping the original"),
+    ("lyrics.save_failed", "Could not save the lyrics file: {error}"),
+    ("track.tokens_failed", "Could not refresh expired track tokens: {error}"),
+    ("track.batch_failed", "Batch URL request failed: {error}"),
+    ("track.fetching", "Fetching track info..."),
+    ("track.downloading", "Downloading: {name}"),
+    ("track.delivered_as", "Note: delivered as {delivered} (requested {requested})"),
+    ("track.saved", "Saved to: {path}"),
+    ("track.download_failed", "Failed to download: {error}"),
+    ("playlist.start", "Downloading playlist: {name}"),
+    ("playlist.done", "Playlist complete: {downloaded} downloaded, {failed} failed out of {total} tracks"),
+    ("favorites.fetching", "Fetching favorite tracks..."),
+    ("favorites.none", "No favorite tracks found."),
+    ("favorites.found", "Found {count} favorite tracks"),
+    ("favorites.done", "Favorites complete: {downloaded} downloaded, {failed} failed out of {total} tracks"),
+    ("artist.release", "{title} ({kind}, {count} tracks)"),
+    ("artist.select_releases", "Select releases to download (space to toggle, enter to confirm)"),
+    ("artist.fetching", "Fetching discography for: {name}"),
+    ("artist.featured", "Found {count} releases the artist appears on"),
+    ("artist.featured_failed", "Could not fetch featured releases: {error}"),
+    ("artist.no_albums", "No albums found for this artist."),
+    ("artist.selected", "{count} of {total} releases selected"),
+    ("artist.found", "Found {count} albums/releases"),
+    ("artist.listing", "Listing the tracks of each release..."),
+    ("artist.album", "--- Album: {title} ---"),
+    ("artist.album_failed", "Failed to get album tracks: {error}"),
+    ("artist.not_credited", "No tracks credit {name} on this release"),
+    ("artist.duplicate", "Same recording already downloaded from another release"),
+    ("artist.done", "Artist download complete: {downloaded} downloaded, {failed} failed"),
+    ("related.none", "No related artists found."),
+    ("related.start", "Downloading top tracks of {count} related artists"),
+    ("related.artist", "=== Related artist {n}/{total}: {name} ==="),
+    ("related.failed", "Failed to download {name}: {error}"),
+    ("top.fetching", "Fetching top {count} tracks for: {name}"),
+    ("top.none", "No top tracks found for this artist."),
+    ("top.done", "Top tracks complete: {downloaded} downloaded, {failed} failed out of {total} tracks"),
+    ("progress.tracks", "{done}/{total} tracks"),
+    // Retries
+    ("retry.wait", "{what} failed: {error} (retry {n}/{max} in {delay}s)"),
+    ("retry.pass", "Retrying {count} failed tracks..."),
+    ("retry.failed_again", "Failed again: {error}"),
+    ("retry.saved", "{count} tracks still failing, saved to {path} (run `deezer-dl retry-failed` later)"),
+    ("retry.save_failed", "Could not save failed tracks: {error}"),
+    ("retry.none", "No failed tracks recorded."),
+    ("retry.start", "Retrying {count} previously failed tracks"),
+    ("retry.unavailable", "Track is no longer available"),
+    ("retry.done", "Retry complete: {downloaded} downloaded, {failed} still failing"),
+    // Ctrl+C
+    ("shutdown.stopping", "Stopping after the current chunk (Ctrl+C again to quit now)..."),
+    ("shutdown.stopped", "Interrupted."),
+    ("shutdown.resume_hint", "Run `deezer-dl resume` to continue where the job stopped."),
+    // Pause
+    ("pause.paused", "Paused"),
+    ("pause.paused_keys", "Paused, press p to resume"),
+    // Queue
+    ("queue.added", "Queued {url} (position {position})"),
+    ("queue.already", "Already queued: {url}"),
+    ("queue.invalid", "Not a Deezer track, playlist or artist URL: {url}"),
+    ("queue.invalid_entry", "Not queued, {entry}: {error}"),
+    ("queue.empty", "The queue is empty."),
+    ("queue.removed", "Removed {url}"),
+    ("queue.not_found", "Nothing queued matches {item}"),
+    ("queue.start", "Downloading {count} queued items"),
+    ("queue.item", "=== Queued {url} ==="),
+    ("queue.item_failed", "Queued {url} failed, it stays in the queue: {error}"),
+    ("queue.read_failed", "Could not read the queue: {error}"),
+    ("queue.done", "Queue run complete: {done} done, {failed} failed, {left} left in the queue"),
+    (
+        "clipboard.watching",
+        "Watching the clipboard: copied Deezer track, playlist and artist URLs are queued (Ctrl+C to stop). \
+         Download them with `queue run`, or let a running daemon pick them up.",
+    ),
+    // Job folder
+    ("jobs.watching", "Watching {dir} for .txt and .json job files (Ctrl+C to stop)"),
+    ("jobs.file", "=== Job file {name} ==="),
+    ("jobs.invalid", "{name} cannot be run, moved to failed/: {error}"),
+    ("jobs.item_failed", "{url} failed: {error}"),
+    ("jobs.done", "{name}: {done} done, {failed} failed, moved to {folder}/"),
+    // Export
+    ("export.written", "{count} tracks written to {path}"),
+    // Backup and restore
+    ("backup.favorites", "Fetching favorite tracks..."),
+    ("backup.playlist", "[{index}/{total}] Playlist {name}"),
+    ("backup.written", "Backup of {favorites} favorite tracks and {playlists} playlists written to {path}"),
+    ("restore.start", "Restoring the backup of {name} into the account of {user}"),
+    ("restore.favorites", "{count} favorite tracks to add, {present} already favorited"),
+    ("restore.track_failed", "Could not add {name} to favorites: {error}"),
+    ("restore.playlist_exists", "{name} (already in the account)"),
+    ("restore.playlist_create", "{name}: creating with {count} tracks"),
+    ("restore.playlist_follow", "{name}: adding to favorite playlists"),
+    ("restore.playlist_failed", "Could not restore playlist {name}: {error}"),
+    ("restore.dry_run_done", "Dry run, nothing was changed."),
+    (
+        "restore.done",
+        "Restore complete: {favorites} favorite tracks added, {created} playlists created, {followed} followed, \
+         {failed} failed",
+    ),
+    // Compare
+    ("compare.fetching", "Fetching favorite tracks..."),
+    ("compare.header", "{favorites} favorite tracks, {files} audio files in {dir}"),
+    ("compare.missing", "Favorites with no file on disk ({count}):"),
+    ("compare.unfavorited", "Files whose track is not in your favorites ({count}):"),
+    (
+        "compare.summary",
+        "{missing} favorites missing from disk, {unfavorited} files not favorited, \
+         {unidentified} files without a Deezer ID or ISRC to compare",
+    ),
+    // Probe
+    ("probe.album", "Album: {album} ({id})"),
+    ("probe.account", "Account {name}: high quality {hq}, lossless {lossless}"),
+    ("probe.yes", "yes"),
+    ("probe.no", "no"),
+    ("probe.allowed", "downloadable"),
+    ("probe.refused", "not allowed for this account"),
+    ("probe.missing", "not on Deezer"),
+    ("probe.total", "{format}: {allowed}/{total} tracks downloadable, {size}"),
+    // Account
+    ("whoami.user", "User: {name} (ID {id})"),
+    ("whoami.country", "Country: {country}"),
+    ("whoami.hq", "High quality (MP3 320): {value}"),
+    ("whoami.lossless", "Lossless (FLAC): {value}"),
+    ("whoami.best", "Best format downloads get: {format}"),
+    ("whoami.loved", "Loved tracks playlist: {id}"),
+    (
+        "whoami.arl",
+        "ARL saved {age} days ago; Deezer ARLs usually last about {lifetime} days, so roughly {left} days left",
+    ),
+    (
+        "whoami.arl_old",
+        "ARL saved {age} days ago, past the usual {lifetime} days: expect to paste a fresh one soon",
+    ),
+    ("whoami.arl_unknown", "ARL age unknown: no stored ARL at {path}"),
+    // Backup accounts
+    ("accounts.logged_in", "Backup account: {name} (up to {format})"),
+    ("accounts.login_failed", "Backup ARL #{number} was rejected, skipping it"),
+    ("accounts.login_error", "Backup ARL #{number} could not log in, skipping it: {error}"),
+    ("accounts.used", "{format} stream from backup account {name}"),
+    // Duplicate audit
+    ("dedupe.scanning", "Scanning {count} files in {dir} for duplicate recordings"),
+    ("dedupe.group", "{name}: {count} copies"),
+    ("dedupe.keep_prompt", "Keep which copy?"),
+    ("dedupe.leave", "Leave all copies"),
+    ("dedupe.stop", "Stop reviewing"),
+    ("dedupe.action_prompt", "What to do with the other copies?"),
+    ("dedupe.action_hardlink", "Replace them with hardlinks to the kept copy"),
+    ("dedupe.action_delete", "Delete them"),
+    ("dedupe.linked", "{file} now links to the kept copy"),
+    ("dedupe.deleted", "Deleted {file}"),
+    ("dedupe.failed", "Could not replace {file}: {error}"),
+    (
+        "dedupe.dry_run_done",
+        "{groups} recordings stored more than once ({unidentified} files without ISRC or history entry)",
+    ),
+    (
+        "dedupe.done",
+        "Duplicate audit complete: {groups} recordings stored more than once, {linked} copies linked, \
+         {deleted} deleted, {freed} freed ({unidentified} files without ISRC or history entry)",
+    ),
+    // Sessions
+    ("session.write_failed", "Could not save the job session, it cannot be resumed: {error}"),
+    ("session.unreadable", "Skipping unreadable session {path}: {error}"),
+    ("session.none", "No interrupted job to resume."),
+    ("session.entry", "{kind} {name}: {count} of {total} tracks left"),
+    ("session.resuming", "Resuming {kind} {name}: {count} of {total} tracks left"),
+    ("session.complete", "Resumed job complete: {downloaded} downloaded, {failed} failed"),
+    // Sync
+    ("sync.playlist", "Syncing playlist: {name}"),
+    ("sync.favorites", "Syncing favorite tracks..."),
+    ("sync.prune_ignored", "--prune is ignored with --layout, files are not in a folder of their own"),
+    ("sync.counts", "{total} tracks in the list, {present} already downloaded, {missing} to fetch"),
+    ("sync.removing", "Removing {count} tracks no longer in the list:"),
+    ("sync.remove_failed", "Could not remove {path}: {error}"),
+    ("sync.removed_hint", "{count} local tracks are no longer in the list (use --prune to remove them)"),
+    ("sync.done", "Sync complete: {downloaded} downloaded, {failed} failed, {present} already present"),
+    ("sync.m3u_written", "Playlist file written to {path}"),
+    ("sync.m3u_failed", "Could not write {path}: {error}"),
+    ("diff.header", "{name}: {total} tracks on Deezer, compared with {target}"),
+    ("diff.track_id", "Track {id}"),
+    ("diff.summary", "{added} added (sync would download them), {removed} removed (--prune would delete them)"),
+    // Watch and daemon
+    ("watch.check_failed", "Could not check artist {id}: {error}"),
+    ("watch.new_releases", "New releases for {name}:"),
+    ("watch.baseline", "Watching {name}: {count} existing releases recorded, later ones will be downloaded"),
+    ("watch.download_failed", "Failed to download new releases: {error}"),
+    ("watch.followed_failed", "Could not fetch followed artists: {error}"),
+    ("watch.checking", "Checking {count} artists for new releases..."),
+    ("watch.none", "No new releases."),
+    ("watch.grabbed", "Watch: {count} new releases grabbed"),
+    ("watch.next", "Next check in {secs}s"),
+    ("daemon.playlist", "=== Playlist {id} ==="),
+    ("daemon.playlist_failed", "Playlist {id} failed: {error}"),
+    ("daemon.favorites", "=== Favorites ==="),
+    ("daemon.favorites_failed", "Favorites sync failed: {error}"),
+    ("daemon.artists", "=== Watched artists ({count}) ==="),
+    ("daemon.grabbed", "{count} new releases grabbed"),
+    ("daemon.artists_failed", "Artist check failed: {error}"),
+    ("daemon.queued", "=== Queued {kind} {id} ==="),
+    ("daemon.queued_failed", "Queued {kind} {id} failed: {error}"),
+    ("daemon.pass_start", "Starting sync pass..."),
+    ("daemon.pass_done", "Sync pass finished. Next pass in {secs}s"),
+    // Library
+    ("library.verify_done", "Verify complete: {checked} files checked, {ok} ok, {problems} with problems"),
+    ("library.scanning", "Scanning {count} files in {dir}"),
+    ("library.unknown_file", "{path} (no tags, not in download history)"),
+    ("library.target_exists", "{path} -> {target} (target exists)"),
+    (
+        "library.organize_preview",
+        "Organize complete: {moved} would be moved, {unchanged} already in place, {unknown} unknown, \
+         {conflicts} conflicts",
+    ),
+    (
+        "library.organize_done",
+        "Organize complete: {moved} moved, {unchanged} already in place, {unknown} unknown, {conflicts} conflicts",
+    ),
+    ("library.tags_failed", "Could not write tags: {error}"),
+    ("library.no_mp3", "No MP3 tracks found in the download history."),
+    ("library.checking_flac", "Checking {count} MP3 tracks for FLAC availability..."),
+    ("library.upgraded", "Upgraded to FLAC"),
+    ("library.upgrade_would", "Would be upgraded to {path}"),
+    (
+        "library.upgrade_preview",
+        "Upgrade complete: {upgraded} can be upgraded, {unavailable} not available in FLAC, {failed} failed",
+    ),
+    (
+        "library.upgrade_done",
+        "Upgrade complete: {upgraded} upgraded, {unavailable} not available in FLAC, {failed} failed",
+    ),
+    ("library.nothing_to_repair", "All {count} files passed verification, nothing to repair."),
+    ("library.problems_found", "Found {count} files with problems out of {checked}"),
+    ("library.unavailable", "Track {id} is no longer available"),
+    ("library.repaired", "Repaired ({format})"),
+    ("library.repair_preview", "{count} files would be re-downloaded"),
+    ("library.repair_done", "Repair complete: {repaired} repaired, {failed} failed"),
+    // Artwork and NFO
+    ("artwork.artist_failed", "Could not save artist images: {error}"),
+    ("artwork.save_failed", "Could not save {path}: {error}"),
+    ("nfo.write_failed", "Could not write {path}: {error}"),
+    ("nfo.create_failed", "Could not create {path}: {error}"),
+    ("nfo.no_album", "No album data for NFO: {error}"),
+    // Hooks
+    ("hooks.beets", "Importing {path} with beets..."),
+    ("hooks.exec_failed", "--exec failed: {error}"),
+    ("hooks.exec_after_failed", "--exec-after failed: {error}"),
+    // Notifications
+    ("notify.finished", "{entity} finished: {name}"),
+    ("notify.finished_with_failures", "{entity} finished with failures: {name}"),
+    ("notify.counts", "{downloaded} downloaded, {failed} failed"),
+    ("notify.refused", ", {count} below minimum quality"),
+    ("notify.filtered", ", {count} filtered"),
+    ("notify.more_failures", "and {count} more"),
+    ("notify.failed", "{target} notification failed: {error}"),
+    ("notify.desktop_failed", "Desktop notification failed: {error}"),
+    // Control socket and RPC
+    ("control.socket", "Control socket: {path}"),
+    ("control.pipe", "Control pipe: {name}"),
+    ("control.connection_failed", "Control connection failed: {error}"),
+    ("control.accept_failed", "Control connection could not be accepted: {error}"),
+    ("control.pipe_stopped", "Control pipe stopped: {error}"),
+    ("rpc.stdin_failed", "Could not read stdin: {error}"),
+    ("rpc.arl_not_saved", "Could not store the ARL: {error}"),
+    // Rate limits
+    ("throttle.rate_limit", "Deezer rate limit reached"),
+    ("throttle.waiting", "{reason}, resuming in {secs}s"),
+    ("throttle.resuming", "{reason}, resuming now"),
+];
+
+const FR: &[(&str, &str)] = &[
+    // Connexion
+    ("auth.stored_invalid", "L'ARL enregistré n'est plus valide, suppression..."),
+    ("auth.need_arl", "Un cookie ARL Deezer est nécessaire pour utiliser cet outil."),
+    (
+        "auth.arl_howto",
+        "Récupérez-le dans votre navigateur : ouvrez deezer.com, appuyez sur F12, puis Application > Cookies > arl",
+    ),
+    ("auth.prompt", "Entrez votre ARL"),
+    ("auth.failed", "Échec de la connexion. ARL invalide."),
+    ("auth.logged_in", "Connecté en tant que : {name}"),
+    (
+        "auth.quality_limited",
+        "{name} ne peut pas écouter en {format} avec cet abonnement : les titres arriveront au mieux en {best} \
+         (--strict-quality arrête plutôt)",
+    ),
+    ("auth.logged_out", "Déconnecté. ARL enregistré supprimé."),
+    ("net.insecure", "Attention : la vérification des certificats TLS est désactivée (--insecure)"),
+    // Mode interactif
+    ("menu.output_dir", "Dossier de destination : {dir}"),
+    ("menu.output_dirs", "Dossiers de destination :"),
+    ("menu.pause_hint", "Appuyez sur p pendant un téléchargement pour le mettre en pause ou le reprendre"),
+    ("menu.dir.tracks", "titres"),
+    ("menu.dir.playlists", "playlists"),
+    ("menu.dir.favorites", "favoris"),
+    ("menu.dir.artists", "artistes"),
+    ("menu.prompt", "Que voulez-vous faire ?"),
+    ("menu.track", "Télécharger un titre (URL ou recherche)"),
+    ("menu.playlist", "Télécharger une playlist"),
+    ("menu.favorites", "Télécharger les favoris (titres likés)"),
+    ("menu.artist", "Télécharger tous les titres d'un artiste"),
+    ("menu.quit", "Quitter"),
+    ("menu.track_prompt", "Entrez l'URL ou l'ID du titre"),
+    ("menu.playlist_how", "Comment trouver la playlist ?"),
+    ("menu.playlist_prompt", "Entrez l'URL ou l'ID de la playlist"),
+    ("menu.playlist_mine", "Choisir parmi mes playlists"),
+    ("menu.no_playlists", "Aucune playlist trouvée."),
+    ("menu.select_playlist", "Choisissez une playlist"),
+    ("menu.artist_prompt", "Entrez l'URL, l'ID ou le nom de l'artiste à rechercher"),
+    ("menu.no_artists", "Aucun artiste trouvé pour « {query} »."),
+    ("menu.select_artist", "Choisissez un artiste"),
+    ("menu.artist_fans", "{name} ({fans} fans)"),
+    ("menu.bye", "Au revoir !"),
+    // Téléchargements
+    ("job.found_tracks", "{count} titres trouvés"),
+    ("job.ok", "Téléchargé avec succès"),
+    ("job.failed", "Échec : {error}"),
+    ("job.fallbacks", "{count} titres livrés dans un format inférieur :"),
+    ("job.refused", "{count} titres refusés (qualité inférieure au minimum) :"),
+    ("job.filtered", "{count} titres écartés par les filtres :"),
+    ("job.duplicates", "{count} enregistrements en double ignorés (même ISRC sur une autre sortie)"),
+    ("job.no_lyrics", "{count} titres sans paroles sur Deezer :"),
+    ("job.fallback_report", "Rapport des replis écrit dans {path}"),
+    ("job.fallback_report_failed", "Impossible d'écrire le rapport des replis : {error}"),
+    ("batch.item_failed", "{item} en échec : {error}"),
+    (
+        "batch.done",
+        "Les {items} éléments sont terminés : {downloaded} titres téléchargés, {failed} en échec, \
+         {errors} éléments n'ont pas pu être téléchargés",
+    ),
+    ("track.clean", "Version non explicite utilisée : {id}"),
+    ("track.clean_failed", "Échec de la version non explicite : {error}"),
+    ("track.alt_fallback", "{id} ({error}), utilisation de la version alternative {alt}"),
+    ("track.alt_trying", "Essai de {id} depuis « {album} » (même enregistrement)"),
+    ("track.alt_failed", "{id} a échoué : {error}"),
+    ("track.substitute_blocked", "{id} n'est pas utilisé à la place : bloqué par la liste de blocage ({reason})"),
+    ("track.exists", "{file} (existe déjà)"),
+    ("track.exists_as", "{file} (déjà téléchargé sous {existing})"),
+    ("track.hardlinked", "{file} (lien physique vers {source})"),
+    ("track.symlinked", "{file} (lien symbolique vers {source})"),
+    ("track.link_failed", "Impossible de créer le lien vers {source} : {error} ; nouveau téléchargement"),
+    ("pool.m3u_written", "Playlist des fichiers du pool écrite dans {path}"),
+    ("pool.m3u_failed", "Impossible d'écrire {path} : {error}"),
+    ("track.fresh_token", "{error}, nouvel essai avec un jeton de titre neuf"),
+    ("track.tags_failed", "Impossible d'écrire les tags : {error}"),
+    ("track.history_failed", "Impossible de mettre à jour l'historique des téléchargements : {error}"),
+    ("track.album_failed", "Impossible de récupérer les détails de l'album : {error}"),
+    ("track.genres_failed", "Impossible de récupérer les genres de l'album : {error}"),
+    ("track.musicbrainz_failed", "Échec de la recherche MusicBrainz : {error}"),
+    ("track.page_failed", "Impossible de récupérer la page du titre : {error}"),
+    ("lyrics.failed", "Impossible de récupérer les paroles : {error}"),
+    ("lyrics.no_translation", "Pas de traduction {lang} des paroles, paroles originales conservées"),
+    ("lyrics.save_failed", "Impossible d'enregistrer le fichier de paroles : {error}"),
+    ("track.tokens_failed", "Impossible de renouveler les jetons de titre expirés : {error}"),
+    ("track.batch_failed", "Échec de la requête groupée d'URL : {error}"),
+    ("track.fetching", "Récupération des informations du titre..."),
+    ("track.downloading", "Téléchargement : {name}"),
+    ("track.delivered_as", "Remarque : livré en {delivered} (demandé : {requested})"),
+    ("track.saved", "Enregistré dans : {path}"),
+    ("track.download_failed", "Échec du téléchargement : {error}"),
+    ("playlist.start", "Téléchargement de la playlist : {name}"),
+    ("playlist.done", "Playlist terminée : {downloaded} téléchargés, {failed} en échec sur {total} titres"),
+    ("favorites.fetching", "Récupération des titres favoris..."),
+    ("favorites.none", "Aucun titre favori trouvé."),
+    ("favorites.found", "{count} titres favoris trouvés"),
+    ("favorites.done", "Favoris terminés : {downloaded} téléchargés, {failed} en échec sur {total} titres"),
+    ("artist.release", "{title} ({kind}, {count} titres)"),
+    ("artist.select_releases", "Choisissez les sorties à télécharger (espace pour cocher, entrée pour valider)"),
+    ("artist.fetching", "Récupération de la discographie de : {name}"),
+    ("artist.featured", "{count} sorties où l'artiste apparaît"),
+    ("artist.featured_failed", "Impossible de récupérer les participations : {error}"),
+    ("artist.no_albums", "Aucun album trouvé pour cet artiste."),
+    ("artist.selected", "{count} sorties sélectionnées sur {total}"),
+    ("artist.found", "{count} albums/sorties trouvés"),
+    ("artist.listing", "Liste des titres de chaque sortie..."),
+    ("artist.album", "--- Album : {title} ---"),
+    ("artist.album_failed", "Impossible de récupérer les titres de l'album : {error}"),
+    ("artist.not_credited", "Aucun titre de cette sortie ne crédite {name}"),
+    ("artist.duplicate", "Même enregistrement déjà téléchargé depuis une autre sortie"),
+    ("artist.done", "Artiste terminé : {downloaded} téléchargés, {failed} en échec"),
+    ("related.none", "Aucun artiste similaire trouvé."),
+    ("related.start", "Téléchargement des meilleurs titres de {count} artistes similaires"),
+    ("related.artist", "=== Artiste similaire {n}/{total} : {name} ==="),
+    ("related.failed", "Échec du téléchargement de {name} : {error}"),
+    ("top.fetching", "Récupération des {count} meilleurs titres de : {name}"),
+    ("top.none", "Aucun titre populaire trouvé pour cet artiste."),
+    ("top.done", "Meilleurs titres terminés : {downloaded} téléchargés, {failed} en échec sur {total} titres"),
+    ("progress.tracks", "{done}/{total} titres"),
+    // Nouvelles tentatives
+    ("retry.wait", "{what} a échoué : {error} (nouvel essai {n}/{max} dans {delay} s)"),
+    ("retry.pass", "Nouvel essai de {count} titres en échec..."),
+    ("retry.failed_again", "Nouvel échec : {error}"),
+    (
+        "retry.saved",
+        "{count} titres toujours en échec, enregistrés dans {path} (lancez `deezer-dl retry-failed` plus tard)",
+    ),
+    ("retry.save_failed", "Impossible d'enregistrer les titres en échec : {error}"),
+    ("retry.none", "Aucun titre en échec enregistré."),
+    ("retry.start", "Nouvel essai de {count} titres précédemment en échec"),
+    ("retry.unavailable", "Le titre n'est plus disponible"),
+    ("retry.done", "Nouvel essai terminé : {downloaded} téléchargés, {failed} toujours en échec"),
+    // Ctrl+C
+    ("shutdown.stopping", "Arrêt après le bloc en cours (Ctrl+C à nouveau pour quitter tout de suite)..."),
+    ("shutdown.stopped", "Interrompu."),
+    ("shutdown.resume_hint", "Lancez `deezer-dl resume` pour reprendre là où la tâche s'est arrêtée."),
+    // Pause
+    ("pause.paused", "En pause"),
+    ("pause.paused_keys", "En pause, appuyez sur p pour reprendre"),
+    // File d'attente
+    ("queue.added", "Ajouté à la file : {url} (position {position})"),
+    ("queue.already", "Déjà dans la file : {url}"),
+    ("queue.invalid", "Pas une URL Deezer de titre, de playlist ou d'artiste : {url}"),
+    ("queue.invalid_entry", "Non ajouté, {entry} : {error}"),
+    ("queue.empty", "La file est vide."),
+    ("queue.removed", "Retiré : {url}"),
+    ("queue.not_found", "Rien dans la file ne correspond à {item}"),
+    ("queue.start", "Téléchargement de {count} éléments en file"),
+    ("queue.item", "=== En file : {url} ==="),
+    ("queue.item_failed", "{url} en file a échoué, il reste dans la file : {error}"),
+    ("queue.read_failed", "Impossible de lire la file : {error}"),
+    ("queue.done", "File terminée : {done} terminés, {failed} en échec, {left} restants dans la file"),
+    (
+        "clipboard.watching",
+        "Surveillance du presse-papiers : les URL Deezer de titres, playlists et artistes copiées sont \
+         ajoutées à la file (Ctrl+C pour arrêter). Téléchargez-les avec `queue run`, ou laissez un daemon actif \
+         s'en charger.",
+    ),
+    // Job folder
+    ("jobs.watching", "Surveillance de {dir} pour des fichiers de tâches .txt et .json (Ctrl+C pour arrêter)"),
+    ("jobs.file", "=== Fichier de tâche {name} ==="),
+    ("jobs.invalid", "{name} ne peut pas être lancé, déplacé dans failed/ : {error}"),
+    ("jobs.item_failed", "{url} en échec : {error}"),
+    ("jobs.done", "{name} : {done} terminés, {failed} en échec, déplacé dans {folder}/"),
+    // Export
+    ("export.written", "{count} titres écrits dans {path}"),
+    // Sauvegarde et restauration
+    ("backup.favorites", "Récupération des titres favoris..."),
+    ("backup.playlist", "[{index}/{total}] Playlist {name}"),
+    ("backup.written", "Sauvegarde de {favorites} titres favoris et {playlists} playlists écrite dans {path}"),
+    ("restore.start", "Restauration de la sauvegarde de {name} dans le compte de {user}"),
+    ("restore.favorites", "{count} titres favoris à ajouter, {present} déjà en favoris"),
+    ("restore.track_failed", "Impossible d'ajouter {name} aux favoris : {error}"),
+    ("restore.playlist_exists", "{name} (déjà dans le compte)"),
+    ("restore.playlist_create", "{name} : création avec {count} titres"),
+    ("restore.playlist_follow", "{name} : ajout aux playlists favorites"),
+    ("restore.playlist_failed", "Impossible de restaurer la playlist {name} : {error}"),
+    ("restore.dry_run_done", "Simulation, rien n'a été modifié."),
+    (
+        "restore.done",
+        "Restauration terminée : {favorites} titres favoris ajoutés, {created} playlists créées, {followed} suivies, \
+         {failed} échecs",
+    ),
+    // Compare
+    ("compare.fetching", "Récupération des titres favoris..."),
+    ("compare.header", "{favorites} titres favoris, {files} fichiers audio dans {dir}"),
+    ("compare.missing", "Favoris sans fichier sur le disque ({count}) :"),
+    ("compare.unfavorited", "Fichiers dont le titre n'est pas dans vos favoris ({count}) :"),
+    (
+        "compare.summary",
+        "{missing} favoris absents du disque, {unfavorited} fichiers hors favoris, \
+         {unidentified} fichiers sans ID Deezer ni ISRC à comparer",
+    ),
+    // Probe
+    ("probe.album", "Album : {album} ({id})"),
+    ("probe.account", "Compte {name} : haute qualité {hq}, sans perte {lossless}"),
+    ("probe.yes", "oui"),
+    ("probe.no", "non"),
+    ("probe.allowed", "téléchargeable"),
+    ("probe.refused", "non autorisé pour ce compte"),
+    ("probe.missing", "absent de Deezer"),
+    ("probe.total", "{format} : {allowed}/{total} titres téléchargeables, {size}"),
+    // Compte
+    ("whoami.user", "Utilisateur : {name} (ID {id})"),
+    ("whoami.country", "Pays : {country}"),
+    ("whoami.hq", "Haute qualité (MP3 320) : {value}"),
+    ("whoami.lossless", "Sans perte (FLAC) : {value}"),
+    ("whoami.best", "Meilleur format téléchargeable : {format}"),
+    ("whoami.loved", "Playlist des coups de cœur : {id}"),
+    (
+        "whoami.arl",
+        "ARL enregistré il y a {age} jours ; un ARL Deezer dure en général environ {lifetime} jours, \
+         il en resterait donc environ {left}",
+    ),
+    (
+        "whoami.arl_old",
+        "ARL enregistré il y a {age} jours, au-delà des {lifetime} jours habituels : \
+         il faudra sans doute bientôt en coller un nouveau",
+    ),
+    ("whoami.arl_unknown", "Âge de l'ARL inconnu : aucun ARL enregistré dans {path}"),
+    // Comptes de secours
+    ("accounts.logged_in", "Compte de secours : {name} (jusqu'à {format})"),
+    ("accounts.login_failed", "L'ARL de secours n°{number} a été refusé, il est ignoré"),
+    ("accounts.login_error", "L'ARL de secours n°{number} n'a pas pu se connecter, il est ignoré : {error}"),
+    ("accounts.used", "Flux {format} du compte de secours {name}"),
+    // Audit des doublons
+    ("dedupe.scanning", "Recherche d'enregistrements en double parmi {count} fichiers dans {dir}"),
+    ("dedupe.group", "{name} : {count} copies"),
+    ("dedupe.keep_prompt", "Quelle copie garder ?"),
+    ("dedupe.leave", "Garder toutes les copies"),
+    ("dedupe.stop", "Arrêter la revue"),
+    ("dedupe.action_prompt", "Que faire des autres copies ?"),
+    ("dedupe.action_hardlink", "Les remplacer par des liens physiques vers la copie gardée"),
+    ("dedupe.action_delete", "Les supprimer"),
+    ("dedupe.linked", "{file} pointe maintenant vers la copie gardée"),
+    ("dedupe.deleted", "{file} supprimé"),
+    ("dedupe.failed", "Impossible de remplacer {file} : {error}"),
+    (
+        "dedupe.dry_run_done",
+        "{groups} enregistrements présents plusieurs fois ({unidentified} fichiers sans ISRC ni historique)",
+    ),
+    (
+        "dedupe.done",
+        "Audit des doublons terminé : {groups} enregistrements présents plusieurs fois, {linked} copies liées, \
+         {deleted} supprimées, {freed} libérés ({unidentified} fichiers sans ISRC ni historique)",
+    ),
+    // Sessions
+    ("session.write_failed", "Impossible d'enregistrer la session, la tâche ne pourra pas être reprise : {error}"),
+    ("session.unreadable", "Session illisible ignorée {path} : {error}"),
+    ("session.none", "Aucune tâche interrompue à reprendre."),
+    ("session.entry", "{kind} {name} : {count} titres restants sur {total}"),
+    ("session.resuming", "Reprise de {kind} {name} : {count} titres restants sur {total}"),
+    ("session.complete", "Reprise terminée : {downloaded} téléchargés, {failed} en échec"),
+    // Synchronisation
+    ("sync.playlist", "Synchronisation de la playlist : {name}"),
+    ("sync.favorites", "Synchronisation des titres favoris..."),
+    ("sync.prune_ignored", "--prune est ignoré avec --layout, les fichiers ne sont pas dans un dossier dédié"),
+    ("sync.counts", "{total} titres dans la liste, {present} déjà téléchargés, {missing} à récupérer"),
+    ("sync.removing", "Suppression de {count} titres qui ne sont plus dans la liste :"),
+    ("sync.remove_failed", "Impossible de supprimer {path} : {error}"),
+    (
+        "sync.removed_hint",
+        "{count} titres locaux ne sont plus dans la liste (utilisez --prune pour les supprimer)",
+    ),
+    (
+        "sync.done",
+        "Synchronisation terminée : {downloaded} téléchargés, {failed} en échec, {present} déjà présents",
+    ),
+    ("sync.m3u_written", "Fichier de playlist écrit dans {path}"),
+    ("sync.m3u_failed", "Impossible d'écrire {path} : {error}"),
+    ("diff.header", "{name} : {total} titres sur Deezer, comparés avec {target}"),
+    ("diff.track_id", "Titre {id}"),
+    ("diff.summary", "{added} ajoutés (sync les téléchargerait), {removed} retirés (--prune les supprimerait)"),
+    // Surveillance et démon
+    ("watch.check_failed", "Impossible de vérifier l'artiste {id} : {error}"),
+    ("watch.new_releases", "Nouvelles sorties de {name} :"),
+    ("watch.baseline", "Suivi de {name} : {count} sorties existantes notées, les suivantes seront téléchargées"),
+    ("watch.download_failed", "Échec du téléchargement des nouvelles sorties : {error}"),
+    ("watch.followed_failed", "Impossible de récupérer les artistes suivis : {error}"),
+    ("watch.checking", "Recherche de nouvelles sorties pour {count} artistes..."),
+    ("watch.none", "Aucune nouvelle sortie."),
+    ("watch.grabbed", "Watch : {count} nouvelles sorties récupérées"),
+    ("watch.next", "Prochaine vérification dans {secs} s"),
+    ("daemon.playlist", "=== Playlist {id} ==="),
+    ("daemon.playlist_failed", "La playlist {id} a échoué : {error}"),
+    ("daemon.favorites", "=== Favoris ==="),
+    ("daemon.favorites_failed", "Échec de la synchronisation des favoris : {error}"),
+    ("daemon.artists", "=== Artistes suivis ({count}) ==="),
+    ("daemon.grabbed", "{count} nouvelles sorties récupérées"),
+    ("daemon.artists_failed", "Échec de la vérification des artistes : {error}"),
+    ("daemon.queued", "=== En file : {kind} {id} ==="),
+    ("daemon.queued_failed", "{kind} {id} en file a échoué : {error}"),
+    ("daemon.pass_start", "Début de la synchronisation..."),
+    ("daemon.pass_done", "Synchronisation terminée. Prochain passage dans {secs} s"),
+    // Bibliothèque
+    (
+        "library.verify_done",
+        "Vérification terminée : {checked} fichiers vérifiés, {ok} corrects, {problems} avec des problèmes",
+    ),
+    ("library.scanning", "Analyse de {count} fichiers dans {dir}"),
+    ("library.unknown_file", "{path} (pas de tags, absent de l'historique)"),
+    ("library.target_exists", "{path} -> {target} (la cible existe déjà)"),
+    (
+        "library.organize_preview",
+        "Rangement terminé : {moved} seraient déplacés, {unchanged} déjà en place, {unknown} inconnus, \
+         {conflicts} conflits",
+    ),
+    (
+        "library.organize_done",
+        "Rangement terminé : {moved} déplacés, {unchanged} déjà en place, {unknown} inconnus, \
+         {conflicts} conflits",
+    ),
+    ("library.tags_failed", "Impossible d'écrire les tags : {error}"),
+    ("library.no_mp3", "Aucun titre MP3 dans l'historique des téléchargements."),
+    ("library.checking_flac", "Recherche d'une version FLAC pour {count} titres MP3..."),
+    ("library.upgraded", "Passé en FLAC"),
+    ("library.upgrade_would", "Serait remplacé par {path}"),
+    (
+        "library.upgrade_preview",
+        "Mise à niveau terminée : {upgraded} peuvent passer en FLAC, {unavailable} indisponibles en FLAC, \
+         {failed} échecs",
+    ),
+    (
+        "library.upgrade_done",
+        "Mise à niveau terminée : {upgraded} passés en FLAC, {unavailable} indisponibles en FLAC, {failed} échecs",
+    ),
+    ("library.nothing_to_repair", "Les {count} fichiers sont corrects, rien à réparer."),
+    ("library.problems_found", "{count} fichiers avec des problèmes sur {checked}"),
+    ("library.unavailable", "Le titre {id} n'est plus disponible"),
+    ("library.repaired", "Réparé ({format})"),
+    ("library.repair_preview", "{count} fichiers seraient retéléchargés"),
+    ("library.repair_done", "Réparation terminée : {repaired} réparés, {failed} échecs"),
+    // Pochettes et NFO
+    ("artwork.artist_failed", "Impossible d'enregistrer les images de l'artiste : {error}"),
+    ("artwork.save_failed", "Impossible d'enregistrer {path} : {error}"),
+    ("nfo.write_failed", "Impossible d'écrire {path} : {error}"),
+    ("nfo.create_failed", "Impossible de créer {path} : {error}"),
+    ("nfo.no_album", "Pas de données d'album pour le NFO : {error}"),
+    // Hooks
+    ("hooks.beets", "Import de {path} avec beets..."),
+    ("hooks.exec_failed", "Échec de --exec : {error}"),
+    ("hooks.exec_after_failed", "Échec de --exec-after : {error}"),
+    // Notifications
+    ("notify.finished", "{entity} terminé : {name}"),
+    ("notify.finished_with_failures", "{entity} terminé avec des échecs : {name}"),
+    ("notify.counts", "{downloaded} téléchargés, {failed} échecs"),
+    ("notify.refused", ", {count} sous la qualité minimale"),
+    ("notify.filtered", ", {count} filtrés"),
+    ("notify.more_failures", "et {count} de plus"),
+    ("notify.failed", "Échec de la notification {target} : {error}"),
+    ("notify.desktop_failed", "Échec de la notification de bureau : {error}"),
+    // Socket de contrôle et RPC
+    ("control.socket", "Socket de contrôle : {path}"),
+    ("control.pipe", "Pipe de contrôle : {name}"),
+    ("control.connection_failed", "Échec de la connexion de contrôle : {error}"),
+    ("control.accept_failed", "Impossible d'accepter la connexion de contrôle : {error}"),
+    ("control.pipe_stopped", "Pipe de contrôle arrêté : {error}"),
+    ("rpc.stdin_failed", "Impossible de lire l'entrée standard : {error}"),
+    ("rpc.arl_not_saved", "Impossible d'enregistrer l'ARL : {error}"),
+    // Limites de débit
+    ("throttle.rate_limit", "Limite de requêtes Deezer atteinte"),
+    ("throttle.waiting", "{reason}, reprise dans {secs} s"),
+    ("throttle.resuming", "{reason}, reprise maintenant"),
+];
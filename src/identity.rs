@@ -0,0 +1,120 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use crate::history;
+use crate::library;
+use crate::models::GwTrack;
+use crate::tags;
+
+/// Audio files under one output folder, by Deezer ID (from the history) and ISRC
+/// (from the history and the files' own tags)
+#[derive(Debug, Default)]
+struct LibraryIndex {
+    by_id: HashMap<String, Vec<PathBuf>>,
+    by_isrc: HashMap<String, Vec<PathBuf>>,
+}
+
+impl LibraryIndex {
+    fn insert(&mut self, sng_id: Option<&str>, isrc: Option<&str>, path: &Path) {
+        if let Some(id) = sng_id.filter(|id| !id.is_empty()) {
+            self.by_id.entry(id.to_string()).or_default().push(path.to_path_buf());
+        }
+        if let Some(isrc) = isrc.filter(|isrc| !isrc.is_empty()) {
+            self.by_isrc.entry(isrc.to_uppercase()).or_default().push(path.to_path_buf());
+        }
+    }
+}
+
+/// Indexes built so far in this run, by canonical output folder
+static INDEXES: LazyLock<Mutex<HashMap<PathBuf, LibraryIndex>>> = LazyLock::new(Default::default);
+
+/// A file under `root` holding the same track (Deezer ID) or recording (ISRC) as `track`,
+/// with the extension of `target`, whatever it is named now.
+/// The folder is indexed once per run, on first use.
+pub async fn find(root: &Path, track: &GwTrack, target: &Path) -> Option<PathBuf> {
+    let root = tokio::fs::canonicalize(root).await.ok()?;
+    let indexed = INDEXES.lock().unwrap_or_else(|e| e.into_inner()).contains_key(&root);
+    if !indexed {
+        let index = build(&root).await;
+        INDEXES.lock().unwrap_or_else(|e| e.into_inner()).entry(root.clone()).or_insert(index);
+    }
+
+    let indexes = INDEXES.lock().unwrap_or_else(|e| e.into_inner());
+    let index = indexes.get(&root)?;
+    let by_id = index.by_id.get(&track.id_str());
+    let by_isrc = track
+        .isrc
+        .as_deref()
+        .filter(|isrc| !isrc.is_empty())
+        .and_then(|isrc| index.by_isrc.get(&isrc.to_uppercase()));
+    by_id
+        .into_iter()
+        .chain(by_isrc)
+        .flatten()
+        .filter(|path| path.extension() == target.extension())
+        .find(|path| path.is_file())
+        .cloned()
+}
+
+/// Add a file just written to the indexes of the folders it is in
+pub fn remember(track: &GwTrack, path: &Path) {
+    let mut indexes = INDEXES.lock().unwrap_or_else(|e| e.into_inner());
+    for (root, index) in indexes.iter_mut() {
+        if path.starts_with(root) {
+            index.insert(Some(&track.id_str()), track.isrc.as_deref(), path);
+        }
+    }
+}
+
+/// An audio file under an output folder, with what identifies its track
+#[derive(Debug, Clone)]
+pub struct LocalFile {
+    pub path: PathBuf,
+    /// Deezer track ID, for files in the download history
+    pub sng_id: Option<String>,
+    /// From the history, else from the file's tags
+    pub isrc: Option<String>,
+}
+
+/// Audio files under `root`: those in the history, then the others that have an ISRC tag
+pub async fn local_files(root: &Path) -> Vec<LocalFile> {
+    // A path downloaded more than once keeps its latest entry
+    let mut from_history: HashMap<PathBuf, LocalFile> = HashMap::new();
+    for entry in history::load().await.unwrap_or_default() {
+        if entry.path.starts_with(root) && entry.path.is_file() {
+            let file = LocalFile {
+                path: entry.path.clone(),
+                sng_id: Some(entry.sng_id).filter(|id| !id.is_empty()),
+                isrc: entry.isrc.filter(|isrc| !isrc.is_empty()),
+            };
+            from_history.insert(entry.path, file);
+        }
+    }
+
+    let known: HashSet<PathBuf> = from_history.keys().cloned().collect();
+    let root = root.to_path_buf();
+    let scanned = tokio::task::spawn_blocking(move || {
+        library::audio_files(&root)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|path| !known.contains(path))
+            .filter_map(|path| {
+                let isrc = tags::read_isrc(&path)?;
+                Some(LocalFile { path, sng_id: None, isrc: Some(isrc) })
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .unwrap_or_default();
+    from_history.into_values().chain(scanned).collect()
+}
+
+/// Index the audio files under `root` by Deezer ID and ISRC
+async fn build(root: &Path) -> LibraryIndex {
+    let mut index = LibraryIndex::default();
+    for file in local_files(root).await {
+        index.insert(file.sng_id.as_deref(), file.isrc.as_deref(), &file.path);
+    }
+    index
+}
@@ -0,0 +1,206 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+
+use crate::client::DeezerClient;
+use crate::color;
+use crate::config::OutputDirs;
+use crate::control::{self, ItemKind};
+use crate::download::{self, DownloadOptions};
+use crate::queue::{self, BatchEntry};
+use crate::tr;
+
+/// Subfolder of the watched folder for job files whose items all downloaded
+pub const DONE_DIR: &str = "done";
+/// Subfolder for job files that could not be read or had items or tracks that failed
+pub const FAILED_DIR: &str = "failed";
+
+/// A job file is left alone until it has not changed for this long, so it is not read half-written
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// What a job file asks for
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobFile {
+    /// Deezer track, playlist or artist URLs, each optionally followed by
+    /// `| quality | output folder`
+    pub urls: Vec<String>,
+    /// Same values as `--quality`, `best` included
+    pub quality: Option<String>,
+    /// Base folder for every item, instead of the configured output folders
+    pub output: Option<PathBuf>,
+}
+
+impl JobFile {
+    /// Parse a `.json` job file, or a `.txt` one: a URL per line, `key = value` lines for the
+    /// options and `#` comments
+    pub fn parse(path: &Path, content: &str) -> Result<Self> {
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            return serde_json::from_str(content).context("Invalid JSON job");
+        }
+        let mut job = JobFile::default();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // URLs have slashes, option names do not
+            match line.split_once('=') {
+                Some((key, value)) if !key.contains('/') => match key.trim() {
+                    "quality" => job.quality = Some(value.trim().to_string()),
+                    "output" => job.output = Some(PathBuf::from(value.trim())),
+                    other => bail!("Unknown option '{}'", other),
+                },
+                _ => job.urls.push(line.to_string()),
+            }
+        }
+        Ok(job)
+    }
+
+    /// Each URL with its own quality and output folder, if any
+    pub fn entries(&self) -> Result<Vec<BatchEntry>> {
+        if self.urls.is_empty() {
+            bail!("No URLs");
+        }
+        if let Some(quality) = &self.quality
+            && DownloadOptions::default().with_quality(quality).is_none()
+        {
+            bail!("Unknown quality '{}'", quality);
+        }
+        self.urls
+            .iter()
+            .map(|line| BatchEntry::parse(line).with_context(|| format!("Invalid line: {}", line)))
+            .collect()
+    }
+
+    /// The items to download, or why the job cannot run
+    pub fn items(&self) -> Result<Vec<(ItemKind, String)>> {
+        self.entries()?
+            .iter()
+            .map(|entry| {
+                control::parse_url(&entry.url)
+                    .with_context(|| format!("Not a Deezer track, playlist or artist URL: {}", entry.url))
+            })
+            .collect()
+    }
+}
+
+/// Run `watch-folder`: every `interval`, download the jobs of the `.txt` and `.json` files
+/// dropped into `dir`, in name order, then move each file to `done/` or `failed/`
+pub async fn watch(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    outputs: &OutputDirs,
+    dir: &Path,
+    interval: Duration,
+) -> Result<()> {
+    println!("{}", tr!("jobs.watching", dir = dir.display()));
+    loop {
+        run_ready(api, opts, outputs, dir).await?;
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Download the job files currently in `dir` and move each to `done/`, or to `failed/` when
+/// it could not be read or one of its items or their tracks failed
+pub async fn run_ready(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    outputs: &OutputDirs,
+    dir: &Path,
+) -> Result<()> {
+    for subfolder in [DONE_DIR, FAILED_DIR] {
+        fs::create_dir_all(dir.join(subfolder))
+            .await
+            .with_context(|| format!("Failed to create {}", dir.join(subfolder).display()))?;
+    }
+    for path in ready_files(dir).await? {
+        run_file(api, opts, outputs, dir, &path).await?;
+    }
+    Ok(())
+}
+
+/// Job files in `dir` that are no longer being written, by name
+async fn ready_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(dir).await.with_context(|| format!("Failed to read {}", dir.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_job = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("txt") || ext.eq_ignore_ascii_case("json"));
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        let settled = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|age| age >= SETTLE_TIME);
+        if is_job && !hidden && metadata.is_file() && settled {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Download one job file's items, each with the file's quality and output folder unless its
+/// line has its own, and move the file away. Errors only when the whole run has to stop;
+/// the file then stays where it is, to be picked up again.
+async fn run_file(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    outputs: &OutputDirs,
+    dir: &Path,
+    path: &Path,
+) -> Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    println!("\n{}", tr!("jobs.file", name = name));
+    let parsed = match fs::read_to_string(path).await {
+        Ok(content) => JobFile::parse(path, &content).and_then(|job| Ok((job.entries()?, job.items()?, job))),
+        Err(e) => Err(e.into()),
+    };
+    let (entries, items, job) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{} {}", color::err(), tr!("jobs.invalid", name = name, error = e));
+            return finish(dir, path, FAILED_DIR).await;
+        }
+    };
+
+    let opts = job.quality.as_deref().and_then(|q| opts.with_quality(q)).unwrap_or_else(|| opts.clone());
+    let outputs = job.output.as_deref().map(OutputDirs::all).unwrap_or_else(|| outputs.clone());
+    let mut failed = 0;
+    for (entry, (kind, id)) in entries.iter().zip(&items) {
+        let (opts, outputs) = entry.apply(&opts, &outputs);
+        if let Err(e) = queue::download_item(api, &opts, &outputs, *kind, id).await {
+            if download::stops_job(&e) {
+                return Err(e);
+            }
+            failed += 1;
+            eprintln!("{} {}", color::err(), tr!("jobs.item_failed", url = entry.url, error = e));
+        }
+    }
+
+    let folder = if failed == 0 { DONE_DIR } else { FAILED_DIR };
+    println!("{}", tr!("jobs.done", name = name, done = items.len() - failed, failed = failed, folder = folder));
+    finish(dir, path, folder).await
+}
+
+/// Move a job file into `dir/<folder>`, keeping an older file of the same name
+async fn finish(dir: &Path, path: &Path, folder: &str) -> Result<()> {
+    let name = path.file_name().unwrap_or_default();
+    let mut target = dir.join(folder).join(name);
+    if target.exists() {
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let stem = Path::new(name).file_stem().unwrap_or_default().to_string_lossy();
+        let ext = Path::new(name).extension().unwrap_or_default().to_string_lossy();
+        target.set_file_name(format!("{}-{}.{}", stem, stamp, ext));
+    }
+    fs::rename(path, &target)
+        .await
+        .with_context(|| format!("Failed to move {} to {}", path.display(), target.display()))
+}
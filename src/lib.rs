@@ -0,0 +1,127 @@
+//! Deezer client and downloader behind the `deezer-dl` command.
+//!
+//! [`DeezerApi`] talks to Deezer's GW, public and media APIs, [`download`]
+//! fetches, decrypts, tags and writes tracks, and the remaining modules cover
+//! the library features built on top (sync, watch, templates, history...).
+//!
+//! ```no_run
+//! use deezer_dl::{DeezerApi, DownloadOptions, NetworkOptions, TrackFormat, download};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let api = DeezerApi::new(&NetworkOptions::default())?;
+//! if !api.login_via_arl("your arl cookie").await? {
+//!     anyhow::bail!("ARL rejected");
+//! }
+//! let opts = DownloadOptions {
+//!     format: TrackFormat::Flac,
+//!     ..Default::default()
+//! };
+//! download::download_playlist(&api, "908622995", &opts, "downloads".as_ref()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Download functions report progress on stdout/stderr, like the CLI does.
+
+/// Secondary accounts asked for streams the logged-in one cannot deliver (`backup_arls`)
+pub mod accounts;
+/// Deezer GW (internal) API, public API and media URL client
+pub mod api;
+/// Album, playlist and artist images from the Deezer image CDN
+pub mod artwork;
+/// ARL-based login and persistent credential storage
+pub mod auth;
+/// Account favorites and playlists saved to a JSON file and restored (`backup`, `restore`)
+pub mod backup;
+/// Tracks, albums and artists never downloaded (`--blocklist`)
+pub mod blocklist;
+/// `DeezerClient` trait over the API client, for fakes and alternative backends
+pub mod client;
+/// Deezer URLs copied to the system clipboard added to the queue (`watch-clipboard`)
+pub mod clipboard;
+/// `--color` and the status tag theme
+pub mod color;
+/// Local library checked against the account's favorites (`compare`)
+pub mod compare;
+/// Config file (`~/.config/deezer-dl/config.toml`)
+pub mod config;
+/// Daemon control socket and the `ctl` client
+pub mod control;
+/// ffmpeg transcoding
+pub mod convert;
+/// Blowfish CBC stream decryption and media URL key derivation
+pub mod crypto;
+/// Scheduled sync of configured playlists, favorites and artists
+pub mod daemon;
+/// Linking tracks already downloaded elsewhere instead of storing them twice
+pub mod dedupe;
+/// Track, playlist, favorites and artist downloads
+pub mod download;
+/// Error type of the Deezer client, classified for retry/skip/abort decisions
+pub mod error;
+/// Machine-readable progress events of download jobs
+pub mod events;
+/// Playlist track lists written to files without downloading audio (`export`)
+pub mod export;
+/// Download history (JSON lines)
+pub mod history;
+/// `handle-url`: links opened from the browser or the desktop, queued in the daemon or downloaded
+pub mod handler;
+/// External programs run around downloads
+pub mod hooks;
+/// Recognizing tracks already downloaded under another name, by Deezer ID or ISRC
+pub mod identity;
+/// Console message translations (English, French)
+pub mod i18n;
+/// Job files dropped into a watched folder (`watch-folder`)
+pub mod jobs;
+/// Library maintenance (organize, upgrade, verify, repair)
+pub mod library;
+/// Deezer links in the shapes the site, apps and embeds use
+pub mod links;
+/// Track lyrics from the pipe API, translations and `.lrc` files
+pub mod lyrics;
+/// API data structures (tracks, playlists, albums, formats)
+pub mod models;
+/// MusicBrainz ISRC lookups
+pub mod musicbrainz;
+/// Kodi/Jellyfin NFO metadata files
+pub mod nfo;
+/// Job completion reports (webhook, desktop, chat services)
+pub mod notify;
+/// Pausing downloads mid-track (`p` key, `ctl pause`)
+pub mod pause;
+/// Storage of every track once, by ID, with links or playlists into job folders (`--pool`)
+pub mod pool;
+/// Formats a track or album can be downloaded in by this account (`probe`)
+pub mod probe;
+/// Per-track and whole-job progress bars
+pub mod progress;
+/// Persistent download queue (`queue add/list/remove/run`)
+pub mod queue;
+/// Retry policy, end-of-run retry pass and persisted failures
+pub mod retry;
+/// JSON-RPC over stdio for GUI frontends
+pub mod rpc;
+/// Rhai filter scripts
+pub mod script;
+/// Saved job plans and `resume` of interrupted jobs
+pub mod session;
+/// Ctrl+C handling: finish the current chunk, clean up, point at `resume`
+pub mod shutdown;
+/// Incremental playlist/favorites sync and .m3u8 playlists
+pub mod sync;
+/// ID3 and FLAC Vorbis comment tagging
+pub mod tags;
+/// Filename templates
+pub mod template;
+/// API rate limiting, quota pauses and bandwidth limiting
+pub mod throttle;
+/// New-release monitor for artists
+pub mod watch;
+
+pub use api::{DeezerApi, Endpoints, NetworkOptions};
+pub use client::DeezerClient;
+pub use download::{DownloadOptions, DownloadedTrack, JobSummary};
+pub use error::DeezerError;
+pub use models::{GwTrack, TrackFormat};
@@ -0,0 +1,544 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+
+use crate::client::DeezerClient;
+use crate::color;
+use crate::convert;
+use crate::download::{self, DownloadOptions};
+use crate::history::{self, HistoryEntry};
+use crate::models::{GwTrack, TrackFormat};
+use crate::tags::{self, TrackTags};
+use crate::template::{self, TemplateVars};
+use crate::tr;
+
+/// Extensions of the files Deezer delivers and `--convert` writes
+pub const AUDIO_EXTENSIONS: &[&str] = &["mp3", "flac", "mp4", "m4a", "opus", "ogg"];
+
+/// Why a downloaded file failed verification
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum VerifyProblem {
+    Missing,
+    Empty,
+    SizeMismatch { expected: u64, actual: u64 },
+    BadHeader,
+}
+
+impl std::fmt::Display for VerifyProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyProblem::Missing => write!(f, "file missing"),
+            VerifyProblem::Empty => write!(f, "file is empty"),
+            VerifyProblem::SizeMismatch { expected, actual } => {
+                write!(f, "size mismatch: expected ~{} bytes, got {}", expected, actual)
+            }
+            VerifyProblem::BadHeader => write!(f, "not a recognised audio header"),
+        }
+    }
+}
+
+/// A history entry whose file failed verification
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyIssue {
+    pub sng_id: String,
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub problem: VerifyProblem,
+}
+
+/// Whether the first bytes look like an MP3 (ID3 tag or frame sync), FLAC, Ogg or MP4 stream
+pub fn has_audio_header(header: &[u8]) -> bool {
+    header.starts_with(b"ID3")
+        || header.starts_with(b"fLaC")
+        || header.starts_with(b"OggS")
+        || (header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0)
+        || (header.len() >= 8 && &header[4..8] == b"ftyp")
+}
+
+/// Check a single file against its history entry
+async fn check_file(entry: &HistoryEntry) -> Option<VerifyProblem> {
+    let Ok(meta) = fs::metadata(&entry.path).await else {
+        return Some(VerifyProblem::Missing);
+    };
+    let actual = meta.len();
+    if actual == 0 {
+        return Some(VerifyProblem::Empty);
+    }
+    if !download::size_matches(entry.expected_size, actual) {
+        return Some(VerifyProblem::SizeMismatch {
+            expected: entry.expected_size,
+            actual,
+        });
+    }
+
+    let mut header = [0u8; 10];
+    let read = match fs::File::open(&entry.path).await {
+        Ok(mut f) => f.read(&mut header).await.unwrap_or(0),
+        Err(_) => return Some(VerifyProblem::Missing),
+    };
+    if !has_audio_header(&header[..read]) {
+        return Some(VerifyProblem::BadHeader);
+    }
+    None
+}
+
+/// Verify every file in the history (optionally under `dir`), returning the failures
+pub async fn find_corrupt(dir: Option<&Path>) -> Result<(usize, Vec<VerifyIssue>)> {
+    let entries = history::load().await?;
+    let indices = latest_entries(&entries, dir).await?;
+
+    let mut issues = Vec::new();
+    for &i in &indices {
+        if let Some(problem) = check_file(&entries[i]).await {
+            issues.push(VerifyIssue {
+                sng_id: entries[i].sng_id.clone(),
+                path: entries[i].path.clone(),
+                problem,
+            });
+        }
+    }
+    Ok((indices.len(), issues))
+}
+
+/// Re-check downloaded files and report missing, truncated, or corrupt ones
+pub async fn verify(dir: Option<&Path>, json: bool) -> Result<()> {
+    let (checked, issues) = find_corrupt(dir).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues)?);
+        return Ok(());
+    }
+
+    for issue in &issues {
+        eprintln!("  {} {} ({})", color::err(), issue.path.display(), issue.problem);
+    }
+    println!(
+        "\n{}",
+        tr!("library.verify_done", checked = checked, ok = checked - issues.len(), problems = issues.len())
+    );
+    Ok(())
+}
+
+/// Recursively collect audio files under a directory
+pub fn audio_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let entries = std::fs::read_dir(&current)
+            .with_context(|| format!("Failed to read directory {}", current.display()))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Remove now-empty directories from `start` up to (but excluding) `root`
+async fn prune_empty_dirs(start: &Path, root: &Path) {
+    let mut current = start.to_path_buf();
+    while current.starts_with(root) && current != root {
+        if fs::remove_dir(&current).await.is_err() {
+            break;
+        }
+        match current.parent() {
+            Some(parent) => current = parent.to_path_buf(),
+            None => break,
+        }
+    }
+}
+
+/// Move audio files to match the current filename template, naming them from their tags
+/// (or from the download history for untagged files)
+pub async fn organize(dir: &Path, opts: &DownloadOptions, dry_run: bool) -> Result<()> {
+    let root = fs::canonicalize(dir)
+        .await
+        .with_context(|| format!("Library directory not found: {}", dir.display()))?;
+
+    let mut entries = history::load().await?;
+    let by_path: HashMap<PathBuf, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.path.clone(), i))
+        .collect();
+
+    let files = audio_files(&root)?;
+    println!("{}\n", tr!("library.scanning", count = files.len(), dir = root.display()));
+
+    let mut moved = 0;
+    let mut unchanged = 0;
+    let mut unmatched = 0;
+    let mut conflicts = 0;
+    let mut history_changed = false;
+
+    for file in &files {
+        // The file's own tags describe it best; history covers untagged files
+        let idx = by_path.get(file).copied();
+        let vars = match (tags::read(file), idx) {
+            (Some(tags), idx) => {
+                let mut vars = TemplateVars::from(&tags);
+                if let Some(entry) = idx.map(|i| &entries[i]) {
+                    vars.id = entry.sng_id.clone();
+                    vars.format = entry.format.clone();
+                }
+                vars
+            }
+            (None, Some(i)) => TemplateVars::from(&entries[i]),
+            (None, None) => {
+                unmatched += 1;
+                println!("  {} {}", color::skip(), tr!("library.unknown_file", path = file.display()));
+                continue;
+            }
+        };
+
+        let extension = file
+            .extension()
+            .map(|e| format!(".{}", e.to_string_lossy()))
+            .unwrap_or_default();
+        let target = root.join(template::render_path(&opts.template, &vars, &extension, opts));
+
+        if &target == file {
+            unchanged += 1;
+            continue;
+        }
+        if target.exists() {
+            conflicts += 1;
+            let message = tr!("library.target_exists", path = file.display(), target = target.display());
+            eprintln!("  {} {}", color::err(), message);
+            continue;
+        }
+
+        println!(
+            "  {} -> {}",
+            file.strip_prefix(&root).unwrap_or(file).display(),
+            target.strip_prefix(&root).unwrap_or(&target).display()
+        );
+        moved += 1;
+
+        if dry_run {
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::rename(file, &target)
+            .await
+            .with_context(|| format!("Failed to move {}", file.display()))?;
+        if let Some(i) = idx {
+            entries[i].path = target;
+            history_changed = true;
+        }
+
+        if let Some(parent) = file.parent() {
+            prune_empty_dirs(parent, &root).await;
+        }
+    }
+
+    if history_changed {
+        history::save_all(&entries).await?;
+    }
+
+    let counts = if dry_run {
+        tr!(
+            "library.organize_preview",
+            moved = moved,
+            unchanged = unchanged,
+            unknown = unmatched,
+            conflicts = conflicts
+        )
+    } else {
+        tr!(
+            "library.organize_done",
+            moved = moved,
+            unchanged = unchanged,
+            unknown = unmatched,
+            conflicts = conflicts
+        )
+    };
+    println!("\n{}", counts);
+    Ok(())
+}
+
+/// Latest history entry per file path, optionally restricted to a directory
+async fn latest_entries(entries: &[HistoryEntry], dir: Option<&Path>) -> Result<Vec<usize>> {
+    let root = match dir {
+        Some(d) => Some(
+            fs::canonicalize(d)
+                .await
+                .with_context(|| format!("Directory not found: {}", d.display()))?,
+        ),
+        None => None,
+    };
+
+    let mut latest: HashMap<&Path, usize> = HashMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if let Some(root) = &root
+            && !entry.path.starts_with(root)
+        {
+            continue;
+        }
+        latest.insert(&entry.path, i);
+    }
+
+    let mut indices: Vec<usize> = latest.into_values().collect();
+    indices.sort();
+    Ok(indices)
+}
+
+/// Validate in-memory audio the same way `verify` validates files
+pub fn check_data(data: &[u8], expected_size: u64) -> Option<VerifyProblem> {
+    if data.is_empty() {
+        return Some(VerifyProblem::Empty);
+    }
+    let actual = data.len() as u64;
+    if !download::size_matches(expected_size, actual) {
+        return Some(VerifyProblem::SizeMismatch {
+            expected: expected_size,
+            actual,
+        });
+    }
+    if !has_audio_header(data) {
+        return Some(VerifyProblem::BadHeader);
+    }
+    None
+}
+
+/// Download `track` again and replace `old_path` once the new copy passes the checks, converting
+/// it like a new download with `--convert`. With `strict`, a fallback to another format is treated
+/// as a failure.
+async fn replace_file(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    track: &GwTrack,
+    format: TrackFormat,
+    old_path: &Path,
+    strict: bool,
+) -> Result<(PathBuf, TrackFormat)> {
+    let (url, actual_format, is_crypted) = download::get_download_url(api, track, format, None).await?;
+    if strict && actual_format != format {
+        bail!("{} stream not available (got {})", format, actual_format);
+    }
+
+    let sng_id = track.id_str();
+    let data = api
+        .retry_policy()
+        .run("Download", || download::fetch_audio(api, &url, is_crypted, &sng_id, true, opts))
+        .await?;
+    if let Some(problem) = check_data(&data, track.filesize_for_format(actual_format)) {
+        bail!("New copy failed verification: {}", problem);
+    }
+
+    let album = download::album_details(api, track).await;
+    let mut track_tags = TrackTags::new(track, album.as_ref());
+    track_tags.title = opts.track_title(track);
+    track_tags.genres = download::album_genres(api, track).await;
+    if opts.musicbrainz {
+        track_tags.musicbrainz = download::musicbrainz_ids(api, track).await;
+    }
+    let data = match tags::apply(&data, actual_format, &track_tags) {
+        Ok(tagged) => tagged,
+        Err(e) => {
+            eprintln!("  {} {}", color::warn(), tr!("library.tags_failed", error = e));
+            data
+        }
+    };
+
+    let new_path = old_path.with_extension(actual_format.extension().trim_start_matches('.'));
+    download::write_file_atomic(&new_path, &data).await?;
+    let new_path = match opts.convert {
+        Some(target) => convert::convert(&new_path, target).await?,
+        None => new_path,
+    };
+    if new_path != old_path && old_path.exists() {
+        fs::remove_file(old_path).await?;
+    }
+    Ok((new_path, actual_format))
+}
+
+/// Point history entries for `old_path` at the replacement file
+fn update_entries(
+    entries: &mut [HistoryEntry],
+    old_path: &Path,
+    new_path: &Path,
+    format: TrackFormat,
+) {
+    let size = std::fs::metadata(new_path).map(|m| m.len()).unwrap_or(0);
+    for entry in entries.iter_mut().filter(|e| e.path == old_path) {
+        entry.path = new_path.to_path_buf();
+        entry.format = format.api_name().to_string();
+        entry.expected_size = size;
+    }
+}
+
+/// Re-download MP3 tracks from the history that are now available in FLAC
+pub async fn upgrade(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    dir: Option<&Path>,
+    dry_run: bool,
+) -> Result<()> {
+    if !api.user().await.is_some_and(|u| u.can_stream_lossless) {
+        bail!("This account cannot stream FLAC, nothing can be upgraded");
+    }
+
+    let mut entries = history::load().await?;
+    let candidates: Vec<usize> = latest_entries(&entries, dir)
+        .await?
+        .into_iter()
+        .filter(|&i| {
+            entries[i].path.exists()
+                && TrackFormat::from_api_name(&entries[i].format).is_some_and(|f| f.is_mp3())
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        println!("{}", tr!("library.no_mp3"));
+        return Ok(());
+    }
+
+    println!("{}\n", tr!("library.checking_flac", count = candidates.len()));
+
+    let mut upgraded = 0;
+    let mut unavailable = 0;
+    let mut failed = 0;
+
+    for batch in candidates.chunks(50) {
+        let ids: Vec<String> = batch.iter().map(|&i| entries[i].sng_id.clone()).collect();
+        let tracks = api.get_tracks_by_ids(&ids).await?;
+
+        for &idx in batch {
+            let Some(track) = tracks.iter().find(|t| t.id_str() == entries[idx].sng_id) else {
+                unavailable += 1;
+                continue;
+            };
+            if track.filesize_for_format(TrackFormat::Flac) == 0 {
+                unavailable += 1;
+                continue;
+            }
+
+            let old_path = entries[idx].path.clone();
+            println!("{}", track.display_name());
+
+            if dry_run {
+                let new_path = old_path.with_extension("flac");
+                let new_path = opts.convert.map_or_else(|| new_path.clone(), |target| target.output_path(&new_path));
+                println!("  {} {}", color::ok(), tr!("library.upgrade_would", path = new_path.display()));
+                upgraded += 1;
+                continue;
+            }
+
+            match replace_file(api, opts, track, TrackFormat::Flac, &old_path, true).await {
+                Ok((new_path, format)) => {
+                    upgraded += 1;
+                    println!("  {} {}", color::ok(), tr!("library.upgraded"));
+                    update_entries(&mut entries, &old_path, &new_path, format);
+                }
+                Err(e) => {
+                    if download::stops_job(&e) {
+                        return Err(e);
+                    }
+                    failed += 1;
+                    eprintln!("  {} {}", color::err(), tr!("job.failed", error = e));
+                }
+            }
+        }
+    }
+
+    if !dry_run && upgraded > 0 {
+        history::save_all(&entries).await?;
+    }
+
+    let counts = if dry_run {
+        tr!("library.upgrade_preview", upgraded = upgraded, unavailable = unavailable, failed = failed)
+    } else {
+        tr!("library.upgrade_done", upgraded = upgraded, unavailable = unavailable, failed = failed)
+    };
+    println!("\n{}", counts);
+    Ok(())
+}
+
+/// Re-download every file that fails verification
+pub async fn repair(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    dir: Option<&Path>,
+    dry_run: bool,
+) -> Result<()> {
+    let (checked, issues) = find_corrupt(dir).await?;
+    if issues.is_empty() {
+        println!("{}", tr!("library.nothing_to_repair", count = checked));
+        return Ok(());
+    }
+
+    println!("{}\n", tr!("library.problems_found", count = issues.len(), checked = checked));
+
+    let mut entries = history::load().await?;
+    let mut repaired = 0;
+    let mut failed = 0;
+
+    for batch in issues.chunks(50) {
+        let ids: Vec<String> = batch.iter().map(|i| i.sng_id.clone()).collect();
+        let tracks = api.get_tracks_by_ids(&ids).await?;
+
+        for issue in batch {
+            println!("{} ({})", issue.path.display(), issue.problem);
+            if dry_run {
+                continue;
+            }
+
+            let Some(track) = tracks.iter().find(|t| t.id_str() == issue.sng_id) else {
+                failed += 1;
+                eprintln!("  {} {}", color::err(), tr!("library.unavailable", id = issue.sng_id));
+                continue;
+            };
+            let format = entries
+                .iter()
+                .rev()
+                .find(|e| e.path == issue.path)
+                .and_then(|e| TrackFormat::from_api_name(&e.format))
+                .unwrap_or(TrackFormat::Mp3_320);
+
+            match replace_file(api, opts, track, format, &issue.path, false).await {
+                Ok((new_path, actual_format)) => {
+                    repaired += 1;
+                    println!("  {} {}", color::ok(), tr!("library.repaired", format = actual_format));
+                    update_entries(&mut entries, &issue.path, &new_path, actual_format);
+                }
+                Err(e) => {
+                    if download::stops_job(&e) {
+                        return Err(e);
+                    }
+                    failed += 1;
+                    eprintln!("  {} {}", color::err(), tr!("job.failed", error = e));
+                }
+            }
+        }
+    }
+
+    if dry_run {
+        println!("\n{}", tr!("library.repair_preview", count = issues.len()));
+        return Ok(());
+    }
+
+    if repaired > 0 {
+        history::save_all(&entries).await?;
+    }
+    println!("\n{}", tr!("library.repair_done", repaired = repaired, failed = failed));
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use anyhow::{Result, bail};
+
+/// What a Deezer link points to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    Track,
+    Album,
+    Playlist,
+    Artist,
+}
+
+impl LinkKind {
+    /// The word Deezer uses in its paths, e.g. `track`
+    pub fn name(self) -> &'static str {
+        match self {
+            LinkKind::Track => "track",
+            LinkKind::Album => "album",
+            LinkKind::Playlist => "playlist",
+            LinkKind::Artist => "artist",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "track" | "tracks" => Some(LinkKind::Track),
+            "album" | "albums" => Some(LinkKind::Album),
+            "playlist" | "playlists" => Some(LinkKind::Playlist),
+            "artist" | "artists" => Some(LinkKind::Artist),
+            _ => None,
+        }
+    }
+}
+
+/// (kind, ID) of a deezer.com link, in the shapes the site, the apps and the embeds use:
+/// with or without the scheme and a locale (`/en/`, `/us/`), on `www.`, `m.`, `widget.` or
+/// `api.deezer.com`, followed by a subpage (`/artist/27/top_track`), a query string or a
+/// fragment, or the old `plugins/player?type=playlist&id=...` player
+pub fn parse(input: &str) -> Option<(LinkKind, String)> {
+    let input = input.trim();
+    let without_scheme = ["https://", "http://"]
+        .iter()
+        .find_map(|scheme| {
+            let prefix = input.get(..scheme.len())?;
+            prefix.eq_ignore_ascii_case(scheme).then(|| &input[scheme.len()..])
+        })
+        .unwrap_or(input);
+    let (host, rest) = without_scheme.split_once('/')?;
+    let host = host.to_ascii_lowercase();
+    if host != "deezer.com" && !host.ends_with(".deezer.com") {
+        return None;
+    }
+    let rest = rest.split('#').next().unwrap_or_default();
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let from_path = segments.windows(2).find_map(|pair| {
+        let kind = LinkKind::from_name(pair[0])?;
+        is_id(pair[1]).then(|| (kind, pair[1].to_string()))
+    });
+    if from_path.is_some() {
+        return from_path;
+    }
+
+    // The old embedded player names the item in its query string
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    };
+    let kind = LinkKind::from_name(param("type")?)?;
+    let id = param("id")?;
+    is_id(id).then(|| (kind, id.to_string()))
+}
+
+/// The ID of a `kind` given as a bare ID or as any link [`parse`] understands
+pub fn id_of(input: &str, kind: LinkKind) -> Result<String> {
+    let input = input.trim();
+    if is_id(input) {
+        return Ok(input.to_string());
+    }
+    match parse(input) {
+        Some((found, id)) if found == kind => Ok(id),
+        Some((found, id)) => {
+            bail!("Wrong kind of link for {}s: {} points to {} {}", kind.name(), input, found.name(), id)
+        }
+        None if is_short_link(input) => {
+            bail!("{} is a short link; open it in a browser and use the deezer.com address it leads to", input)
+        }
+        None => bail!("Not a Deezer {} link or ID: {}", kind.name(), input),
+    }
+}
+
+/// Share links that only redirect to the real address
+fn is_short_link(input: &str) -> bool {
+    let lower = input.to_ascii_lowercase();
+    lower.contains("deezer.page.link/") || lower.contains("link.deezer.com/")
+}
+
+fn is_id(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::client::DeezerClient;
+use crate::color;
+use crate::models::{GwTrack, LyricsData, LyricsLine};
+use crate::tr;
+
+/// Returned for tracks Deezer has no lyrics for, with `--lyrics-only`
+#[derive(Debug)]
+pub struct NoLyrics;
+
+impl std::fmt::Display for NoLyrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("No lyrics on Deezer")
+    }
+}
+
+impl std::error::Error for NoLyrics {}
+
+/// One line of synced lyrics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncedLine {
+    /// `[mm:ss.xx]`
+    pub timestamp: String,
+    pub text: String,
+}
+
+/// A track's lyrics, as plain text and, when Deezer has them, synced lines
+#[derive(Debug, Clone, Default)]
+pub struct Lyrics {
+    pub text: String,
+    pub synced: Vec<SyncedLine>,
+    /// Language the lines were translated into; `None` for the original lyrics
+    pub translation: Option<String>,
+}
+
+impl Lyrics {
+    /// Lyrics from the pipe API; with `lang`, each line is taken from its translation where
+    /// there is one. A missing translation leaves the original lyrics.
+    pub fn from_pipe(lyrics: &LyricsData, lang: Option<&str>) -> Self {
+        let lines = lyrics.synchronized_lines.as_deref().unwrap_or_default();
+        let use_translation = lang.is_some() && lines.iter().any(|line| translated(line).is_some());
+        let synced: Vec<SyncedLine> = lines
+            .iter()
+            .filter_map(|line| {
+                let original = line.line.as_deref().unwrap_or_default();
+                let text = if use_translation { translated(line).unwrap_or(original) } else { original };
+                Some(SyncedLine {
+                    timestamp: line.lrc_timestamp.clone()?,
+                    text: text.to_string(),
+                })
+            })
+            .collect();
+        // The plain text is only in the original language; rebuild it from translated lines
+        let text = if use_translation {
+            synced.iter().map(|line| line.text.as_str()).collect::<Vec<_>>().join("\n")
+        } else {
+            lyrics.text.clone().unwrap_or_default()
+        };
+        Self {
+            text,
+            synced,
+            translation: lang.filter(|_| use_translation).map(str::to_string),
+        }
+    }
+
+    /// Lyrics from the `LYRICS` object of a GW track page (`LYRICS_TEXT`, `LYRICS_SYNC_JSON`)
+    pub fn from_gw(lyrics: &Value) -> Self {
+        let synced = lyrics["LYRICS_SYNC_JSON"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|line| {
+                Some(SyncedLine {
+                    timestamp: line["lrc_timestamp"].as_str()?.to_string(),
+                    text: line["line"].as_str().unwrap_or_default().to_string(),
+                })
+            })
+            .collect();
+        Self {
+            text: lyrics["LYRICS_TEXT"].as_str().unwrap_or_default().to_string(),
+            synced,
+            translation: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.synced.is_empty()
+    }
+
+    /// Plain text, from the synced lines when Deezer only has those
+    pub fn plain(&self) -> String {
+        if self.text.trim().is_empty() {
+            self.synced.iter().map(|line| line.text.as_str()).collect::<Vec<_>>().join("\n")
+        } else {
+            self.text.replace("\r\n", "\n")
+        }
+    }
+
+    /// LRC file contents; `None` without synced lines
+    pub fn lrc(&self) -> Option<String> {
+        if self.synced.is_empty() {
+            return None;
+        }
+        let mut out = String::new();
+        for line in &self.synced {
+            out.push_str(&line.timestamp);
+            out.push_str(&line.text);
+            out.push('\n');
+        }
+        Some(out)
+    }
+}
+
+/// Translation of a pipe API line, if it has a non-empty one
+fn translated(line: &LyricsLine) -> Option<&str> {
+    line.line_translated.as_deref().filter(|t| !t.trim().is_empty())
+}
+
+/// The track's lyrics, translated into `lang` when Deezer has a translation. Falls back to the
+/// lyrics of the track page when the pipe API fails; `None` when the track has none.
+pub async fn fetch(api: &impl DeezerClient, track: &GwTrack, lang: Option<&str>) -> Option<Lyrics> {
+    let lyrics = match api.get_lyrics(&track.id_str(), lang).await {
+        Ok(lyrics) => lyrics.map(|l| Lyrics::from_pipe(&l, lang)),
+        Err(e) => {
+            eprintln!("  {} {}", color::warn(), tr!("lyrics.failed", error = e));
+            track.lyrics.as_ref().map(Lyrics::from_gw)
+        }
+    };
+    let lyrics = lyrics.filter(|l| !l.is_empty())?;
+    if let Some(lang) = lang
+        && lyrics.translation.is_none()
+    {
+        println!("  {} {}", color::note("[lyrics]"), tr!("lyrics.no_translation", lang = lang));
+    }
+    Some(lyrics)
+}
+
+/// Save the lyrics next to `audio`: `.lrc` when synced, `.txt` otherwise. Returns the file written.
+pub async fn save(audio: &Path, lyrics: &Lyrics) -> Result<PathBuf> {
+    let (path, content) = match lyrics.lrc() {
+        Some(lrc) => (audio.with_extension("lrc"), lrc),
+        None => (audio.with_extension("txt"), lyrics.plain() + "\n"),
+    };
+    fs::write(&path, content)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
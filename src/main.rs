@@ -1,16 +1,40 @@
-mod api;
-mod auth;
-mod crypto;
-mod download;
-mod models;
-
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
 use dialoguer::{Input, Select};
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use deezer_dl::accounts::BackupAccounts;
+use deezer_dl::api::{DeezerApi, NetworkOptions};
+use deezer_dl::blocklist::{self, Blocklist};
+use deezer_dl::{
+    artwork, auth, backup, clipboard, color, compare, config, control, daemon, download, handler, hooks, i18n, jobs,
+    library, notify, probe, queue, retry, rpc, session, shutdown, sync, template, throttle, tr, watch,
+};
+use deezer_dl::color::ColorChoice;
+use deezer_dl::config::{OutputDirs, Preset};
+use deezer_dl::control::CtlCommand;
+use deezer_dl::convert::ConvertFormat;
+use deezer_dl::compare::CompareTarget;
+use deezer_dl::dedupe::{self, DedupeMode};
+use deezer_dl::export::{self, ExportTarget};
+use deezer_dl::download::{ArtistOptions, DownloadOptions, ExplicitFilter};
+use deezer_dl::i18n::Lang;
+use deezer_dl::links::{self, LinkKind};
+use deezer_dl::models::TrackFormat;
+use deezer_dl::pause::PauseSwitch;
+use deezer_dl::pool::{Pool, PoolView};
+use deezer_dl::queue::QueueCommand;
+use deezer_dl::retry::RetryPolicy;
+use deezer_dl::script::FilterScript;
+use deezer_dl::sync::SyncTarget;
+use deezer_dl::template::{Layout, TrackPositions};
+use deezer_dl::watch::WatchOptions;
 
-use crate::api::DeezerApi;
-use crate::models::TrackFormat;
+/// Shortest `--max-filename-length`: room for a track number, some of the title and the extension
+const MIN_FILENAME_LENGTH: i64 = 32;
 
 #[derive(Parser)]
 #[command(name = "deezer-dl", version, about = "Deezer music downloader CLI")]
@@ -22,82 +46,517 @@ struct Cli {
     #[arg(short, long)]
     output: Option<PathBuf>,
 
-    /// Audio quality: flac, 320, 128
-    #[arg(short, long, default_value = "320")]
+    /// Use the options of a [presets.NAME] section of config.toml as defaults
+    #[arg(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Language of console messages (default: from LC_ALL, LC_MESSAGES or LANG)
+    #[arg(long, value_enum)]
+    lang: Option<Lang>,
+
+    /// Color status tags and progress bars: auto (terminal and no NO_COLOR), always, never
+    #[arg(long, value_enum, value_name = "WHEN")]
+    color: Option<ColorChoice>,
+
+    /// Audio quality: flac, 320, 128, misc, 360, 360_mid, 360_low, or best (FLAC, 320 or 128 per
+    /// track, from its files and what the account streams)
+    #[arg(short, long, value_parser = download::parse_quality, default_value = "320")]
     quality: String,
+
+    /// Minimum acceptable quality when falling back: flac, 320, 128
+    #[arg(long, value_parser = download::parse_min_quality)]
+    min_quality: Option<TrackFormat>,
+
+    /// Stop before downloading when the account cannot stream --quality (free accounts and
+    /// FLAC, for instance) instead of only warning
+    #[arg(long)]
+    strict_quality: bool,
+
+    /// Write tracks delivered in a lower format than requested to this JSON file
+    #[arg(long)]
+    fallback_report: Option<PathBuf>,
+
+    /// How many times to retry failed API calls and downloads
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// Initial delay in seconds before retrying (doubled each time, with jitter)
+    #[arg(long, value_name = "SECS", value_parser = retry::parse_delay, default_value = "1")]
+    retry_delay: Duration,
+
+    /// Maximum Deezer API requests per second
+    #[arg(long, value_name = "RPS", value_parser = throttle::parse_api_rate, default_value = "10")]
+    api_rate: f64,
+
+    /// Limit download speed, e.g. 500K or 2M (bytes per second)
+    #[arg(long, value_parser = throttle::parse_rate)]
+    limit_rate: Option<u64>,
+
+    /// Proxy URL: http://[user:pass@]host:port or socks5://[user:pass@]host:port
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Accept invalid TLS certificates (insecure, use only if you know why)
+    #[arg(long)]
+    insecure: bool,
+
+    /// When a track is unavailable, look for another release of the same recording
+    #[arg(long)]
+    alt_search: bool,
+
+    /// Transcode downloads with ffmpeg (must be on PATH)
+    #[arg(long, value_enum)]
+    convert: Option<ConvertFormat>,
+
+    /// Save the album/playlist picture into each album and playlist folder
+    /// (file name defaults to cover.jpg, e.g. --save-cover folder.jpg)
+    #[arg(long, value_name = "NAME", num_args = 0..=1, default_missing_value = "cover.jpg")]
+    save_cover: Option<String>,
+
+    /// Size in pixels of saved album, playlist and artist pictures: 500, 1000, 1200 or 1800
+    #[arg(long, value_name = "PX", value_parser = artwork::parse_cover_size)]
+    cover_size: Option<u32>,
+
+    /// Write Kodi/Jellyfin album.nfo and artist.nfo files next to downloads
+    #[arg(long)]
+    nfo: bool,
+
+    /// Download into a staging folder (default: <output>/beets-staging) and run
+    /// `beet import --quiet` on it after the job
+    #[arg(long, value_name = "STAGING_DIR")]
+    beets: Option<Option<PathBuf>>,
+
+    /// Shell command run after each downloaded track; {path}, {artist}, {title},
+    /// {album} and {format} are replaced with quoted values
+    #[arg(long, value_name = "CMD")]
+    exec: Option<String>,
+
+    /// Shell command run after each job; {path} (job folder), {name}, {entity},
+    /// {downloaded} and {failed} are replaced with quoted values
+    #[arg(long, value_name = "CMD")]
+    exec_after: Option<String>,
+
+    /// Rhai script whose filter(track) function returns true to download a track,
+    /// false to skip it, or a path to save it under
+    #[arg(long, value_name = "FILE")]
+    script: Option<PathBuf>,
+
+    /// File of track, album and artist IDs or URLs, or artist names, never to download
+    /// (default: blocklist.txt in the config folder, when it exists)
+    #[arg(long, value_name = "FILE")]
+    blocklist: Option<PathBuf>,
+
+    /// Look up each track's ISRC on MusicBrainz and write recording/release/artist IDs
+    #[arg(long)]
+    musicbrainz: bool,
+
+    /// Fetch each track's page for contributors, BPM, gain and lyrics before naming and tagging it
+    /// (one more request per track; always done by `track`)
+    #[arg(long)]
+    full_metadata: bool,
+
+    /// Embed each track's lyrics and save them next to it as .lrc (synced) or .txt
+    #[arg(long)]
+    lyrics: bool,
+
+    /// Translate lyrics into this language (fr, es, de...) where Deezer has a translation,
+    /// keeping the original otherwise; implies --lyrics
+    #[arg(long, value_name = "LANG")]
+    lyrics_lang: Option<String>,
+
+    /// Download no audio: only save lyrics files next to the tracks already on disk (or where
+    /// they would go)
+    #[arg(long)]
+    lyrics_only: bool,
+
+    /// Append featured artists to the title in file names and tags, e.g. "Title (feat. X)"
+    #[arg(long)]
+    feat_in_title: bool,
+
+    /// Leave the track version (live, remix, remaster...) out of the title in file names and tags
+    #[arg(long)]
+    no_version_in_title: bool,
+
+    /// Store a track found in several playlists or artist folders once, and link
+    /// it into the others
+    #[arg(long, value_enum, value_name = "MODE")]
+    dedupe: Option<DedupeMode>,
+
+    /// Store every track once in DIR, named by its Deezer ID; job folders get links
+    /// to the pool files (or only an .m3u8 with --pool-view m3u)
+    #[arg(long, value_name = "DIR")]
+    pool: Option<PathBuf>,
+
+    /// Only skip tracks whose file exists at the templated path, instead of also
+    /// recognizing renamed files by their Deezer ID or ISRC
+    #[arg(long)]
+    no_identity_skip: bool,
+
+    /// How pooled tracks show up in job folders
+    #[arg(long, value_enum, default_value_t = PoolView::Links)]
+    pool_view: PoolView,
+
+    /// Put tracks of multi-disc albums into "Disc N" subfolders
+    #[arg(long)]
+    disc_folders: bool,
+
+    /// Transliterate file and folder names to ASCII (é → e, Кино → Kino), for
+    /// car stereos, DJ software and filesystems with limited character support
+    #[arg(long)]
+    ascii_filenames: bool,
+
+    /// Longest file or folder name to write, in bytes; longer titles are cut,
+    /// keeping the track number and extension
+    #[arg(long, value_name = "BYTES", value_parser = clap::value_parser!(u16).range(MIN_FILENAME_LENGTH..))]
+    max_filename_length: Option<u16>,
+
+    /// Leave out tracks marked as explicit
+    #[arg(long, conflicts_with = "explicit_only")]
+    skip_explicit: bool,
+
+    /// Only download tracks marked as explicit
+    #[arg(long)]
+    explicit_only: bool,
+
+    /// Replace explicit tracks with their clean edit when one exists
+    #[arg(long)]
+    prefer_clean: bool,
+
+    /// Show a desktop notification when a playlist, favorites, artist or sync job ends
+    #[arg(long)]
+    notify: bool,
+
+    /// POST a JSON summary (counts, failures, file paths) to this URL when a job finishes
+    #[arg(long, value_name = "URL")]
+    webhook: Option<String>,
+
+    /// Use a ready-made layout instead of --template
+    #[arg(long, value_enum, conflicts_with = "template")]
+    layout: Option<Layout>,
+
+    /// Filename template, e.g. "{artist}/{album}/{artist} - {title}" [default: {artist}/{artist} - {title}]
+    /// (variables: artist, title, album, album_artist, id, isrc, year, track, disc, disc_folder,
+    /// position, featuring, format)
+    #[arg(short, long)]
+    template: Option<String>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Download a track by URL or ID
     Track {
-        /// Deezer track URL or track ID
-        url: String,
+        /// Deezer track URLs or track IDs, downloaded one after another
+        #[arg(required = true)]
+        urls: Vec<String>,
     },
     /// Download a playlist by URL or ID
     Playlist {
-        /// Deezer playlist URL or playlist ID
-        url: String,
+        /// Deezer playlist URLs or playlist IDs, downloaded one after another
+        #[arg(required = true)]
+        urls: Vec<String>,
     },
     /// Download your liked/favorite songs
     Favorites,
     /// Download all songs from an artist
     Artist {
-        /// Deezer artist URL, ID, or search name
-        query: String,
+        /// Deezer artist URLs, IDs, or search names (quote names with spaces)
+        #[arg(required = true)]
+        queries: Vec<String>,
+        #[command(flatten)]
+        filters: ArtistOptions,
+    },
+    /// Keep a local copy in step with Deezer, downloading only what is missing
+    Sync {
+        #[command(subcommand)]
+        target: SyncTarget,
+    },
+    /// Keep running and re-sync the playlists, favorites and artists set up in config.toml
+    Daemon {
+        /// Time between sync passes, e.g. 30m, 6h or 1d
+        #[arg(long, value_parser = watch::parse_interval, default_value = "6h")]
+        interval: Duration,
+    },
+    /// Write a playlist's track list (titles, artists, ISRCs, Deezer IDs) to a file, without downloading
+    Export {
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+    /// Show which tracks were added to or removed from a playlist since the last sync, without downloading
+    Diff {
+        /// Deezer playlist URL or playlist ID
+        url: String,
+        /// The playlist's folder, or the session file of an interrupted job
+        target: PathBuf,
+    },
+    /// Show the logged-in account: country, HQ and lossless streaming, loved tracks playlist and
+    /// how long the ARL probably has left
+    Whoami,
+    /// List the formats (FLAC, 320, 128) each track can be downloaded in, with sizes, without downloading
+    Probe {
+        /// Deezer track or album URL, or track ID
+        url: String,
+    },
+    /// Compare the local library with your Deezer favorites, without changing either
+    Compare {
+        #[command(subcommand)]
+        target: CompareTarget,
+    },
+    /// Save your favorites and playlists (not audio) to a JSON file
+    Backup {
+        /// Backup file to write
+        file: PathBuf,
+    },
+    /// Re-add the favorites and playlists of a backup to the logged-in account
+    Restore {
+        /// Backup file written by `backup`
+        file: PathBuf,
+        /// Only show what would be added
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Send a command to a running daemon
+    Ctl {
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
+    /// Serve JSON-RPC on stdin/stdout for GUI frontends (queue, cancel, progress events)
+    Rpc,
+    /// Check artists for new releases and download them
+    Watch {
+        /// Artist URLs or IDs (added to `watch_artists` from config.toml)
+        artists: Vec<String>,
+        #[command(flatten)]
+        watch: WatchOptions,
     },
     /// Interactive mode - choose what to download
     Interactive,
     /// Remove stored login credentials
     Logout,
+    /// Move previously downloaded files to match the current template
+    Organize {
+        /// Library directory to re-layout
+        dir: PathBuf,
+        /// Only show what would be moved
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Re-download MP3 tracks from the history that are now available in FLAC
+    Upgrade {
+        /// Only upgrade files under this directory
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Only show what would be upgraded
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Find recordings stored more than once (same ISRC or Deezer ID) and review them
+    Dedupe {
+        /// Library directory to audit
+        #[arg(long, value_name = "DIR")]
+        scan: PathBuf,
+        /// Only list the duplicates
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check downloaded files for missing, truncated, or corrupt audio
+    Verify {
+        /// Only check files under this directory
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Print the problems as a JSON list
+        #[arg(long)]
+        json: bool,
+    },
+    /// Retry tracks that still failed at the end of earlier runs
+    RetryFailed,
+    /// Collect URLs to download later in one batch (the daemon drains the queue too)
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommand,
+    },
+    /// Download the jobs of .txt/.json files dropped into a folder, then move each file to done/
+    WatchFolder {
+        /// Folder to watch for job files
+        dir: PathBuf,
+        /// Time between checks of the folder, e.g. 10s
+        #[arg(long, value_parser = watch::parse_interval, default_value = "10s")]
+        interval: Duration,
+    },
+    /// Open a link as the system handler for deezer.com or `deezerdl://` links: queue it in the
+    /// running daemon, or download it right away when there is none
+    HandleUrl {
+        /// Deezer track, playlist or artist link (https://, deezerdl:// or deezer://)
+        url: String,
+    },
+    /// Add the Deezer URLs you copy to the clipboard to the queue, until Ctrl+C
+    WatchClipboard {
+        /// Time between clipboard checks, e.g. 1s
+        #[arg(long, value_parser = watch::parse_interval, default_value = "1s")]
+        interval: Duration,
+    },
+    /// Continue playlist, favorites and artist jobs that were interrupted
+    Resume {
+        /// Only list the interrupted jobs
+        #[arg(long)]
+        list: bool,
+    },
+    /// Re-download files that fail verification
+    Repair {
+        /// Only repair files under this directory
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Only show what would be re-downloaded
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
-fn parse_format(quality: &str) -> TrackFormat {
-    match quality.to_lowercase().as_str() {
-        "flac" | "lossless" | "9" => TrackFormat::Flac,
-        "320" | "mp3_320" | "3" => TrackFormat::Mp3_320,
-        "128" | "mp3_128" | "1" => TrackFormat::Mp3_128,
-        _ => TrackFormat::Mp3_320,
+impl Commands {
+    /// Whether the command downloads tracks, and so needs the backup accounts and the check that
+    /// the account streams `--quality`
+    fn downloads(&self) -> bool {
+        matches!(
+            self,
+            Commands::Track { .. }
+                | Commands::Playlist { .. }
+                | Commands::Favorites
+                | Commands::Artist { .. }
+                | Commands::Watch { .. }
+                | Commands::Sync { .. }
+                | Commands::Daemon { .. }
+                | Commands::Upgrade { .. }
+                | Commands::RetryFailed
+                | Commands::Resume { list: false }
+                | Commands::Queue { command: QueueCommand::Run }
+                | Commands::Repair { .. }
+                | Commands::HandleUrl { .. }
+                | Commands::WatchFolder { .. }
+                | Commands::Interactive
+        )
     }
 }
 
-/// Extract ID from a Deezer URL or return the input as-is if it's already an ID
-fn extract_id(input: &str, _entity: &str) -> String {
-    // Handle URLs like https://www.deezer.com/en/track/12345
-    if input.contains("deezer.com") {
-        if let Some(pos) = input.rfind('/') {
-            let id_part = &input[pos + 1..];
-            // Remove query params
-            let id = id_part.split('?').next().unwrap_or(id_part);
-            return id.to_string();
+/// Take the options that were not given on the command line from a preset
+fn apply_preset(cli: &mut Cli, preset: &Preset, matches: &ArgMatches) -> Result<()> {
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    fn fill<T>(field: &mut T, value: Option<T>, given: bool) {
+        if !given && let Some(value) = value {
+            *field = value;
         }
     }
-    // Already an ID
-    input.to_string()
+
+    fill(&mut cli.output, preset.output.clone().map(Some), given("output"));
+    let quality = match &preset.quality {
+        Some(quality) => Some(download::parse_quality(quality).map_err(|e| anyhow::anyhow!("Preset quality: {}", e))?),
+        None => None,
+    };
+    fill(&mut cli.quality, quality, given("quality"));
+    let min_quality = match &preset.min_quality {
+        Some(quality) => Some(Some(
+            download::parse_min_quality(quality).map_err(|e| anyhow::anyhow!("Preset min_quality: {}", e))?,
+        )),
+        None => None,
+    };
+    fill(&mut cli.min_quality, min_quality, given("min_quality"));
+    fill(&mut cli.strict_quality, preset.strict_quality, given("strict_quality"));
+    // --template and --layout replace each other
+    let layout_given = given("template") || given("layout");
+    fill(&mut cli.template, preset.template.clone().map(Some), layout_given);
+    fill(&mut cli.layout, preset.layout.map(Some), layout_given);
+    fill(&mut cli.disc_folders, preset.disc_folders, given("disc_folders"));
+    fill(&mut cli.ascii_filenames, preset.ascii_filenames, given("ascii_filenames"));
+    fill(&mut cli.max_filename_length, preset.max_filename_length.map(Some), given("max_filename_length"));
+    fill(&mut cli.convert, preset.convert.map(Some), given("convert"));
+    let limit_rate = match &preset.limit_rate {
+        Some(rate) => Some(Some(throttle::parse_rate(rate).map_err(|e| anyhow::anyhow!("Preset limit_rate: {}", e))?)),
+        None => None,
+    };
+    fill(&mut cli.limit_rate, limit_rate, given("limit_rate"));
+    fill(&mut cli.save_cover, preset.save_cover.clone().map(Some), given("save_cover"));
+    let cover_size = match preset.cover_size {
+        Some(size) => {
+            Some(Some(artwork::check_cover_size(size).map_err(|e| anyhow::anyhow!("Preset cover_size: {}", e))?))
+        }
+        None => None,
+    };
+    fill(&mut cli.cover_size, cover_size, given("cover_size"));
+    fill(&mut cli.nfo, preset.nfo, given("nfo"));
+    fill(&mut cli.musicbrainz, preset.musicbrainz, given("musicbrainz"));
+    fill(&mut cli.full_metadata, preset.full_metadata, given("full_metadata"));
+    fill(&mut cli.lyrics, preset.lyrics, given("lyrics"));
+    fill(&mut cli.lyrics_lang, preset.lyrics_lang.clone().map(Some), given("lyrics_lang"));
+    fill(&mut cli.lyrics_only, preset.lyrics_only, given("lyrics_only"));
+    fill(&mut cli.feat_in_title, preset.feat_in_title, given("feat_in_title"));
+    fill(&mut cli.no_version_in_title, preset.version_in_title.map(|v| !v), given("no_version_in_title"));
+    fill(&mut cli.dedupe, preset.dedupe.map(Some), given("dedupe"));
+    fill(&mut cli.pool, preset.pool.clone().map(Some), given("pool"));
+    fill(&mut cli.pool_view, preset.pool_view, given("pool_view"));
+    fill(&mut cli.no_identity_skip, preset.identity_skip.map(|v| !v), given("no_identity_skip"));
+    fill(&mut cli.alt_search, preset.alt_search, given("alt_search"));
+    let explicit_given = given("skip_explicit") || given("explicit_only");
+    fill(&mut cli.skip_explicit, preset.skip_explicit, explicit_given);
+    fill(&mut cli.explicit_only, preset.explicit_only, explicit_given);
+    fill(&mut cli.prefer_clean, preset.prefer_clean, given("prefer_clean"));
+    fill(&mut cli.script, preset.script.clone().map(Some), given("script"));
+    fill(&mut cli.blocklist, preset.blocklist.clone().map(Some), given("blocklist"));
+    fill(&mut cli.exec, preset.exec.clone().map(Some), given("exec"));
+    fill(&mut cli.exec_after, preset.exec_after.clone().map(Some), given("exec_after"));
+
+    if cli.skip_explicit && cli.explicit_only {
+        anyhow::bail!("Preset sets both skip_explicit and explicit_only");
+    }
+    if cli.max_filename_length.is_some_and(|max| i64::from(max) < MIN_FILENAME_LENGTH) {
+        anyhow::bail!("Preset max_filename_length must be at least {}", MIN_FILENAME_LENGTH);
+    }
+    Ok(())
 }
 
+/// `deezer-dl` in the user's download folder (localized, e.g. from XDG user dirs),
+/// or `./downloads` when there is no home directory
 fn default_output_dir() -> PathBuf {
-    dirs::home_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("Téléchargements")
-        .join("mp3")
+    dirs::download_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join("Downloads")))
+        .map(|dir| dir.join("deezer-dl"))
+        .unwrap_or_else(|| PathBuf::from("./downloads"))
 }
 
-async fn interactive_mode(api: &DeezerApi, format: TrackFormat, output: &PathBuf) -> Result<()> {
-    println!("Output directory: {}\n", output.display());
+async fn interactive_mode(api: &DeezerApi, opts: &DownloadOptions, outputs: &OutputDirs) -> Result<()> {
+    match outputs.single() {
+        Some(dir) => println!("{}\n", tr!("menu.output_dir", dir = dir.display())),
+        None => {
+            println!("{}", tr!("menu.output_dirs"));
+            let dirs = [
+                (tr!("menu.dir.tracks"), &outputs.tracks),
+                (tr!("menu.dir.playlists"), &outputs.playlists),
+                (tr!("menu.dir.favorites"), &outputs.favorites),
+                (tr!("menu.dir.artists"), &outputs.artists),
+            ];
+            for (label, dir) in &dirs {
+                println!("  {:<11}{}", format!("{}:", label), dir.display());
+            }
+            println!();
+        }
+    }
+    let opts = &DownloadOptions {
+        pause: PauseSwitch::with_keys(),
+        ..opts.clone()
+    };
+    if opts.pause.has_keys() {
+        println!("{}", tr!("menu.pause_hint"));
+    }
 
     loop {
         println!();
         let choices = &[
-            "Download a track (URL or search)",
-            "Download a playlist",
-            "Download favorites (liked songs)",
-            "Download all songs from an artist",
-            "Quit",
+            tr!("menu.track"),
+            tr!("menu.playlist"),
+            tr!("menu.favorites"),
+            tr!("menu.artist"),
+            tr!("menu.quit"),
         ];
 
         let selection = Select::new()
-            .with_prompt("What would you like to do?")
+            .with_prompt(tr!("menu.prompt"))
             .items(choices)
             .default(0)
             .interact()?;
@@ -105,19 +564,19 @@ async fn interactive_mode(api: &DeezerApi, format: TrackFormat, output: &PathBuf
         match selection {
             0 => {
                 let input: String = Input::new()
-                    .with_prompt("Enter track URL or ID")
+                    .with_prompt(tr!("menu.track_prompt"))
                     .interact_text()?;
-                let id = extract_id(&input, "track");
-                download::download_single_track(api, &id, format, output).await?;
+                let id = links::id_of(&input, LinkKind::Track)?;
+                download::download_single_track(api, &id, opts, &outputs.tracks).await?;
             }
             1 => {
                 // Show user playlists or enter URL
                 let playlist_choices = &[
-                    "Enter playlist URL or ID",
-                    "Choose from my playlists",
+                    tr!("menu.playlist_prompt"),
+                    tr!("menu.playlist_mine"),
                 ];
                 let pl_sel = Select::new()
-                    .with_prompt("How to find the playlist?")
+                    .with_prompt(tr!("menu.playlist_how"))
                     .items(playlist_choices)
                     .default(0)
                     .interact()?;
@@ -125,10 +584,10 @@ async fn interactive_mode(api: &DeezerApi, format: TrackFormat, output: &PathBuf
                 match pl_sel {
                     0 => {
                         let input: String = Input::new()
-                            .with_prompt("Enter playlist URL or ID")
+                            .with_prompt(tr!("menu.playlist_prompt"))
                             .interact_text()?;
-                        let id = extract_id(&input, "playlist");
-                        download::download_playlist(api, &id, format, output).await?;
+                        let id = links::id_of(&input, LinkKind::Playlist)?;
+                        download::download_playlist(api, &id, opts, &outputs.playlists).await?;
                     }
                     1 => {
                         let user = api.current_user.lock().await;
@@ -137,7 +596,7 @@ async fn interactive_mode(api: &DeezerApi, format: TrackFormat, output: &PathBuf
 
                         let playlists = api.get_user_playlists(user_id).await?;
                         if playlists.is_empty() {
-                            println!("No playlists found.");
+                            println!("{}", tr!("menu.no_playlists"));
                             continue;
                         }
 
@@ -147,60 +606,59 @@ async fn interactive_mode(api: &DeezerApi, format: TrackFormat, output: &PathBuf
                             .collect();
 
                         let sel = Select::new()
-                            .with_prompt("Select a playlist")
+                            .with_prompt(tr!("menu.select_playlist"))
                             .items(&names)
                             .default(0)
                             .interact()?;
 
                         let playlist_id = playlists[sel].id_str();
-                        download::download_playlist(api, &playlist_id, format, output).await?;
+                        download::download_playlist(api, &playlist_id, opts, &outputs.playlists).await?;
                     }
                     _ => {}
                 }
             }
             2 => {
-                download::download_favorites(api, format, output).await?;
+                download::download_favorites(api, opts, &outputs.favorites).await?;
             }
             3 => {
                 let input: String = Input::new()
-                    .with_prompt("Enter artist URL, ID, or name to search")
+                    .with_prompt(tr!("menu.artist_prompt"))
                     .interact_text()?;
 
+                let artist_opts = ArtistOptions {
+                    select: true,
+                    ..Default::default()
+                };
+
                 // Check if it's a URL or ID
-                if input.contains("deezer.com") || input.chars().all(|c| c.is_ascii_digit()) {
-                    let id = extract_id(&input, "artist");
-                    download::download_artist(api, &id, format, output).await?;
+                if input.contains("deezer.") || input.chars().all(|c| c.is_ascii_digit()) {
+                    let id = links::id_of(&input, LinkKind::Artist)?;
+                    download::download_artist(api, &id, opts, &artist_opts, &outputs.artists).await?;
                 } else {
                     // Search for artist
-                    let results = api.search_artist(&input).await?;
-                    let data = results["data"].as_array();
-                    if data.is_none() || data.unwrap().is_empty() {
-                        println!("No artists found for '{}'.", input);
+                    let artists = api.search_artist(&input).await?;
+                    if artists.is_empty() {
+                        println!("{}", tr!("menu.no_artists", query = input));
                         continue;
                     }
-                    let data = data.unwrap();
 
-                    let names: Vec<String> = data
+                    let names: Vec<String> = artists
                         .iter()
-                        .map(|a| {
-                            let name = a["name"].as_str().unwrap_or("Unknown");
-                            let fans = a["nb_fan"].as_u64().unwrap_or(0);
-                            format!("{} ({} fans)", name, fans)
-                        })
+                        .map(|a| tr!("menu.artist_fans", name = a.name, fans = a.nb_fan))
                         .collect();
 
                     let sel = Select::new()
-                        .with_prompt("Select an artist")
+                        .with_prompt(tr!("menu.select_artist"))
                         .items(&names)
                         .default(0)
                         .interact()?;
 
-                    let art_id = data[sel]["id"].as_u64().unwrap_or(0).to_string();
-                    download::download_artist(api, &art_id, format, output).await?;
+                    let art_id = artists[sel].id.to_string();
+                    download::download_artist(api, &art_id, opts, &artist_opts, &outputs.artists).await?;
                 }
             }
             4 => {
-                println!("Bye!");
+                println!("{}", tr!("menu.bye"));
                 break;
             }
             _ => {}
@@ -211,26 +669,220 @@ async fn interactive_mode(api: &DeezerApi, format: TrackFormat, output: &PathBuf
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let format = parse_format(&cli.quality);
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let notify_failure = cli.notify
+        && matches!(
+            cli.command,
+            Some(Commands::Playlist { .. })
+                | Some(Commands::Favorites)
+                | Some(Commands::Artist { .. })
+                | Some(Commands::Sync { .. })
+                | Some(Commands::Watch { .. })
+                | Some(Commands::Daemon { .. })
+        );
+
+    let result = run(cli, &matches).await;
+    if shutdown::requested() {
+        shutdown::exit_interrupted();
+    }
+    if let Err(e) = &result
+        && notify_failure
+    {
+        notify::desktop("deezer-dl failed", &e.to_string()).await;
+    }
+    result
+}
+
+async fn run(mut cli: Cli, matches: &ArgMatches) -> Result<()> {
+    let config = config::load()?;
+    i18n::set_lang(cli.lang.or(config.lang).unwrap_or_else(Lang::from_env));
+    color::init(cli.color.or(config.color).unwrap_or_default(), &config.theme);
+    shutdown::install();
+    if let Some(name) = &cli.preset {
+        let preset = config.presets.get(name).ok_or_else(|| {
+            let known: Vec<&str> = config.presets.keys().map(String::as_str).collect();
+            anyhow::anyhow!(
+                "No preset '{}' in {} (defined: {})",
+                name,
+                config::config_path().display(),
+                if known.is_empty() { "none".to_string() } else { known.join(", ") }
+            )
+        })?;
+        apply_preset(&mut cli, preset, matches)?;
+    }
+    let layout = match &cli.template {
+        Some(_) => None,
+        None => cli.layout.or(config.layout),
+    };
+    let mut template = match (layout, &cli.template) {
+        (Some(layout), _) => layout.template().to_string(),
+        (None, Some(template)) => template.clone(),
+        (None, None) => template::DEFAULT_TEMPLATE.to_string(),
+    };
+    if cli.disc_folders {
+        template = template::with_disc_folder(&template);
+    }
+    let cover_size = match (cli.cover_size, config.cover_size) {
+        (Some(size), _) => size,
+        (None, Some(size)) => artwork::check_cover_size(size).map_err(|e| anyhow::anyhow!("Config cover_size: {}", e))?,
+        (None, None) => artwork::COVER_SIZE,
+    };
+    let blocklist_file = cli
+        .blocklist
+        .clone()
+        .or(config.blocklist.clone())
+        .or_else(|| Some(blocklist::blocklist_path()).filter(|path| path.exists()));
+    let best_quality = cli.quality.eq_ignore_ascii_case(download::BEST_QUALITY);
+    let opts = DownloadOptions {
+        // Checked by parse_quality: only `best` has no format of its own
+        format: TrackFormat::from_quality(&cli.quality).unwrap_or(TrackFormat::Flac),
+        best_quality,
+        // Logged in after the main account
+        backup_accounts: None,
+        template,
+        ascii_filenames: cli.ascii_filenames,
+        max_filename_length: cli.max_filename_length.map_or(download::DEFAULT_MAX_FILENAME_LENGTH, usize::from),
+        min_format: cli.min_quality,
+        fallback_report: cli.fallback_report.clone(),
+        limit_rate: cli.limit_rate,
+        alt_search: cli.alt_search,
+        convert: cli.convert,
+        cover_file: cli.save_cover.clone(),
+        cover_size,
+        nfo: cli.nfo,
+        job_folders: layout.is_none(),
+        musicbrainz: cli.musicbrainz,
+        full_metadata: cli.full_metadata,
+        lyrics: cli.lyrics || cli.lyrics_lang.is_some(),
+        lyrics_lang: cli.lyrics_lang.clone(),
+        lyrics_only: cli.lyrics_only,
+        explicit_filter: if cli.skip_explicit {
+            Some(ExplicitFilter::SkipExplicit)
+        } else if cli.explicit_only {
+            Some(ExplicitFilter::ExplicitOnly)
+        } else {
+            None
+        },
+        prefer_clean: cli.prefer_clean,
+        webhook: cli.webhook.clone().or(config.webhook.clone()),
+        notify: cli.notify,
+        notify_targets: config.notify.clone(),
+        exec: cli.exec.clone(),
+        exec_after: cli.exec_after.clone(),
+        script: cli
+            .script
+            .clone()
+            .or(config.script.clone())
+            .map(|path| FilterScript::load(&path).map(Arc::new))
+            .transpose()?,
+        blocklist: blocklist_file
+            .map(|path| Blocklist::load(&path).map(Arc::new))
+            .transpose()?,
+
+        events: None,
+        job_progress: None,
+        pause: PauseSwitch::default(),
+        positions: TrackPositions::default(),
+        feat_in_title: cli.feat_in_title,
+        version_in_title: !cli.no_version_in_title,
+        dedupe: cli.dedupe,
+        pool: cli.pool.clone().map(|dir| Pool { dir, view: cli.pool_view }),
+        identity_skip: !cli.no_identity_skip,
+    };
+    if let Some(min) = opts.min_format
+        && !opts.format.is_at_least(min)
+    {
+        if opts.format.is_360() != min.is_360() {
+            anyhow::bail!("--quality {} and --min-quality {} mix 360 Reality Audio with stereo", opts.format, min);
+        }
+        anyhow::bail!("--quality {} is below --min-quality {}", opts.format, min);
+    }
+    if opts.convert.is_some() && !hooks::program_on_path("ffmpeg") {
+        anyhow::bail!("--convert needs ffmpeg, but it was not found on PATH");
+    }
     let is_interactive = matches!(cli.command, Some(Commands::Interactive) | None);
-    let output = cli.output.clone().unwrap_or_else(|| {
+    let output = cli.output.clone().or(config.output.default.clone()).unwrap_or_else(|| {
         if is_interactive {
             default_output_dir()
         } else {
             PathBuf::from("./downloads")
         }
     });
+    let beets_staging = cli
+        .beets
+        .clone()
+        .map(|dir| dir.unwrap_or_else(|| output.join("beets-staging")));
+    if beets_staging.is_some() && !hooks::program_on_path("beet") {
+        anyhow::bail!("--beets needs the `beet` command, but it was not found on PATH");
+    }
+    let outputs = match (&beets_staging, &cli.output) {
+        (Some(staging), _) => OutputDirs::all(staging),
+        (None, Some(dir)) => OutputDirs::all(dir),
+        (None, None) => OutputDirs::new(&output, &config.output),
+    };
+
+    let network = NetworkOptions {
+        proxy: cli.proxy.clone().or(config.proxy.clone()),
+        insecure: cli.insecure,
+    };
+    if network.insecure {
+        eprintln!("{}\n", tr!("net.insecure"));
+    }
 
-    let api = DeezerApi::new()?;
+    let api = DeezerApi::new(&network)?.with_retry_policy(RetryPolicy {
+        retries: cli.retries,
+        base_delay: cli.retry_delay,
+    })
+    .with_rate_limit(cli.api_rate);
 
     // Handle logout without login
     if let Some(Commands::Logout) = &cli.command {
         auth::remove_arl().await?;
-        println!("Logged out. Stored ARL removed.");
+        println!("{}", tr!("auth.logged_out"));
         return Ok(());
     }
 
+    // Library maintenance works offline
+    match &cli.command {
+        Some(Commands::Organize { dir, dry_run }) => {
+            return library::organize(dir, &opts, *dry_run).await;
+        }
+        Some(Commands::Verify { dir, json }) => {
+            return library::verify(dir.as_deref(), *json).await;
+        }
+        Some(Commands::Dedupe { scan, dry_run }) => {
+            return dedupe::scan(scan, *dry_run).await;
+        }
+        Some(Commands::Ctl { command }) => {
+            return control::ctl(command).await;
+        }
+        Some(Commands::Resume { list: true }) => {
+            return session::list().await;
+        }
+        Some(Commands::Queue { command: QueueCommand::Add { urls } }) => {
+            return queue::add(urls).await;
+        }
+        Some(Commands::Queue { command: QueueCommand::List }) => {
+            return queue::list().await;
+        }
+        Some(Commands::Queue { command: QueueCommand::Remove { items } }) => {
+            return queue::remove(items).await;
+        }
+        Some(Commands::WatchClipboard { interval }) => {
+            return clipboard::watch(*interval).await;
+        }
+        Some(Commands::HandleUrl { url }) if handler::queue_in_daemon(url).await? => {
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // Logs in by itself: there is no terminal to prompt on
+    if let Some(Commands::Rpc) = &cli.command {
+        return rpc::serve(&api, &opts, &outputs).await;
+    }
+
     // Login
     if !auth::login(&api).await? {
         return Ok(());
@@ -239,62 +891,213 @@ async fn main() -> Result<()> {
     {
         let user = api.current_user.lock().await;
         if let Some(u) = user.as_ref() {
-            println!("Logged in as: {}\n", u.name);
+            println!("{}\n", tr!("auth.logged_in", name = u.name));
         }
     }
 
-    // Create output dir
-    tokio::fs::create_dir_all(&output).await?;
+    // Job folders are created as files are written; beets needs the staging folder either way
+    if let Some(staging) = &beets_staging {
+        tokio::fs::create_dir_all(staging).await?;
+    }
+
+    let downloads = cli.command.as_ref().is_none_or(Commands::downloads);
+    let opts = if downloads && !config.backup_arls.is_empty() {
+        let new_session = || {
+            Ok(DeezerApi::new(&network)?
+                .with_retry_policy(api.retry_policy())
+                .with_rate_limit(cli.api_rate))
+        };
+        let backups = BackupAccounts::login(&config.backup_arls, new_session).await?;
+        println!();
+        DownloadOptions {
+            backup_accounts: Some(Arc::new(backups)),
+            ..opts
+        }
+    } else {
+        opts
+    };
+    let user = api.current_user.lock().await.clone();
+    let backups_stream = opts.backup_accounts.as_ref().is_some_and(|b| b.can_stream(opts.format));
+    if downloads
+        && !backups_stream
+        && let Some(user) = &user
+    {
+        download::check_account_quality(user, &opts, cli.strict_quality)?;
+    }
 
     match cli.command {
-        Some(Commands::Track { url }) => {
-            let id = extract_id(&url, "track");
-            download::download_single_track(&api, &id, format, &output).await?;
+        Some(Commands::Track { urls }) => {
+            let mut batch = download::Batch::new(&opts, urls.len());
+            for url in &urls {
+                let result = async {
+                    let id = links::id_of(url, LinkKind::Track)?;
+                    download::download_single_track(&api, &id, batch.opts(), &outputs.tracks).await
+                };
+                batch.record(url, result.await)?;
+            }
+            batch.finish()?;
         }
-        Some(Commands::Playlist { url }) => {
-            let id = extract_id(&url, "playlist");
-            download::download_playlist(&api, &id, format, &output).await?;
+        Some(Commands::Playlist { urls }) => {
+            let mut batch = download::Batch::new(&opts, urls.len());
+            for url in &urls {
+                let result = async {
+                    let id = links::id_of(url, LinkKind::Playlist)?;
+                    download::download_playlist(&api, &id, batch.opts(), &outputs.playlists).await
+                };
+                batch.record(url, result.await)?;
+            }
+            batch.finish()?;
         }
         Some(Commands::Favorites) => {
-            download::download_favorites(&api, format, &output).await?;
-        }
-        Some(Commands::Artist { query }) => {
-            if query.contains("deezer.com") || query.chars().all(|c| c.is_ascii_digit()) {
-                let id = extract_id(&query, "artist");
-                download::download_artist(&api, &id, format, &output).await?;
-            } else {
-                // Search
-                let results = api.search_artist(&query).await?;
-                let data = results["data"].as_array();
-                if data.is_none() || data.unwrap().is_empty() {
-                    println!("No artists found for '{}'.", query);
-                    return Ok(());
-                }
-                let data = data.unwrap();
-
-                let names: Vec<String> = data
-                    .iter()
-                    .map(|a| {
-                        let name = a["name"].as_str().unwrap_or("Unknown");
-                        let fans = a["nb_fan"].as_u64().unwrap_or(0);
-                        format!("{} ({} fans)", name, fans)
-                    })
-                    .collect();
-
-                let sel = Select::new()
-                    .with_prompt("Select an artist")
-                    .items(&names)
-                    .default(0)
-                    .interact()?;
+            let batch = download::Batch::new(&opts, 1);
+            download::download_favorites(&api, batch.opts(), &outputs.favorites).await?;
+            batch.finish()?;
+        }
+        Some(Commands::Artist { queries, filters }) => {
+            let mut batch = download::Batch::new(&opts, queries.len());
+            for query in &queries {
+                let id = if query.contains("deezer.") || query.chars().all(|c| c.is_ascii_digit()) {
+                    match links::id_of(query, LinkKind::Artist) {
+                        Ok(id) => id,
+                        Err(e) => {
+                            batch.record(query, Err(e))?;
+                            continue;
+                        }
+                    }
+                } else {
+                    // Search
+                    let artists = match api.search_artist(query).await {
+                        Ok(artists) if artists.is_empty() => {
+                            batch.record(query, Err(anyhow::anyhow!(tr!("menu.no_artists", query = query))))?;
+                            continue;
+                        }
+                        Ok(artists) => artists,
+                        Err(e) => {
+                            batch.record(query, Err(e.into()))?;
+                            continue;
+                        }
+                    };
 
-                let art_id = data[sel]["id"].as_u64().unwrap_or(0).to_string();
-                download::download_artist(&api, &art_id, format, &output).await?;
+                    let names: Vec<String> = artists
+                        .iter()
+                        .map(|a| tr!("menu.artist_fans", name = a.name, fans = a.nb_fan))
+                        .collect();
+
+                    let sel = Select::new()
+                        .with_prompt(tr!("menu.select_artist"))
+                        .items(&names)
+                        .default(0)
+                        .interact()?;
+
+                    artists[sel].id.to_string()
+                };
+                let result = download::download_artist(&api, &id, batch.opts(), &filters, &outputs.artists).await;
+                batch.record(query, result)?;
+            }
+            batch.finish()?;
+        }
+        Some(Commands::Sync { target }) => {
+            let batch = download::Batch::new(&opts, 1);
+            match target {
+                SyncTarget::Playlist { url, prune } => {
+                    let id = links::id_of(&url, LinkKind::Playlist)?;
+                    sync::sync_playlist(&api, &id, batch.opts(), &outputs.playlists, &prune).await?;
+                }
+                SyncTarget::Favorites { prune } => {
+                    sync::sync_favorites(&api, batch.opts(), &outputs.favorites, &prune).await?;
+                }
             }
+            batch.finish()?;
+        }
+        Some(Commands::Daemon { interval }) => {
+            let playlists = config
+                .daemon
+                .playlists
+                .iter()
+                .map(|p| links::id_of(p, LinkKind::Playlist))
+                .collect::<Result<_>>()?;
+            let artists = config
+                .watch_artists
+                .iter()
+                .map(|a| links::id_of(a, LinkKind::Artist))
+                .collect::<Result<_>>()?;
+            let jobs = daemon::DaemonJobs::new(&config.daemon, playlists, artists);
+            daemon::run(&api, &opts, &jobs, interval, &outputs).await?;
+        }
+        Some(Commands::Watch { artists, watch }) => {
+            let ids: Vec<String> = artists
+                .iter()
+                .chain(&config.watch_artists)
+                .map(|a| links::id_of(a, LinkKind::Artist))
+                .collect::<Result<_>>()?;
+            // Only returns with --once
+            let batch = download::Batch::new(&opts, 1);
+            watch::run(&api, batch.opts(), &watch, &ids, &outputs.artists).await?;
+            batch.finish()?;
+        }
+        Some(Commands::WatchFolder { dir, interval }) => {
+            jobs::watch(&api, &opts, &outputs, &dir, interval).await?;
+        }
+        Some(Commands::HandleUrl { url }) => {
+            handler::download(&api, &opts, &outputs, &url).await?;
+        }
+        Some(Commands::Diff { url, target }) => {
+            let id = links::id_of(&url, LinkKind::Playlist)?;
+            sync::diff_playlist(&api, &id, &opts, &target).await?;
+        }
+        Some(Commands::Whoami) => {
+            auth::whoami(&api).await?;
+        }
+        Some(Commands::Probe { url }) => {
+            probe::run(&api, &url).await?;
+        }
+        Some(Commands::Compare { target }) => {
+            compare::run(&api, &target, &outputs.favorites).await?;
+        }
+        Some(Commands::Backup { file }) => {
+            backup::backup(&api, &file).await?;
+        }
+        Some(Commands::Restore { file, dry_run }) => {
+            backup::restore(&api, &file, dry_run).await?;
+        }
+        Some(Commands::Export { target }) => {
+            export::run(&api, &target, &opts, &outputs.playlists).await?;
+        }
+        Some(Commands::Upgrade { dir, dry_run }) => {
+            library::upgrade(&api, &opts, dir.as_deref(), dry_run).await?;
+        }
+        Some(Commands::RetryFailed) => {
+            retry::retry_failed(&api, &opts).await?;
+        }
+        Some(Commands::Resume { list: false }) => {
+            session::resume(&api, &opts).await?;
+        }
+        Some(Commands::Queue { command: QueueCommand::Run }) => {
+            queue::run(&api, &opts, &outputs).await?;
+        }
+        Some(Commands::Repair { dir, dry_run }) => {
+            library::repair(&api, &opts, dir.as_deref(), dry_run).await?;
         }
         Some(Commands::Interactive) | None => {
-            interactive_mode(&api, format, &output).await?;
+            interactive_mode(&api, &opts, &outputs).await?;
+        }
+        Some(Commands::Logout)
+        | Some(Commands::Organize { .. })
+        | Some(Commands::Verify { .. })
+        | Some(Commands::Dedupe { .. })
+        | Some(Commands::Ctl { .. })
+        | Some(Commands::Resume { list: true })
+        | Some(Commands::Queue { .. })
+        | Some(Commands::WatchClipboard { .. })
+        | Some(Commands::Rpc) => {
+            unreachable!()
         }
-        Some(Commands::Logout) => unreachable!(),
+    }
+
+    if let Some(staging) = &beets_staging
+        && downloads
+    {
+        hooks::beets_import(staging).await?;
     }
 
     Ok(())
@@ -48,6 +48,19 @@ pub struct CurrentUser {
     pub loved_tracks_id: u64,
 }
 
+impl CurrentUser {
+    /// Best of FLAC, MP3 320 and MP3 128 the subscription streams
+    pub fn best_format(&self) -> TrackFormat {
+        if self.can_stream_lossless {
+            TrackFormat::Flac
+        } else if self.can_stream_hq {
+            TrackFormat::Mp3_320
+        } else {
+            TrackFormat::Mp3_128
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GwResponse {
     pub error: serde_json::Value,
@@ -94,14 +107,27 @@ pub struct GwTrack {
     pub filesize_flac: Option<serde_json::Value>,
     #[serde(rename = "FILESIZE_MP3_MISC")]
     pub filesize_mp3_misc: Option<serde_json::Value>,
+    #[serde(rename = "FILESIZE_MP4_RA1")]
+    pub filesize_mp4_ra1: Option<serde_json::Value>,
+    #[serde(rename = "FILESIZE_MP4_RA2")]
+    pub filesize_mp4_ra2: Option<serde_json::Value>,
+    #[serde(rename = "FILESIZE_MP4_RA3")]
+    pub filesize_mp4_ra3: Option<serde_json::Value>,
     #[serde(rename = "EXPLICIT_LYRICS")]
     pub explicit_lyrics: Option<serde_json::Value>,
     #[serde(rename = "GAIN")]
     pub gain: Option<serde_json::Value>,
+    #[serde(rename = "BPM")]
+    pub bpm: Option<serde_json::Value>,
     #[serde(rename = "ARTISTS")]
     pub artists: Option<Vec<serde_json::Value>>,
+    /// Credits by role (`main_artist`, `featuring`, `composer`...)
+    #[serde(rename = "SNG_CONTRIBUTORS")]
+    pub contributors: Option<serde_json::Value>,
     #[serde(rename = "LYRICS")]
     pub lyrics: Option<serde_json::Value>,
+    #[serde(rename = "LYRICS_ID")]
+    pub lyrics_id: Option<serde_json::Value>,
     #[serde(rename = "FALLBACK")]
     pub fallback: Option<serde_json::Value>,
     #[serde(rename = "VERSION")]
@@ -131,6 +157,172 @@ impl GwTrack {
         self.alb_title.clone().unwrap_or_default()
     }
 
+    pub fn album_id_str(&self) -> String {
+        match &self.alb_id {
+            Some(serde_json::Value::Number(n)) => n.to_string(),
+            Some(serde_json::Value::String(s)) => s.clone(),
+            _ => "0".to_string(),
+        }
+    }
+
+    pub fn artist_id_str(&self) -> String {
+        match &self.art_id {
+            Some(serde_json::Value::Number(n)) => n.to_string(),
+            Some(serde_json::Value::String(s)) => s.clone(),
+            _ => "0".to_string(),
+        }
+    }
+
+    /// The main artist followed by the other artists in ARTISTS, without duplicates
+    pub fn artist_names(&self) -> Vec<String> {
+        let mut names = vec![self.artist()];
+        let others = self.artists.iter().flatten().filter_map(|a| a.get("ART_NAME").and_then(|n| n.as_str()));
+        for name in others {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+        names
+    }
+
+    /// TRACK_NUMBER, 0 when unknown
+    pub fn track_no(&self) -> u32 {
+        value_u32(&self.track_number)
+    }
+
+    /// DISK_NUMBER, defaulting to 1
+    pub fn disc_no(&self) -> u32 {
+        value_u32(&self.disk_number).max(1)
+    }
+
+    /// DURATION in seconds, 0 when unknown
+    pub fn duration_secs(&self) -> u32 {
+        value_u32(&self.duration)
+    }
+
+    /// BPM rounded to a whole beat, 0 when unknown
+    pub fn beats_per_minute(&self) -> u32 {
+        value_f64(&self.bpm).map_or(0, |bpm| bpm.round() as u32)
+    }
+
+    /// GAIN: loudness in dB as measured by Deezer, `None` when unknown
+    pub fn gain_db(&self) -> Option<f64> {
+        value_f64(&self.gain).filter(|gain| *gain != 0.0)
+    }
+
+    /// Names credited in SNG_CONTRIBUTORS under `role` (`composer`, `author`, `producer`...)
+    pub fn contributors_as(&self, role: &str) -> Vec<String> {
+        self.contributors
+            .as_ref()
+            .and_then(|c| c.get(role))
+            .and_then(|names| names.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|n| n.as_str().map(str::to_string))
+            .collect()
+    }
+
+    /// Fill in what this track is missing from its `deezer.pageTrack` page: contributors,
+    /// lyrics, BPM, gain and album details that `song.getData` and the list calls leave out.
+    /// Fields that are already set are kept.
+    pub fn merge_page(&mut self, page: TrackPage) -> serde_json::Result<()> {
+        let mut merged = serde_json::to_value(&*self)?;
+        if let (Some(fields), serde_json::Value::Object(page_fields)) =
+            (merged.as_object_mut(), serde_json::to_value(page.data)?)
+        {
+            for (key, value) in page_fields {
+                if !value.is_null() && fields.get(&key).is_none_or(serde_json::Value::is_null) {
+                    fields.insert(key, value);
+                }
+            }
+        }
+        *self = serde_json::from_value(merged)?;
+        if self.lyrics.is_none() {
+            self.lyrics = page.lyrics;
+        }
+        Ok(())
+    }
+
+    /// EXPLICIT_LYRICS flag
+    pub fn is_explicit(&self) -> bool {
+        match &self.explicit_lyrics {
+            Some(serde_json::Value::Bool(b)) => *b,
+            other => value_u32(other) == 1,
+        }
+    }
+
+    /// Whether the artist is credited on this track, as main or featured artist
+    pub fn credits_artist(&self, art_id: &str) -> bool {
+        let Ok(art_id) = art_id.parse::<u32>() else {
+            return false;
+        };
+        value_u32(&self.art_id) == art_id
+            || self
+                .artists
+                .iter()
+                .flatten()
+                .any(|a| value_u32(&a.get("ART_ID").cloned()) == art_id)
+    }
+
+    /// Artists featured on the track, from SNG_CONTRIBUTORS or the roles in ARTISTS
+    pub fn featured_artists(&self) -> Vec<String> {
+        let contributors = self
+            .contributors
+            .as_ref()
+            .and_then(|c| c.get("featuring"))
+            .and_then(|f| f.as_array());
+        let names: Vec<&str> = match contributors {
+            Some(names) => names.iter().filter_map(|n| n.as_str()).collect(),
+            None => self
+                .artists
+                .iter()
+                .flatten()
+                .filter(|a| value_u32(&a.get("ROLE_ID").cloned()) == FEATURED_ROLE_ID)
+                .filter_map(|a| a.get("ART_NAME").and_then(|n| n.as_str()))
+                .collect(),
+        };
+        let main = self.artist();
+        let mut featured: Vec<String> = Vec::new();
+        for name in names {
+            if name != main && !featured.iter().any(|f| f == name) {
+                featured.push(name.to_string());
+            }
+        }
+        featured
+    }
+
+    /// `feat. A, B & C`, empty without featured artists
+    pub fn featuring(&self) -> String {
+        match self.featured_artists().as_slice() {
+            [] => String::new(),
+            [only] => format!("feat. {}", only),
+            [rest @ .., last] => format!("feat. {} & {}", rest.join(", "), last),
+        }
+    }
+
+    /// The title with VERSION (`Live 2019`, `Radio Edit`...) appended in parentheses, unless it already has it
+    pub fn title_with_version(&self) -> String {
+        let title = self.title();
+        let version = self.version.as_deref().unwrap_or_default().trim();
+        let bare = version.trim_start_matches(['(', '[']).trim_end_matches([')', ']']).trim();
+        if bare.is_empty() || title.to_lowercase().contains(&bare.to_lowercase()) {
+            title
+        } else {
+            format!("{} ({})", title, bare)
+        }
+    }
+
+    /// `title` with the featured artists appended, unless it already credits them
+    pub fn append_featuring(&self, title: String) -> String {
+        let featuring = self.featuring();
+        let lower = title.to_lowercase();
+        if featuring.is_empty() || ["feat.", "(ft.", " ft."].iter().any(|f| lower.contains(f)) {
+            title
+        } else {
+            format!("{} ({})", title, featuring)
+        }
+    }
+
     pub fn md5(&self) -> String {
         self.md5_origin.clone().unwrap_or_default()
     }
@@ -147,11 +339,40 @@ impl GwTrack {
         format!("{} - {}", self.artist(), self.title())
     }
 
+    /// The alternative version Deezer offers when this one is unavailable
+    pub fn fallback_track(&self) -> Option<GwTrack> {
+        let fallback = self.fallback.clone()?;
+        let track: GwTrack = serde_json::from_value(fallback).ok()?;
+        (track.id_str() != "0" && track.id_str() != self.id_str()).then_some(track)
+    }
+
+    /// Whether TRACK_TOKEN_EXPIRE is in the past (or less than a minute away).
+    /// Tracks without an expiry are assumed valid.
+    pub fn token_expired(&self) -> bool {
+        let expire = match &self.track_token_expire {
+            Some(serde_json::Value::Number(n)) => n.as_u64().unwrap_or(0),
+            Some(serde_json::Value::String(s)) => s.parse().unwrap_or(0),
+            _ => 0,
+        };
+        if expire == 0 {
+            return false;
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        now + 60 >= expire
+    }
+
     pub fn filesize_for_format(&self, format: TrackFormat) -> u64 {
         let val = match format {
             TrackFormat::Flac => &self.filesize_flac,
             TrackFormat::Mp3_320 => &self.filesize_mp3_320,
             TrackFormat::Mp3_128 => &self.filesize_mp3_128,
+            TrackFormat::Mp3Misc => &self.filesize_mp3_misc,
+            TrackFormat::Mp4Ra1 => &self.filesize_mp4_ra1,
+            TrackFormat::Mp4Ra2 => &self.filesize_mp4_ra2,
+            TrackFormat::Mp4Ra3 => &self.filesize_mp4_ra3,
         };
         match val {
             Some(serde_json::Value::Number(n)) => n.as_u64().unwrap_or(0),
@@ -159,6 +380,126 @@ impl GwTrack {
             _ => 0,
         }
     }
+
+    /// FLAC when the account streams lossless and the track has a FLAC file, else MP3 320 when
+    /// the account streams HQ and the track has one, else MP3 128. A track without any file
+    /// sizes gets the best format the account streams.
+    pub fn best_format(&self, lossless: bool, hq: bool) -> TrackFormat {
+        let sizes_known = [TrackFormat::Flac, TrackFormat::Mp3_320, TrackFormat::Mp3_128]
+            .iter()
+            .any(|&format| self.filesize_for_format(format) > 0);
+        let has = |format: TrackFormat| !sizes_known || self.filesize_for_format(format) > 0;
+        if lossless && has(TrackFormat::Flac) {
+            TrackFormat::Flac
+        } else if hq && has(TrackFormat::Mp3_320) {
+            TrackFormat::Mp3_320
+        } else {
+            TrackFormat::Mp3_128
+        }
+    }
+}
+
+pub const VARIOUS_ARTISTS: &str = "Various Artists";
+/// Deezer's artist ID for "Various Artists"
+const VARIOUS_ARTISTS_ID: u32 = 5080;
+/// Albums credited to at least this many main artists are treated as compilations
+const COMPILATION_MIN_ARTISTS: usize = 4;
+/// `ROLE_ID` of featured artists in ARTISTS (0 is a main artist)
+const FEATURED_ROLE_ID: u32 = 5;
+
+/// Numeric GW field that may come as a number or a string
+fn value_u32(value: &Option<serde_json::Value>) -> u32 {
+    match value {
+        Some(serde_json::Value::Number(n)) => n.as_u64().unwrap_or(0) as u32,
+        Some(serde_json::Value::String(s)) => s.parse().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// Numeric GW field that may come as a number or a string, with decimals
+fn value_f64(value: &Option<serde_json::Value>) -> Option<f64> {
+    match value {
+        Some(serde_json::Value::Number(n)) => n.as_f64(),
+        Some(serde_json::Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Album details from `album.getData`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlbumData {
+    #[serde(rename = "ALB_ID")]
+    pub alb_id: Option<serde_json::Value>,
+    #[serde(rename = "ALB_TITLE")]
+    pub alb_title: Option<String>,
+    #[serde(rename = "ART_ID")]
+    pub art_id: Option<serde_json::Value>,
+    #[serde(rename = "ART_NAME")]
+    pub art_name: Option<String>,
+    #[serde(rename = "ARTISTS")]
+    pub artists: Option<Vec<serde_json::Value>>,
+    #[serde(rename = "ALB_PICTURE")]
+    pub alb_picture: Option<String>,
+    #[serde(rename = "ORIGINAL_RELEASE_DATE")]
+    pub original_release_date: Option<String>,
+    #[serde(rename = "PHYSICAL_RELEASE_DATE")]
+    pub physical_release_date: Option<String>,
+    #[serde(rename = "DIGITAL_RELEASE_DATE")]
+    pub digital_release_date: Option<String>,
+    #[serde(rename = "NUMBER_DISK")]
+    pub number_disk: Option<serde_json::Value>,
+    #[serde(rename = "NUMBER_TRACK")]
+    pub number_track: Option<serde_json::Value>,
+    #[serde(rename = "LABEL_NAME")]
+    pub label_name: Option<String>,
+    #[serde(rename = "COPYRIGHT")]
+    pub copyright: Option<String>,
+}
+
+impl AlbumData {
+    /// Best known release date (`YYYY-MM-DD`), preferring the original release
+    pub fn release_date(&self) -> Option<String> {
+        [
+            &self.original_release_date,
+            &self.physical_release_date,
+            &self.digital_release_date,
+        ]
+        .into_iter()
+        .flatten()
+        .find(|d| !d.is_empty() && !d.starts_with("0000"))
+        .cloned()
+    }
+
+    pub fn year(&self) -> String {
+        self.release_date()
+            .and_then(|d| d.get(..4).map(|y| y.to_string()))
+            .unwrap_or_default()
+    }
+
+    /// NUMBER_DISK, defaulting to 1
+    pub fn disc_count(&self) -> u32 {
+        value_u32(&self.number_disk).max(1)
+    }
+
+    /// Whether this is a various-artists compilation: credited to Deezer's
+    /// "Various Artists" artist, or to many main artists at once
+    pub fn is_compilation(&self) -> bool {
+        let various = value_u32(&self.art_id) == VARIOUS_ARTISTS_ID
+            || self
+                .art_name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(VARIOUS_ARTISTS));
+        let many_artists = self
+            .artists
+            .as_ref()
+            .is_some_and(|a| a.len() >= COMPILATION_MIN_ARTISTS);
+        various || many_artists
+    }
+
+    /// NUMBER_TRACK, 0 when unknown
+    pub fn track_count(&self) -> u32 {
+        value_u32(&self.number_track)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,6 +512,8 @@ pub struct PlaylistInfo {
     pub nb_song: Option<serde_json::Value>,
     #[serde(rename = "PARENT_USERNAME")]
     pub parent_username: Option<String>,
+    #[serde(rename = "PARENT_USER_ID")]
+    pub parent_user_id: Option<serde_json::Value>,
     #[serde(rename = "PLAYLIST_PICTURE")]
     pub playlist_picture: Option<String>,
 }
@@ -187,6 +530,147 @@ impl PlaylistInfo {
     pub fn display_name(&self) -> String {
         self.title.clone().unwrap_or_else(|| "Unknown Playlist".to_string())
     }
+
+    /// Whether `user` created the playlist, rather than adding someone else's to their favorites
+    pub fn is_owned_by(&self, user: &CurrentUser) -> bool {
+        match &self.parent_user_id {
+            Some(serde_json::Value::Number(n)) => n.as_u64() == Some(user.id),
+            Some(serde_json::Value::String(s)) => s.parse() == Ok(user.id),
+            _ => self.parent_username.as_deref() == Some(user.name.as_str()),
+        }
+    }
+}
+
+/// `deezer.pagePlaylist` response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistPage {
+    #[serde(rename = "DATA")]
+    pub data: PlaylistInfo,
+}
+
+/// `deezer.pageTrack` response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackPage {
+    #[serde(rename = "DATA")]
+    pub data: GwTrack,
+    #[serde(rename = "LYRICS")]
+    pub lyrics: Option<serde_json::Value>,
+}
+
+/// Artist details from `artist.getData`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtistData {
+    #[serde(rename = "ART_ID")]
+    pub art_id: Option<serde_json::Value>,
+    #[serde(rename = "ART_NAME")]
+    pub art_name: Option<String>,
+    #[serde(rename = "ART_PICTURE")]
+    pub art_picture: Option<String>,
+    #[serde(rename = "NB_FAN")]
+    pub nb_fan: Option<serde_json::Value>,
+}
+
+impl ArtistData {
+    pub fn id_str(&self) -> String {
+        match &self.art_id {
+            Some(serde_json::Value::Number(n)) => n.to_string(),
+            Some(serde_json::Value::String(s)) => s.clone(),
+            _ => "0".to_string(),
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        self.art_name.clone().unwrap_or_else(|| "Unknown Artist".to_string())
+    }
+}
+
+/// One artist from the public API `/search/artist`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtistSearchResult {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub nb_fan: u64,
+}
+
+/// One track from the public API `/search/track`, or the track found for an ISRC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchTrackResult {
+    pub id: u64,
+    #[serde(default)]
+    pub title: String,
+    pub title_short: Option<String>,
+    /// Seconds
+    pub duration: Option<u64>,
+    pub explicit_lyrics: Option<bool>,
+    /// Whether the track can be streamed
+    pub readable: Option<bool>,
+}
+
+/// A `{ "data": [...] }` list of the public API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PublicList<T> {
+    #[serde(default)]
+    pub data: Vec<T>,
+}
+
+/// An artist or genre of a public API album, by name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PublicName {
+    #[serde(default)]
+    pub name: String,
+}
+
+/// One track of a public API album
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PublicAlbumTrack {
+    pub id: u64,
+    #[serde(default)]
+    pub title: String,
+    /// Seconds
+    #[serde(default)]
+    pub duration: u64,
+}
+
+/// An album from the public API `/album/{id}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicAlbum {
+    pub id: u64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub artist: PublicName,
+    #[serde(default)]
+    pub genres: PublicList<PublicName>,
+    /// `YYYY-MM-DD`
+    #[serde(default)]
+    pub release_date: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub cover_xl: String,
+    #[serde(default)]
+    pub tracks: PublicList<PublicAlbumTrack>,
+}
+
+/// A track's lyrics from the pipe API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsData {
+    pub text: Option<String>,
+    /// `null` when Deezer only has the plain text
+    pub synchronized_lines: Option<Vec<LyricsLine>>,
+}
+
+/// One synced line of [`LyricsData`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsLine {
+    /// `[mm:ss.xx]`
+    pub lrc_timestamp: Option<String>,
+    pub line: Option<String>,
+    /// The line in the requested language, when Deezer has a translation
+    pub line_translated: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -197,12 +681,18 @@ pub struct AlbumInfo {
     pub alb_title: Option<String>,
     #[serde(rename = "ART_NAME")]
     pub art_name: Option<String>,
+    #[serde(rename = "ALB_PICTURE")]
+    pub alb_picture: Option<String>,
     #[serde(rename = "NB_TRACKS")]
     pub nb_tracks: Option<serde_json::Value>,
     #[serde(rename = "ARTISTS_ALBUMS_IS_OFFICIAL")]
     pub is_official: Option<bool>,
     #[serde(rename = "TYPE")]
     pub album_type: Option<serde_json::Value>,
+    #[serde(rename = "PHYSICAL_RELEASE_DATE")]
+    pub physical_release_date: Option<String>,
+    #[serde(rename = "DIGITAL_RELEASE_DATE")]
+    pub digital_release_date: Option<String>,
 }
 
 impl AlbumInfo {
@@ -213,6 +703,40 @@ impl AlbumInfo {
             _ => "0".to_string(),
         }
     }
+
+    /// TYPE: 0 = single, 1 = album, 2 = compilation, 3 = EP
+    pub fn type_code(&self) -> u32 {
+        value_u32(&self.album_type)
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self.type_code() {
+            0 => "single",
+            1 => "album",
+            2 => "compilation",
+            3 => "EP",
+            _ => "release",
+        }
+    }
+
+    pub fn track_count(&self) -> u32 {
+        value_u32(&self.nb_tracks)
+    }
+
+    /// Release date (`YYYY-MM-DD`), empty when unknown
+    pub fn release_date(&self) -> String {
+        [&self.physical_release_date, &self.digital_release_date]
+            .into_iter()
+            .flatten()
+            .find(|d| !d.is_empty() && !d.starts_with("0000"))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// ARTISTS_ALBUMS_IS_OFFICIAL; releases without the flag count as official
+    pub fn is_official(&self) -> bool {
+        self.is_official.unwrap_or(true)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -242,11 +766,19 @@ pub struct MediaError {
     pub message: Option<String>,
 }
 
+/// Formats of the media API. Its AAC streams are left out: their format name, legacy URL
+/// code and file size field are undocumented, and a wrong guess would fail every download.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrackFormat {
     Flac,
     Mp3_320,
     Mp3_128,
+    /// MP3 of unknown bitrate, used for user uploads and some old catalogue entries
+    Mp3Misc,
+    /// 360 Reality Audio (MPEG-H in MP4), low/medium/high bitrate
+    Mp4Ra1,
+    Mp4Ra2,
+    Mp4Ra3,
 }
 
 impl TrackFormat {
@@ -255,6 +787,37 @@ impl TrackFormat {
             TrackFormat::Flac => 9,
             TrackFormat::Mp3_320 => 3,
             TrackFormat::Mp3_128 => 1,
+            TrackFormat::Mp3Misc => 0,
+            TrackFormat::Mp4Ra1 => 13,
+            TrackFormat::Mp4Ra2 => 14,
+            TrackFormat::Mp4Ra3 => 15,
+        }
+    }
+
+    /// A `--quality` value: flac, 320, 128, misc, 360/360_mid/360_low, the API names or format codes
+    pub fn from_quality(quality: &str) -> Option<TrackFormat> {
+        match quality.to_lowercase().as_str() {
+            "flac" | "lossless" | "9" => Some(TrackFormat::Flac),
+            "320" | "mp3_320" | "3" => Some(TrackFormat::Mp3_320),
+            "128" | "mp3_128" | "1" => Some(TrackFormat::Mp3_128),
+            "misc" | "mp3_misc" | "0" => Some(TrackFormat::Mp3Misc),
+            "360" | "360_high" | "mp4_ra3" | "15" => Some(TrackFormat::Mp4Ra3),
+            "360_mid" | "mp4_ra2" | "14" => Some(TrackFormat::Mp4Ra2),
+            "360_low" | "mp4_ra1" | "13" => Some(TrackFormat::Mp4Ra1),
+            _ => None,
+        }
+    }
+
+    pub fn from_api_name(name: &str) -> Option<TrackFormat> {
+        match name {
+            "FLAC" => Some(TrackFormat::Flac),
+            "MP3_320" => Some(TrackFormat::Mp3_320),
+            "MP3_128" => Some(TrackFormat::Mp3_128),
+            "MP3_MISC" => Some(TrackFormat::Mp3Misc),
+            "MP4_RA1" => Some(TrackFormat::Mp4Ra1),
+            "MP4_RA2" => Some(TrackFormat::Mp4Ra2),
+            "MP4_RA3" => Some(TrackFormat::Mp4Ra3),
+            _ => None,
         }
     }
 
@@ -263,21 +826,60 @@ impl TrackFormat {
             TrackFormat::Flac => "FLAC",
             TrackFormat::Mp3_320 => "MP3_320",
             TrackFormat::Mp3_128 => "MP3_128",
+            TrackFormat::Mp3Misc => "MP3_MISC",
+            TrackFormat::Mp4Ra1 => "MP4_RA1",
+            TrackFormat::Mp4Ra2 => "MP4_RA2",
+            TrackFormat::Mp4Ra3 => "MP4_RA3",
         }
     }
 
     pub fn extension(&self) -> &'static str {
         match self {
             TrackFormat::Flac => ".flac",
-            TrackFormat::Mp3_320 | TrackFormat::Mp3_128 => ".mp3",
+            TrackFormat::Mp3_320 | TrackFormat::Mp3_128 | TrackFormat::Mp3Misc => ".mp3",
+            TrackFormat::Mp4Ra1 | TrackFormat::Mp4Ra2 | TrackFormat::Mp4Ra3 => ".mp4",
         }
     }
 
+    pub fn is_mp3(&self) -> bool {
+        matches!(self, TrackFormat::Mp3_320 | TrackFormat::Mp3_128 | TrackFormat::Mp3Misc)
+    }
+
+    pub fn is_360(&self) -> bool {
+        matches!(self, TrackFormat::Mp4Ra1 | TrackFormat::Mp4Ra2 | TrackFormat::Mp4Ra3)
+    }
+
+    /// Relative audio quality, higher is better. Stereo formats and 360 Reality Audio are
+    /// ranked on separate ladders: compare only formats with the same [`TrackFormat::is_360`].
+    pub fn quality_rank(&self) -> u8 {
+        match self {
+            TrackFormat::Flac => 3,
+            TrackFormat::Mp3_320 => 2,
+            TrackFormat::Mp3_128 => 1,
+            TrackFormat::Mp3Misc => 0,
+            TrackFormat::Mp4Ra3 => 2,
+            TrackFormat::Mp4Ra2 => 1,
+            TrackFormat::Mp4Ra1 => 0,
+        }
+    }
+
+    /// Whether this format is as good as `other`. A 360 Reality Audio mix is never taken for
+    /// a stereo one of any quality, nor the other way round.
+    pub fn is_at_least(&self, other: TrackFormat) -> bool {
+        self.is_360() == other.is_360() && self.quality_rank() >= other.quality_rank()
+    }
+
+    /// Next format to try when this one has no file. 360 Reality Audio only
+    /// falls back within its own bitrates, since a stereo file is a different mix.
     pub fn fallback(&self) -> Option<TrackFormat> {
         match self {
             TrackFormat::Flac => Some(TrackFormat::Mp3_320),
             TrackFormat::Mp3_320 => Some(TrackFormat::Mp3_128),
-            TrackFormat::Mp3_128 => None,
+            TrackFormat::Mp3_128 => Some(TrackFormat::Mp3Misc),
+            TrackFormat::Mp3Misc => None,
+            TrackFormat::Mp4Ra3 => Some(TrackFormat::Mp4Ra2),
+            TrackFormat::Mp4Ra2 => Some(TrackFormat::Mp4Ra1),
+            TrackFormat::Mp4Ra1 => None,
         }
     }
 }
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::client::DeezerClient;
+
+/// MusicBrainz identifiers for a recording
+#[derive(Debug, Clone, Default)]
+pub struct MusicBrainzIds {
+    pub recording: String,
+    pub release: String,
+    pub artist: String,
+}
+
+/// Pick the recording and release for `album` from an `/isrc/{isrc}` response
+fn pick_ids(response: &Value, album: &str) -> Option<MusicBrainzIds> {
+    let recordings = response["recordings"].as_array()?;
+    let recording = recordings.first()?;
+
+    let releases = recordings
+        .iter()
+        .flat_map(|r| r["releases"].as_array().into_iter().flatten().map(move |rel| (r, rel)));
+    let (recording, release) = releases
+        .clone()
+        .find(|(_, rel)| rel["title"].as_str().is_some_and(|t| t.eq_ignore_ascii_case(album)))
+        .or_else(|| releases.clone().next())
+        .map(|(rec, rel)| (rec, Some(rel)))
+        .unwrap_or((recording, None));
+
+    Some(MusicBrainzIds {
+        recording: recording["id"].as_str().unwrap_or_default().to_string(),
+        release: release
+            .and_then(|r| r["id"].as_str())
+            .unwrap_or_default()
+            .to_string(),
+        artist: recording["artist-credit"][0]["artist"]["id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+/// Look up a track's ISRC; `None` when MusicBrainz doesn't know it
+pub async fn lookup(api: &impl DeezerClient, isrc: &str, album: &str) -> Result<Option<MusicBrainzIds>> {
+    let response = api.musicbrainz_isrc(isrc).await?;
+    Ok(pick_ids(&response, album))
+}
@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::client::DeezerClient;
+use crate::artwork::{self, ImageKind};
+use crate::color;
+use crate::download;
+use crate::models::{ArtistData, PublicAlbum};
+use crate::tr;
+
+/// Escape text for use inside an XML element
+pub fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Append `<tag>value</tag>` if the value is not empty
+fn element(out: &mut String, indent: &str, tag: &str, value: &str) {
+    if !value.is_empty() {
+        let _ = writeln!(out, "{}<{}>{}</{}>", indent, tag, xml_escape(value), tag);
+    }
+}
+
+/// Kodi/Jellyfin `album.nfo` from a public API album (`/album/{id}`)
+pub fn album_nfo(album: &PublicAlbum) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<album>\n");
+    let release_date = &album.release_date;
+
+    element(&mut out, "  ", "title", &album.title);
+    element(&mut out, "  ", "artistdesc", &album.artist.name);
+    for genre in &album.genres.data {
+        element(&mut out, "  ", "genre", &genre.name);
+    }
+    element(&mut out, "  ", "year", release_date.get(..4).unwrap_or_default());
+    element(&mut out, "  ", "releasedate", release_date);
+    element(&mut out, "  ", "label", &album.label);
+    let _ = writeln!(out, "  <uniqueid type=\"deezer\" default=\"true\">{}</uniqueid>", album.id);
+    element(&mut out, "  ", "thumb", &album.cover_xl);
+
+    for (i, track) in album.tracks.data.iter().enumerate() {
+        let secs = track.duration;
+        out.push_str("  <track>\n");
+        element(&mut out, "    ", "position", &(i + 1).to_string());
+        element(&mut out, "    ", "title", &track.title);
+        element(&mut out, "    ", "duration", &format!("{}:{:02}", secs / 60, secs % 60));
+        element(&mut out, "    ", "deezerid", &track.id.to_string());
+        out.push_str("  </track>\n");
+    }
+
+    out.push_str("</album>\n");
+    out
+}
+
+/// Kodi/Jellyfin `artist.nfo` from `artist.getData`
+pub fn artist_nfo(artist: &ArtistData) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<artist>\n");
+
+    element(&mut out, "  ", "name", artist.art_name.as_deref().unwrap_or_default());
+    let _ = writeln!(
+        out,
+        "  <uniqueid type=\"deezer\" default=\"true\">{}</uniqueid>",
+        xml_escape(&artist.id_str())
+    );
+    if let Some(md5) = artist.art_picture.as_deref().filter(|m| !m.is_empty()) {
+        element(
+            &mut out,
+            "  ",
+            "thumb",
+            &artwork::image_url(ImageKind::Artist, md5, artwork::COVER_SIZE),
+        );
+    }
+
+    out.push_str("</artist>\n");
+    out
+}
+
+/// Write an NFO file, warning instead of failing the job
+async fn write(path: &Path, content: &str) {
+    if let Err(e) = download::write_file_atomic(path, content.as_bytes()).await {
+        eprintln!("  {} {}", color::warn(), tr!("nfo.write_failed", path = path.display(), error = e));
+    }
+}
+
+/// Write `artist.nfo` into an artist folder
+pub async fn write_artist(dir: &Path, artist: &ArtistData) {
+    if let Err(e) = tokio::fs::create_dir_all(dir).await {
+        eprintln!("  {} {}", color::warn(), tr!("nfo.create_failed", path = dir.display(), error = e));
+        return;
+    }
+    write(&dir.join("artist.nfo"), &artist_nfo(artist)).await;
+}
+
+/// Writes `--nfo` album files, at most once per directory and job
+pub struct NfoWriter {
+    enabled: bool,
+    done: HashSet<PathBuf>,
+}
+
+impl NfoWriter {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            done: HashSet::new(),
+        }
+    }
+
+    /// Fetch the album from the public API and write `album.nfo` into `dir`
+    pub async fn save_album(&mut self, api: &impl DeezerClient, dir: &Path, alb_id: &str) {
+        if !self.enabled || alb_id == "0" || !self.done.insert(dir.to_path_buf()) {
+            return;
+        }
+        match api.get_album_public(alb_id).await {
+            Ok(album) => {
+                if let Err(e) = tokio::fs::create_dir_all(dir).await {
+                    eprintln!("  {} {}", color::warn(), tr!("nfo.create_failed", path = dir.display(), error = e));
+                    return;
+                }
+                write(&dir.join("album.nfo"), &album_nfo(&album)).await;
+            }
+            Err(e) => eprintln!("  {} {}", color::warn(), tr!("nfo.no_album", error = e)),
+        }
+    }
+}
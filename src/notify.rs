@@ -0,0 +1,281 @@
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::client::{DeezerClient, PostBody};
+use crate::color;
+use crate::download::{DownloadOptions, JobSummary};
+use crate::events::Event;
+use crate::hooks;
+use crate::pool;
+use crate::tr;
+
+/// `[notify]` section: chat services told about each finished job
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct NotifyTargets {
+    pub ntfy: Option<NtfyTarget>,
+    pub discord: Option<DiscordTarget>,
+    pub telegram: Option<TelegramTarget>,
+}
+
+/// `[notify.ntfy]`
+#[derive(Debug, Clone, Deserialize)]
+pub struct NtfyTarget {
+    pub topic: String,
+    /// Defaults to `https://ntfy.sh`
+    #[serde(default = "default_ntfy_server")]
+    pub server: String,
+    /// Access token for protected topics
+    pub token: Option<String>,
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+/// `[notify.discord]`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordTarget {
+    pub webhook: String,
+}
+
+/// `[notify.telegram]`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramTarget {
+    pub bot_token: String,
+    pub chat_id: String,
+}
+
+/// Failures listed in chat messages before "and N more"
+const MAX_LISTED_FAILURES: usize = 5;
+
+/// A track that could not be downloaded
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureReport {
+    pub sng_id: String,
+    pub track: String,
+    pub error: String,
+}
+
+/// Outcome of a finished download job, as posted to the webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct JobReport {
+    /// `track`, `playlist`, `favorites`, `artist` or `sync`
+    pub entity: String,
+    pub name: String,
+    pub output_dir: PathBuf,
+    pub downloaded: usize,
+    pub failed: usize,
+    /// Tracks refused by `--min-quality`
+    pub refused: usize,
+    /// Tracks left out by the explicit content filter or the filter script
+    pub filtered: usize,
+    pub failures: Vec<FailureReport>,
+    /// Files written or already present
+    pub paths: Vec<PathBuf>,
+}
+
+impl JobReport {
+    pub fn new(entity: &str, name: &str, output_dir: &Path, summary: &JobSummary) -> Self {
+        Self {
+            entity: entity.to_string(),
+            name: name.to_string(),
+            output_dir: output_dir.to_path_buf(),
+            downloaded: summary.downloaded,
+            failed: summary.failed,
+            refused: summary.refused.len(),
+            filtered: summary.filtered.len(),
+            failures: summary
+                .failures
+                .iter()
+                .map(|f| FailureReport {
+                    sng_id: f.track.id_str(),
+                    track: f.track.display_name(),
+                    error: f.error.clone(),
+                })
+                .collect(),
+            paths: summary.paths.clone(),
+        }
+    }
+
+    /// Short headline, e.g. `Playlist finished: Chill`
+    fn title(&self) -> String {
+        let mut entity = self.entity.clone();
+        if let Some(first) = entity.get_mut(..1) {
+            first.make_ascii_uppercase();
+        }
+        if self.failed > 0 {
+            tr!("notify.finished_with_failures", entity = entity, name = self.name)
+        } else {
+            tr!("notify.finished", entity = entity, name = self.name)
+        }
+    }
+
+    /// One line of counts, e.g. `12 downloaded, 1 failed`
+    fn counts(&self) -> String {
+        let mut counts = tr!("notify.counts", downloaded = self.downloaded, failed = self.failed);
+        if self.refused > 0 {
+            counts.push_str(&tr!("notify.refused", count = self.refused));
+        }
+        if self.filtered > 0 {
+            counts.push_str(&tr!("notify.filtered", count = self.filtered));
+        }
+        counts
+    }
+
+    /// The first failures as `track: error` lines, plus a line for the rest
+    fn failure_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .failures
+            .iter()
+            .take(MAX_LISTED_FAILURES)
+            .map(|f| format!("{}: {}", f.track, f.error))
+            .collect();
+        if self.failures.len() > MAX_LISTED_FAILURES {
+            lines.push(tr!("notify.more_failures", count = self.failures.len() - MAX_LISTED_FAILURES));
+        }
+        lines
+    }
+}
+
+/// Report a finished job to the configured targets and run `--exec-after`. Failures only warn.
+pub async fn job_finished(api: &impl DeezerClient, opts: &DownloadOptions, report: &JobReport) {
+    pool::write_job_playlist(opts, report).await;
+    opts.emit(|| Event::JobFinished { report: report.clone() });
+    if let Some(command) = &opts.exec_after {
+        hooks::exec_job(command, report).await;
+    }
+    if let Some(url) = &opts.webhook
+        && let Err(e) = post_webhook(api, url, report).await
+    {
+        eprintln!("{} {}", color::warn(), tr!("notify.failed", target = "Webhook", error = e));
+    }
+    // Single tracks finish too quickly to be worth a notification
+    if report.entity == "track" {
+        return;
+    }
+    if opts.notify {
+        desktop(&report.title(), &report.counts()).await;
+    }
+    let targets = &opts.notify_targets;
+    if let Some(ntfy) = &targets.ntfy
+        && let Err(e) = post_ntfy(api, ntfy, report).await
+    {
+        eprintln!("{} {}", color::warn(), tr!("notify.failed", target = "ntfy", error = e));
+    }
+    if let Some(discord) = &targets.discord
+        && let Err(e) = post_discord(api, discord, report).await
+    {
+        eprintln!("{} {}", color::warn(), tr!("notify.failed", target = "Discord", error = e));
+    }
+    if let Some(telegram) = &targets.telegram
+        && let Err(e) = post_telegram(api, telegram, report).await
+    {
+        eprintln!("{} {}", color::warn(), tr!("notify.failed", target = "Telegram", error = e));
+    }
+}
+
+/// Show a desktop notification, warning if the notification service is unavailable
+pub async fn desktop(title: &str, body: &str) {
+    let title = title.to_string();
+    let body = body.to_string();
+    let shown = tokio::task::spawn_blocking(move || {
+        notify_rust::Notification::new()
+            .appname("deezer-dl")
+            .summary(&title)
+            .body(&body)
+            .show()
+            .map(|_| ())
+    })
+    .await;
+    match shown {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => eprintln!("{} {}", color::warn(), tr!("notify.desktop_failed", error = e)),
+        Err(e) => eprintln!("{} {}", color::warn(), tr!("notify.desktop_failed", error = e)),
+    }
+}
+
+/// POST the report as JSON
+async fn post_webhook(api: &impl DeezerClient, url: &str, report: &JobReport) -> anyhow::Result<()> {
+    send(api, "webhook", url, Vec::new(), PostBody::Json(serde_json::to_value(report)?)).await
+}
+
+/// Plain-text message; the title and tags go in ntfy's headers
+async fn post_ntfy(api: &impl DeezerClient, ntfy: &NtfyTarget, report: &JobReport) -> anyhow::Result<()> {
+    let url = format!("{}/{}", ntfy.server.trim_end_matches('/'), ntfy.topic);
+    let mut body = report.counts();
+    for line in report.failure_lines() {
+        body.push_str(&format!("\n- {}", line));
+    }
+    let tags = if report.failed > 0 { "warning" } else { "white_check_mark" };
+    let mut headers = vec![("Title", report.title()), ("Tags", tags.to_string())];
+    if let Some(token) = &ntfy.token {
+        headers.push(("Authorization", format!("Bearer {}", token)));
+    }
+    send(api, "ntfy", &url, headers, PostBody::Text(body)).await
+}
+
+/// Embed colored green, or orange when tracks failed
+async fn post_discord(api: &impl DeezerClient, discord: &DiscordTarget, report: &JobReport) -> anyhow::Result<()> {
+    let mut description = report.counts();
+    let failures = report.failure_lines();
+    if !failures.is_empty() {
+        description.push_str("\n\n**Failed**");
+        for line in &failures {
+            description.push_str(&format!("\n- {}", line));
+        }
+    }
+    let payload = serde_json::json!({
+        "username": "deezer-dl",
+        "embeds": [{
+            "title": report.title(),
+            "description": description,
+            "color": if report.failed > 0 { 0xE67E22 } else { 0x2ECC71 },
+            "footer": { "text": report.output_dir.display().to_string() },
+        }],
+    });
+    send(api, "Discord", &discord.webhook, Vec::new(), PostBody::Json(payload)).await
+}
+
+/// HTML-formatted message sent through the Bot API
+async fn post_telegram(api: &impl DeezerClient, telegram: &TelegramTarget, report: &JobReport) -> anyhow::Result<()> {
+    let url = format!("https://api.telegram.org/bot{}/sendMessage", telegram.bot_token);
+    let mut text = format!("<b>{}</b>\n{}", html_escape(&report.title()), html_escape(&report.counts()));
+    for line in report.failure_lines() {
+        text.push_str(&format!("\n• {}", html_escape(&line)));
+    }
+    text.push_str(&format!("\n<code>{}</code>", html_escape(&report.output_dir.display().to_string())));
+    let payload = serde_json::json!({
+        "chat_id": telegram.chat_id,
+        "text": text,
+        "parse_mode": "HTML",
+        "disable_web_page_preview": true,
+    });
+    // The URL holds the bot token, keep it out of error messages
+    send(api, "Telegram", &url, Vec::new(), PostBody::Json(payload)).await
+}
+
+/// Send a request with retries, failing on a non-success status
+async fn send(
+    api: &impl DeezerClient,
+    service: &str,
+    url: &str,
+    headers: Vec<(&str, String)>,
+    body: PostBody,
+) -> anyhow::Result<()> {
+    api.retry_policy()
+        .run(service, || async {
+            let status = api.post(url, &headers, body.clone()).await?;
+            if !(200..300).contains(&status) {
+                bail!("{} returned {}", service, status);
+            }
+            Ok(())
+        })
+        .await
+}
+
+/// Escape the characters Telegram's HTML parse mode treats as markup
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,179 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+use crate::shutdown;
+
+/// Pauses downloads at the next chunk of the stream, keeping the job where it is.
+/// Shared by the job, the `p` key in interactive mode and `ctl pause`.
+#[derive(Debug, Clone, Default)]
+pub struct PauseSwitch {
+    state: Arc<PauseState>,
+    /// Toggle with the `p` key while a track downloads
+    keys: bool,
+}
+
+#[derive(Debug, Default)]
+struct PauseState {
+    paused: AtomicBool,
+    resumed: Notify,
+}
+
+impl PauseSwitch {
+    /// A switch also toggled by the `p` key (interactive mode)
+    pub fn with_keys() -> Self {
+        Self {
+            keys: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn pause(&self) {
+        self.state.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.state.paused.store(false, Ordering::SeqCst);
+        self.state.resumed.notify_waiters();
+    }
+
+    /// Pause or resume; returns whether downloads are now paused
+    pub fn toggle(&self) -> bool {
+        if self.is_paused() {
+            self.resume();
+            false
+        } else {
+            self.pause();
+            true
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.paused.load(Ordering::SeqCst)
+    }
+
+    /// Block while paused, or until Ctrl+C
+    pub async fn wait(&self) {
+        loop {
+            let resumed = self.state.resumed.notified();
+            tokio::pin!(resumed);
+            // Registered before checking, so a resume in between is not missed
+            resumed.as_mut().enable();
+            if !self.is_paused() || shutdown::requested() {
+                return;
+            }
+            tokio::select! {
+                _ = resumed => {}
+                _ = shutdown::signalled() => return,
+            }
+        }
+    }
+
+    /// Whether this switch listens for the `p` key on this platform
+    pub fn has_keys(&self) -> bool {
+        self.keys && cfg!(unix)
+    }
+
+    /// Toggle the switch with `p` until the returned guard is dropped.
+    /// `None` without keys or when stdin is not a terminal.
+    #[cfg(unix)]
+    pub fn listen_keys(&self) -> Option<KeyListener> {
+        use std::io::IsTerminal;
+
+        if !self.keys || !std::io::stdin().is_terminal() {
+            return None;
+        }
+        KeyListener::start(self.clone())
+    }
+
+    #[cfg(not(unix))]
+    pub fn listen_keys(&self) -> Option<KeyListener> {
+        None
+    }
+}
+
+/// Reads single keys from the terminal on a thread, without echo or waiting for Enter.
+/// Dropping it stops the thread and restores the terminal mode.
+pub struct KeyListener {
+    #[cfg(unix)]
+    stop: Arc<AtomicBool>,
+    #[cfg(unix)]
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+/// Terminal mode to restore while a `KeyListener` has changed it
+#[cfg(unix)]
+static SAVED_TERMINAL: std::sync::Mutex<Option<libc::termios>> = std::sync::Mutex::new(None);
+
+/// Put the terminal back in its normal mode, for exits that skip `KeyListener`'s drop
+pub fn restore_terminal() {
+    #[cfg(unix)]
+    if let Some(saved) = SAVED_TERMINAL.lock().unwrap().take() {
+        // SAFETY: restores the mode read by tcgetattr on the same descriptor
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &saved);
+        }
+    }
+}
+
+#[cfg(unix)]
+impl KeyListener {
+    /// How long the thread waits for a key before checking whether to stop
+    const POLL_MS: libc::c_int = 50;
+
+    fn start(switch: PauseSwitch) -> Option<Self> {
+        let fd = libc::STDIN_FILENO;
+        // SAFETY: termios is plain data, filled in by tcgetattr before use
+        let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+        // SAFETY: tcgetattr/tcsetattr on the process's own stdin with valid pointers
+        unsafe {
+            if libc::tcgetattr(fd, &mut saved) != 0 {
+                return None;
+            }
+            let mut raw = saved;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(fd, libc::TCSANOW, &raw) != 0 {
+                return None;
+            }
+        }
+        *SAVED_TERMINAL.lock().unwrap() = Some(saved);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = std::thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                let mut pollfd = libc::pollfd {
+                    fd,
+                    events: libc::POLLIN,
+                    revents: 0,
+                };
+                let mut key = 0u8;
+                // SAFETY: one pollfd and a one-byte buffer, both valid for the calls
+                let read = unsafe {
+                    libc::poll(&mut pollfd, 1, Self::POLL_MS) > 0
+                        && libc::read(fd, (&mut key as *mut u8).cast(), 1) == 1
+                };
+                if read && key.eq_ignore_ascii_case(&b'p') {
+                    switch.toggle();
+                }
+            }
+        });
+        Some(Self {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        restore_terminal();
+    }
+}
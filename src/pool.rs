@@ -0,0 +1,60 @@
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use crate::color;
+use crate::download::{self, DownloadOptions};
+use crate::notify::JobReport;
+use crate::tr;
+
+/// How the tracks of a job show up outside the pool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolView {
+    /// A link at each track's usual path (symlinks, or hardlinks with `--dedupe hardlink`)
+    #[default]
+    Links,
+    /// Only a `<job>.m3u8` playlist in the job folder, listing the pool files
+    M3u,
+}
+
+/// `--pool`: each track is stored once, under its Deezer ID, whatever the jobs it belongs to
+#[derive(Debug, Clone)]
+pub struct Pool {
+    pub dir: PathBuf,
+    pub view: PoolView,
+}
+
+impl Pool {
+    /// `<pool>/<last two digits of the ID>/<ID><extension>`, so no folder gets too many files
+    pub fn path_for(&self, sng_id: &str, extension: &str) -> PathBuf {
+        let shard = &sng_id[sng_id.len().saturating_sub(2)..];
+        self.dir.join(shard).join(format!("{}{}", sng_id, extension))
+    }
+}
+
+/// With the m3u view, write the job's playlist of pool files into its folder.
+/// Single tracks get none, and `sync` writes its own.
+pub async fn write_job_playlist(opts: &DownloadOptions, report: &JobReport) {
+    if !opts.pool.as_ref().is_some_and(|pool| pool.view == PoolView::M3u)
+        || matches!(report.entity.as_str(), "track" | "sync")
+        || report.paths.is_empty()
+    {
+        return;
+    }
+    let path = report.output_dir.join(format!("{}.m3u8", opts.file_name(&report.name)));
+    let mut content = String::from("#EXTM3U\n");
+    for file in &report.paths {
+        let file = std::path::absolute(file).unwrap_or_else(|_| file.clone());
+        let _ = writeln!(content, "{}", file.display());
+    }
+
+    let result = async {
+        tokio::fs::create_dir_all(&report.output_dir).await?;
+        download::write_file_atomic(&path, content.as_bytes()).await
+    }
+    .await;
+    match result {
+        Ok(()) => println!("{}", tr!("pool.m3u_written", path = path.display())),
+        Err(e) => eprintln!("{} {}", color::warn(), tr!("pool.m3u_failed", path = path.display(), error = e)),
+    }
+}
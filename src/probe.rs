@@ -0,0 +1,156 @@
+use anyhow::{Result, bail};
+use indicatif::HumanBytes;
+
+use crate::client::DeezerClient;
+use crate::color;
+use crate::download::{self, URL_BATCH_SIZE};
+use crate::links::{self, LinkKind};
+use crate::models::{GwTrack, TrackFormat};
+use crate::tr;
+
+/// Formats `probe` checks, best first
+pub const PROBED_FORMATS: [TrackFormat; 3] = [TrackFormat::Flac, TrackFormat::Mp3_320, TrackFormat::Mp3_128];
+
+/// Whether one format of a track can be downloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    /// The media API hands out a stream URL for it
+    Allowed,
+    /// Deezer has the file, but not for this account (subscription, region)
+    Refused,
+    /// Deezer has no file in this format
+    Missing,
+}
+
+/// One probed track: availability and size of each of [`PROBED_FORMATS`]
+#[derive(Debug, Clone)]
+pub struct ProbedTrack {
+    pub track: GwTrack,
+    pub formats: Vec<(TrackFormat, Availability, u64)>,
+}
+
+/// Run `probe`: list which formats of a track, or of every track of an album, the account can
+/// download, with their sizes. Nothing is downloaded.
+pub async fn run(api: &impl DeezerClient, input: &str) -> Result<()> {
+    let (kind, id) = match links::parse(input) {
+        Some((kind @ (LinkKind::Track | LinkKind::Album), id)) => (kind, id),
+        Some((kind, _)) => bail!("probe takes a track or album, not a {}: {}", kind.name(), input),
+        None => (LinkKind::Track, links::id_of(input, LinkKind::Track)?),
+    };
+    let mut tracks = match kind {
+        LinkKind::Album => {
+            let album = api.get_album_data(&id).await?;
+            println!("{}", tr!("probe.album", album = album.alb_title.unwrap_or_default(), id = id));
+            api.get_album_tracks(&id).await?
+        }
+        _ => vec![api.get_track(&id).await?],
+    };
+    if let Some(user) = api.user().await {
+        let yes_no = |allowed: bool| if allowed { tr!("probe.yes") } else { tr!("probe.no") };
+        println!(
+            "{}\n",
+            tr!(
+                "probe.account",
+                name = user.name,
+                hq = yes_no(user.can_stream_hq),
+                lossless = yes_no(user.can_stream_lossless),
+            )
+        );
+    }
+
+    let mut probed = Vec::with_capacity(tracks.len());
+    for batch in tracks.chunks_mut(URL_BATCH_SIZE) {
+        probed.extend(probe_tracks(api, batch).await?);
+    }
+    for (i, track) in probed.iter().enumerate() {
+        print_track(i + 1, track);
+    }
+    if probed.len() > 1 {
+        print_totals(&probed);
+    }
+    Ok(())
+}
+
+/// Ask the media API for every probed format of `tracks` at once
+pub async fn probe_tracks(api: &impl DeezerClient, tracks: &mut [GwTrack]) -> Result<Vec<ProbedTrack>> {
+    download::refresh_expired(api, tracks.iter_mut()).await;
+    let token = |track: &GwTrack| track.track_token.clone().unwrap_or_default();
+    let tokens: Vec<String> = tracks.iter().map(token).collect();
+    let requests: Vec<(&str, &str)> = tokens
+        .iter()
+        .filter(|token| !token.is_empty())
+        .flat_map(|token| PROBED_FORMATS.map(|format| (token.as_str(), format.api_name())))
+        .collect();
+    let urls = if requests.is_empty() {
+        Vec::new()
+    } else {
+        api.get_track_urls(&requests).await?
+    };
+    let allowed = |token: &str, format: TrackFormat| {
+        requests
+            .iter()
+            .zip(&urls)
+            .any(|((t, f), url)| *t == token && *f == format.api_name() && url.is_some())
+    };
+
+    Ok(tracks
+        .iter()
+        .zip(&tokens)
+        .map(|(track, token)| {
+            let formats = PROBED_FORMATS
+                .iter()
+                .map(|&format| {
+                    let size = track.filesize_for_format(format);
+                    let availability = if allowed(token, format) {
+                        Availability::Allowed
+                    } else if size > 0 {
+                        Availability::Refused
+                    } else {
+                        Availability::Missing
+                    };
+                    (format, availability, size)
+                })
+                .collect();
+            ProbedTrack {
+                track: track.clone(),
+                formats,
+            }
+        })
+        .collect())
+}
+
+fn print_track(number: usize, probed: &ProbedTrack) {
+    println!("{:02}. {}", number, probed.track.display_name());
+    for (format, availability, size) in &probed.formats {
+        let size = if *size > 0 { HumanBytes(*size).to_string() } else { "-".to_string() };
+        let (tag, status) = match availability {
+            Availability::Allowed => (color::ok(), tr!("probe.allowed")),
+            Availability::Refused => (color::warn(), tr!("probe.refused")),
+            Availability::Missing => (color::skip(), tr!("probe.missing")),
+        };
+        println!("    {:<8} {:>10}  {} {}", format.api_name(), size, tag, status);
+    }
+}
+
+/// Per format: how many tracks the account can download, and their total size
+fn print_totals(probed: &[ProbedTrack]) {
+    println!();
+    for (i, format) in PROBED_FORMATS.iter().enumerate() {
+        let allowed: Vec<u64> = probed
+            .iter()
+            .filter_map(|p| p.formats.get(i))
+            .filter(|(_, availability, _)| *availability == Availability::Allowed)
+            .map(|(_, _, size)| *size)
+            .collect();
+        println!(
+            "{}",
+            tr!(
+                "probe.total",
+                format = format.api_name(),
+                allowed = allowed.len(),
+                total = probed.len(),
+                size = HumanBytes(allowed.iter().sum()),
+            )
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::sync::{Arc, Mutex};
+
+use crate::models::{GwTrack, TrackFormat};
+use crate::tr;
+
+/// Overall progress of a playlist, favorites, artist or sync job, drawn above the
+/// bar of the track being downloaded (`DownloadOptions::job_progress`).
+///
+/// The byte total comes from the tracks' `FILESIZE_*`. Tracks that are announced
+/// but not listed yet (albums of an artist not fetched so far) count for the
+/// average size of the listed ones; skipped and failed tracks leave the total.
+#[derive(Debug, Clone)]
+pub struct JobProgress {
+    bar: ProgressBar,
+    format: TrackFormat,
+    state: Arc<Mutex<JobState>>,
+}
+
+#[derive(Debug, Default)]
+struct JobState {
+    /// Announced tracks whose sizes are not known yet
+    planned: u64,
+    listed: u64,
+    /// Expected size of the listed tracks, replaced by the bytes received as they finish
+    listed_bytes: u64,
+    finished: u64,
+    /// Bytes received for the finished tracks
+    received: u64,
+}
+
+impl JobProgress {
+    /// A job of `planned` tracks downloaded in `format`; list them with [`add_tracks`](Self::add_tracks)
+    pub fn new(planned: usize, format: TrackFormat) -> Self {
+        let bar = ProgressBar::hidden();
+        bar.set_style(
+            ProgressStyle::default_bar()
+                .template("  [{bar:40.green/blue}] {bytes}/{total_bytes} ({eta}) {msg}")
+                .unwrap()
+                .progress_chars("##-"),
+        );
+        let progress = Self {
+            bar,
+            format,
+            state: Arc::new(Mutex::new(JobState {
+                planned: planned as u64,
+                ..Default::default()
+            })),
+        };
+        progress.update(&progress.state.lock().unwrap());
+        progress
+    }
+
+    /// A job over `tracks`, all known up front
+    pub fn for_tracks<'a>(tracks: impl IntoIterator<Item = &'a GwTrack>, format: TrackFormat) -> Self {
+        let progress = Self::new(0, format);
+        progress.add_tracks(tracks, 0);
+        progress
+    }
+
+    /// Count the sizes of `tracks`, which stand for `planned` of the announced tracks
+    pub fn add_tracks<'a>(&self, tracks: impl IntoIterator<Item = &'a GwTrack>, planned: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.planned = state.planned.saturating_sub(planned as u64);
+        for track in tracks {
+            state.listed += 1;
+            state.listed_bytes += self.expected_size(track);
+        }
+        self.update(&state);
+    }
+
+    /// A listed track is done: downloaded, skipped or failed
+    pub fn finish_track(&self, track: &GwTrack) {
+        let mut state = self.state.lock().unwrap();
+        let position = self.bar.position();
+        let received = position.saturating_sub(state.received);
+        state.listed_bytes = (state.listed_bytes + received).saturating_sub(self.expected_size(track));
+        state.received = position;
+        state.finished += 1;
+        self.update(&state);
+    }
+
+    /// Size in the requested format, or in the first fallback format Deezer has
+    fn expected_size(&self, track: &GwTrack) -> u64 {
+        std::iter::successors(Some(self.format), |f| f.fallback())
+            .map(|f| track.filesize_for_format(f))
+            .find(|&size| size > 0)
+            .unwrap_or(0)
+    }
+
+    fn update(&self, state: &JobState) {
+        let average = state.listed_bytes.checked_div(state.listed).unwrap_or(0);
+        self.bar.set_length(state.received.max(state.listed_bytes) + average * state.planned);
+        let total = state.listed + state.planned;
+        self.bar.set_message(tr!("progress.tracks", done = state.finished, total = total));
+    }
+}
+
+/// Download progress of one track, with the job's bar above it when there is one
+pub struct TrackBar {
+    bar: ProgressBar,
+    job: Option<(MultiProgress, ProgressBar)>,
+}
+
+impl TrackBar {
+    pub fn new(total: u64, job: Option<&JobProgress>) -> Self {
+        let bar = ProgressBar::new(total);
+        bar.set_style(
+            ProgressStyle::default_bar()
+                .template("  [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta}) {msg}")
+                .unwrap()
+                .progress_chars("##-"),
+        );
+        let job = job.map(|job| {
+            let multi = MultiProgress::new();
+            let job_bar = multi.add(job.bar.clone());
+            (multi, job_bar)
+        });
+        let bar = match &job {
+            Some((multi, _)) => multi.add(bar),
+            None => bar,
+        };
+        Self { bar, job }
+    }
+
+    pub fn inc(&self, delta: u64) {
+        self.bar.inc(delta);
+        if let Some((_, job_bar)) = &self.job {
+            job_bar.inc(delta);
+        }
+    }
+
+    pub fn set_message(&self, message: String) {
+        self.bar.set_message(message);
+    }
+
+    /// Clear both bars; the job's bar keeps its position for the next track
+    pub fn finish(self) {
+        self.bar.finish_and_clear();
+        if let Some((multi, job_bar)) = self.job {
+            let _ = multi.clear();
+            multi.remove(&job_bar);
+        }
+    }
+}
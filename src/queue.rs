@@ -0,0 +1,322 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs;
+
+use crate::auth::config_dir;
+use crate::client::DeezerClient;
+use crate::color;
+use crate::config::OutputDirs;
+use crate::control::{self, ItemKind};
+use crate::download::{self, ArtistOptions, DownloadOptions, TracksFailed};
+use crate::tr;
+
+/// Commands of `queue`
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum QueueCommand {
+    /// Add Deezer track, playlist or artist URLs to the queue, each optionally followed by
+    /// "| quality | output folder"
+    Add {
+        #[arg(required = true)]
+        urls: Vec<String>,
+    },
+    /// Show the queued URLs
+    List,
+    /// Remove queued items by position (as shown by `queue list`) or URL
+    Remove {
+        #[arg(required = true)]
+        items: Vec<String>,
+    },
+    /// Download every queued item; items that fail or have failed tracks stay queued
+    Run,
+}
+
+/// A URL waiting in the persistent queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub url: String,
+    pub added_at: u64,
+    /// Same values as `--quality`, instead of the run's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<String>,
+    /// Base folder for this item, instead of the configured output folders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+}
+
+impl QueueEntry {
+    pub fn batch_entry(&self) -> BatchEntry {
+        BatchEntry {
+            url: self.url.clone(),
+            quality: self.quality.clone(),
+            output: self.output.clone(),
+        }
+    }
+}
+
+/// An item of a batch (a queued URL, a job file line): the URL, optionally followed by its own
+/// quality and output folder, as in `https://www.deezer.com/playlist/908622995 | flac | /mnt/archive`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchEntry {
+    pub url: String,
+    /// Same values as `--quality`
+    pub quality: Option<String>,
+    pub output: Option<PathBuf>,
+}
+
+impl BatchEntry {
+    /// Parse `URL [| quality [| output folder]]`; an empty field keeps the run's setting
+    pub fn parse(line: &str) -> Result<Self> {
+        let mut fields = line.split('|').map(str::trim);
+        let url = fields.next().unwrap_or_default().to_string();
+        let mut field = || fields.next().filter(|f| !f.is_empty());
+        let quality = field().map(str::to_string);
+        let output = field().map(PathBuf::from);
+        if let Some(extra) = fields.next() {
+            bail!("Expected URL | quality | output folder, found an extra field '{}'", extra);
+        }
+        if url.is_empty() {
+            bail!("No URL before '|'");
+        }
+        if let Some(quality) = &quality
+            && DownloadOptions::default().with_quality(quality).is_none()
+        {
+            bail!("Unknown quality '{}'", quality);
+        }
+        Ok(Self { url, quality, output })
+    }
+
+    /// `opts` and `outputs` with this entry's quality and output folder
+    pub fn apply(&self, opts: &DownloadOptions, outputs: &OutputDirs) -> (DownloadOptions, OutputDirs) {
+        let opts = self.quality.as_deref().and_then(|q| opts.with_quality(q)).unwrap_or_else(|| opts.clone());
+        let outputs = self.output.as_deref().map(OutputDirs::all).unwrap_or_else(|| outputs.clone());
+        (opts, outputs)
+    }
+}
+
+impl fmt::Display for BatchEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.url)?;
+        match (&self.quality, &self.output) {
+            (Some(quality), Some(output)) => write!(f, " | {} | {}", quality, output.display()),
+            (Some(quality), None) => write!(f, " | {}", quality),
+            (None, Some(output)) => write!(f, " | | {}", output.display()),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Location of the persistent queue
+pub fn queue_path() -> PathBuf {
+    config_dir().join("queue.json")
+}
+
+/// Load the queue, oldest first
+pub async fn load() -> Result<Vec<QueueEntry>> {
+    match fs::read_to_string(queue_path()).await {
+        Ok(content) => serde_json::from_str(&content).context("Failed to parse queue.json"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).context("Failed to read queue.json"),
+    }
+}
+
+/// Overwrite the queue (removing the file when empty). The new content is written next to it
+/// and moved into place, so a reader never sees a half-written queue.
+async fn save(entries: &[QueueEntry]) -> Result<()> {
+    let path = queue_path();
+    if entries.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path).await?;
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(config_dir()).await.context("Failed to create config dir")?;
+    download::write_file_atomic(&path, serde_json::to_string_pretty(entries)?.as_bytes())
+        .await
+        .context("Failed to write queue.json")
+}
+
+/// Exclusive hold on the queue file, released when dropped. Taken around each
+/// load-change-save, so `queue add`, `queue run` and a daemon never drop each other's changes.
+struct QueueLock {
+    _file: std::fs::File,
+}
+
+async fn lock() -> Result<QueueLock> {
+    fs::create_dir_all(config_dir()).await.context("Failed to create config dir")?;
+    let path = config_dir().join("queue.lock");
+    let file = tokio::task::spawn_blocking(move || {
+        let file = std::fs::OpenOptions::new().create(true).truncate(false).write(true).open(&path)?;
+        file.lock()?;
+        Ok::<_, std::io::Error>(file)
+    })
+    .await?
+    .context("Failed to lock the queue")?;
+    Ok(QueueLock { _file: file })
+}
+
+/// Run `queue add`, each URL optionally followed by `| quality | output folder`; URLs already
+/// queued for the same item are skipped
+pub async fn add(urls: &[String]) -> Result<()> {
+    let _lock = lock().await?;
+    let mut entries = load().await?;
+    let added_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    for line in urls {
+        let entry = match BatchEntry::parse(line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("{} {}", color::warn(), tr!("queue.invalid_entry", entry = line, error = e));
+                continue;
+            }
+        };
+        let url = &entry.url;
+        let Some(item) = control::parse_url(url) else {
+            eprintln!("{} {}", color::warn(), tr!("queue.invalid", url = url));
+            continue;
+        };
+        if entries.iter().any(|e| control::parse_url(&e.url).as_ref() == Some(&item)) {
+            println!("{} {}", color::skip(), tr!("queue.already", url = url));
+            continue;
+        }
+        println!("{} {}", color::ok(), tr!("queue.added", url = entry, position = entries.len() + 1));
+        entries.push(QueueEntry {
+            url: entry.url,
+            added_at,
+            quality: entry.quality,
+            output: entry.output,
+        });
+    }
+    save(&entries).await
+}
+
+/// Run `queue list`
+pub async fn list() -> Result<()> {
+    let entries = load().await?;
+    if entries.is_empty() {
+        println!("{}", tr!("queue.empty"));
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        println!("{:>3}. {}", i + 1, entry.batch_entry());
+    }
+    Ok(())
+}
+
+/// Run `queue remove`; positions refer to the list before any removal
+pub async fn remove(items: &[String]) -> Result<()> {
+    let _lock = lock().await?;
+    let entries = load().await?;
+    let mut removed = HashSet::new();
+    for item in items {
+        let matches: Vec<usize> = match item.parse::<usize>() {
+            Ok(position) => (position >= 1 && position <= entries.len())
+                .then_some(position - 1)
+                .into_iter()
+                .collect(),
+            Err(_) => {
+                let target = control::parse_url(item);
+                let same_item = |url: &str| target.is_some() && control::parse_url(url) == target;
+                (0..entries.len())
+                    .filter(|&i| entries[i].url == *item || same_item(&entries[i].url))
+                    .collect()
+            }
+        };
+        if matches.is_empty() {
+            eprintln!("{} {}", color::warn(), tr!("queue.not_found", item = item));
+        }
+        removed.extend(matches);
+    }
+
+    let mut kept = Vec::new();
+    for (i, entry) in entries.into_iter().enumerate() {
+        if removed.contains(&i) {
+            println!("{} {}", color::ok(), tr!("queue.removed", url = entry.url));
+        } else {
+            kept.push(entry);
+        }
+    }
+    save(&kept).await
+}
+
+/// The oldest entry whose URL is not in `tried`. The file is read each time, so
+/// items added while a queue is being worked through are picked up too.
+pub async fn next(tried: &HashSet<String>) -> Result<Option<QueueEntry>> {
+    Ok(load().await?.into_iter().find(|e| !tried.contains(&e.url)))
+}
+
+/// Download a track, playlist or artist into its configured output folder. Fails with
+/// [`TracksFailed`] when some of its tracks could not be downloaded.
+pub async fn download_item(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    outputs: &OutputDirs,
+    kind: ItemKind,
+    id: &str,
+) -> Result<()> {
+    let output_dir = outputs.for_kind(kind);
+    let (opts, reports) = download::collecting_reports(opts);
+    let opts = &opts;
+    match kind {
+        ItemKind::Track => download::download_single_track(api, id, opts, output_dir).await?,
+        ItemKind::Playlist => download::download_playlist(api, id, opts, output_dir).await?,
+        ItemKind::Artist => download::download_artist(api, id, opts, &ArtistOptions::default(), output_dir).await?,
+    }
+    let failed: usize = reports.lock().unwrap().iter().map(|r| r.failed).sum();
+    if failed > 0 {
+        return Err(TracksFailed(failed).into());
+    }
+    Ok(())
+}
+
+/// Download a queued entry, with its own quality and output folder if it has them, and take it
+/// off the queue once all its tracks are downloaded
+pub async fn download_entry(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    outputs: &OutputDirs,
+    entry: &QueueEntry,
+) -> Result<()> {
+    let (kind, id) = control::parse_url(&entry.url).context("Not a Deezer track, playlist or artist URL")?;
+    let (opts, outputs) = entry.batch_entry().apply(opts, outputs);
+    download_item(api, &opts, &outputs, kind, &id).await?;
+    let _lock = lock().await?;
+    let mut entries = load().await?;
+    entries.retain(|e| e.url != entry.url);
+    save(&entries).await
+}
+
+/// Run `queue run`
+pub async fn run(api: &impl DeezerClient, opts: &DownloadOptions, outputs: &OutputDirs) -> Result<()> {
+    let count = load().await?.len();
+    if count == 0 {
+        println!("{}", tr!("queue.empty"));
+        return Ok(());
+    }
+    println!("{}", tr!("queue.start", count = count));
+
+    let mut tried = HashSet::new();
+    let (mut done, mut failed) = (0, 0);
+    while let Some(entry) = next(&tried).await? {
+        tried.insert(entry.url.clone());
+        println!("\n{}", tr!("queue.item", url = entry.url));
+        match download_entry(api, opts, outputs, &entry).await {
+            Ok(()) => done += 1,
+            Err(e) => {
+                eprintln!("{} {}", color::err(), tr!("queue.item_failed", url = entry.url, error = e));
+                if download::stops_job(&e) {
+                    return Err(e);
+                }
+                failed += 1;
+            }
+        }
+    }
+
+    let left = load().await?.len();
+    println!("\n{}", tr!("queue.done", done = done, failed = failed, left = left));
+    Ok(())
+}
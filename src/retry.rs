@@ -0,0 +1,260 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+
+use crate::client::DeezerClient;
+use crate::auth::config_dir;
+use crate::color;
+use crate::download::{self, DownloadOptions, FailedDownload, JobSummary};
+use crate::error::DeezerError;
+use crate::tr;
+
+/// How often and how patiently transient failures are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Extra attempts after the first one
+    pub retries: u32,
+    /// Delay before the first retry; doubled for each following one
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            base_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Parse `--retry-delay` seconds, rejecting negative, infinite and NaN values
+pub fn parse_delay(input: &str) -> Result<Duration, String> {
+    let secs: f64 = input
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid delay '{}' (expected seconds, e.g. 1 or 0.5)", input))?;
+    Duration::try_from_secs_f64(secs)
+        .map_err(|_| format!("delay must be a finite number of seconds, at least 0 (got {})", input))
+}
+
+impl RetryPolicy {
+    /// Exponential backoff for the given retry (0-based) plus up to 50% jitter
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.base_delay.saturating_mul(1u32 << attempt.min(16));
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let jitter = backoff.mul_f64((nanos % 500) as f64 / 1000.0);
+        backoff + jitter
+    }
+
+    /// Run `op`, retrying transient errors with backoff
+    pub async fn run<T, E, F, Fut>(&self, what: &str, mut op: F) -> std::result::Result<T, E>
+    where
+        E: Retryable,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match op().await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.retries && e.is_retryable() => {
+                    let delay = self.delay(attempt);
+                    eprintln!(
+                        "  {} {}",
+                        color::warn(),
+                        tr!(
+                            "retry.wait",
+                            what = what,
+                            error = e,
+                            n = attempt + 1,
+                            max = self.retries,
+                            delay = format!("{:.1}", delay.as_secs_f64()),
+                        )
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Errors `RetryPolicy::run` can tell apart
+pub trait Retryable: std::fmt::Display {
+    fn is_retryable(&self) -> bool;
+}
+
+impl Retryable for DeezerError {
+    fn is_retryable(&self) -> bool {
+        DeezerError::is_retryable(self)
+    }
+}
+
+impl Retryable for anyhow::Error {
+    /// Errors from elsewhere are retried; a `DeezerError` decides for itself
+    /// (client errors from the CDN won't go away by retrying the same URL)
+    fn is_retryable(&self) -> bool {
+        DeezerError::find(self).is_none_or(DeezerError::is_retryable)
+    }
+}
+
+/// A failed track persisted for `retry-failed`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedEntry {
+    pub sng_id: String,
+    pub name: String,
+    pub output_dir: PathBuf,
+    pub error: String,
+}
+
+impl From<&FailedDownload> for FailedEntry {
+    fn from(f: &FailedDownload) -> Self {
+        Self {
+            sng_id: f.track.id_str(),
+            name: f.track.display_name(),
+            output_dir: f.output_dir.clone(),
+            error: f.error.clone(),
+        }
+    }
+}
+
+/// Location of the persisted failure list
+pub fn failed_path() -> PathBuf {
+    config_dir().join("failed.json")
+}
+
+/// Load persisted failures
+pub async fn load() -> Result<Vec<FailedEntry>> {
+    match fs::read_to_string(failed_path()).await {
+        Ok(content) => serde_json::from_str(&content).context("Failed to parse failed.json"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).context("Failed to read failed.json"),
+    }
+}
+
+/// Overwrite the persisted failure list (removing the file when empty)
+async fn save(entries: &[FailedEntry]) -> Result<()> {
+    let path = failed_path();
+    if entries.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path).await?;
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(config_dir()).await.context("Failed to create config dir")?;
+    download::write_file_atomic(&path, serde_json::to_string_pretty(entries)?.as_bytes())
+        .await
+        .context("Failed to write failed.json")?;
+    Ok(())
+}
+
+/// Add failures to the persisted list, replacing older entries for the same track and folder
+pub async fn persist(failures: &[FailedDownload]) -> Result<()> {
+    let mut entries = load().await?;
+    for f in failures {
+        let entry = FailedEntry::from(f);
+        entries.retain(|e| !(e.sng_id == entry.sng_id && e.output_dir == entry.output_dir));
+        entries.push(entry);
+    }
+    save(&entries).await
+}
+
+/// Retry every failed track of a job once, then persist whatever still fails
+pub async fn retry_pass(api: &impl DeezerClient, opts: &DownloadOptions, summary: &mut JobSummary) {
+    if summary.failures.is_empty() {
+        return;
+    }
+
+    // Rights restrictions and the like fail the same way again: keep them as they are
+    let (failures, permanent): (Vec<_>, Vec<_>) =
+        std::mem::take(&mut summary.failures).into_iter().partition(|f| f.retryable);
+    summary.failures = permanent;
+    if !failures.is_empty() {
+        println!("\n{}\n", tr!("retry.pass", count = failures.len()));
+    }
+
+    for f in failures {
+        println!("{}", f.track.display_name());
+        summary.failed -= 1;
+        match download::download_track(api, &f.track, opts, &f.output_dir, true).await {
+            Ok(done) => {
+                println!("  {} {}", color::ok(), tr!("job.ok"));
+                summary.record_success(&f.track, &done);
+            }
+            Err(e) => {
+                eprintln!("  {} {}", color::err(), tr!("retry.failed_again", error = e));
+                summary.record_error(&f.track, &f.output_dir, &e);
+            }
+        }
+    }
+
+    if !summary.failures.is_empty() {
+        match persist(&summary.failures).await {
+            Ok(()) => println!(
+                "\n{}",
+                tr!("retry.saved", count = summary.failures.len(), path = failed_path().display())
+            ),
+            Err(e) => eprintln!("\n{} {}", color::warn(), tr!("retry.save_failed", error = e)),
+        }
+    }
+}
+
+/// Retry the tracks persisted by earlier runs
+pub async fn retry_failed(api: &impl DeezerClient, opts: &DownloadOptions) -> Result<()> {
+    let entries = load().await?;
+    if entries.is_empty() {
+        println!("{}", tr!("retry.none"));
+        return Ok(());
+    }
+
+    println!("{}\n", tr!("retry.start", count = entries.len()));
+
+    let mut summary = JobSummary::default();
+    let mut missing = Vec::new();
+
+    for batch in entries.chunks(50) {
+        let ids: Vec<String> = batch.iter().map(|e| e.sng_id.clone()).collect();
+        let tracks = api.get_tracks_by_ids(&ids).await?;
+
+        for entry in batch {
+            println!("{}", entry.name);
+            let Some(track) = tracks.iter().find(|t| t.id_str() == entry.sng_id) else {
+                eprintln!("  {} {}", color::err(), tr!("retry.unavailable"));
+                missing.push(entry.clone());
+                continue;
+            };
+
+            match download::download_track(api, track, opts, &entry.output_dir, true).await {
+                Ok(done) => {
+                    println!("  {} {}", color::ok(), tr!("job.ok"));
+                    summary.record_success(track, &done);
+                }
+                Err(e) => {
+                    eprintln!("  {} {}", color::err(), tr!("job.failed", error = e));
+                    summary.record_error(track, &entry.output_dir, &e);
+                    if download::stops_job(&e) {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut remaining = missing;
+    remaining.extend(summary.failures.iter().map(FailedEntry::from));
+    save(&remaining).await?;
+
+    println!(
+        "\n{}",
+        tr!("retry.done", downloaded = summary.downloaded, failed = remaining.len())
+    );
+    summary.print_details(opts);
+    Ok(())
+}
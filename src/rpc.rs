@@ -0,0 +1,284 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::api::DeezerApi;
+use crate::auth;
+use crate::color;
+use crate::config::OutputDirs;
+use crate::control::{self, ItemKind};
+use crate::download::{self, ArtistOptions, DownloadOptions};
+use crate::events::EventSink;
+use crate::tr;
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// `login` was given an ARL Deezer does not accept
+const LOGIN_FAILED: i64 = -32001;
+/// `queue` before a successful login
+const NOT_LOGGED_IN: i64 = -32002;
+
+/// One line of input; requests without `id` are notifications and get no reply
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginParams {
+    arl: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueueParams {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CancelParams {
+    job: u64,
+}
+
+/// A queued URL
+#[derive(Debug, Clone)]
+struct Job {
+    id: u64,
+    url: String,
+    kind: ItemKind,
+    item_id: String,
+}
+
+impl Job {
+    fn to_json(&self) -> Value {
+        json!({ "job": self.id, "url": self.url })
+    }
+}
+
+/// Writes protocol messages, one JSON object per line
+#[derive(Clone)]
+struct Output(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl Output {
+    fn send(&self, message: Value) {
+        let mut out = self.0.lock().unwrap();
+        let _ = out.write_all(format!("{}\n", message).as_bytes());
+        let _ = out.flush();
+    }
+
+    fn reply(&self, id: Option<Value>, result: std::result::Result<Value, (i64, String)>) {
+        let Some(id) = id else {
+            return;
+        };
+        self.send(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => {
+                json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+            }
+        });
+    }
+
+    /// `event` notification; `params.event` names the event
+    fn event(&self, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": "event", "params": params }));
+    }
+}
+
+/// Keep the real stdout for the protocol and send console output to stderr
+#[cfg(unix)]
+fn take_stdout() -> Result<Box<dyn Write + Send>> {
+    use std::os::fd::AsFd;
+
+    std::io::stdout().flush()?;
+    let protocol = std::io::stdout().as_fd().try_clone_to_owned()?;
+    // SAFETY: dup2 on the process's own standard descriptors
+    if unsafe { libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) } < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(Box::new(std::fs::File::from(protocol)))
+}
+
+/// Console output stays on stdout here: frontends must skip lines that are not JSON
+#[cfg(not(unix))]
+fn take_stdout() -> Result<Box<dyn Write + Send>> {
+    Ok(Box::new(std::io::stdout()))
+}
+
+type JobFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + 'a>>;
+
+/// Run `rpc`: read JSON-RPC requests from stdin, one per line, and download queued
+/// URLs one after another. Exits once stdin is closed and the queue is empty.
+pub async fn serve(api: &DeezerApi, opts: &DownloadOptions, outputs: &OutputDirs) -> Result<()> {
+    let out = Output(Arc::new(Mutex::new(take_stdout()?)));
+    let logged_in = auth::login_stored(api).await;
+    out.event(json!({ "event": "ready", "logged_in": logged_in, "user": user_name(api).await }));
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut input_open = true;
+    let mut queue: VecDeque<Job> = VecDeque::new();
+    let mut running: Option<(Job, JobFuture)> = None;
+    let mut next_id = 1;
+
+    loop {
+        if running.is_none()
+            && let Some(job) = queue.pop_front()
+        {
+            out.event(json!({ "event": "job_started", "job": job.id, "url": job.url }));
+            let future = start_job(api, opts, &job, outputs, &out);
+            running = Some((job, future));
+        }
+        if running.is_none() && !input_open {
+            return Ok(());
+        }
+
+        tokio::select! {
+            line = lines.next_line(), if input_open => {
+                let line = match line {
+                    Ok(Some(line)) => line,
+                    Ok(None) => {
+                        input_open = false;
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("{} {}", color::warn(), tr!("rpc.stdin_failed", error = e));
+                        input_open = false;
+                        continue;
+                    }
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let request: Request = match serde_json::from_str(&line) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        out.reply(Some(Value::Null), Err((PARSE_ERROR, e.to_string())));
+                        continue;
+                    }
+                };
+                let result = match request.method.as_str() {
+                    "login" => login(api, request.params).await,
+                    "status" => Ok(status(api, running.as_ref().map(|(job, _)| job), &queue).await),
+                    "queue" if api.current_user.lock().await.is_none() => {
+                        Err((NOT_LOGGED_IN, "Not logged in, call login first".to_string()))
+                    }
+                    "queue" => params::<QueueParams>(request.params).and_then(|p| {
+                        let (kind, item_id) = control::parse_url(&p.url).ok_or_else(|| {
+                            (INVALID_PARAMS, format!("Not a Deezer track, playlist or artist URL: {}", p.url))
+                        })?;
+                        let job = Job { id: next_id, url: p.url, kind, item_id };
+                        next_id += 1;
+                        queue.push_back(job);
+                        Ok(json!({ "job": next_id - 1, "position": queue.len() }))
+                    }),
+                    "cancel" => params::<CancelParams>(request.params).map(|p| {
+                        let cancelled = if running.as_ref().is_some_and(|(job, _)| job.id == p.job) {
+                            // Dropping the future stops the download at its next await
+                            running = None;
+                            true
+                        } else if let Some(pos) = queue.iter().position(|job| job.id == p.job) {
+                            queue.remove(pos);
+                            true
+                        } else {
+                            false
+                        };
+                        if cancelled {
+                            out.event(json!({ "event": "job_cancelled", "job": p.job }));
+                        }
+                        json!({ "cancelled": cancelled })
+                    }),
+                    "shutdown" => {
+                        out.reply(request.id, Ok(Value::Null));
+                        return Ok(());
+                    }
+                    other => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", other))),
+                };
+                out.reply(request.id, result);
+            }
+            result = async { running.as_mut().unwrap().1.as_mut().await }, if running.is_some() => {
+                let (job, _) = running.take().unwrap();
+                match result {
+                    Ok(()) => out.event(json!({ "event": "job_completed", "job": job.id })),
+                    Err(e) => {
+                        out.event(json!({ "event": "job_failed", "job": job.id, "error": e.to_string() }));
+                        if download::stops_job(&e) {
+                            return Err(e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Download one queued item, forwarding its progress events tagged with the job ID
+fn start_job<'a>(
+    api: &'a DeezerApi,
+    opts: &DownloadOptions,
+    job: &Job,
+    outputs: &'a OutputDirs,
+    out: &Output,
+) -> JobFuture<'a> {
+    let events = out.clone();
+    let job_id = job.id;
+    let opts = DownloadOptions {
+        events: Some(EventSink::new(move |event| {
+            let mut params = serde_json::to_value(&event).unwrap_or_default();
+            params["job"] = job_id.into();
+            events.event(params);
+        })),
+        ..opts.clone()
+    };
+    let (kind, id) = (job.kind, job.item_id.clone());
+    let output_dir = outputs.for_kind(kind);
+    Box::pin(async move {
+        match kind {
+            ItemKind::Track => download::download_single_track(api, &id, &opts, output_dir).await,
+            ItemKind::Playlist => download::download_playlist(api, &id, &opts, output_dir).await,
+            ItemKind::Artist => {
+                download::download_artist(api, &id, &opts, &ArtistOptions::default(), output_dir).await
+            }
+        }
+    })
+}
+
+fn params<T: serde::de::DeserializeOwned>(params: Value) -> std::result::Result<T, (i64, String)> {
+    serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, e.to_string()))
+}
+
+async fn user_name(api: &DeezerApi) -> Option<String> {
+    api.current_user.lock().await.as_ref().map(|u| u.name.clone())
+}
+
+/// `login`: check the ARL and store it like the interactive prompt does
+async fn login(api: &DeezerApi, raw: Value) -> std::result::Result<Value, (i64, String)> {
+    let p: LoginParams = params(raw)?;
+    match api.login_via_arl(&p.arl).await {
+        Ok(true) => {
+            if let Err(e) = auth::save_arl(&p.arl).await {
+                eprintln!("{} {}", color::warn(), tr!("rpc.arl_not_saved", error = e));
+            }
+            Ok(json!({ "user": user_name(api).await }))
+        }
+        Ok(false) => Err((LOGIN_FAILED, "Invalid ARL".to_string())),
+        Err(e) => Err((LOGIN_FAILED, e.to_string())),
+    }
+}
+
+async fn status(api: &DeezerApi, running: Option<&Job>, queue: &VecDeque<Job>) -> Value {
+    json!({
+        "user": user_name(api).await,
+        "running": running.map(Job::to_json),
+        "queued": queue.iter().map(Job::to_json).collect::<Vec<_>>(),
+    })
+}
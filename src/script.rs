@@ -0,0 +1,123 @@
+use anyhow::{Result, anyhow, bail};
+use rhai::{AST, Dynamic, Engine, Map, Scope};
+use std::path::{Path, PathBuf};
+
+use crate::download::sanitize_filename;
+use crate::models::{GwTrack, TrackFormat};
+
+/// Name of the function a `--script` file must define
+const FILTER_FN: &str = "filter";
+
+/// What a filter script decided for one track
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptDecision {
+    Accept,
+    Skip,
+    /// Accept, writing to this path (relative to the job folder, without extension)
+    Path(PathBuf),
+}
+
+/// Returned for tracks the filter script skipped
+#[derive(Debug)]
+pub struct ScriptSkipped;
+
+impl std::fmt::Display for ScriptSkipped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Skipped by the filter script")
+    }
+}
+
+impl std::error::Error for ScriptSkipped {}
+
+/// A compiled Rhai script defining `fn filter(track)`
+pub struct FilterScript {
+    path: PathBuf,
+    engine: Engine,
+    ast: AST,
+}
+
+impl std::fmt::Debug for FilterScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilterScript").field("path", &self.path).finish()
+    }
+}
+
+impl FilterScript {
+    /// Compile `path`, failing if it does not define `filter(track)`
+    pub fn load(path: &Path) -> Result<Self> {
+        let engine = Engine::new();
+        let ast = engine
+            .compile_file(path.to_path_buf())
+            .map_err(|e| anyhow!("Failed to load script {}: {}", path.display(), e))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == FILTER_FN && f.params.len() == 1)
+        {
+            bail!("Script {} does not define fn {}(track)", path.display(), FILTER_FN);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            engine,
+            ast,
+        })
+    }
+
+    /// Run `filter(track)`. It returns `true` (or nothing) to download the track,
+    /// `false` to skip it, or a string to download it to that path.
+    pub fn decide(&self, track: &GwTrack, format: TrackFormat) -> Result<ScriptDecision> {
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, FILTER_FN, (track_map(track, format),))
+            .map_err(|e| anyhow!("{}: {}", self.path.display(), e))?;
+
+        if result.is_unit() {
+            return Ok(ScriptDecision::Accept);
+        }
+        if let Ok(accept) = result.as_bool() {
+            return Ok(if accept {
+                ScriptDecision::Accept
+            } else {
+                ScriptDecision::Skip
+            });
+        }
+        if result.is_string() {
+            let path = relative_path(&result.into_string().unwrap_or_default());
+            if path.as_os_str().is_empty() {
+                bail!("{}: {}() returned an empty path", self.path.display(), FILTER_FN);
+            }
+            return Ok(ScriptDecision::Path(path));
+        }
+        bail!(
+            "{}: {}() must return a bool or a path string, not {}",
+            self.path.display(),
+            FILTER_FN,
+            result.type_name()
+        )
+    }
+}
+
+/// Track metadata passed to the script
+fn track_map(track: &GwTrack, format: TrackFormat) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), track.id_str().into());
+    map.insert("title".into(), track.title().into());
+    map.insert("artist".into(), track.artist().into());
+    map.insert("album".into(), track.album().into());
+    map.insert("album_id".into(), track.album_id_str().into());
+    map.insert("isrc".into(), track.isrc.clone().unwrap_or_default().into());
+    map.insert("track_number".into(), (track.track_no() as i64).into());
+    map.insert("disc_number".into(), (track.disc_no() as i64).into());
+    map.insert("duration".into(), (track.duration_secs() as i64).into());
+    map.insert("explicit".into(), track.is_explicit().into());
+    map.insert("format".into(), format.api_name().into());
+    map
+}
+
+/// Sanitized components of a script-provided path; `..` and roots are dropped
+fn relative_path(raw: &str) -> PathBuf {
+    raw.split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .map(sanitize_filename)
+        .filter(|part| !part.is_empty())
+        .collect()
+}
@@ -0,0 +1,306 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::auth::config_dir;
+use crate::client::DeezerClient;
+use crate::color;
+use crate::download::{self, DownloadOptions, JobRun, TrackJob, URL_BATCH_SIZE, sanitize_filename};
+use crate::models::TrackFormat;
+use crate::notify::{self, JobReport};
+use crate::progress::JobProgress;
+use crate::template::TrackPositions;
+use crate::tr;
+
+/// What a playlist, favorites or artist job is going to download, written
+/// before its first track so that `resume` can finish it after a crash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionPlan {
+    /// `playlist`, `favorites` or `artist`, as in the job's report
+    pub kind: String,
+    /// Deezer ID of the job, `<artist ID>-top` for `artist --top`, empty for favorites
+    pub id: String,
+    pub name: String,
+    /// Folder reported when the job finishes
+    pub dir: PathBuf,
+    /// Requested format (API name) and filename template, reused by `resume`
+    pub format: String,
+    pub template: String,
+    /// Each track's `output_dir` is an album folder that gets the cover and NFO
+    #[serde(default)]
+    pub album_folders: bool,
+    pub started_at: u64,
+    pub tracks: Vec<SessionTrack>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTrack {
+    pub sng_id: String,
+    pub output_dir: PathBuf,
+}
+
+impl SessionTrack {
+    pub fn new(sng_id: String, output_dir: &Path) -> Self {
+        Self {
+            sng_id,
+            output_dir: output_dir.to_path_buf(),
+        }
+    }
+}
+
+impl SessionPlan {
+    pub fn new(
+        kind: &str,
+        id: &str,
+        name: &str,
+        dir: &Path,
+        opts: &DownloadOptions,
+        tracks: Vec<SessionTrack>,
+    ) -> Self {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            name: name.to_string(),
+            dir: dir.to_path_buf(),
+            format: opts.format.api_name().to_string(),
+            template: opts.template.clone(),
+            album_folders: false,
+            started_at,
+            tracks,
+        }
+    }
+
+    fn file_name(&self) -> String {
+        match self.id.as_str() {
+            "" => format!("{}.jsonl", self.kind),
+            id => format!("{}-{}.jsonl", self.kind, sanitize_filename(id)),
+        }
+    }
+}
+
+/// Line appended to the session file when a planned track is done (downloaded or already on disk)
+#[derive(Serialize, Deserialize)]
+struct DoneLine {
+    done: usize,
+}
+
+/// Folder of the session files, one per unfinished job
+pub fn sessions_dir() -> PathBuf {
+    config_dir().join("sessions")
+}
+
+/// Records the progress of a running job; the file is removed once the job completes.
+/// The default one records nothing, for jobs that are not resumed.
+#[derive(Default)]
+pub struct Session {
+    path: Option<PathBuf>,
+}
+
+impl Session {
+    /// Write the plan of a new job. Failing to do so only warns: the job runs, but cannot be resumed.
+    pub async fn start(plan: &SessionPlan) -> Self {
+        let path = sessions_dir().join(plan.file_name());
+        let written = async {
+            fs::create_dir_all(sessions_dir()).await?;
+            let mut line = serde_json::to_string(plan)?;
+            line.push('\n');
+            fs::write(&path, line).await?;
+            anyhow::Ok(())
+        };
+        match written.await {
+            Ok(()) => Self { path: Some(path) },
+            Err(e) => {
+                eprintln!("  {} {}", color::warn(), tr!("session.write_failed", error = e));
+                Self { path: None }
+            }
+        }
+    }
+
+    /// Mark the track at `index` of the plan as done
+    pub async fn done(&mut self, index: usize) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let appended = async {
+            let mut line = serde_json::to_string(&DoneLine { done: index })?;
+            line.push('\n');
+            let mut file = fs::OpenOptions::new().append(true).open(path).await?;
+            file.write_all(line.as_bytes()).await?;
+            anyhow::Ok(())
+        };
+        if let Err(e) = appended.await {
+            eprintln!("  {} {}", color::warn(), tr!("session.write_failed", error = e));
+            self.path = None;
+        }
+    }
+
+    /// The job completed: nothing is left to resume
+    pub async fn finish(self) {
+        if let Some(path) = self.path {
+            let _ = fs::remove_file(path).await;
+        }
+    }
+}
+
+/// An unfinished job read back from its session file
+#[derive(Debug, Clone)]
+pub struct SavedSession {
+    pub path: PathBuf,
+    pub plan: SessionPlan,
+    /// Indexes into `plan.tracks` that are done
+    pub done: HashSet<usize>,
+}
+
+impl SavedSession {
+    /// Indexes of the tracks still to download, in plan order
+    pub fn pending(&self) -> Vec<usize> {
+        (0..self.plan.tracks.len()).filter(|i| !self.done.contains(i)).collect()
+    }
+}
+
+/// Read one session file
+pub async fn read_session(path: &Path) -> Result<SavedSession> {
+    let content = fs::read_to_string(path).await?;
+    let mut lines = content.lines();
+    let plan: SessionPlan = serde_json::from_str(lines.next().unwrap_or_default()).context("Invalid plan")?;
+    // A line cut short by a crash is ignored: that track is simply downloaded again
+    let done = lines
+        .filter_map(|line| serde_json::from_str::<DoneLine>(line).ok())
+        .map(|line| line.done)
+        .collect();
+    Ok(SavedSession {
+        path: path.to_path_buf(),
+        plan,
+        done,
+    })
+}
+
+/// Unfinished jobs, oldest first. Unreadable session files are skipped with a warning.
+pub async fn load_all() -> Result<Vec<SavedSession>> {
+    let mut entries = match fs::read_dir(sessions_dir()).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("Failed to read the sessions folder"),
+    };
+    let mut sessions = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "jsonl") {
+            continue;
+        }
+        match read_session(&path).await {
+            Ok(session) => sessions.push(session),
+            Err(e) => eprintln!("{} {}", color::warn(), tr!("session.unreadable", path = path.display(), error = e)),
+        }
+    }
+    sessions.sort_by_key(|s| s.plan.started_at);
+    Ok(sessions)
+}
+
+/// Run `resume --list`
+pub async fn list() -> Result<()> {
+    let sessions = load_all().await?;
+    if sessions.is_empty() {
+        println!("{}", tr!("session.none"));
+    }
+    for session in &sessions {
+        let plan = &session.plan;
+        let left = session.pending().len();
+        println!(
+            "{}",
+            tr!("session.entry", kind = plan.kind, name = plan.name, count = left, total = plan.tracks.len())
+        );
+    }
+    Ok(())
+}
+
+/// Run `resume`: finish every interrupted job, oldest first
+pub async fn resume(api: &impl DeezerClient, opts: &DownloadOptions) -> Result<()> {
+    let sessions = load_all().await?;
+    if sessions.is_empty() {
+        println!("{}", tr!("session.none"));
+        return Ok(());
+    }
+    for session in sessions {
+        resume_session(api, opts, session).await?;
+        println!();
+    }
+    Ok(())
+}
+
+/// Download the tracks of a session that are not done yet, with the format and
+/// template the job was started with
+async fn resume_session(api: &impl DeezerClient, opts: &DownloadOptions, saved: SavedSession) -> Result<()> {
+    let plan = &saved.plan;
+    let pending = saved.pending();
+    println!(
+        "{}\n",
+        tr!("session.resuming", kind = plan.kind, name = plan.name, count = pending.len(), total = plan.tracks.len())
+    );
+
+    let format = TrackFormat::from_api_name(&plan.format).unwrap_or(opts.format);
+    let progress = JobProgress::new(pending.len(), format);
+    // Only artist discographies are not numbered
+    let numbered = plan.kind != "artist" || plan.id.ends_with("-top");
+    let opts = &DownloadOptions {
+        format,
+        template: plan.template.clone(),
+        positions: if numbered {
+            TrackPositions::new(plan.tracks.iter().map(|t| t.sng_id.clone()))
+        } else {
+            TrackPositions::default()
+        },
+        ..opts.with_job_progress(&progress)
+    };
+    let session = Session {
+        path: Some(saved.path.clone()),
+    };
+    let mut run = JobRun::new(opts, session, pending.len());
+    run.album_folders = plan.album_folders;
+
+    for batch in pending.chunks(URL_BATCH_SIZE) {
+        let ids: Vec<String> = batch.iter().map(|&i| plan.tracks[i].sng_id.clone()).collect();
+        let tracks = api.get_tracks_by_ids(&ids).await?;
+        progress.add_tracks(&tracks, batch.len());
+
+        let mut jobs = Vec::with_capacity(batch.len());
+        for &i in batch {
+            let entry = &plan.tracks[i];
+            match tracks.iter().find(|t| t.id_str() == entry.sng_id) {
+                Some(track) => {
+                    let job = TrackJob { track: track.clone(), slot: Some(i) };
+                    jobs.push((entry.output_dir.as_path(), job));
+                }
+                None => {
+                    eprintln!("{} {}", color::err(), tr!("retry.unavailable"));
+                    run.summary.failed += 1;
+                    run.shown += 1;
+                }
+            }
+        }
+        // A discography's tracks go to one folder per album
+        while let Some(&(dir, _)) = jobs.first() {
+            let len = jobs.iter().take_while(|(d, _)| *d == dir).count();
+            let mut group: Vec<TrackJob> = jobs.drain(..len).map(|(_, job)| job).collect();
+            download::run_track_jobs(api, opts, &mut run, &mut group, dir).await?;
+        }
+    }
+
+    let summary = run.finish(api, opts).await;
+
+    println!(
+        "\n{}",
+        tr!("session.complete", downloaded = summary.downloaded, failed = summary.failed)
+    );
+    summary.print_details(opts);
+    notify::job_finished(api, opts, &JobReport::new(&plan.kind, &plan.name, &plan.dir, &summary)).await;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+use tokio::sync::Notify;
+
+use crate::color;
+use crate::error::DeezerError;
+use crate::pause;
+use crate::session;
+use crate::tr;
+
+/// Set by the first Ctrl+C
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Tracks being downloaded right now
+static BUSY: AtomicUsize = AtomicUsize::new(0);
+/// Files being written, deleted if the process has to stop before they are complete
+static PARTIAL: LazyLock<Mutex<HashSet<PathBuf>>> = LazyLock::new(Default::default);
+static SIGNALLED: Notify = Notify::const_new();
+
+/// Handle Ctrl+C: the track being downloaded stops after its current chunk, and the job
+/// fails with [`DeezerError::Interrupted`] once it is cleaned up; when nothing is downloading,
+/// or on a second Ctrl+C, the process exits right away
+pub fn install() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if REQUESTED.swap(true, Ordering::SeqCst) || BUSY.load(Ordering::SeqCst) == 0 {
+                exit_interrupted();
+            }
+            eprintln!("\n{} {}", color::warn(), tr!("shutdown.stopping"));
+            SIGNALLED.notify_waiters();
+        }
+    });
+}
+
+/// Whether Ctrl+C was pressed
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Resolves once Ctrl+C is pressed
+pub async fn signalled() {
+    let signalled = SIGNALLED.notified();
+    tokio::pin!(signalled);
+    // Registered before checking, so a signal in between is not missed
+    signalled.as_mut().enable();
+    if !requested() {
+        signalled.await;
+    }
+}
+
+/// Delete incomplete files, point at `resume` and exit with the usual SIGINT status
+pub fn exit_interrupted() -> ! {
+    pause::restore_terminal();
+    for path in PARTIAL.lock().unwrap().drain() {
+        let _ = std::fs::remove_file(path);
+    }
+    eprintln!("\n{}", tr!("shutdown.stopped"));
+    let has_sessions = std::fs::read_dir(session::sessions_dir()).is_ok_and(|mut dir| dir.next().is_some());
+    if has_sessions {
+        eprintln!("{}", tr!("shutdown.resume_hint"));
+    }
+    std::process::exit(130);
+}
+
+/// Held while a track downloads so that Ctrl+C waits for it
+pub struct Busy(());
+
+impl Busy {
+    pub fn start() -> Self {
+        BUSY.fetch_add(1, Ordering::SeqCst);
+        Self(())
+    }
+
+    /// The track is done; fails with [`DeezerError::Interrupted`] if Ctrl+C was pressed
+    /// meanwhile, so that the job stops and `main` exits
+    pub fn finish(self) -> Result<(), DeezerError> {
+        drop(self);
+        if requested() {
+            return Err(DeezerError::Interrupted);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        BUSY.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A file being written. Unless marked [`complete`](Self::complete), it is deleted
+/// when dropped after Ctrl+C, or when the process is stopped while it is held.
+pub struct PartialFile {
+    path: PathBuf,
+    complete: bool,
+}
+
+impl PartialFile {
+    pub fn new(path: &Path) -> Self {
+        PARTIAL.lock().unwrap().insert(path.to_path_buf());
+        Self {
+            path: path.to_path_buf(),
+            complete: false,
+        }
+    }
+
+    pub fn complete(mut self) {
+        self.complete = true;
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        PARTIAL.lock().unwrap().remove(&self.path);
+        if !self.complete && requested() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::client::DeezerClient;
+use crate::color;
+use crate::download::{self, DownloadOptions, JobRun, TrackJob};
+use crate::history;
+use crate::models::GwTrack;
+use crate::notify::{self, JobReport};
+use crate::progress::JobProgress;
+use crate::session::{self, Session};
+use crate::template::TrackPositions;
+use crate::tr;
+
+/// Targets of `sync`
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum SyncTarget {
+    /// Mirror a playlist: download new tracks and rewrite its .m3u8
+    Playlist {
+        /// Deezer playlist URL or playlist ID
+        url: String,
+        #[command(flatten)]
+        prune: PruneArgs,
+    },
+    /// Mirror your liked songs: download new likes and rewrite Favorites.m3u8
+    Favorites {
+        #[command(flatten)]
+        prune: PruneArgs,
+    },
+}
+
+/// What to do with local files of tracks that are no longer listed
+#[derive(Debug, Clone, clap::Args)]
+pub struct PruneArgs {
+    /// Delete local files of tracks that were removed from the list
+    #[arg(long)]
+    pub prune: bool,
+    /// With --prune, move the files into this folder instead of deleting them
+    #[arg(long, value_name = "DIR", requires = "prune")]
+    pub quarantine: Option<PathBuf>,
+}
+
+/// Bring a playlist folder up to date with the playlist on Deezer
+pub async fn sync_playlist(
+    api: &impl DeezerClient,
+    playlist_id: &str,
+    opts: &DownloadOptions,
+    output_dir: &Path,
+    prune: &PruneArgs,
+) -> Result<()> {
+    let info = api.get_playlist_info(playlist_id).await?;
+    let playlist_name = info.data.display_name();
+    let playlist_name = playlist_name.as_str();
+    let playlist_dir = if opts.job_folders {
+        output_dir.join(opts.file_name(playlist_name))
+    } else {
+        output_dir.to_path_buf()
+    };
+    println!("{}\n", tr!("sync.playlist", name = playlist_name));
+
+    let tracks = api.get_playlist_tracks(playlist_id).await?;
+    let m3u_path = playlist_dir.join(format!("{}.m3u8", opts.file_name(playlist_name)));
+    sync_tracks(api, &tracks, opts, playlist_name, &playlist_dir, &m3u_path, prune_args(prune, opts)).await
+}
+
+/// Bring the Favorites folder up to date with your liked songs
+pub async fn sync_favorites(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    output_dir: &Path,
+    prune: &PruneArgs,
+) -> Result<()> {
+    println!("{}\n", tr!("sync.favorites"));
+
+    let ids = api.get_favorite_track_ids().await?;
+    let mut tracks = Vec::with_capacity(ids.len());
+    for batch in ids.chunks(50) {
+        tracks.extend(api.get_tracks_by_ids(batch).await?);
+    }
+
+    let favorites_dir = if opts.job_folders {
+        output_dir.join("Favorites")
+    } else {
+        output_dir.to_path_buf()
+    };
+    let m3u_path = favorites_dir.join("Favorites.m3u8");
+    sync_tracks(api, &tracks, opts, "Favorites", &favorites_dir, &m3u_path, prune_args(prune, opts)).await
+}
+
+/// `--prune` only applies when the list has its own folder; with `--layout`
+/// the files may be shared with other downloads
+fn prune_args<'a>(prune: &'a PruneArgs, opts: &DownloadOptions) -> Option<&'a PruneArgs> {
+    if !prune.prune {
+        return None;
+    }
+    if !opts.job_folders {
+        eprintln!("{} {}\n", color::warn(), tr!("sync.prune_ignored"));
+        return None;
+    }
+    Some(prune)
+}
+
+/// Download the tracks that are not on disk yet, rewrite the .m3u8 in
+/// playlist order, and handle files whose tracks are no longer listed
+async fn sync_tracks(
+    api: &impl DeezerClient,
+    tracks: &[GwTrack],
+    opts: &DownloadOptions,
+    name: &str,
+    dir: &Path,
+    m3u_path: &Path,
+    prune: Option<&PruneArgs>,
+) -> Result<()> {
+    // History stores canonical paths: a relative or symlinked folder would match none of them.
+    // The folder does not exist before the first sync, nothing is on disk then.
+    let canonical = tokio::fs::canonicalize(dir).await.unwrap_or_else(|_| dir.to_path_buf());
+    let m3u_path = &canonical.join(m3u_path.strip_prefix(dir).unwrap_or(m3u_path));
+    let dir = canonical.as_path();
+    let LocalList { on_disk, previous } = local_list(dir, m3u_path).await?;
+
+    let missing: Vec<usize> = (0..tracks.len())
+        .filter(|&i| !on_disk.contains_key(&tracks[i].id_str()))
+        .collect();
+    println!(
+        "{}\n",
+        tr!(
+            "sync.counts",
+            total = tracks.len(),
+            present = tracks.len() - missing.len(),
+            missing = missing.len(),
+        )
+    );
+
+    let mut paths: Vec<Option<PathBuf>> = tracks.iter().map(|t| on_disk.get(&t.id_str()).cloned()).collect();
+    let progress = JobProgress::for_tracks(missing.iter().map(|&i| &tracks[i]), opts.format);
+    let opts = &DownloadOptions {
+        positions: TrackPositions::new(tracks.iter().map(GwTrack::id_str)),
+        ..opts.with_job_progress(&progress)
+    };
+    let mut run = JobRun::new(opts, Session::default(), missing.len());
+    let mut jobs: Vec<TrackJob> = missing
+        .iter()
+        .map(|&i| TrackJob { track: tracks[i].clone(), slot: None })
+        .collect();
+    let downloaded = download::run_track_jobs(api, opts, &mut run, &mut jobs, dir).await?;
+    for (&i, path) in missing.iter().zip(downloaded) {
+        if path.is_some() {
+            paths[i] = path;
+        }
+    }
+    let summary = run.finish(api, opts).await;
+
+    let listed: Vec<(&GwTrack, &PathBuf)> = tracks
+        .iter()
+        .zip(&paths)
+        .filter_map(|(t, p)| Some((t, p.as_ref()?)))
+        .collect();
+    write_m3u(m3u_path, &listed).await;
+
+    let current: HashSet<&PathBuf> = listed.iter().map(|(_, p)| *p).collect();
+    let removed = unlisted(previous, &current, dir);
+    if !removed.is_empty() {
+        match prune {
+            Some(prune) => {
+                println!("\n{}", tr!("sync.removing", count = removed.len()));
+                for path in &removed {
+                    match remove_track(path, dir, prune.quarantine.as_deref()).await {
+                        Ok(()) => println!("  - {}", path.display()),
+                        Err(e) => {
+                            let message = tr!("sync.remove_failed", path = path.display(), error = e);
+                            eprintln!("  {} {}", color::warn(), message)
+                        }
+                    }
+                }
+            }
+            None => println!("\n{}", tr!("sync.removed_hint", count = removed.len())),
+        }
+    }
+
+    println!(
+        "\n{}",
+        tr!(
+            "sync.done",
+            downloaded = summary.downloaded,
+            failed = summary.failed,
+            present = tracks.len() - missing.len(),
+        )
+    );
+    summary.print_details(opts);
+    notify::job_finished(api, opts, &JobReport::new("sync", name, dir, &summary)).await;
+    Ok(())
+}
+
+/// What a list's folder holds
+struct LocalList {
+    /// Files of the downloaded tracks, by Deezer ID
+    on_disk: HashMap<String, PathBuf>,
+    /// Files that belonged to the list before: the previous .m3u8 plus history entries in its folder
+    previous: Vec<PathBuf>,
+}
+
+async fn local_list(dir: &Path, m3u_path: &Path) -> Result<LocalList> {
+    let downloaded: Vec<(String, PathBuf)> = history::load()
+        .await?
+        .into_iter()
+        .filter(|e| e.path.starts_with(dir) && e.path.exists())
+        .map(|e| (e.sng_id, e.path))
+        .collect();
+    let on_disk: HashMap<String, PathBuf> = downloaded.iter().cloned().collect();
+    let mut previous = read_m3u(m3u_path).await;
+    previous.extend(downloaded.into_iter().map(|(_, path)| path));
+    Ok(LocalList { on_disk, previous })
+}
+
+/// Files of `previous` still on disk in `dir` that are not `current`ly listed, once each
+fn unlisted(previous: Vec<PathBuf>, current: &HashSet<&PathBuf>, dir: &Path) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    previous
+        .into_iter()
+        .filter(|p| !current.contains(p) && p.starts_with(dir) && p.exists())
+        .filter(|p| seen.insert(p.clone()))
+        .collect()
+}
+
+/// Run `diff`: what `sync playlist` would download and what `--prune` would remove, changing nothing.
+/// `target` is the playlist's folder, or a session file of an interrupted job.
+pub async fn diff_playlist(
+    api: &impl DeezerClient,
+    playlist_id: &str,
+    opts: &DownloadOptions,
+    target: &Path,
+) -> Result<()> {
+    let info = api.get_playlist_info(playlist_id).await?;
+    let playlist_name = info.data.display_name();
+    let tracks = api.get_playlist_tracks(playlist_id).await?;
+    let entries = history::load().await?;
+    let names: HashMap<&Path, String> = entries.iter().map(|e| (e.path.as_path(), e.display_name())).collect();
+
+    let (added, removed): (Vec<&GwTrack>, Vec<String>) = if target.is_dir() {
+        let dir = tokio::fs::canonicalize(target).await?;
+        let m3u_path = dir.join(format!("{}.m3u8", opts.file_name(&playlist_name)));
+        let LocalList { on_disk, previous } = local_list(&dir, &m3u_path).await?;
+        let added = tracks.iter().filter(|t| !on_disk.contains_key(&t.id_str())).collect();
+        let current: HashSet<&PathBuf> = tracks.iter().filter_map(|t| on_disk.get(&t.id_str())).collect();
+        let removed = unlisted(previous, &current, &dir)
+            .iter()
+            .map(|p| match names.get(p.as_path()) {
+                Some(name) => format!("{} ({})", name, p.strip_prefix(&dir).unwrap_or(p).display()),
+                None => p.strip_prefix(&dir).unwrap_or(p).display().to_string(),
+            })
+            .collect();
+        (added, removed)
+    } else {
+        // The plan of an interrupted job is the list as it was when the job started
+        let saved = session::read_session(target)
+            .await
+            .with_context(|| format!("{} is neither a folder nor a session file", target.display()))?;
+        let planned: HashSet<&str> = saved.plan.tracks.iter().map(|t| t.sng_id.as_str()).collect();
+        let listed: HashSet<String> = tracks.iter().map(GwTrack::id_str).collect();
+        let added = tracks.iter().filter(|t| !planned.contains(t.id_str().as_str())).collect();
+        let by_id: HashMap<&str, String> = entries.iter().map(|e| (e.sng_id.as_str(), e.display_name())).collect();
+        let removed = saved
+            .plan
+            .tracks
+            .iter()
+            .filter(|t| !listed.contains(&t.sng_id))
+            .map(|t| by_id.get(t.sng_id.as_str()).cloned().unwrap_or_else(|| tr!("diff.track_id", id = t.sng_id)))
+            .collect();
+        (added, removed)
+    };
+
+    println!("{}\n", tr!("diff.header", name = playlist_name, total = tracks.len(), target = target.display()));
+    for track in &added {
+        println!("  {} {}", color::note("[+]"), track.display_name());
+    }
+    for name in &removed {
+        println!("  {} {}", color::note("[-]"), name);
+    }
+    println!("\n{}", tr!("diff.summary", added = added.len(), removed = removed.len()));
+    Ok(())
+}
+
+/// Delete a file, or move it into `quarantine` keeping its path below `dir`
+async fn remove_track(path: &Path, dir: &Path, quarantine: Option<&Path>) -> Result<()> {
+    let Some(quarantine) = quarantine else {
+        tokio::fs::remove_file(path).await?;
+        return Ok(());
+    };
+    let target = quarantine.join(path.strip_prefix(dir).unwrap_or(path));
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::rename(path, &target).await?;
+    Ok(())
+}
+
+/// Paths listed in an existing .m3u8, resolved against its folder
+async fn read_m3u(path: &Path) -> Vec<PathBuf> {
+    let Ok(content) = tokio::fs::read_to_string(path).await else {
+        return Vec::new();
+    };
+    let base = path.parent().unwrap_or(Path::new("."));
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .collect()
+}
+
+/// Write an extended M3U playlist with paths relative to its folder
+async fn write_m3u(path: &Path, tracks: &[(&GwTrack, &PathBuf)]) {
+    let base = path.parent().unwrap_or(Path::new("."));
+    let mut content = String::from("#EXTM3U\n");
+    for (track, file) in tracks {
+        let entry = file.strip_prefix(base).unwrap_or(file);
+        let _ = writeln!(content, "#EXTINF:{},{}", track.duration_secs(), track.display_name());
+        let _ = writeln!(content, "{}", entry.display());
+    }
+
+    let result = async {
+        tokio::fs::create_dir_all(base).await?;
+        download::write_file_atomic(path, content.as_bytes()).await
+    }
+    .await;
+    match result {
+        Ok(()) => println!("\n{}", tr!("sync.m3u_written", path = path.display())),
+        Err(e) => eprintln!("\n{} {}", color::warn(), tr!("sync.m3u_failed", path = path.display(), error = e)),
+    }
+}
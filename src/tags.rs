@@ -0,0 +1,435 @@
+use anyhow::{Context, Result, bail};
+use id3::TagLike;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::models::{AlbumData, GwTrack, TrackFormat, VARIOUS_ARTISTS};
+use crate::musicbrainz::MusicBrainzIds;
+
+/// Offset between Deezer's GAIN and a ReplayGain track gain, as other Deezer taggers apply it
+const REPLAYGAIN_REFERENCE: f64 = 18.4;
+
+/// Metadata written into downloaded files
+#[derive(Debug, Clone, Default)]
+pub struct TrackTags {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub album_artist: String,
+    pub compilation: bool,
+    pub track_number: u32,
+    /// Tracks on the album, 0 when unknown
+    pub track_total: u32,
+    pub disc_number: u32,
+    /// Discs in the album, 0 when unknown
+    pub disc_total: u32,
+    pub isrc: String,
+    pub genres: Vec<String>,
+    /// Release date, `YYYY-MM-DD`
+    pub date: String,
+    pub label: String,
+    pub copyright: String,
+    /// From SNG_CONTRIBUTORS, when Deezer credits them
+    pub composers: Vec<String>,
+    /// 0 when unknown
+    pub bpm: u32,
+    /// ReplayGain track gain in dB, from Deezer's loudness measurement
+    pub replaygain: Option<f64>,
+    /// Unsynced lyrics
+    pub lyrics: String,
+    pub musicbrainz: Option<MusicBrainzIds>,
+}
+
+impl TrackTags {
+    /// Tags for `track`; album details fill in the album artist, release date, label and copyright
+    pub fn new(track: &GwTrack, album: Option<&AlbumData>) -> Self {
+        let compilation = album.is_some_and(|a| a.is_compilation());
+        let album_artist = if compilation {
+            VARIOUS_ARTISTS.to_string()
+        } else {
+            album
+                .and_then(|a| a.art_name.clone())
+                .filter(|a| !a.is_empty())
+                .unwrap_or_else(|| track.artist())
+        };
+        Self {
+            title: track.title(),
+            artist: track.artist(),
+            album: track.album(),
+            album_artist,
+            compilation,
+            track_number: track.track_no(),
+            track_total: album.map_or(0, |a| a.track_count()),
+            disc_number: track.disc_no(),
+            disc_total: album.map_or(0, |a| a.disc_count()),
+            isrc: track.isrc.clone().unwrap_or_default(),
+            genres: Vec::new(),
+            date: album.and_then(|a| a.release_date()).unwrap_or_default(),
+            label: album.and_then(|a| a.label_name.clone()).unwrap_or_default(),
+            copyright: album.and_then(|a| a.copyright.clone()).unwrap_or_default(),
+            composers: track.contributors_as("composer"),
+            bpm: track.beats_per_minute(),
+            replaygain: track.gain_db().map(|gain| -(gain + REPLAYGAIN_REFERENCE)),
+            lyrics: String::new(),
+            musicbrainz: None,
+        }
+    }
+
+    /// Vorbis comment fields, skipping empty values
+    fn vorbis_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![
+            ("TITLE", self.title.clone()),
+            ("ARTIST", self.artist.clone()),
+            ("ALBUM", self.album.clone()),
+            ("ALBUMARTIST", self.album_artist.clone()),
+            ("ISRC", self.isrc.clone()),
+            ("DATE", self.date.clone()),
+            ("YEAR", self.date.get(..4).unwrap_or_default().to_string()),
+            ("LABEL", self.label.clone()),
+            ("COPYRIGHT", self.copyright.clone()),
+            ("LYRICS", self.lyrics.clone()),
+        ];
+        if self.track_number > 0 {
+            fields.push(("TRACKNUMBER", self.track_number.to_string()));
+        }
+        if self.compilation {
+            fields.push(("COMPILATION", "1".to_string()));
+        }
+        if self.track_total > 0 {
+            fields.push(("TRACKTOTAL", self.track_total.to_string()));
+        }
+        if self.disc_number > 0 {
+            fields.push(("DISCNUMBER", self.disc_number.to_string()));
+        }
+        if self.disc_total > 0 {
+            fields.push(("DISCTOTAL", self.disc_total.to_string()));
+        }
+        fields.extend(self.genres.iter().map(|g| ("GENRE", g.clone())));
+        fields.extend(self.composers.iter().map(|c| ("COMPOSER", c.clone())));
+        if self.bpm > 0 {
+            fields.push(("BPM", self.bpm.to_string()));
+        }
+        if let Some(gain) = self.replaygain_text() {
+            fields.push(("REPLAYGAIN_TRACK_GAIN", gain));
+        }
+        if let Some(mb) = &self.musicbrainz {
+            fields.push(("MUSICBRAINZ_TRACKID", mb.recording.clone()));
+            fields.push(("MUSICBRAINZ_ALBUMID", mb.release.clone()));
+            fields.push(("MUSICBRAINZ_ARTISTID", mb.artist.clone()));
+        }
+        fields.retain(|(_, value)| !value.is_empty());
+        fields
+    }
+
+    /// ReplayGain as players read it, e.g. `-6.20 dB`
+    fn replaygain_text(&self) -> Option<String> {
+        self.replaygain.map(|gain| format!("{:.2} dB", gain))
+    }
+}
+
+/// Return `data` with tags applied. MP4 streams are returned unchanged.
+pub fn apply(data: &[u8], format: TrackFormat, tags: &TrackTags) -> Result<Vec<u8>> {
+    if data.starts_with(b"fLaC") {
+        tag_flac(data, tags)
+    } else if format.is_mp3() {
+        tag_mp3(data, tags)
+    } else {
+        Ok(data.to_vec())
+    }
+}
+
+/// Length of a leading ID3v2 tag, including header and footer
+fn id3_len(data: &[u8]) -> usize {
+    if data.len() < 10 || !data.starts_with(b"ID3") {
+        return 0;
+    }
+    let size = data[6..10]
+        .iter()
+        .fold(0usize, |acc, &b| (acc << 7) | (b & 0x7F) as usize);
+    let footer = if data[5] & 0x10 != 0 { 10 } else { 0 };
+    (10 + size + footer).min(data.len())
+}
+
+fn tag_mp3(data: &[u8], tags: &TrackTags) -> Result<Vec<u8>> {
+    let existing = id3_len(data);
+    let mut tag = if existing > 0 {
+        id3::Tag::read_from2(Cursor::new(&data[..existing])).unwrap_or_default()
+    } else {
+        id3::Tag::new()
+    };
+
+    tag.set_title(&tags.title);
+    tag.set_artist(&tags.artist);
+    tag.set_album(&tags.album);
+    tag.set_album_artist(&tags.album_artist);
+    if tags.compilation {
+        // iTunes compilation flag, also read by most other players
+        tag.set_text("TCMP", "1");
+    }
+    // TRCK / TPOS as "n/total" when the totals are known
+    if tags.track_number > 0 {
+        tag.set_track(tags.track_number);
+    }
+    if tags.track_total > 0 {
+        tag.set_total_tracks(tags.track_total);
+    }
+    if tags.disc_number > 0 {
+        tag.set_disc(tags.disc_number);
+    }
+    if tags.disc_total > 0 {
+        tag.set_total_discs(tags.disc_total);
+    }
+    if !tags.isrc.is_empty() {
+        tag.set_text("TSRC", &tags.isrc);
+    }
+    if !tags.genres.is_empty() {
+        tag.set_text_values("TCON", &tags.genres);
+    }
+    if !tags.composers.is_empty() {
+        tag.set_text_values("TCOM", &tags.composers);
+    }
+    if tags.bpm > 0 {
+        tag.set_text("TBPM", tags.bpm.to_string());
+    }
+    if !tags.lyrics.is_empty() {
+        // ID3 wants an ISO 639-2 code, which Deezer does not give
+        tag.add_frame(id3::frame::Lyrics {
+            lang: "XXX".to_string(),
+            description: String::new(),
+            text: tags.lyrics.clone(),
+        });
+    }
+    if let Some(gain) = tags.replaygain_text() {
+        tag.add_frame(id3::frame::ExtendedText {
+            description: "REPLAYGAIN_TRACK_GAIN".to_string(),
+            value: gain,
+        });
+    }
+    for (frame, value) in [("TDRC", &tags.date), ("TPUB", &tags.label), ("TCOP", &tags.copyright)] {
+        if !value.is_empty() {
+            tag.set_text(frame, value);
+        }
+    }
+    if let Some(mb) = &tags.musicbrainz {
+        // Field names as written by Picard
+        if !mb.recording.is_empty() {
+            tag.add_frame(id3::frame::UniqueFileIdentifier {
+                owner_identifier: "http://musicbrainz.org".to_string(),
+                identifier: mb.recording.as_bytes().to_vec(),
+            });
+        }
+        for (description, value) in [
+            ("MusicBrainz Album Id", &mb.release),
+            ("MusicBrainz Artist Id", &mb.artist),
+        ] {
+            if !value.is_empty() {
+                tag.add_frame(id3::frame::ExtendedText {
+                    description: description.to_string(),
+                    value: value.clone(),
+                });
+            }
+        }
+    }
+
+    let mut out = Vec::with_capacity(data.len() + 1024);
+    tag.write_to(&mut out, id3::Version::Id3v24)
+        .context("Failed to write ID3 tag")?;
+    out.extend_from_slice(&data[existing..]);
+    Ok(out)
+}
+
+const FLAC_VORBIS_COMMENT: u8 = 4;
+
+/// ISRC tagged in an MP3 (ID3 `TSRC`) or FLAC (Vorbis `ISRC`) file, reading only its header
+pub fn read_isrc(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic).ok()?;
+    let isrc = if magic.starts_with(b"ID3") {
+        let tag = id3::Tag::read_from_path(path).ok()?;
+        tag.get("TSRC")?.content().text()?.to_string()
+    } else if &magic == b"fLaC" {
+        flac_comment(&mut file, "ISRC")?
+    } else {
+        return None;
+    };
+    let isrc = isrc.trim().to_uppercase();
+    (!isrc.is_empty()).then_some(isrc)
+}
+
+/// Tags read back from an MP3 or FLAC file, as far as they are set.
+/// Returns `None` for untagged files and other formats.
+pub fn read(path: &Path) -> Option<TrackTags> {
+    let mut file = File::open(path).ok()?;
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic).ok()?;
+    let tags = if magic.starts_with(b"ID3") {
+        let tag = id3::Tag::read_from_path(path).ok()?;
+        let text = |frame: &str| {
+            tag.get(frame)
+                .and_then(|f| f.content().text())
+                .map(|t| t.to_string())
+                .unwrap_or_default()
+        };
+        TrackTags {
+            title: tag.title().unwrap_or_default().to_string(),
+            artist: tag.artist().unwrap_or_default().to_string(),
+            album: tag.album().unwrap_or_default().to_string(),
+            album_artist: tag.album_artist().unwrap_or_default().to_string(),
+            compilation: text("TCMP") == "1",
+            track_number: tag.track().unwrap_or(0),
+            track_total: tag.total_tracks().unwrap_or(0),
+            disc_number: tag.disc().unwrap_or(0),
+            disc_total: tag.total_discs().unwrap_or(0),
+            isrc: text("TSRC"),
+            date: text("TDRC"),
+            ..Default::default()
+        }
+    } else if &magic == b"fLaC" {
+        let comments = flac_comments(&mut file)?;
+        let field = |name: &str| {
+            comments
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default()
+        };
+        let number = |name: &str| field(name).split('/').next().and_then(|n| n.trim().parse().ok()).unwrap_or(0);
+        TrackTags {
+            title: field("TITLE"),
+            artist: field("ARTIST"),
+            album: field("ALBUM"),
+            album_artist: field("ALBUMARTIST"),
+            compilation: field("COMPILATION") == "1",
+            track_number: number("TRACKNUMBER"),
+            track_total: number("TRACKTOTAL"),
+            disc_number: number("DISCNUMBER"),
+            disc_total: number("DISCTOTAL"),
+            isrc: field("ISRC"),
+            date: field("DATE"),
+            ..Default::default()
+        }
+    } else {
+        return None;
+    };
+    (!tags.title.is_empty() || !tags.artist.is_empty()).then_some(tags)
+}
+
+/// Value of a Vorbis comment field, reading FLAC metadata blocks up to the VORBIS_COMMENT one
+fn flac_comment(file: &mut File, name: &str) -> Option<String> {
+    flac_comments(file)?
+        .into_iter()
+        .find_map(|(key, value)| key.eq_ignore_ascii_case(name).then_some(value))
+}
+
+/// Every Vorbis comment as (field, value), reading FLAC metadata blocks up to the VORBIS_COMMENT one
+fn flac_comments(file: &mut File) -> Option<Vec<(String, String)>> {
+    loop {
+        let mut header = [0u8; 4];
+        file.read_exact(&mut header).ok()?;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        if header[0] & 0x7F == FLAC_VORBIS_COMMENT {
+            let mut body = vec![0u8; len];
+            file.read_exact(&mut body).ok()?;
+            let (_, comments) = parse_vorbis_comment(&body)?;
+            return Some(
+                comments
+                    .iter()
+                    .filter_map(|c| {
+                        let c = String::from_utf8_lossy(c);
+                        let (key, value) = c.split_once('=')?;
+                        Some((key.to_string(), value.to_string()))
+                    })
+                    .collect(),
+            );
+        }
+        if header[0] & 0x80 != 0 {
+            return None;
+        }
+        // Skip pictures and other blocks without reading them
+        file.seek(SeekFrom::Current(len as i64)).ok()?;
+    }
+}
+
+/// Parse a VORBIS_COMMENT block body into (vendor, comments)
+fn parse_vorbis_comment(body: &[u8]) -> Option<(Vec<u8>, Vec<Vec<u8>>)> {
+    let read_u32 = |pos: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(body.get(pos..pos + 4)?.try_into().ok()?) as usize)
+    };
+    let vendor_len = read_u32(0)?;
+    let vendor = body.get(4..4 + vendor_len)?.to_vec();
+    let mut pos = 4 + vendor_len;
+    let count = read_u32(pos)?;
+    pos += 4;
+    let mut comments = Vec::with_capacity(count);
+    for _ in 0..count {
+        let len = read_u32(pos)?;
+        comments.push(body.get(pos + 4..pos + 4 + len)?.to_vec());
+        pos += 4 + len;
+    }
+    Some((vendor, comments))
+}
+
+/// Replace our fields in the FLAC VORBIS_COMMENT block, keeping any others
+fn tag_flac(data: &[u8], tags: &TrackTags) -> Result<Vec<u8>> {
+    let mut blocks: Vec<(u8, Vec<u8>)> = Vec::new();
+    let mut pos = 4;
+    loop {
+        let Some(header) = data.get(pos..pos + 4) else {
+            bail!("Truncated FLAC metadata");
+        };
+        let is_last = header[0] & 0x80 != 0;
+        let kind = header[0] & 0x7F;
+        let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        let Some(body) = data.get(pos + 4..pos + 4 + len) else {
+            bail!("Truncated FLAC metadata block");
+        };
+        blocks.push((kind, body.to_vec()));
+        pos += 4 + len;
+        if is_last {
+            break;
+        }
+    }
+
+    let fields = tags.vorbis_fields();
+    let (vendor, mut comments) = blocks
+        .iter()
+        .find(|(kind, _)| *kind == FLAC_VORBIS_COMMENT)
+        .and_then(|(_, body)| parse_vorbis_comment(body))
+        .unwrap_or_else(|| (b"deezer-dl".to_vec(), Vec::new()));
+    comments.retain(|c| {
+        let key = c.split(|&b| b == b'=').next().unwrap_or_default();
+        !fields.iter().any(|(name, _)| key.eq_ignore_ascii_case(name.as_bytes()))
+    });
+    comments.extend(fields.iter().map(|(name, value)| format!("{}={}", name, value).into_bytes()));
+
+    let mut body = Vec::new();
+    body.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    body.extend_from_slice(&vendor);
+    body.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for comment in &comments {
+        body.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        body.extend_from_slice(comment);
+    }
+    if body.len() >= 1 << 24 {
+        bail!("Vorbis comment block too large");
+    }
+
+    blocks.retain(|(kind, _)| *kind != FLAC_VORBIS_COMMENT);
+    // STREAMINFO must stay first
+    let insert_at = blocks.len().min(1);
+    blocks.insert(insert_at, (FLAC_VORBIS_COMMENT, body));
+
+    let mut out = Vec::with_capacity(data.len() + 1024);
+    out.extend_from_slice(b"fLaC");
+    let count = blocks.len();
+    for (i, (kind, body)) in blocks.iter().enumerate() {
+        let last_flag = if i + 1 == count { 0x80 } else { 0 };
+        let len = (body.len() as u32).to_be_bytes();
+        out.extend_from_slice(&[kind | last_flag, len[1], len[2], len[3]]);
+        out.extend_from_slice(body);
+    }
+    out.extend_from_slice(&data[pos..]);
+    Ok(out)
+}
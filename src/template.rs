@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::download::{DownloadOptions, truncate_filename};
+use crate::history::HistoryEntry;
+use crate::models::{AlbumData, GwTrack, VARIOUS_ARTISTS};
+use crate::tags::TrackTags;
+
+/// Default layout: `Artist/Artist - Title.ext`
+pub const DEFAULT_TEMPLATE: &str = "{artist}/{artist} - {title}";
+
+/// Ready-made templates for media servers. Files are placed by the template
+/// alone, without playlist/favorites/artist job folders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// `Artist/Album (Year)/Disc N/NN - Title` as expected by Plex and Jellyfin
+    Plex,
+    /// `Album Artist/Album/NN - Title` for Navidrome and other Subsonic servers
+    Navidrome,
+}
+
+impl Layout {
+    pub fn template(&self) -> &'static str {
+        match self {
+            Layout::Plex => "{artist}/{album} ({year})/{disc_folder}/{track} - {title}",
+            Layout::Navidrome => "{album_artist}/{album}/{disc_folder}/{track} - {title}",
+        }
+    }
+}
+
+/// Values available to filename templates
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVars {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    /// Album's main artist, so compilations stay in one folder
+    pub album_artist: String,
+    pub id: String,
+    pub isrc: String,
+    pub year: String,
+    /// Two-digit track number
+    pub track: String,
+    pub disc: String,
+    /// `Disc N` on multi-disc albums, empty otherwise
+    pub disc_folder: String,
+    /// Zero-padded place in the playlist, favorites or top tracks, empty elsewhere
+    pub position: String,
+    /// `feat. A & B`, empty without featured artists
+    pub featuring: String,
+    /// Format of the Deezer stream (`FLAC`, `MP3_320`...), set once it is known
+    pub format: String,
+    /// Part of a various-artists compilation
+    pub compilation: bool,
+}
+
+impl TemplateVars {
+    fn get(&self, key: &str) -> Option<&str> {
+        match key {
+            "artist" => Some(&self.artist),
+            "title" => Some(&self.title),
+            "album" => Some(&self.album),
+            "album_artist" => Some(&self.album_artist),
+            "id" => Some(&self.id),
+            "isrc" => Some(&self.isrc),
+            "year" => Some(&self.year),
+            "track" => Some(&self.track),
+            "disc" => Some(&self.disc),
+            "disc_folder" => Some(&self.disc_folder),
+            "position" => Some(&self.position),
+            "featuring" => Some(&self.featuring),
+            "format" => Some(&self.format),
+            _ => None,
+        }
+    }
+
+    /// Fill in the variables that need album details
+    pub fn with_album(mut self, album: &AlbumData) -> Self {
+        self.year = album.year();
+        if album.is_compilation() {
+            self.compilation = true;
+            self.album_artist = VARIOUS_ARTISTS.to_string();
+        } else if let Some(artist) = album.art_name.as_ref().filter(|a| !a.is_empty()) {
+            self.album_artist = artist.clone();
+        }
+        if album.disc_count() > 1 {
+            self.disc_folder = format!("Disc {}", self.disc);
+        }
+        self
+    }
+}
+
+/// Order of a job's tracks, numbered from 1 for `{position}`
+#[derive(Debug, Clone, Default)]
+pub struct TrackPositions {
+    by_id: Arc<HashMap<String, usize>>,
+    /// Digits of the last position, at least two
+    width: usize,
+    /// Position of the track being downloaded
+    current: Option<usize>,
+}
+
+impl TrackPositions {
+    /// Number the tracks in the given order; a track listed twice keeps its first position
+    pub fn new(ids: impl IntoIterator<Item = String>) -> Self {
+        let mut by_id = HashMap::new();
+        let mut count = 0;
+        for id in ids {
+            count += 1;
+            by_id.entry(id).or_insert(count);
+        }
+        Self {
+            by_id: Arc::new(by_id),
+            width: count.to_string().len().max(2),
+            current: None,
+        }
+    }
+
+    /// The same numbering, with `sng_id` as the track being downloaded
+    pub fn select(&self, sng_id: &str) -> Self {
+        Self {
+            current: self.by_id.get(sng_id).copied(),
+            ..self.clone()
+        }
+    }
+
+    /// Zero-padded position of the track being downloaded, empty when it is not numbered
+    pub fn current(&self) -> String {
+        self.current
+            .map(|position| format!("{:0width$}", position, width = self.width))
+            .unwrap_or_default()
+    }
+}
+
+fn track_var(number: u32) -> String {
+    if number == 0 { String::new() } else { format!("{:02}", number) }
+}
+
+impl From<&GwTrack> for TemplateVars {
+    fn from(track: &GwTrack) -> Self {
+        Self {
+            artist: track.artist(),
+            title: track.title(),
+            album: track.album(),
+            album_artist: track.artist(),
+            id: track.id_str(),
+            isrc: track.isrc.clone().unwrap_or_default(),
+            track: track_var(track.track_no()),
+            disc: track.disc_no().to_string(),
+            featuring: track.featuring(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&HistoryEntry> for TemplateVars {
+    fn from(entry: &HistoryEntry) -> Self {
+        Self {
+            artist: entry.artist.clone(),
+            title: entry.title.clone(),
+            album: entry.album.clone(),
+            album_artist: if entry.compilation {
+                VARIOUS_ARTISTS.to_string()
+            } else {
+                entry.artist.clone()
+            },
+            id: entry.sng_id.clone(),
+            isrc: entry.isrc.clone().unwrap_or_default(),
+            track: track_var(entry.track_number),
+            disc: entry.disc_number.max(1).to_string(),
+            format: entry.format.clone(),
+            compilation: entry.compilation,
+            ..Default::default()
+        }
+    }
+}
+
+/// Variables from the tags of a file already on disk. The Deezer id and format are not tagged.
+impl From<&TrackTags> for TemplateVars {
+    fn from(tags: &TrackTags) -> Self {
+        let disc = tags.disc_number.max(1).to_string();
+        Self {
+            artist: tags.artist.clone(),
+            title: tags.title.clone(),
+            album: tags.album.clone(),
+            album_artist: if tags.compilation {
+                VARIOUS_ARTISTS.to_string()
+            } else if tags.album_artist.is_empty() {
+                tags.artist.clone()
+            } else {
+                tags.album_artist.clone()
+            },
+            isrc: tags.isrc.clone(),
+            year: tags.date.get(..4).unwrap_or_default().to_string(),
+            track: track_var(tags.track_number),
+            disc_folder: if tags.disc_total > 1 { format!("Disc {}", disc) } else { String::new() },
+            disc,
+            compilation: tags.compilation,
+            ..Default::default()
+        }
+    }
+}
+
+/// Substitute `{var}` placeholders in a single path component.
+/// Unknown placeholders are kept verbatim.
+fn render_component(component: &str, vars: &TemplateVars) -> String {
+    let mut out = String::new();
+    let mut rest = component;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let key = &after[..end];
+                match vars.get(key) {
+                    Some(val) => out.push_str(val),
+                    None => {
+                        out.push('{');
+                        out.push_str(key);
+                        out.push('}');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Drop `()` / `[]` left behind by empty variables, e.g. `Album ()` without a year
+fn strip_empty_brackets(component: &str) -> String {
+    component
+        .replace(" ()", "")
+        .replace("()", "")
+        .replace(" []", "")
+        .replace("[]", "")
+}
+
+/// Render a template into a relative path (without extension).
+/// Each `/`-separated component is made a file name with `opts`; empty components are dropped.
+pub fn render(template: &str, vars: &TemplateVars, opts: &DownloadOptions) -> PathBuf {
+    template
+        .split('/')
+        .map(|c| opts.file_name(&strip_empty_brackets(&render_component(c, vars))))
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+/// Add a `{disc_folder}` level just above the file name, unless the template already has one
+pub fn with_disc_folder(template: &str) -> String {
+    if template.contains("{disc_folder}") {
+        return template.to_string();
+    }
+    match template.rsplit_once('/') {
+        Some((dir, file)) => format!("{}/{{disc_folder}}/{}", dir, file),
+        None => format!("{{disc_folder}}/{}", template),
+    }
+}
+
+/// Whether the directory part of a template includes `{album}`, i.e. each album gets its own folder
+pub fn groups_by_album(template: &str) -> bool {
+    template.rsplit_once('/').is_some_and(|(dir, _)| dir.contains("{album}"))
+}
+
+/// Keep compilations together: folder levels use `Various Artists` instead of
+/// the track artist, and get an album folder if the template has none
+fn compilation_template(template: &str) -> String {
+    let (dir, file) = template.rsplit_once('/').unwrap_or(("", template));
+    let mut dir = dir.replace("{artist}", "{album_artist}");
+    if !dir.contains("{album}") {
+        dir = if dir.is_empty() { "{album}".to_string() } else { format!("{}/{{album}}", dir) };
+    }
+    format!("{}/{}", dir, file)
+}
+
+/// Render a template into a relative file path ending in `extension` (e.g. `.flac`).
+/// A file name over the length limit is shortened with [`shorten_file_name`].
+pub fn render_path(template: &str, vars: &TemplateVars, extension: &str, opts: &DownloadOptions) -> PathBuf {
+    let template = if vars.compilation {
+        compilation_template(template)
+    } else {
+        template.to_string()
+    };
+    let mut path = render(&template, vars, opts);
+    let mut file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| opts.file_name(&vars.id));
+    let max = opts.max_name_length().saturating_sub(extension.len());
+    if file_name.len() > max {
+        file_name = shorten_file_name(&template, vars, max, opts);
+    }
+    path.set_file_name(format!("{}{}", file_name, extension));
+    path
+}
+
+/// Render the file name part of `template` in at most `max` bytes by cutting the
+/// title, so the track number and the rest of the name stay. Templates without a
+/// `{title}`, or too long even without one, are cut at the end.
+fn shorten_file_name(template: &str, vars: &TemplateVars, max: usize, opts: &DownloadOptions) -> String {
+    let file_template = template.rsplit('/').next().unwrap_or(template);
+    let mut vars = vars.clone();
+    loop {
+        let name = opts.file_name(&strip_empty_brackets(&render_component(file_template, &vars)));
+        let excess = name.len().saturating_sub(max);
+        if excess == 0 {
+            return name;
+        }
+        if !file_template.contains("{title}") || vars.title.is_empty() {
+            return truncate_filename(&name, max);
+        }
+        vars.title = truncate_filename(&vars.title, vars.title.len().saturating_sub(excess));
+    }
+}
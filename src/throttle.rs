@@ -0,0 +1,105 @@
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::color;
+use crate::tr;
+
+/// Spaces out requests so that at most `rps` start per second
+#[derive(Clone)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Arc<Mutex<Instant>>,
+}
+
+/// Parse `--api-rate` requests per second: a finite number above 0, small enough for the
+/// time between two requests to fit a `Duration`
+pub fn parse_api_rate(input: &str) -> Result<f64, String> {
+    let rps: f64 = input
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid rate '{}' (expected requests per second, e.g. 10 or 0.5)", input))?;
+    if !rps.is_finite() || rps <= 0.0 || Duration::try_from_secs_f64(1.0 / rps).is_err() {
+        return Err(format!("rate must be a finite number of requests per second above 0 (got {})", input));
+    }
+    Ok(rps)
+}
+
+impl RateLimiter {
+    pub fn new(rps: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / rps),
+            next_slot: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Wait until the next request slot is free
+    pub async fn acquire(&self) {
+        let wait_until = {
+            let mut next = self.next_slot.lock().await;
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(wait_until).await;
+    }
+}
+
+/// Keeps a single stream at or below `bytes_per_sec`
+pub struct BandwidthLimiter {
+    bytes_per_sec: u64,
+    started: Instant,
+    transferred: u64,
+}
+
+impl BandwidthLimiter {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            bytes_per_sec,
+            started: Instant::now(),
+            transferred: 0,
+        }
+    }
+
+    /// Account for `bytes` just received and sleep if we are ahead of the allowed rate
+    pub async fn consume(&mut self, bytes: usize) {
+        self.transferred += bytes as u64;
+        let allowed_at = Duration::from_secs_f64(self.transferred as f64 / self.bytes_per_sec as f64);
+        let elapsed = self.started.elapsed();
+        if allowed_at > elapsed {
+            tokio::time::sleep(allowed_at - elapsed).await;
+        }
+    }
+}
+
+/// Parse a rate like `2M`, `500K`, `1.5m` or `800000` into bytes per second
+pub fn parse_rate(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let (number, multiplier) = match input.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&input[..input.len() - 1], 1024.0),
+        Some('M') => (&input[..input.len() - 1], 1024.0 * 1024.0),
+        Some('G') => (&input[..input.len() - 1], 1024.0 * 1024.0 * 1024.0),
+        _ => (input, 1.0),
+    };
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid rate '{}' (expected e.g. 500K or 2M)", input))?;
+    let bytes = (value * multiplier) as u64;
+    if bytes == 0 {
+        return Err("rate must be greater than zero".to_string());
+    }
+    Ok(bytes)
+}
+
+/// Sleep for `secs` seconds while showing a countdown on stderr
+pub async fn countdown(reason: &str, secs: u64) {
+    for remaining in (1..=secs).rev() {
+        eprint!("\r  {} {}   ", color::note("[wait]"), tr!("throttle.waiting", reason = reason, secs = remaining));
+        let _ = std::io::stderr().flush();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    eprintln!("\r  {} {}          ", color::note("[wait]"), tr!("throttle.resuming", reason = reason));
+}
@@ -0,0 +1,208 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::auth::config_dir;
+use crate::client::DeezerClient;
+use crate::color;
+use crate::download::{self, ArtistOptions, DownloadOptions};
+use crate::history::{self, HistoryEntry};
+use crate::models::AlbumInfo;
+use crate::tr;
+
+/// Options for `watch`
+#[derive(Debug, Clone, clap::Args)]
+pub struct WatchOptions {
+    /// Also watch the artists you follow on Deezer
+    #[arg(long)]
+    pub followed: bool,
+    /// Ignore releases dated before this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE")]
+    pub since: Option<String>,
+    /// Time between checks, e.g. 30m, 6h or 1d
+    #[arg(long, value_parser = parse_interval, default_value = "6h")]
+    pub interval: Duration,
+    /// Check once and exit instead of running until interrupted
+    #[arg(long)]
+    pub once: bool,
+}
+
+/// Parse a duration like `90s`, `30m`, `6h` or `1d` (plain numbers are seconds)
+pub fn parse_interval(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let (number, multiplier) = match input.chars().last().map(|c| c.to_ascii_lowercase()) {
+        Some('s') => (&input[..input.len() - 1], 1.0),
+        Some('m') => (&input[..input.len() - 1], 60.0),
+        Some('h') => (&input[..input.len() - 1], 3600.0),
+        Some('d') => (&input[..input.len() - 1], 86400.0),
+        _ => (input, 1.0),
+    };
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid interval '{}' (expected e.g. 30m, 6h or 1d)", input))?;
+    let secs = value * multiplier;
+    if !secs.is_finite() || secs < 1.0 {
+        return Err("interval must be at least one second".to_string());
+    }
+    Ok(Duration::from_secs_f64(secs))
+}
+
+/// Albums already in the download history, by ID and by (artist, title)
+struct KnownAlbums {
+    ids: HashSet<String>,
+    names: HashSet<(String, String)>,
+}
+
+impl KnownAlbums {
+    fn new(entries: &[HistoryEntry]) -> Self {
+        Self {
+            ids: entries
+                .iter()
+                .map(|e| e.album_id.clone())
+                .filter(|id| !id.is_empty())
+                .collect(),
+            names: entries
+                .iter()
+                .map(|e| (e.artist.to_lowercase(), e.album.to_lowercase()))
+                .collect(),
+        }
+    }
+
+    fn contains(&self, album: &AlbumInfo) -> bool {
+        let artist = album.art_name.as_deref().unwrap_or_default().to_lowercase();
+        let title = album.alb_title.as_deref().unwrap_or_default().to_lowercase();
+        self.ids.contains(&album.id_str()) || self.names.contains(&(artist, title))
+    }
+}
+
+/// Release IDs each artist had when first checked without `--since`, by artist ID
+pub fn baseline_path() -> PathBuf {
+    config_dir().join("watch.json")
+}
+
+async fn load_baseline() -> Result<HashMap<String, HashSet<String>>> {
+    match tokio::fs::read_to_string(baseline_path()).await {
+        Ok(content) => serde_json::from_str(&content).context("Failed to parse watch.json"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e).context("Failed to read watch.json"),
+    }
+}
+
+async fn save_baseline(baseline: &HashMap<String, HashSet<String>>) -> Result<()> {
+    tokio::fs::create_dir_all(config_dir()).await?;
+    download::write_file_atomic(&baseline_path(), &serde_json::to_vec_pretty(baseline)?).await
+}
+
+/// Check each artist once and download releases missing from the history. The first check
+/// of an artist without `since` only records its current releases, so the back catalogue
+/// is not taken for new. Returns the number of new releases downloaded.
+pub async fn check(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    artist_ids: &[String],
+    since: Option<&str>,
+    output_dir: &Path,
+) -> Result<usize> {
+    let known = KnownAlbums::new(&history::load().await?);
+    let mut baseline = load_baseline().await?;
+    let mut found = 0;
+
+    for art_id in artist_ids {
+        let albums = match api.get_artist_discography(art_id, "all").await {
+            Ok(albums) => albums,
+            Err(e) => {
+                eprintln!("  {} {}", color::warn(), tr!("watch.check_failed", id = art_id, error = e));
+                continue;
+            }
+        };
+        if since.is_none() && !baseline.contains_key(art_id) {
+            let name = albums.first().and_then(|a| a.art_name.as_deref()).unwrap_or(art_id);
+            println!("{}", tr!("watch.baseline", name = name, count = albums.len()));
+            baseline.insert(art_id.clone(), albums.iter().map(|a| a.id_str()).collect());
+            save_baseline(&baseline).await?;
+            continue;
+        }
+        let listed = baseline.get(art_id);
+        let new: Vec<&AlbumInfo> = albums
+            .iter()
+            .filter(|a| !known.contains(a) && !listed.is_some_and(|ids| ids.contains(&a.id_str())))
+            .filter(|a| since.is_none_or(|since| a.release_date().as_str() >= since))
+            .collect();
+        if new.is_empty() {
+            continue;
+        }
+
+        let artist_name = new[0].art_name.as_deref().unwrap_or(art_id);
+        println!("\n{}", tr!("watch.new_releases", name = artist_name));
+        for album in &new {
+            println!(
+                "  + {} ({}, {})",
+                album.alb_title.as_deref().unwrap_or("Unknown Album"),
+                album.type_name(),
+                album.release_date()
+            );
+        }
+
+        // One release at a time, so that only those that downloaded in full are counted
+        for album in new {
+            let (release_opts, reports) = download::collecting_reports(opts);
+            let artist_opts = ArtistOptions {
+                release_ids: Some(HashSet::from([album.id_str()])),
+                ..Default::default()
+            };
+            match download::download_artist(api, art_id, &release_opts, &artist_opts, output_dir).await {
+                Ok(()) if reports.lock().unwrap().iter().all(|r| r.failed == 0) => found += 1,
+                Ok(()) => {}
+                Err(e) if download::stops_job(&e) => return Err(e),
+                Err(e) => eprintln!("  {} {}", color::err(), tr!("watch.download_failed", error = e)),
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// `artist_ids` plus, with `followed`, the artists the user follows, without repeats
+pub async fn resolve_artists(api: &impl DeezerClient, artist_ids: &[String], followed: bool) -> Vec<String> {
+    let mut ids: Vec<String> = artist_ids.to_vec();
+    if followed {
+        let user_id = api.user().await.map(|u| u.id).unwrap_or(0);
+        match api.get_followed_artists(user_id).await {
+            Ok(followed) => ids.extend(followed.into_iter().map(|(id, _)| id)),
+            Err(e) => eprintln!("  {} {}", color::warn(), tr!("watch.followed_failed", error = e)),
+        }
+    }
+    let mut seen = HashSet::new();
+    ids.retain(|id| seen.insert(id.clone()));
+    ids
+}
+
+/// Run `watch`: check the artists, then repeat every `interval` unless `--once`
+pub async fn run(
+    api: &impl DeezerClient,
+    opts: &DownloadOptions,
+    watch_opts: &WatchOptions,
+    artist_ids: &[String],
+    output_dir: &Path,
+) -> Result<()> {
+    loop {
+        let ids = resolve_artists(api, artist_ids, watch_opts.followed).await;
+        if ids.is_empty() {
+            anyhow::bail!("No artists to watch: pass artist IDs, use --followed, or set watch_artists in config.toml");
+        }
+
+        println!("{}", tr!("watch.checking", count = ids.len()));
+        let found = check(api, opts, &ids, watch_opts.since.as_deref(), output_dir).await?;
+        if found == 0 {
+            println!("{}", tr!("watch.none"));
+        } else {
+            println!("\n{}", tr!("watch.grabbed", count = found));
+        }
+
+        if watch_opts.once {
+            return Ok(());
+        }
+        println!("{}\n", tr!("watch.next", secs = watch_opts.interval.as_secs()));
+        tokio::time::sleep(watch_opts.interval).await;
+    }
+}
@@ -0,0 +1,580 @@
+//! API client tests against a local mock of the Deezer endpoints
+
+use deezer_dl::accounts::BackupAccounts;
+use deezer_dl::backup;
+use deezer_dl::download::{self, DownloadOptions, QualityRefused};
+use deezer_dl::lyrics;
+use deezer_dl::models::CurrentUser;
+use deezer_dl::probe::{self, Availability};
+use deezer_dl::{DeezerApi, Endpoints, GwTrack, NetworkOptions, TrackFormat};
+use serde_json::{Value, json};
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+const GW_PATH: &str = "/ajax/gw-light.php";
+const MEDIA_PATH: &str = "/media/get_url";
+
+fn client(server: &MockServer) -> DeezerApi {
+    DeezerApi::new(&NetworkOptions::default())
+        .unwrap()
+        .with_endpoints(Endpoints::local(&server.uri()))
+}
+
+fn gw_ok(results: Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "error": {}, "results": results }))
+}
+
+fn user_data(user_id: u64, check_form: &str) -> Value {
+    json!({
+        "USER": {
+            "USER_ID": user_id,
+            "BLOG_NAME": "tester",
+            "OPTIONS": { "license_token": "license", "web_hq": true, "web_lossless": true },
+        },
+        "checkForm": check_form,
+    })
+}
+
+/// Mount the site page and a getUserData answer, up to `times` calls when given
+async fn mount_user_data(server: &MockServer, user_id: u64, check_form: &str, times: Option<u64>) {
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(server)
+        .await;
+    let mock = Mock::given(method("POST"))
+        .and(path(GW_PATH))
+        .and(query_param("method", "deezer.getUserData"))
+        .respond_with(gw_ok(user_data(user_id, check_form)));
+    match times {
+        Some(n) => mock.up_to_n_times(n).mount(server).await,
+        None => mock.mount(server).await,
+    }
+}
+
+async fn logged_in(server: &MockServer) -> DeezerApi {
+    mount_user_data(server, 123, "token1", Some(1)).await;
+    let api = client(server);
+    assert!(api.login_via_arl("arl").await.unwrap());
+    api
+}
+
+/// Answer media requests for `format` with a stream URL
+async fn mount_media_url(server: &MockServer, format: &'static str, url: &str) {
+    Mock::given(method("POST"))
+        .and(path(MEDIA_PATH))
+        .and(move |req: &Request| media_format(req).as_deref() == Some(format))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{ "media": [{ "sources": [{ "url": url }] }] }],
+        })))
+        .mount(server)
+        .await;
+}
+
+/// Answer media requests for `format` with Deezer's "no rights" error
+async fn mount_media_refused(server: &MockServer, format: &'static str) {
+    Mock::given(method("POST"))
+        .and(path(MEDIA_PATH))
+        .and(move |req: &Request| media_format(req).as_deref() == Some(format))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{ "errors": [{ "code": 2002, "message": "Track token has no sufficient rights on requested media" }] }],
+        })))
+        .mount(server)
+        .await;
+}
+
+fn media_format(req: &Request) -> Option<String> {
+    let body: Value = req.body_json().ok()?;
+    body["media"][0]["formats"][0]["format"].as_str().map(str::to_string)
+}
+
+fn track(extra: Value) -> GwTrack {
+    let mut data = json!({ "SNG_ID": "3135556", "SNG_TITLE": "Harder, Better, Faster, Stronger", "ART_NAME": "Daft Punk" });
+    data.as_object_mut()
+        .unwrap()
+        .extend(extra.as_object().unwrap().clone());
+    serde_json::from_value(data).unwrap()
+}
+
+#[tokio::test]
+async fn login_stores_user_and_token() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+
+    let user = api.current_user.lock().await.clone().unwrap();
+    assert_eq!(user.id, 123);
+    assert_eq!(user.name, "tester");
+    assert_eq!(user.license_token, "license");
+    assert!(user.can_stream_lossless);
+}
+
+#[tokio::test]
+async fn login_rejects_anonymous_user() {
+    let server = MockServer::start().await;
+    mount_user_data(&server, 0, "token1", None).await;
+
+    let api = client(&server);
+    assert!(!api.login_via_arl("expired").await.unwrap());
+    assert!(api.current_user.lock().await.is_none());
+}
+
+#[tokio::test]
+async fn invalid_token_is_refreshed_once() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_user_data(&server, 123, "token2", None).await;
+
+    Mock::given(method("POST"))
+        .and(path(GW_PATH))
+        .and(query_param("method", "song.getData"))
+        .and(query_param("api_token", "token1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "error": { "VALID_TOKEN_REQUIRED": "invalid api token" },
+            "results": {},
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(GW_PATH))
+        .and(query_param("method", "song.getData"))
+        .and(query_param("api_token", "token2"))
+        .respond_with(gw_ok(json!({ "SNG_ID": "3135556", "SNG_TITLE": "Harder, Better, Faster, Stronger" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let track = api.get_track("3135556").await.unwrap();
+    assert_eq!(track.title(), "Harder, Better, Faster, Stronger");
+}
+
+#[tokio::test]
+async fn playlist_info_and_tracks() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+
+    Mock::given(method("POST"))
+        .and(path(GW_PATH))
+        .and(query_param("method", "deezer.pagePlaylist"))
+        .respond_with(gw_ok(json!({ "DATA": { "PLAYLIST_ID": "908622995", "TITLE": "Discovery" } })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(GW_PATH))
+        .and(query_param("method", "playlist.getSongs"))
+        .respond_with(gw_ok(json!({
+            "data": [
+                { "SNG_ID": "1", "SNG_TITLE": "One More Time", "ART_NAME": "Daft Punk" },
+                { "SNG_ID": "2", "SNG_TITLE": "Aerodynamic", "ART_NAME": "Daft Punk" },
+            ],
+        })))
+        .mount(&server)
+        .await;
+
+    let info = api.get_playlist_info("908622995").await.unwrap();
+    assert_eq!(info.data.display_name(), "Discovery");
+
+    let tracks = api.get_playlist_tracks("908622995").await.unwrap();
+    let titles: Vec<String> = tracks.iter().map(|t| t.title()).collect();
+    assert_eq!(titles, ["One More Time", "Aerodynamic"]);
+}
+
+#[tokio::test]
+async fn media_url_falls_back_to_lower_format() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_media_refused(&server, "FLAC").await;
+    mount_media_url(&server, "MP3_320", "https://cdn.test/track.mp3").await;
+
+    let track = track(json!({ "TRACK_TOKEN": "tt", "TRACK_TOKEN_EXPIRE": u32::MAX }));
+    let (url, format, _) = download::get_download_url(&api, &track, TrackFormat::Flac, None)
+        .await
+        .unwrap();
+    assert_eq!(url, "https://cdn.test/track.mp3");
+    assert_eq!(format, TrackFormat::Mp3_320);
+}
+
+#[tokio::test]
+async fn media_url_falls_back_to_legacy_url() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_media_refused(&server, "FLAC").await;
+    mount_media_refused(&server, "MP3_320").await;
+    mount_media_refused(&server, "MP3_128").await;
+
+    let track = track(json!({
+        "TRACK_TOKEN": "tt",
+        "MD5_ORIGIN": "0123456789abcdef0123456789abcdef",
+        "MEDIA_VERSION": "3",
+        "FILESIZE_FLAC": "0",
+        "FILESIZE_MP3_320": "8000000",
+    }));
+    let (url, format, crypted) = download::get_download_url(&api, &track, TrackFormat::Flac, None)
+        .await
+        .unwrap();
+    assert_eq!(format, TrackFormat::Mp3_320);
+    assert!(crypted);
+    assert!(!url.starts_with(&server.uri()));
+}
+
+#[tokio::test]
+async fn media_url_respects_min_quality() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_media_refused(&server, "FLAC").await;
+    mount_media_url(&server, "MP3_320", "https://cdn.test/track.mp3").await;
+
+    let track = track(json!({
+        "TRACK_TOKEN": "tt",
+        "MD5_ORIGIN": "0123456789abcdef0123456789abcdef",
+        "MEDIA_VERSION": "3",
+        "FILESIZE_MP3_320": "8000000",
+    }));
+    let err = download::get_download_url(&api, &track, TrackFormat::Flac, Some(TrackFormat::Flac))
+        .await
+        .unwrap_err();
+    assert!(err.downcast_ref::<QualityRefused>().is_some());
+}
+
+#[tokio::test]
+async fn stereo_min_quality_does_not_accept_360_mixes() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_media_url(&server, "MP4_RA1", "https://cdn.test/track.mp4").await;
+
+    let track = track(json!({ "TRACK_TOKEN": "tt" }));
+    let found = download::media_url(&api, &track, TrackFormat::Mp4Ra1, Some(TrackFormat::Flac), None).await;
+    assert_eq!(found, None);
+    assert!(TrackFormat::Mp4Ra3.is_at_least(TrackFormat::Mp4Ra1));
+    assert!(!TrackFormat::Mp4Ra3.is_at_least(TrackFormat::Mp3Misc));
+}
+
+#[tokio::test]
+async fn best_quality_is_picked_per_track() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    let opts = DownloadOptions::default().with_quality("best").unwrap();
+    let lossless = track(json!({ "FILESIZE_FLAC": "30000000", "FILESIZE_MP3_320": "8000000" }));
+    let lossy = track(json!({ "FILESIZE_FLAC": "0", "FILESIZE_MP3_320": "8000000", "FILESIZE_MP3_128": "3000000" }));
+    assert_eq!(download::requested_format(&api, &lossless, &opts).await, TrackFormat::Flac);
+    assert_eq!(download::requested_format(&api, &lossy, &opts).await, TrackFormat::Mp3_320);
+
+    // Accounts without lossless or HQ streaming
+    assert_eq!(lossless.best_format(false, true), TrackFormat::Mp3_320);
+    assert_eq!(lossless.best_format(false, false), TrackFormat::Mp3_128);
+    let floor = DownloadOptions {
+        min_format: Some(TrackFormat::Flac),
+        ..opts
+    };
+    assert_eq!(download::requested_format(&api, &lossy, &floor).await, TrackFormat::Flac);
+}
+
+#[test]
+fn strict_quality_refuses_formats_the_account_cannot_stream() {
+    let free = CurrentUser {
+        id: 1,
+        name: "free".to_string(),
+        license_token: String::new(),
+        can_stream_hq: false,
+        can_stream_lossless: false,
+        country: "FR".to_string(),
+        loved_tracks_id: 0,
+    };
+    let opts = DownloadOptions::default();
+    let flac = opts.with_quality("flac").unwrap();
+    assert!(download::check_account_quality(&free, &flac, false).is_ok());
+    assert!(download::check_account_quality(&free, &flac, true).is_err());
+    assert!(download::check_account_quality(&free, &opts.with_quality("320").unwrap(), true).is_err());
+    assert!(download::check_account_quality(&free, &opts.with_quality("128").unwrap(), true).is_ok());
+    assert!(download::check_account_quality(&free, &opts.with_quality("best").unwrap(), true).is_ok());
+
+    let hifi = CurrentUser {
+        can_stream_hq: true,
+        can_stream_lossless: true,
+        ..free
+    };
+    assert!(download::check_account_quality(&hifi, &flac, true).is_ok());
+}
+
+/// Answer a GW method with `results`, expecting it to be called `times` times
+async fn mount_gw(server: &MockServer, gw_method: &str, results: Value, times: u64) {
+    Mock::given(method("POST"))
+        .and(path(GW_PATH))
+        .and(query_param("method", gw_method))
+        .respond_with(gw_ok(results))
+        .expect(times)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn restore_adds_only_what_the_account_is_missing() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+
+    let track = |id: &str| json!({ "position": 1, "title": "T", "artists": ["A"], "album": "", "isrc": "",
+        "duration": 0, "track_id": id, "album_id": "0", "artist_id": "0" });
+    let playlist = |id: &str, name: &str, owned: bool, tracks: Vec<Value>| {
+        json!({ "id": id, "name": name, "owned": owned, "tracks": tracks })
+    };
+    let backup = json!({
+        "version": 1, "created_at": 0, "user_id": 999, "user_name": "old account",
+        "favorites": [track("3"), track("2"), track("1")],
+        "playlists": [
+            playlist("10", "Road trip", true, vec![track("1"), track("4")]),
+            playlist("11", "Already here", true, vec![track("5")]),
+            playlist("12", "Someone else's", false, vec![]),
+        ],
+    });
+    let dir = std::env::temp_dir().join(format!("deezer-dl-restore-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("backup.json");
+    std::fs::write(&file, backup.to_string()).unwrap();
+
+    mount_gw(&server, "song.getFavoriteIds", json!({ "data": [{ "SNG_ID": 2 }] }), 1).await;
+    mount_gw(&server, "deezer.pageProfile", json!({ "TAB": { "playlists": { "data": [
+        { "PLAYLIST_ID": "20", "TITLE": "Already here", "PARENT_USER_ID": "123" },
+    ] } } }), 1)
+    .await;
+    Mock::given(method("POST"))
+        .and(path(GW_PATH))
+        .and(query_param("method", "favorite_song.add"))
+        .and(|req: &Request| {
+            let id = req.body_json::<Value>().unwrap()["SNG_ID"].as_str().unwrap().to_string();
+            id == "1" || id == "3"
+        })
+        .respond_with(gw_ok(json!(true)))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(GW_PATH))
+        .and(query_param("method", "playlist.create"))
+        .and(|req: &Request| {
+            let body: Value = req.body_json().unwrap();
+            body["title"] == "Road trip" && body["songs"] == json!([["1", 0], ["4", 0]])
+        })
+        .respond_with(gw_ok(json!(30)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(GW_PATH))
+        .and(query_param("method", "playlist.addFavorite"))
+        .and(|req: &Request| req.body_json::<Value>().unwrap()["PARENT_PLAYLIST_ID"] == "12")
+        .respond_with(gw_ok(json!(true)))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    backup::restore(&api, &file, false).await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn track_page_fills_in_missing_metadata() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+
+    mount_gw(
+        &server,
+        "deezer.pageTrack",
+        json!({
+            "DATA": {
+                "SNG_ID": "3135556",
+                "SNG_TITLE": "Harder Better Faster Stronger",
+                "BPM": "123.4",
+                "GAIN": "-12.5",
+                "LYRICS_ID": 2780622,
+                "SNG_CONTRIBUTORS": { "composer": ["Thomas Bangalter", "Edwin Birdsong"] },
+            },
+            "LYRICS": { "LYRICS_TEXT": "Work it harder" },
+        }),
+        1,
+    )
+    .await;
+
+    let mut track = track(json!({ "ISRC": "GBDUW0000059" }));
+    download::enrich_track(&api, &mut track).await;
+
+    // Fields the track already had are kept
+    assert_eq!(track.title(), "Harder, Better, Faster, Stronger");
+    assert_eq!(track.isrc.as_deref(), Some("GBDUW0000059"));
+    assert_eq!(track.beats_per_minute(), 123);
+    assert_eq!(track.gain_db(), Some(-12.5));
+    assert_eq!(track.lyrics_id, Some(json!(2780622)));
+    assert_eq!(track.contributors_as("composer"), ["Thomas Bangalter", "Edwin Birdsong"]);
+    assert_eq!(track.lyrics.unwrap()["LYRICS_TEXT"], "Work it harder");
+}
+
+/// Answer the pipe login and a lyrics query with `lyrics`
+async fn mount_lyrics(server: &MockServer, lyrics: Value) {
+    Mock::given(method("POST"))
+        .and(path("/auth"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "jwt": "jwt1" })))
+        .expect(1)
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/pipe"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": { "track": { "lyrics": lyrics } } })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn lyrics_use_the_translation_when_there_is_one() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_lyrics(
+        &server,
+        json!({
+            "text": "Work it harder\nMake it better",
+            "synchronizedLines": [
+                { "lrcTimestamp": "[00:01.00]", "line": "Work it harder", "lineTranslated": "Travaille plus dur" },
+                { "lrcTimestamp": "[00:02.50]", "line": "Make it better", "lineTranslated": "" },
+            ],
+        }),
+    )
+    .await;
+
+    let track = track(json!({}));
+    let translated = lyrics::fetch(&api, &track, Some("fr")).await.unwrap();
+    assert_eq!(translated.translation.as_deref(), Some("fr"));
+    // Lines without a translation keep the original
+    assert_eq!(translated.lrc().unwrap(), "[00:01.00]Travaille plus dur\n[00:02.50]Make it better\n");
+    assert_eq!(translated.plain(), "Travaille plus dur\nMake it better");
+
+    let original = lyrics::fetch(&api, &track, None).await.unwrap();
+    assert_eq!(original.translation, None);
+    assert_eq!(original.plain(), "Work it harder\nMake it better");
+}
+
+#[tokio::test]
+async fn lyrics_fall_back_to_the_original_language() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_lyrics(
+        &server,
+        json!({
+            "text": "Work it harder",
+            "synchronizedLines": [{ "lrcTimestamp": "[00:01.00]", "line": "Work it harder", "lineTranslated": null }],
+        }),
+    )
+    .await;
+
+    let lyrics = lyrics::fetch(&api, &track(json!({})), Some("de")).await.unwrap();
+    assert_eq!(lyrics.translation, None);
+    assert_eq!(lyrics.lrc().unwrap(), "[00:01.00]Work it harder\n");
+}
+
+#[tokio::test]
+async fn lyrics_only_writes_next_to_the_existing_audio() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_lyrics(
+        &server,
+        json!({
+            "text": "Work it harder",
+            "synchronizedLines": [{ "lrcTimestamp": "[00:01.00]", "line": "Work it harder" }],
+        }),
+    )
+    .await;
+
+    let dir = std::env::temp_dir().join(format!("deezer-dl-lyrics-only-{}", std::process::id()));
+    let audio = dir.join("Daft Punk/Daft Punk - Harder, Better, Faster, Stronger.flac");
+    std::fs::create_dir_all(audio.parent().unwrap()).unwrap();
+    std::fs::write(&audio, b"fLaC").unwrap();
+
+    let opts = download::DownloadOptions {
+        lyrics_only: true,
+        ..Default::default()
+    };
+    // No media endpoint is mounted: asking for a stream URL would fail the download
+    let done = download::download_track(&api, &track(json!({})), &opts, &dir, false).await.unwrap();
+    assert_eq!(done.path, audio.with_extension("lrc"));
+    assert_eq!(std::fs::read_to_string(&done.path).unwrap(), "[00:01.00]Work it harder\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn probe_tells_refused_formats_from_missing_ones() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_media_refused(&server, "FLAC").await;
+    mount_media_url(&server, "MP3_320", "https://cdn.test/track.mp3").await;
+    mount_media_refused(&server, "MP3_128").await;
+
+    let mut tracks = vec![track(json!({
+        "TRACK_TOKEN": "tt",
+        "FILESIZE_FLAC": "31000000",
+        "FILESIZE_MP3_320": 9000000,
+        "FILESIZE_MP3_128": "0",
+    }))];
+    let probed = probe::probe_tracks(&api, &mut tracks).await.unwrap();
+    assert_eq!(
+        probed[0].formats,
+        [
+            (TrackFormat::Flac, Availability::Refused, 31_000_000),
+            (TrackFormat::Mp3_320, Availability::Allowed, 9_000_000),
+            (TrackFormat::Mp3_128, Availability::Missing, 0),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn backup_accounts_stream_what_the_main_one_cannot() {
+    let server = MockServer::start().await;
+    let api = logged_in(&server).await;
+    mount_media_refused(&server, "FLAC").await;
+    mount_media_url(&server, "MP3_320", "https://cdn.test/main.mp3").await;
+
+    let backup = MockServer::start().await;
+    mount_user_data(&backup, 456, "token2", None).await;
+    mount_gw(&backup, "song.getData", json!({ "SNG_ID": "3135556", "TRACK_TOKEN": "theirs" }), 1).await;
+    mount_media_url(&backup, "FLAC", "https://cdn.test/backup.flac").await;
+    let backups = BackupAccounts::login(&["backup arl".to_string()], || Ok(client(&backup))).await.unwrap();
+    assert!(backups.can_stream(TrackFormat::Flac));
+
+    let track = track(json!({ "TRACK_TOKEN": "ours" }));
+    let own = download::media_url(&api, &track, TrackFormat::Flac, None, None).await;
+    assert_eq!(own, Some(("https://cdn.test/main.mp3".to_string(), TrackFormat::Mp3_320)));
+    let found = backups.find_url(&track, TrackFormat::Flac, None, Some(TrackFormat::Mp3_320)).await;
+    assert_eq!(found, Some(("https://cdn.test/backup.flac".to_string(), TrackFormat::Flac, "tester".to_string())));
+}
+
+#[tokio::test]
+async fn public_album_and_isrc_answers_are_typed() {
+    let server = MockServer::start().await;
+    let album = json!({
+        "id": 302127,
+        "title": "Discovery",
+        "artist": { "name": "Daft Punk" },
+        "genres": { "data": [{ "name": "Electro" }] },
+        "release_date": "2001-03-07",
+        "tracks": { "data": [{ "id": 3135556, "title": "One More Time", "duration": 320 }] },
+    });
+    Mock::given(method("GET"))
+        .and(path("/api/album/302127"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(album))
+        .mount(&server)
+        .await;
+    let not_found = json!({ "error": { "type": "DataException", "message": "no data", "code": 800 } });
+    Mock::given(method("GET"))
+        .and(path("/api/album/1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(not_found.clone()))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/track/isrc:GBDUW0000059"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(not_found))
+        .mount(&server)
+        .await;
+    let api = client(&server);
+
+    let album = api.get_album_public("302127").await.unwrap();
+    assert_eq!((album.artist.name.as_str(), album.genres.data[0].name.as_str()), ("Daft Punk", "Electro"));
+    assert_eq!(album.tracks.data[0].duration, 320);
+    assert!(api.get_album_public("1").await.is_err());
+    assert!(api.get_track_by_isrc("GBDUW0000059").await.unwrap().is_none());
+}
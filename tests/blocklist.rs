@@ -0,0 +1,72 @@
+//! Blocklist files read with `--blocklist`
+
+mod common;
+
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+
+use common::FakeClient;
+use deezer_dl::blocklist::Blocklist;
+use deezer_dl::download::{self, DownloadOptions};
+use deezer_dl::models::{GwTrack, TrackFormat};
+
+fn track(data: Value) -> GwTrack {
+    serde_json::from_value(data).unwrap()
+}
+
+#[test]
+fn blocklists_match_ids_links_and_artist_names() {
+    let content = "\
+# Never again
+3135556
+https://www.deezer.com/en/album/302127?utm_source=share
+artist:27
+nickelback   # case does not matter
+artist:The Chainsmokers
+";
+    let list = Blocklist::parse(content).unwrap();
+    let blocked = |data: Value| list.blocks(&track(data));
+
+    assert_eq!(blocked(json!({ "SNG_ID": "3135556", "ART_NAME": "Daft Punk" })), Some("track 3135556".to_string()));
+    assert_eq!(blocked(json!({ "SNG_ID": "1", "ALB_ID": "302127" })), Some("album 302127".to_string()));
+    let credited = json!({ "SNG_ID": "2", "ART_ID": "1", "ARTISTS": [{ "ART_ID": "27", "ART_NAME": "Daft Punk" }] });
+    assert_eq!(blocked(credited), Some("artist 27".to_string()));
+    assert_eq!(blocked(json!({ "SNG_ID": "3", "ART_NAME": "Nickelback" })), Some("Nickelback".to_string()));
+    let featuring = json!({
+        "SNG_ID": "4",
+        "ART_NAME": "Coldplay",
+        "SNG_CONTRIBUTORS": { "featuring": ["The Chainsmokers"] },
+    });
+    assert_eq!(blocked(featuring), Some("The Chainsmokers".to_string()));
+    assert_eq!(blocked(json!({ "SNG_ID": "5", "ALB_ID": "6", "ART_ID": "7", "ART_NAME": "Justice" })), None);
+}
+
+#[test]
+fn invalid_blocklists_are_rejected() {
+    assert!(Blocklist::parse("https://www.deezer.com/playlist/908622995\n").is_err());
+    assert!(Blocklist::parse("album:Discovery\n").is_err());
+    assert!(Blocklist::parse("# nothing yet\n\n").unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn blocked_substitutes_are_not_downloaded() {
+    common::scratch_config("blocklist");
+    let dir = std::env::temp_dir().join(format!("deezer-dl-blocklist-{}", std::process::id()));
+    let audio = b"fLaC fake stream";
+    let mut substitute = common::track("3135557", "Photograph", audio);
+    substitute.art_name = Some("Nickelback".to_string());
+    // The requested track has no stream, Deezer offers a blocked artist's one instead
+    let mut requested = common::track("3135556", "Photograph", b"");
+    requested.fallback = Some(serde_json::to_value(&substitute).unwrap());
+    let api = FakeClient::default().with_track(substitute, TrackFormat::Flac, audio);
+    let opts = DownloadOptions {
+        format: TrackFormat::Flac,
+        blocklist: Some(Arc::new(Blocklist::parse("nickelback\n").unwrap())),
+        ..Default::default()
+    };
+
+    assert!(download::download_track(&api, &requested, &opts, &dir, false).await.is_err());
+    assert!(api.fetched.lock().unwrap().is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+}
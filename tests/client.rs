@@ -0,0 +1,72 @@
+//! Downloads driven through the `DeezerClient` trait with an in-memory fake
+
+mod common;
+
+use common::FakeClient;
+use deezer_dl::download::{self, Batch, DownloadOptions, TracksFailed};
+use deezer_dl::TrackFormat;
+
+#[tokio::test]
+async fn tracks_download_through_any_client() {
+    common::scratch_config("client");
+    let dir = std::env::temp_dir().join(format!("deezer-dl-fake-client-{}", std::process::id()));
+    let audio = b"fLaC fake stream";
+    let track = common::track("3135556", "Harder Better Faster Stronger", audio);
+    let api = FakeClient::default().with_track(track.clone(), TrackFormat::Flac, audio);
+    let opts = DownloadOptions {
+        format: TrackFormat::Flac,
+        template: "{artist} - {title}".to_string(),
+        ..Default::default()
+    };
+
+    let done = download::download_track(&api, &track, &opts, &dir, false).await.unwrap();
+
+    assert_eq!(done.format, TrackFormat::Flac);
+    assert_eq!(done.path, dir.join("Daft Punk - Harder Better Faster Stronger.flac"));
+    assert!(std::fs::read(&done.path).unwrap().starts_with(b"fLaC"));
+    assert_eq!(*api.fetched.lock().unwrap(), ["https://cdn.test/3135556/FLAC"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn batches_fail_when_tracks_failed() {
+    common::scratch_config("client");
+    let dir = std::env::temp_dir().join(format!("deezer-dl-fake-batch-{}", std::process::id()));
+    let mut api = FakeClient::default();
+    // Deezer knows the track but has no stream for it
+    let track = common::track("3135561", "Superheroes", b"");
+    api.tracks.insert(track.id_str(), track);
+
+    let batch = Batch::new(&DownloadOptions::default(), 1);
+    download::download_single_track(&api, "3135561", batch.opts(), &dir).await.unwrap();
+
+    let err = batch.finish().unwrap_err();
+    assert!(err.downcast_ref::<TracksFailed>().is_some_and(|e| e.0 == 1), "{}", err);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn recordings_come_from_another_release_when_the_first_copy_fails() {
+    common::scratch_config("client");
+    let dir = std::env::temp_dir().join(format!("deezer-dl-fake-discography-{}", std::process::id()));
+    let audio = b"fLaC fake stream";
+    let mut original = common::track("3135553", "One More Time", b"");
+    original.isrc = Some("GBDUW0000053".to_string());
+    let mut reissue = common::track("3135554", "One More Time", audio);
+    reissue.isrc = original.isrc.clone();
+    // Only the reissue has a stream
+    let api = FakeClient::default()
+        .with_track(reissue.clone(), TrackFormat::Flac, audio)
+        .with_album("27", "302127", "Discovery", &[original])
+        .with_album("27", "302128", "Discovery (Reissue)", &[reissue]);
+    let (opts, reports) = download::collecting_reports(&DownloadOptions {
+        format: TrackFormat::Flac,
+        ..Default::default()
+    });
+
+    download::download_artist(&api, "27", &opts, &Default::default(), &dir).await.unwrap();
+
+    assert_eq!(*api.fetched.lock().unwrap(), ["https://cdn.test/3135554/FLAC"]);
+    assert_eq!(reports.lock().unwrap()[0].failed, 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,251 @@
+//! In-memory [`DeezerClient`] for tests that don't need a mock HTTP server
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use deezer_dl::DeezerError;
+use deezer_dl::client::{DeezerClient, Download, PostBody};
+use deezer_dl::error::Result;
+use deezer_dl::models::*;
+use deezer_dl::retry::RetryPolicy;
+use futures_util::StreamExt;
+use serde_json::Value;
+
+/// Tracks, stream URLs and CDN files served from memory. Calls it has no data for
+/// answer with an API error, like a track or album Deezer does not know.
+#[derive(Default)]
+pub struct FakeClient {
+    pub user: Option<CurrentUser>,
+    pub tracks: HashMap<String, GwTrack>,
+    /// Stream URLs by (track token, format)
+    pub urls: HashMap<(String, String), String>,
+    /// Bodies served by `fetch_stream`, by URL
+    pub files: HashMap<String, Vec<u8>>,
+    /// Titles and tracks of playlists, by ID
+    pub playlists: HashMap<String, (String, Vec<GwTrack>)>,
+    /// Releases and their tracks, by album ID
+    pub albums: HashMap<String, (AlbumInfo, Vec<GwTrack>)>,
+    /// Album IDs of each artist's discography, in order
+    pub artists: HashMap<String, Vec<String>>,
+    /// URLs fetched so far, in order
+    pub fetched: Mutex<Vec<String>>,
+}
+
+impl FakeClient {
+    /// Serve `track` with a stream of `data` in `format`
+    pub fn with_track(mut self, track: GwTrack, format: TrackFormat, data: &[u8]) -> Self {
+        let token = track.track_token.clone().expect("fake tracks need a TRACK_TOKEN");
+        let url = format!("https://cdn.test/{}/{}", track.id_str(), format.api_name());
+        self.urls.insert((token, format.api_name().to_string()), url.clone());
+        self.files.insert(url, data.to_vec());
+        self.tracks.insert(track.id_str(), track);
+        self
+    }
+
+    /// List `tracks` as a playlist; they only download when also added with `with_track`
+    pub fn with_playlist(mut self, id: &str, title: &str, tracks: &[GwTrack]) -> Self {
+        self.playlists.insert(id.to_string(), (title.to_string(), tracks.to_vec()));
+        self
+    }
+
+    /// Add an album to the discography of artist `art_id` (named Daft Punk, like the tracks)
+    pub fn with_album(mut self, art_id: &str, alb_id: &str, title: &str, tracks: &[GwTrack]) -> Self {
+        let info = serde_json::from_value(serde_json::json!({
+            "ALB_ID": alb_id,
+            "ALB_TITLE": title,
+            "ART_NAME": "Daft Punk",
+            "TYPE": "1",
+        }))
+        .unwrap();
+        self.albums.insert(alb_id.to_string(), (info, tracks.to_vec()));
+        self.artists.entry(art_id.to_string()).or_default().push(alb_id.to_string());
+        self
+    }
+}
+
+/// Track `sng_id` with a TRACK_TOKEN and the size of `audio` as its FLAC file size
+pub fn track(sng_id: &str, title: &str, audio: &[u8]) -> GwTrack {
+    serde_json::from_value(serde_json::json!({
+        "SNG_ID": sng_id,
+        "SNG_TITLE": title,
+        "ART_NAME": "Daft Punk",
+        "ALB_TITLE": "Discovery",
+        "TRACK_TOKEN": format!("token-{}", sng_id),
+        "FILESIZE_FLAC": audio.len(),
+    }))
+    .unwrap()
+}
+
+/// Point the config folder (history, failures, queue) at a scratch one for this test binary.
+/// Every test of a binary sets the same value.
+pub fn scratch_config(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("deezer-dl-{}-config-{}", name, std::process::id()));
+    unsafe { std::env::set_var("XDG_CONFIG_HOME", &dir) };
+    dir
+}
+
+fn missing<T>(what: &str) -> Result<T> {
+    Err(DeezerError::Api(format!("{} is not in the fake", what)))
+}
+
+impl DeezerClient for FakeClient {
+    async fn fetch_stream(&self, url: &str) -> Result<Download> {
+        self.fetched.lock().unwrap().push(url.to_string());
+        let Some(data) = self.files.get(url) else {
+            return Err(DeezerError::Cdn(reqwest::StatusCode::NOT_FOUND));
+        };
+        Ok(Download {
+            len: Some(data.len() as u64),
+            chunks: futures_util::stream::iter([Ok(data.clone())]).boxed(),
+        })
+    }
+
+    async fn post(&self, _url: &str, _headers: &[(&str, String)], _body: PostBody) -> Result<u16> {
+        Ok(200)
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            retries: 0,
+            ..RetryPolicy::default()
+        }
+    }
+
+    async fn user(&self) -> Option<CurrentUser> {
+        self.user.clone()
+    }
+
+    async fn get_track(&self, sng_id: &str) -> Result<GwTrack> {
+        match self.tracks.get(sng_id) {
+            Some(track) => Ok(track.clone()),
+            None => missing(sng_id),
+        }
+    }
+
+    async fn get_tracks_by_ids(&self, ids: &[String]) -> Result<Vec<GwTrack>> {
+        Ok(ids.iter().filter_map(|id| self.tracks.get(id).cloned()).collect())
+    }
+
+    async fn get_track_page(&self, sng_id: &str) -> Result<TrackPage> {
+        missing(sng_id)
+    }
+
+    async fn get_lyrics(&self, _sng_id: &str, _lang: Option<&str>) -> Result<Option<LyricsData>> {
+        Ok(None)
+    }
+
+    async fn get_track_url(&self, track_token: &str, format: &str) -> Result<Option<String>> {
+        Ok(self.urls.get(&(track_token.to_string(), format.to_string())).cloned())
+    }
+
+    async fn get_track_urls(&self, requests: &[(&str, &str)]) -> Result<Vec<Option<String>>> {
+        let mut urls = Vec::new();
+        for (token, format) in requests {
+            urls.push(self.get_track_url(token, format).await?);
+        }
+        Ok(urls)
+    }
+
+    async fn prefetch_track_urls(&self, _requests: &[(&str, &str)]) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_playlist_info(&self, playlist_id: &str) -> Result<PlaylistPage> {
+        let Some((title, tracks)) = self.playlists.get(playlist_id) else {
+            return missing(playlist_id);
+        };
+        Ok(serde_json::from_value(serde_json::json!({
+            "DATA": { "PLAYLIST_ID": playlist_id, "TITLE": title, "NB_SONG": tracks.len() },
+        }))?)
+    }
+
+    async fn get_playlist_tracks(&self, playlist_id: &str) -> Result<Vec<GwTrack>> {
+        match self.playlists.get(playlist_id) {
+            Some((_, tracks)) => Ok(tracks.clone()),
+            None => missing(playlist_id),
+        }
+    }
+
+    async fn get_user_playlists(&self, _user_id: u64) -> Result<Vec<PlaylistInfo>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_favorite_track_ids(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    async fn add_favorite_track(&self, _sng_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn create_playlist(&self, title: &str, _description: &str, _sng_ids: &[String]) -> Result<String> {
+        missing(title)
+    }
+
+    async fn add_playlist_tracks(&self, playlist_id: &str, _sng_ids: &[String]) -> Result<()> {
+        missing(playlist_id)
+    }
+
+    async fn add_favorite_playlist(&self, playlist_id: &str) -> Result<()> {
+        missing(playlist_id)
+    }
+
+    async fn get_album_data(&self, alb_id: &str) -> Result<AlbumData> {
+        missing(alb_id)
+    }
+
+    async fn get_album_tracks(&self, alb_id: &str) -> Result<Vec<GwTrack>> {
+        match self.albums.get(alb_id) {
+            Some((_, tracks)) => Ok(tracks.clone()),
+            None => missing(alb_id),
+        }
+    }
+
+    async fn get_album_public(&self, alb_id: &str) -> Result<PublicAlbum> {
+        missing(alb_id)
+    }
+
+    async fn get_artist_info(&self, art_id: &str) -> Result<ArtistData> {
+        if !self.artists.contains_key(art_id) {
+            return missing(art_id);
+        }
+        Ok(serde_json::from_value(serde_json::json!({ "ART_ID": art_id, "ART_NAME": "Daft Punk" })).unwrap())
+    }
+
+    async fn get_artist_discography(&self, art_id: &str, _mode: &str) -> Result<Vec<AlbumInfo>> {
+        match self.artists.get(art_id) {
+            Some(ids) => Ok(ids.iter().map(|id| self.albums[id].0.clone()).collect()),
+            None => missing(art_id),
+        }
+    }
+
+    async fn get_artist_top(&self, art_id: &str, _limit: usize) -> Result<Vec<String>> {
+        missing(art_id)
+    }
+
+    async fn get_related_artists(&self, _art_id: &str, _limit: usize) -> Result<Vec<(String, String)>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_followed_artists(&self, _user_id: u64) -> Result<Vec<(String, String)>> {
+        Ok(Vec::new())
+    }
+
+    async fn search_artist(&self, _query: &str) -> Result<Vec<ArtistSearchResult>> {
+        Ok(Vec::new())
+    }
+
+    async fn search_track(&self, _query: &str) -> Result<Vec<SearchTrackResult>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_track_by_isrc(&self, _isrc: &str) -> Result<Option<SearchTrackResult>> {
+        Ok(None)
+    }
+
+    async fn musicbrainz_isrc(&self, _isrc: &str) -> Result<Value> {
+        Ok(Value::Null)
+    }
+}
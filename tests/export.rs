@@ -0,0 +1,78 @@
+//! Playlist exports: CSV quoting, XSPF and iTunes XML escaping and file locations
+
+use deezer_dl::export::{self, ExportFormat, ExportedPlaylist, ExportedTrack};
+use std::path::Path;
+
+fn playlist() -> ExportedPlaylist {
+    let track = |position: usize, title: &str, file: Option<&str>| ExportedTrack {
+        position,
+        title: title.to_string(),
+        artists: vec!["Simon & Garfunkel".into(), "Guest".into()],
+        album: "Bookends".into(),
+        isrc: "USSM16800123".into(),
+        duration: 185,
+        track_id: format!("{}", 1000 + position),
+        album_id: "42".into(),
+        artist_id: "7".into(),
+        file: file.map(Into::into),
+    };
+    ExportedPlaylist {
+        id: "908622995".into(),
+        name: "Rock & <Roll>".into(),
+        tracks: vec![
+            track(1, "Mrs. Robinson, Pt. 1", Some("/music/S&G/01 Mrs Robinson.flac")),
+            track(2, "America \"Live\"", None),
+        ],
+    }
+}
+
+#[test]
+fn csv_quotes_fields_and_joins_artists() {
+    let csv = export::render(&playlist(), ExportFormat::Csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("position,title,artists,album,isrc,duration,track_id,album_id,artist_id,file")
+    );
+    assert_eq!(
+        lines.next(),
+        Some(
+            "1,\"Mrs. Robinson, Pt. 1\",Simon & Garfunkel; Guest,Bookends,USSM16800123,185,1001,42,7,\
+             /music/S&G/01 Mrs Robinson.flac"
+        )
+    );
+    assert_eq!(
+        lines.next(),
+        Some("2,\"America \"\"Live\"\"\",Simon & Garfunkel; Guest,Bookends,USSM16800123,185,1002,42,7,")
+    );
+}
+
+#[test]
+fn xspf_escapes_metadata_and_locates_downloaded_tracks() {
+    let xspf = export::render(&playlist(), ExportFormat::Xspf).unwrap();
+    assert!(xspf.contains("<title>Rock &amp; &lt;Roll&gt;</title>"));
+    assert!(xspf.contains("<location>file:///music/S%26G/01%20Mrs%20Robinson.flac</location>"));
+    assert!(xspf.contains("<creator>Simon &amp; Garfunkel, Guest</creator>"));
+    assert!(xspf.contains("<title>America &quot;Live&quot;</title>"));
+    assert!(xspf.contains("<duration>185000</duration>"));
+    assert!(xspf.contains("<identifier>https://www.deezer.com/track/1002</identifier>"));
+    assert_eq!(xspf.matches("<location>file://").count(), 1);
+}
+
+#[test]
+fn itunes_xml_lists_only_downloaded_tracks() {
+    let xml = export::render(&playlist(), ExportFormat::Itunes).unwrap();
+    assert!(xml.contains("<key>Location</key><string>file://localhost/music/S%26G/01%20Mrs%20Robinson.flac</string>"));
+    assert!(xml.contains("<key>Name</key><string>Mrs. Robinson, Pt. 1</string>"));
+    assert!(xml.contains("<key>Total Time</key><integer>185000</integer>"));
+    assert!(xml.contains("<key>Persistent ID</key><string>00000000000003E9</string>"));
+    assert!(xml.contains("<key>Name</key><string>Rock &amp; &lt;Roll&gt;</string>"));
+    assert!(!xml.contains("America"));
+    assert_eq!(xml.matches("<dict><key>Track ID</key><integer>1</integer></dict>").count(), 1);
+    assert!(!xml.contains("<integer>2</integer></dict>"));
+}
+
+#[test]
+fn file_uris_are_percent_encoded() {
+    assert_eq!(export::file_uri(Path::new("/a b/Ünïcode #1.mp3")), "file:///a%20b/%C3%9Cn%C3%AFcode%20%231.mp3");
+}
@@ -0,0 +1,19 @@
+//! Command lines built for `--exec` and `--exec-after`
+
+use deezer_dl::hooks::{Shell, fill_command};
+
+fn vars(title: &str) -> Vec<(&'static str, String)> {
+    vec![("title", title.to_string()), ("format", "FLAC".to_string())]
+}
+
+#[test]
+fn values_are_quoted_for_sh() {
+    let line = fill_command("notify {title} {format} {unknown}", &vars("It's {format}"), Shell::Sh);
+    assert_eq!(line, r"notify 'It'\''s {format}' 'FLAC' {unknown}");
+}
+
+#[test]
+fn cmd_reads_values_from_the_environment() {
+    let line = fill_command("echo {title} {format}", &vars(r#"100% "%PATH%""#), Shell::Cmd);
+    assert_eq!(line, r#"echo "%DEEZER_DL_TITLE%" "%DEEZER_DL_FORMAT%""#);
+}
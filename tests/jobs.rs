@@ -0,0 +1,123 @@
+//! Job files read by `watch-folder`
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use common::FakeClient;
+use deezer_dl::config::OutputDirs;
+use deezer_dl::control::ItemKind;
+use deezer_dl::jobs::{self, JobFile};
+use deezer_dl::queue::BatchEntry;
+use deezer_dl::{DownloadOptions, TrackFormat};
+
+#[test]
+fn text_jobs_mix_urls_options_and_comments() {
+    let content = "\
+# Saturday mix
+https://www.deezer.com/en/playlist/908622995
+quality = flac
+
+https://www.deezer.com/track/3135556?utm_source=share
+output = /mnt/archive
+";
+    let job = JobFile::parse(Path::new("mix.txt"), content).unwrap();
+    assert_eq!(job.quality.as_deref(), Some("flac"));
+    assert_eq!(job.output, Some(PathBuf::from("/mnt/archive")));
+    let items = job.items().unwrap();
+    assert_eq!(
+        items,
+        vec![(ItemKind::Playlist, "908622995".to_string()), (ItemKind::Track, "3135556".to_string())]
+    );
+}
+
+#[test]
+fn json_jobs() {
+    let content = r#"{"urls": ["https://www.deezer.com/artist/27"], "quality": "320"}"#;
+    let job = JobFile::parse(Path::new("job.JSON"), content).unwrap();
+    assert_eq!(job.items().unwrap(), vec![(ItemKind::Artist, "27".to_string())]);
+    assert_eq!(job.output, None);
+}
+
+#[test]
+fn invalid_jobs_are_rejected() {
+    assert!(JobFile::parse(Path::new("job.txt"), "format = flac\n").is_err());
+    assert!(JobFile::parse(Path::new("job.json"), r#"{"urls": [], "bitrate": 320}"#).is_err());
+    let no_urls = JobFile::parse(Path::new("job.txt"), "# nothing yet\n").unwrap();
+    assert!(no_urls.items().is_err());
+    let bad_quality = JobFile::parse(Path::new("job.txt"), "https://www.deezer.com/track/1\nquality = ultra\n").unwrap();
+    assert!(bad_quality.items().is_err());
+    let best = JobFile::parse(Path::new("job.txt"), "https://www.deezer.com/track/1 | BEST\nquality = Best\n").unwrap();
+    assert!(best.items().is_ok());
+    let bad_url = JobFile::parse(Path::new("job.txt"), "https://example.com/page\n").unwrap();
+    assert!(bad_url.items().is_err());
+}
+
+#[test]
+fn lines_can_set_their_own_quality_and_output() {
+    let content = "\
+quality = 320
+https://www.deezer.com/playlist/908622995 | flac | /mnt/archive
+https://www.deezer.com/track/3135556 | best
+https://www.deezer.com/artist/27 | | /mnt/artists
+https://www.deezer.com/track/3135557
+";
+    let job = JobFile::parse(Path::new("mixed.txt"), content).unwrap();
+    let entries = job.entries().unwrap();
+    let overrides: Vec<(Option<&str>, Option<&Path>)> =
+        entries.iter().map(|e| (e.quality.as_deref(), e.output.as_deref())).collect();
+    assert_eq!(
+        overrides,
+        vec![
+            (Some("flac"), Some(Path::new("/mnt/archive"))),
+            (Some("best"), None),
+            (None, Some(Path::new("/mnt/artists"))),
+            (None, None),
+        ]
+    );
+    assert_eq!(entries[0].url, "https://www.deezer.com/playlist/908622995");
+    assert_eq!(job.items().unwrap().len(), 4);
+    assert_eq!(entries[2].to_string(), "https://www.deezer.com/artist/27 | | /mnt/artists");
+
+    assert!(BatchEntry::parse("https://www.deezer.com/track/1 | ultra").is_err());
+    assert!(BatchEntry::parse("https://www.deezer.com/track/1 | flac | /mnt | extra").is_err());
+    assert!(BatchEntry::parse(" | flac").is_err());
+}
+
+#[tokio::test]
+async fn job_files_with_failed_tracks_go_to_failed() {
+    common::scratch_config("jobs");
+    let dir = std::env::temp_dir().join(format!("deezer-dl-jobs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let audio = b"fLaC something about us";
+    let mut api = FakeClient::default().with_track(
+        common::track("3135562", "Something About Us", audio),
+        TrackFormat::Flac,
+        audio,
+    );
+    // Deezer knows this one but has no stream for it
+    let voyager = common::track("3135563", "Voyager", audio);
+    api.tracks.insert(voyager.id_str(), voyager);
+    let files = [
+        ("ok.txt", "https://www.deezer.com/track/3135562"),
+        ("ko.txt", "https://www.deezer.com/track/3135563"),
+    ];
+    for (name, url) in files {
+        std::fs::write(dir.join(name), url).unwrap();
+        // Old enough to be picked up
+        let file = std::fs::File::options().write(true).open(dir.join(name)).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(60)).unwrap();
+    }
+    let opts = DownloadOptions {
+        format: TrackFormat::Flac,
+        template: "{artist} - {title}".to_string(),
+        ..Default::default()
+    };
+
+    jobs::run_ready(&api, &opts, &OutputDirs::all(&dir.join("music")), &dir).await.unwrap();
+
+    assert!(dir.join(jobs::DONE_DIR).join("ok.txt").exists());
+    assert!(dir.join(jobs::FAILED_DIR).join("ko.txt").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
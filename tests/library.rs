@@ -0,0 +1,45 @@
+//! Library maintenance commands run over files already on disk
+
+mod common;
+
+use deezer_dl::download::DownloadOptions;
+use deezer_dl::library;
+use deezer_dl::models::TrackFormat;
+use deezer_dl::tags::{self, TrackTags};
+
+/// A FLAC header with an empty STREAMINFO block, enough to carry tags
+fn flac() -> Vec<u8> {
+    let mut data = b"fLaC".to_vec();
+    data.extend_from_slice(&[0x80, 0, 0, 34]);
+    data.extend_from_slice(&[0; 34]);
+    data
+}
+
+#[tokio::test]
+async fn organize_uses_the_tags_of_files_missing_from_history() {
+    common::scratch_config("organize");
+    let dir = std::env::temp_dir().join(format!("deezer-dl-organize-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("misc")).unwrap();
+
+    let song = TrackTags {
+        title: "One More Time".to_string(),
+        artist: "Daft Punk".to_string(),
+        album: "Discovery".to_string(),
+        track_number: 1,
+        ..Default::default()
+    };
+    let data = tags::apply(&flac(), TrackFormat::Flac, &song).unwrap();
+    std::fs::write(dir.join("misc/01.flac"), data).unwrap();
+    assert_eq!(tags::read(&dir.join("misc/01.flac")).unwrap().album, "Discovery");
+
+    let opts = DownloadOptions {
+        template: "{artist}/{album}/{track} - {title}".to_string(),
+        ..Default::default()
+    };
+    library::organize(&dir, &opts, false).await.unwrap();
+
+    assert!(dir.join("Daft Punk/Discovery/01 - One More Time.flac").exists());
+    assert!(!dir.join("misc").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,60 @@
+//! The persistent queue, with an in-memory client
+
+mod common;
+
+use common::FakeClient;
+use deezer_dl::config::OutputDirs;
+use deezer_dl::queue;
+use deezer_dl::{DownloadOptions, TrackFormat};
+use tokio::sync::Mutex;
+
+/// Tests share the queue file of the scratch config folder
+static QUEUE: Mutex<()> = Mutex::const_new(());
+
+#[tokio::test]
+async fn items_with_failed_tracks_stay_queued() {
+    let _queue = QUEUE.lock().await;
+    common::scratch_config("queue");
+    let output = std::env::temp_dir().join(format!("deezer-dl-queue-{}", std::process::id()));
+    let outputs = OutputDirs::all(&output);
+    let opts = DownloadOptions {
+        format: TrackFormat::Flac,
+        template: "{artist} - {title}".to_string(),
+        ..Default::default()
+    };
+    let audio = b"fLaC veridis quo";
+    let track = common::track("3135560", "Veridis Quo", audio);
+    queue::add(&["https://www.deezer.com/track/3135560".to_string()]).await.unwrap();
+
+    // Deezer knows the track but has no stream for it
+    let mut unavailable = FakeClient::default();
+    unavailable.tracks.insert(track.id_str(), track.clone());
+    queue::run(&unavailable, &opts, &outputs).await.unwrap();
+    assert_eq!(queue::load().await.unwrap().len(), 1);
+
+    let api = FakeClient::default().with_track(track, TrackFormat::Flac, audio);
+    queue::run(&api, &opts, &outputs).await.unwrap();
+    assert!(queue::load().await.unwrap().is_empty());
+    assert!(output.join("Daft Punk - Veridis Quo.flac").exists());
+    std::fs::remove_dir_all(&output).unwrap();
+}
+
+#[tokio::test]
+async fn concurrent_adds_keep_every_url() {
+    let _queue = QUEUE.lock().await;
+    common::scratch_config("queue");
+    let urls: Vec<String> = (1..=8).map(|id| format!("https://www.deezer.com/track/{}", id)).collect();
+
+    let adds = urls.iter().map(|url| {
+        let url = url.clone();
+        tokio::spawn(async move { queue::add(&[url]).await })
+    });
+    for add in adds.collect::<Vec<_>>() {
+        add.await.unwrap().unwrap();
+    }
+
+    let queued = queue::load().await.unwrap();
+    assert_eq!(queued.len(), urls.len());
+    queue::remove(&urls).await.unwrap();
+    assert!(queue::load().await.unwrap().is_empty());
+}
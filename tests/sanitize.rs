@@ -0,0 +1,102 @@
+//! Filename sanitization under the Unix and Windows rule sets, and length limits
+
+use deezer_dl::download::{DownloadOptions, FilenameRules, sanitize_filename_for, to_ascii, truncate_filename};
+use deezer_dl::template::{self, TemplateVars};
+
+fn unix(name: &str) -> String {
+    sanitize_filename_for(name, FilenameRules::Unix)
+}
+
+fn windows(name: &str) -> String {
+    sanitize_filename_for(name, FilenameRules::Windows)
+}
+
+#[test]
+fn invalid_characters_are_replaced_on_both() {
+    for sanitize in [unix, windows] {
+        assert_eq!(sanitize("AC/DC: Live?"), "AC_DC_ Live_");
+        assert_eq!(sanitize(r#"a\b*c"d<e>f|g"#), "a_b_c_d_e_f_g");
+    }
+}
+
+#[test]
+fn control_characters_are_removed_on_both() {
+    for sanitize in [unix, windows] {
+        assert_eq!(sanitize("Part 1\nPart 2"), "Part 1 Part 2");
+        assert_eq!(sanitize("Bell\u{7}Song\u{0}"), "Bell_Song_");
+        assert_eq!(sanitize("\tIntro\r\n"), "Intro");
+    }
+}
+
+#[test]
+fn trailing_dots_and_spaces_are_stripped_on_windows() {
+    assert_eq!(windows("And Then..."), "And Then");
+    assert_eq!(windows("Vol. 2 . . "), "Vol. 2");
+    assert_eq!(windows("..."), "");
+    assert_eq!(unix("And Then..."), "And Then...");
+}
+
+#[test]
+fn reserved_device_names_are_renamed_on_windows() {
+    assert_eq!(windows("CON"), "CON_");
+    assert_eq!(windows("nul"), "nul_");
+    assert_eq!(windows("Com1"), "Com1_");
+    assert_eq!(windows("LPT9"), "LPT9_");
+    assert_eq!(windows("aux.flac"), "aux_.flac");
+    assert_eq!(windows("PRN ."), "PRN_");
+    assert_eq!(unix("CON"), "CON");
+}
+
+#[test]
+fn names_that_only_start_like_device_names_are_kept() {
+    assert_eq!(windows("Conga"), "Conga");
+    assert_eq!(windows("COM10"), "COM10");
+    assert_eq!(windows("Nulle part"), "Nulle part");
+    assert_eq!(windows("Con Te Partirò"), "Con Te Partirò");
+}
+
+#[test]
+fn ascii_transliteration() {
+    assert_eq!(to_ascii("Beyoncé – Déjà Vu"), "Beyonce - Deja Vu");
+    assert_eq!(to_ascii("Sigur Rós · Ágætis byrjun"), "Sigur Ros * Agaetis byrjun");
+    assert_eq!(to_ascii("Кино"), "Kino");
+    assert_eq!(to_ascii("Plain ASCII"), "Plain ASCII");
+
+    let ascii = DownloadOptions {
+        ascii_filenames: true,
+        ..Default::default()
+    };
+    assert_eq!(ascii.file_name("Sigur Rós: Ágætis byrjun"), "Sigur Ros_ Agaetis byrjun");
+    assert_eq!(DownloadOptions::default().file_name("Sigur Rós"), "Sigur Rós");
+}
+
+#[test]
+fn truncation_keeps_whole_characters() {
+    assert_eq!(truncate_filename("Short", 10), "Short");
+    assert_eq!(truncate_filename("Déjà vu", 2), "D");
+    assert_eq!(truncate_filename("One more. Time", 10), "One more");
+}
+
+#[test]
+fn long_titles_are_cut_keeping_track_number_and_extension() {
+    let vars = TemplateVars {
+        artist: "Artist".into(),
+        title: "Very long title ".repeat(30),
+        track: "07".into(),
+        ..Default::default()
+    };
+    let opts = DownloadOptions::default();
+    let path = template::render_path("{artist}/{track} - {title} [{artist}]", &vars, ".flac", &opts);
+    let name = path.file_name().unwrap().to_str().unwrap();
+    assert!(name.len() <= opts.max_name_length());
+    assert!(name.starts_with("07 - Very long title"));
+    assert!(name.ends_with(" [Artist].flac"));
+    assert_eq!(path.parent().unwrap(), std::path::Path::new("Artist"));
+
+    let short = DownloadOptions {
+        max_filename_length: 40,
+        ..Default::default()
+    };
+    let path = template::render_path("{artist}/{track} - {title} [{artist}]", &vars, ".flac", &short);
+    assert_eq!(path.file_name().unwrap().to_str().unwrap(), "07 - Very long title [Artist].flac");
+}
@@ -0,0 +1,110 @@
+//! `sync` of playlist folders, with an in-memory client
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::FakeClient;
+use deezer_dl::DownloadOptions;
+use deezer_dl::TrackFormat;
+use deezer_dl::sync::{self, PruneArgs};
+use tokio::sync::Mutex;
+
+/// Syncs read and append to the one history file of the scratch config folder
+static HISTORY: Mutex<()> = Mutex::const_new(());
+
+/// A folder relative to the working directory, like `-o music` on the command line
+fn relative_dir(name: &str) -> PathBuf {
+    PathBuf::from(format!("target/sync-{}-{}", name, std::process::id()))
+}
+
+fn options() -> DownloadOptions {
+    DownloadOptions {
+        format: TrackFormat::Flac,
+        template: "{artist} - {title}".to_string(),
+        ..Default::default()
+    }
+}
+
+fn keep() -> PruneArgs {
+    PruneArgs {
+        prune: false,
+        quarantine: None,
+    }
+}
+
+#[tokio::test]
+async fn tracks_synced_into_a_relative_folder_stay_present() {
+    let _history = HISTORY.lock().await;
+    common::scratch_config("sync");
+    let output = relative_dir("present");
+    let audio = b"fLaC one more time";
+    let track = common::track("3135553", "One More Time", audio);
+    let api = FakeClient::default()
+        .with_track(track.clone(), TrackFormat::Flac, audio)
+        .with_playlist("1", "Discovery", std::slice::from_ref(&track));
+    sync::sync_playlist(&api, "1", &options(), &output, &keep()).await.unwrap();
+
+    // Without a stream the track cannot be downloaded again: it has to be found on disk
+    let offline = FakeClient::default().with_playlist("1", "Discovery", &[track]);
+    sync::sync_playlist(&offline, "1", &options(), &output, &keep()).await.unwrap();
+
+    let m3u = std::fs::read_to_string(output.join("Discovery/Discovery.m3u8")).unwrap();
+    assert!(m3u.contains("Daft Punk - One More Time.flac"), "{}", m3u);
+    assert!(offline.fetched.lock().unwrap().is_empty());
+    std::fs::remove_dir_all(&output).unwrap();
+}
+
+#[tokio::test]
+async fn tracks_removed_from_a_playlist_are_pruned_under_a_relative_folder() {
+    let _history = HISTORY.lock().await;
+    common::scratch_config("sync");
+    let output = relative_dir("prune");
+    let kept_audio = b"fLaC aerodynamic";
+    let removed_audio = b"fLaC digital love";
+    let kept = common::track("3135554", "Aerodynamic", kept_audio);
+    let removed = common::track("3135555", "Digital Love", removed_audio);
+    let api = FakeClient::default()
+        .with_track(kept.clone(), TrackFormat::Flac, kept_audio)
+        .with_track(removed.clone(), TrackFormat::Flac, removed_audio)
+        .with_playlist("2", "Discovery", &[kept.clone(), removed]);
+    sync::sync_playlist(&api, "2", &options(), &output, &keep()).await.unwrap();
+
+    let folder = output.join("Discovery");
+    let prune = PruneArgs {
+        prune: true,
+        quarantine: None,
+    };
+    let offline = FakeClient::default().with_playlist("2", "Discovery", &[kept]);
+    sync::sync_playlist(&offline, "2", &options(), &output, &prune).await.unwrap();
+
+    assert!(folder.join("Daft Punk - Aerodynamic.flac").exists());
+    assert!(!folder.join("Daft Punk - Digital Love.flac").exists());
+    let m3u = std::fs::read_to_string(folder.join("Discovery.m3u8")).unwrap();
+    assert!(m3u.contains("Aerodynamic") && !m3u.contains("Digital Love"), "{}", m3u);
+    std::fs::remove_dir_all(&output).unwrap();
+}
+
+#[tokio::test]
+async fn synced_tracks_are_numbered_in_playlist_order() {
+    let _history = HISTORY.lock().await;
+    common::scratch_config("sync");
+    let output = relative_dir("positions");
+    let first_audio = b"fLaC around the world";
+    let second_audio = b"fLaC da funk";
+    let first = common::track("3129407", "Around the World", first_audio);
+    let second = common::track("3129408", "Da Funk", second_audio);
+    let api = FakeClient::default()
+        .with_track(first.clone(), TrackFormat::Flac, first_audio)
+        .with_track(second.clone(), TrackFormat::Flac, second_audio)
+        .with_playlist("3", "Homework", &[first, second]);
+    let opts = DownloadOptions {
+        template: "{position} - {title}".to_string(),
+        ..options()
+    };
+    sync::sync_playlist(&api, "3", &opts, &output, &keep()).await.unwrap();
+
+    assert!(output.join("Homework/01 - Around the World.flac").exists());
+    assert!(output.join("Homework/02 - Da Funk.flac").exists());
+    std::fs::remove_dir_all(&output).unwrap();
+}
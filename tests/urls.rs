@@ -0,0 +1,118 @@
+//! Deezer links as pasted from the site, the apps and embeds, and as handed to `handle-url`
+
+use deezer_dl::control::{self, ItemKind};
+use deezer_dl::handler::deezer_url;
+use deezer_dl::links::{self, LinkKind};
+
+fn parsed(url: &str) -> Option<(LinkKind, String)> {
+    links::parse(url)
+}
+
+fn item(kind: LinkKind, id: &str) -> Option<(LinkKind, String)> {
+    Some((kind, id.to_string()))
+}
+
+#[test]
+fn locale_segments_are_optional() {
+    assert_eq!(parsed("https://www.deezer.com/track/3135556"), item(LinkKind::Track, "3135556"));
+    assert_eq!(parsed("https://www.deezer.com/en/track/3135556"), item(LinkKind::Track, "3135556"));
+    assert_eq!(parsed("https://www.deezer.com/us/album/302127"), item(LinkKind::Album, "302127"));
+    assert_eq!(parsed("https://www.deezer.com/pt-br/playlist/908622995"), item(LinkKind::Playlist, "908622995"));
+}
+
+#[test]
+fn scheme_and_host_variants() {
+    assert_eq!(parsed("www.deezer.com/fr/artist/27"), item(LinkKind::Artist, "27"));
+    assert_eq!(parsed("deezer.com/track/3135556"), item(LinkKind::Track, "3135556"));
+    assert_eq!(parsed("http://www.deezer.com/album/302127"), item(LinkKind::Album, "302127"));
+    assert_eq!(parsed("HTTPS://WWW.DEEZER.COM/EN/TRACK/3135556"), item(LinkKind::Track, "3135556"));
+    assert_eq!(parsed("https://m.deezer.com/track/3135556"), item(LinkKind::Track, "3135556"));
+    assert_eq!(parsed("https://api.deezer.com/playlist/908622995"), item(LinkKind::Playlist, "908622995"));
+    assert_eq!(parsed("  https://www.deezer.com/en/track/3135556\n"), item(LinkKind::Track, "3135556"));
+}
+
+#[test]
+fn query_strings_fragments_and_trailing_slashes() {
+    let shared = "https://www.deezer.com/en/track/3135556?utm_source=deezer&utm_content=track-3135556\
+                  &utm_term=0_1700000000&utm_medium=web";
+    assert_eq!(parsed(shared), item(LinkKind::Track, "3135556"));
+    assert_eq!(parsed("https://www.deezer.com/fr/album/302127?autoplay=true"), item(LinkKind::Album, "302127"));
+    assert_eq!(parsed("https://www.deezer.com/en/playlist/908622995/"), item(LinkKind::Playlist, "908622995"));
+    assert_eq!(parsed("https://www.deezer.com/en/playlist/908622995#tracks"), item(LinkKind::Playlist, "908622995"));
+    assert_eq!(parsed("https://www.deezer.com/artist/27/?deferredFl=1"), item(LinkKind::Artist, "27"));
+}
+
+#[test]
+fn artist_subpages() {
+    assert_eq!(parsed("https://www.deezer.com/en/artist/27/top_track"), item(LinkKind::Artist, "27"));
+    assert_eq!(parsed("https://www.deezer.com/fr/artist/27/discography/all"), item(LinkKind::Artist, "27"));
+    assert_eq!(parsed("https://www.deezer.com/us/artist/27/related_artist"), item(LinkKind::Artist, "27"));
+}
+
+#[test]
+fn widgets_and_embedded_players() {
+    let widget = "https://widget.deezer.com/widget/dark/playlist/908622995?tracklist=false";
+    assert_eq!(parsed(widget), item(LinkKind::Playlist, "908622995"));
+    assert_eq!(parsed("https://widget.deezer.com/widget/auto/album/302127"), item(LinkKind::Album, "302127"));
+    assert_eq!(parsed("https://widget.deezer.com/widget/light/track/3135556"), item(LinkKind::Track, "3135556"));
+    let player = "https://www.deezer.com/plugins/player?format=classic&autoplay=false&playlist=true\
+                  &color=ff0000&layout=dark&size=medium&type=playlist&id=908622995&app_id=1";
+    assert_eq!(parsed(player), item(LinkKind::Playlist, "908622995"));
+    let player = "https://www.deezer.com/plugins/player?type=album&id=302127";
+    assert_eq!(parsed(player), item(LinkKind::Album, "302127"));
+}
+
+#[test]
+fn non_links_are_rejected() {
+    assert_eq!(parsed("https://www.deezer.com/en/"), None);
+    assert_eq!(parsed("https://www.deezer.com/en/profile/123456/loved"), None);
+    assert_eq!(parsed("https://www.deezer.com/en/track/"), None);
+    assert_eq!(parsed("https://example.com/track/3135556"), None);
+    assert_eq!(parsed("https://notdeezer.com/track/3135556"), None);
+    assert_eq!(parsed("https://deezer.page.link/aBcDeF"), None);
+    assert_eq!(parsed("3135556"), None);
+}
+
+#[test]
+fn ids_of_the_expected_kind() {
+    assert_eq!(links::id_of("3135556", LinkKind::Track).unwrap(), "3135556");
+    assert_eq!(links::id_of("https://m.deezer.com/en/track/3135556?a=b", LinkKind::Track).unwrap(), "3135556");
+    assert!(links::id_of("https://www.deezer.com/playlist/908622995", LinkKind::Track).is_err());
+    assert!(links::id_of("https://link.deezer.com/s/30abcdef", LinkKind::Playlist).is_err());
+    assert!(links::id_of("not a link", LinkKind::Artist).is_err());
+}
+
+#[test]
+fn albums_cannot_be_queued() {
+    let playlist = control::parse_url("https://widget.deezer.com/widget/dark/playlist/908622995");
+    assert_eq!(playlist, Some((ItemKind::Playlist, "908622995".to_string())));
+    assert_eq!(control::parse_url("https://www.deezer.com/us/album/302127"), None);
+}
+
+#[test]
+fn web_links_are_kept() {
+    let url = "https://www.deezer.com/en/track/3135556";
+    assert_eq!(deezer_url(url).as_deref(), Some(url));
+}
+
+#[test]
+fn custom_scheme_paths_become_web_links() {
+    assert_eq!(deezer_url("deezerdl://track/3135556").as_deref(), Some("https://www.deezer.com/track/3135556"));
+    assert_eq!(deezer_url("deezerdl:playlist/908622995").as_deref(), Some("https://www.deezer.com/playlist/908622995"));
+    assert_eq!(deezer_url("DEEZER://artist/27/").as_deref(), Some("https://www.deezer.com/artist/27/"));
+}
+
+#[test]
+fn custom_scheme_wrapping_a_web_link() {
+    let expected = Some("https://www.deezer.com/fr/track/3135556");
+    assert_eq!(deezer_url("deezerdl://https://www.deezer.com/fr/track/3135556").as_deref(), expected);
+    assert_eq!(deezer_url("deezerdl://https//www.deezer.com/fr/track/3135556").as_deref(), expected);
+    assert_eq!(deezer_url("deezer://www.deezer.com/fr/track/3135556").as_deref(), expected);
+}
+
+#[test]
+fn other_links_are_rejected() {
+    assert_eq!(deezer_url("deezerdl://album/302127"), None);
+    assert_eq!(deezer_url("https://www.deezer.com/en/track/abc"), None);
+    assert_eq!(deezer_url("mailto:someone@example.com"), None);
+}
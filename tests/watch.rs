@@ -0,0 +1,43 @@
+//! New releases found by `watch`
+
+mod common;
+
+use common::FakeClient;
+use deezer_dl::download::DownloadOptions;
+use deezer_dl::watch;
+use deezer_dl::TrackFormat;
+
+#[tokio::test]
+async fn first_checks_record_the_back_catalogue_and_later_ones_count_downloaded_releases() {
+    let config = common::scratch_config("watch");
+    let _ = std::fs::remove_dir_all(&config);
+    let dir = std::env::temp_dir().join(format!("deezer-dl-watch-{}", std::process::id()));
+    let audio = b"fLaC fake stream";
+    let old = common::track("3135553", "One More Time", audio);
+    let new = common::track("3135554", "Aerodynamic", audio);
+    let broken = common::track("3135555", "Digital Love", b"");
+    let opts = DownloadOptions {
+        format: TrackFormat::Flac,
+        ..Default::default()
+    };
+    let artists = ["27".to_string()];
+
+    let api = FakeClient::default()
+        .with_track(old.clone(), TrackFormat::Flac, audio)
+        .with_album("27", "302127", "Discovery", std::slice::from_ref(&old));
+    assert_eq!(watch::check(&api, &opts, &artists, None, &dir).await.unwrap(), 0);
+    assert!(api.fetched.lock().unwrap().is_empty());
+
+    // A release that downloads and one whose track has no stream
+    let api = FakeClient::default()
+        .with_track(old.clone(), TrackFormat::Flac, audio)
+        .with_track(new.clone(), TrackFormat::Flac, audio)
+        .with_album("27", "302127", "Discovery", &[old])
+        .with_album("27", "302128", "Alive", &[new])
+        .with_album("27", "302129", "Homework", &[broken]);
+    assert_eq!(watch::check(&api, &opts, &artists, None, &dir).await.unwrap(), 1);
+    assert_eq!(*api.fetched.lock().unwrap(), ["https://cdn.test/3135554/FLAC"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_dir_all(&config);
+}